@@ -12,9 +12,12 @@ use codex_cli::login::run_login_with_api_key;
 use codex_cli::login::run_login_with_chatgpt;
 use codex_cli::login::run_logout;
 use codex_cli::proto;
+use codex_cli::usage::UsageCommand;
+use codex_cli::usage::run_usage_command;
 use codex_common::CliConfigOverrides;
 use codex_exec::Cli as ExecCli;
 use codex_tui::Cli as TuiCli;
+use codex_tui::ResumeSelection;
 use std::path::PathBuf;
 
 use crate::proto::ProtoCli;
@@ -56,6 +59,10 @@ enum Subcommand {
     /// Remove stored authentication credentials.
     Logout(LogoutCommand),
 
+    /// Resume a previous interactive session (picker by default; use --last
+    /// or a session id to skip it).
+    Resume(ResumeCommand),
+
     /// Experimental: run Codex as an MCP server.
     Mcp,
 
@@ -63,6 +70,9 @@ enum Subcommand {
     #[clap(visible_alias = "p")]
     Proto(ProtoCli),
 
+    /// Summarize token usage and estimated cost by project and model.
+    Usage(UsageCommand),
+
     /// Generate shell completion scripts.
     Completion(CompletionCommand),
 
@@ -78,6 +88,17 @@ enum Subcommand {
     GenerateTs(GenerateTsCommand),
 }
 
+#[derive(Debug, Parser)]
+struct ResumeCommand {
+    /// Id of the session to resume; a unique prefix is enough.
+    #[arg(value_name = "SESSION_ID")]
+    session_id: Option<String>,
+
+    /// Resume the most recent session without showing the picker.
+    #[arg(long = "last", default_value_t = false, conflicts_with = "session_id")]
+    last: bool,
+}
+
 #[derive(Debug, Parser)]
 struct CompletionCommand {
     /// Shell to generate completions for
@@ -154,6 +175,19 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
                 println!("{}", codex_core::protocol::FinalOutput::from(usage));
             }
         }
+        Some(Subcommand::Resume(resume_cli)) => {
+            let mut tui_cli = cli.interactive;
+            prepend_config_flags(&mut tui_cli.config_overrides, cli.config_overrides);
+            tui_cli.resume = Some(match (resume_cli.last, resume_cli.session_id) {
+                (true, _) => ResumeSelection::Last,
+                (false, Some(id)) => ResumeSelection::Id(id),
+                (false, None) => ResumeSelection::Picker,
+            });
+            let usage = codex_tui::run_main(tui_cli, codex_linux_sandbox_exe).await?;
+            if !usage.is_zero() {
+                println!("{}", codex_core::protocol::FinalOutput::from(usage));
+            }
+        }
         Some(Subcommand::Exec(mut exec_cli)) => {
             prepend_config_flags(&mut exec_cli.config_overrides, cli.config_overrides);
             codex_exec::run_main(exec_cli, codex_linux_sandbox_exe).await?;
@@ -184,6 +218,9 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             prepend_config_flags(&mut proto_cli.config_overrides, cli.config_overrides);
             proto::run_main(proto_cli).await?;
         }
+        Some(Subcommand::Usage(usage_cli)) => {
+            run_usage_command(usage_cli)?;
+        }
         Some(Subcommand::Completion(completion_cli)) => {
             print_completion(completion_cli);
         }
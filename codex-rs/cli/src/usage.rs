@@ -0,0 +1,118 @@
+use chrono::Duration;
+use chrono::Utc;
+use clap::Parser;
+use codex_core::config::find_codex_home;
+use codex_core::usage_ledger::UsageSummary;
+use codex_core::usage_ledger::read_usage_records;
+use codex_core::usage_ledger::summarize_usage;
+
+#[derive(Debug, Parser)]
+pub struct UsageCommand {
+    /// Only include usage from the last N days.
+    #[arg(long, value_name = "N", default_value_t = 30, conflicts_with = "all")]
+    pub days: u32,
+
+    /// Include all recorded usage.
+    #[arg(long, default_value_t = false)]
+    pub all: bool,
+}
+
+pub fn run_usage_command(cmd: UsageCommand) -> anyhow::Result<()> {
+    let codex_home = find_codex_home()?;
+    let since = (!cmd.all).then(|| Utc::now() - Duration::days(i64::from(cmd.days)));
+    let records = read_usage_records(&codex_home, since)?;
+    if records.is_empty() {
+        println!("No usage recorded.");
+        return Ok(());
+    }
+
+    let today = Utc::now().date_naive();
+    let today_summary = total(&summarize_usage(
+        &records
+            .iter()
+            .filter(|record| record.ts.date_naive() == today)
+            .cloned()
+            .collect::<Vec<_>>(),
+    ));
+
+    let summaries = summarize_usage(&records);
+    let rows: Vec<[String; 6]> = summaries
+        .iter()
+        .map(|summary| {
+            [
+                summary.project.display().to_string(),
+                summary.model.clone(),
+                summary.sessions.to_string(),
+                summary.requests.to_string(),
+                summary.blended_tokens().to_string(),
+                format_cost(summary.cost_usd),
+            ]
+        })
+        .collect();
+    let total = total(&summaries);
+    let header = ["PROJECT", "MODEL", "SESSIONS", "REQUESTS", "TOKENS", "COST"].map(String::from);
+    let footer = [
+        "Total".to_string(),
+        String::new(),
+        String::new(),
+        total.requests.to_string(),
+        total.blended_tokens().to_string(),
+        format_cost(total.cost_usd),
+    ];
+
+    let mut widths = [0usize; 6];
+    for row in std::iter::once(&header)
+        .chain(rows.iter())
+        .chain(std::iter::once(&footer))
+    {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let print_row = |row: &[String; 6]| {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    };
+
+    match since {
+        Some(_) => println!("Usage over the last {} days\n", cmd.days),
+        None => println!("All recorded usage\n"),
+    }
+    print_row(&header);
+    for row in &rows {
+        print_row(row);
+    }
+    print_row(&footer);
+    println!(
+        "\nToday: {} tokens, {}",
+        today_summary.blended_tokens(),
+        format_cost(today_summary.cost_usd)
+    );
+    Ok(())
+}
+
+fn total(summaries: &[UsageSummary]) -> UsageSummary {
+    summaries
+        .iter()
+        .fold(UsageSummary::default(), |mut total, summary| {
+            total.requests += summary.requests;
+            total.input_tokens += summary.input_tokens;
+            total.cached_input_tokens += summary.cached_input_tokens;
+            total.output_tokens += summary.output_tokens;
+            if let Some(cost) = summary.cost_usd {
+                total.cost_usd = Some(total.cost_usd.unwrap_or(0.0) + cost);
+            }
+            total
+        })
+}
+
+fn format_cost(cost_usd: Option<f64>) -> String {
+    match cost_usd {
+        Some(cost) => format!("${cost:.2}"),
+        None => "-".to_string(),
+    }
+}
@@ -6,7 +6,9 @@ pub fn summarize_sandbox_policy(sandbox_policy: &SandboxPolicy) -> String {
         SandboxPolicy::ReadOnly => "read-only".to_string(),
         SandboxPolicy::WorkspaceWrite {
             writable_roots,
+            readable_roots,
             network_access,
+            network_allowlist,
             exclude_tmpdir_env_var,
             exclude_slash_tmp,
         } => {
@@ -27,8 +29,25 @@ pub fn summarize_sandbox_policy(sandbox_policy: &SandboxPolicy) -> String {
             );
 
             summary.push_str(&format!(" [{}]", writable_entries.join(", ")));
+            if let Some(readable_roots) = readable_roots {
+                let mut readable_entries = vec!["workdir".to_string(), "system".to_string()];
+                readable_entries.extend(
+                    readable_roots
+                        .iter()
+                        .map(|p| p.to_string_lossy().to_string()),
+                );
+                summary.push_str(&format!(
+                    " (reads limited to {})",
+                    readable_entries.join(", ")
+                ));
+            }
             if *network_access {
                 summary.push_str(" (network access enabled)");
+            } else if !network_allowlist.is_empty() {
+                summary.push_str(&format!(
+                    " (network access limited to {})",
+                    network_allowlist.join(", ")
+                ));
             }
             summary
         }
@@ -0,0 +1,778 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bytes::Bytes;
+use codex_login::CodexAuth;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+
+use crate::ModelProviderInfo;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::model_family::ModelFamily;
+use crate::models::ContentItem;
+use crate::models::ReasoningItemContent;
+use crate::models::ReasoningItemReasoningSummary;
+use crate::models::ResponseItem;
+use crate::openai_tools::create_tools_json_for_messages_api;
+use crate::protocol::TokenUsage;
+use crate::util::backoff;
+
+/// `max_tokens` is required by the Messages API; used when the model's
+/// output limit is not known.
+const DEFAULT_MAX_OUTPUT_TOKENS: u64 = 8192;
+
+/// Implementation for the Anthropic Messages API.
+pub(crate) async fn stream_messages(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    max_output_tokens: Option<u64>,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    auth: &Option<CodexAuth>,
+) -> Result<ResponseStream> {
+    let payload = create_messages_request(prompt, model_family, max_output_tokens)?;
+
+    debug!(
+        "POST to {}: {}",
+        provider.get_full_url(auth),
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+
+    let mut attempt = 0;
+    let max_retries = provider.request_max_retries();
+    loop {
+        attempt += 1;
+
+        let req_builder = provider.create_request_builder(client, auth).await?;
+
+        let res = req_builder
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .json(&payload)
+            .send()
+            .await;
+
+        match res {
+            Ok(resp) if resp.status().is_success() => {
+                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+                tokio::spawn(process_messages_sse(
+                    stream,
+                    tx_event,
+                    provider.stream_idle_timeout(),
+                ));
+                return Ok(ResponseStream { rx_event });
+            }
+            Ok(res) => {
+                let status = res.status();
+                // 529 is returned when the API is temporarily overloaded.
+                let overloaded = status.as_u16() == 529;
+                if !(status == StatusCode::TOO_MANY_REQUESTS
+                    || status.is_server_error()
+                    || overloaded)
+                {
+                    let body = (res.text().await).unwrap_or_default();
+                    return Err(CodexErr::UnexpectedStatus(status, body));
+                }
+
+                if attempt > max_retries {
+                    return Err(CodexErr::RetryLimit(status));
+                }
+
+                let retry_after_secs = res
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok());
+
+                let delay = retry_after_secs
+                    .map(|s| Duration::from_millis(s * 1_000))
+                    .unwrap_or_else(|| backoff(attempt));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                if attempt > max_retries {
+                    return Err(e.into());
+                }
+                let delay = backoff(attempt);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Builds the request body. Consecutive items with the same role are merged
+/// into a single message because the API expects user and assistant
+/// messages to alternate.
+fn create_messages_request(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    max_output_tokens: Option<u64>,
+) -> Result<Value> {
+    let mut messages = Vec::<Value>::new();
+
+    for item in &prompt.get_formatted_input() {
+        let (role, blocks) = match item {
+            ResponseItem::Message { role, content, .. } => {
+                let blocks = content.iter().filter_map(content_block).collect();
+                (role.as_str(), blocks)
+            }
+            ResponseItem::Reasoning {
+                summary,
+                content,
+                encrypted_content,
+                ..
+            } => {
+                // Thinking blocks can only be sent back with the signature
+                // the API attached to them.
+                let Some(signature) = encrypted_content else {
+                    continue;
+                };
+                let thinking = reasoning_text(summary, content.as_deref());
+                let block = if thinking.is_empty() {
+                    json!({"type": "redacted_thinking", "data": signature})
+                } else {
+                    json!({"type": "thinking", "thinking": thinking, "signature": signature})
+                };
+                ("assistant", vec![block])
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let input = serde_json::from_str::<Value>(arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                (
+                    "assistant",
+                    vec![json!({
+                        "type": "tool_use",
+                        "id": call_id,
+                        "name": name,
+                        "input": input,
+                    })],
+                )
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                let mut block = json!({
+                    "type": "tool_result",
+                    "tool_use_id": call_id,
+                    "content": output.content,
+                });
+                if output.success == Some(false) {
+                    block["is_error"] = json!(true);
+                }
+                ("user", vec![block])
+            }
+            ResponseItem::LocalShellCall { .. } | ResponseItem::Other => {
+                // The local shell tool is specific to the Responses API.
+                continue;
+            }
+        };
+        push_blocks(&mut messages, role, blocks);
+    }
+
+    let mut payload = json!({
+        "model": model_family.slug,
+        "system": prompt.get_full_instructions(model_family),
+        "messages": messages,
+        "max_tokens": max_output_tokens.unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS),
+        "stream": true,
+    });
+    let tools_json = create_tools_json_for_messages_api(&prompt.tools)?;
+    if !tools_json.is_empty() {
+        payload["tools"] = json!(tools_json);
+    }
+    Ok(payload)
+}
+
+fn content_block(content: &ContentItem) -> Option<Value> {
+    match content {
+        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+            // Empty text blocks are rejected by the API.
+            (!text.is_empty()).then(|| json!({"type": "text", "text": text}))
+        }
+        ContentItem::InputImage { image_url } => {
+            let source = match image_url
+                .strip_prefix("data:")
+                .and_then(|rest| rest.split_once(";base64,"))
+            {
+                Some((media_type, data)) => {
+                    json!({"type": "base64", "media_type": media_type, "data": data})
+                }
+                None => json!({"type": "url", "url": image_url}),
+            };
+            Some(json!({"type": "image", "source": source}))
+        }
+    }
+}
+
+fn reasoning_text(
+    summary: &[ReasoningItemReasoningSummary],
+    content: Option<&[ReasoningItemContent]>,
+) -> String {
+    let summary = summary.iter().map(|s| match s {
+        ReasoningItemReasoningSummary::SummaryText { text } => text.as_str(),
+    });
+    let content = content.unwrap_or_default().iter().map(|c| match c {
+        ReasoningItemContent::ReasoningText { text } | ReasoningItemContent::Text { text } => {
+            text.as_str()
+        }
+    });
+    summary.chain(content).collect()
+}
+
+fn push_blocks(messages: &mut Vec<Value>, role: &str, blocks: Vec<Value>) {
+    if blocks.is_empty() {
+        return;
+    }
+    if let Some(last) = messages.last_mut()
+        && last["role"] == role
+        && let Some(content) = last["content"].as_array_mut()
+    {
+        content.extend(blocks);
+        return;
+    }
+    messages.push(json!({"role": role, "content": blocks}));
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum MessagesSseEvent {
+    MessageStart {
+        message: MessageStart,
+    },
+    ContentBlockStart {
+        index: usize,
+        content_block: ContentBlockStart,
+    },
+    ContentBlockDelta {
+        index: usize,
+        delta: ContentBlockDelta,
+    },
+    ContentBlockStop {
+        index: usize,
+    },
+    MessageDelta {
+        #[serde(default)]
+        usage: Option<Usage>,
+    },
+    MessageStop,
+    Error {
+        error: ApiError,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageStart {
+    id: String,
+    #[serde(default)]
+    usage: Usage,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Usage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: Option<u64>,
+    #[serde(default)]
+    cache_read_input_tokens: Option<u64>,
+}
+
+impl From<&Usage> for TokenUsage {
+    fn from(usage: &Usage) -> Self {
+        // `input_tokens` only counts the tokens after the last cache
+        // breakpoint, so add the cached ones back in.
+        let input_tokens = usage.input_tokens
+            + usage.cache_creation_input_tokens.unwrap_or(0)
+            + usage.cache_read_input_tokens.unwrap_or(0);
+        TokenUsage {
+            input_tokens,
+            cached_input_tokens: usage.cache_read_input_tokens,
+            output_tokens: usage.output_tokens,
+            reasoning_output_tokens: None,
+            total_tokens: input_tokens + usage.output_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlockStart {
+    Text,
+    Thinking,
+    RedactedThinking {
+        data: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlockDelta {
+    TextDelta {
+        text: String,
+    },
+    ThinkingDelta {
+        thinking: String,
+    },
+    SignatureDelta {
+        signature: String,
+    },
+    InputJsonDelta {
+        partial_json: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+/// A content block that is still being streamed.
+enum ContentBlock {
+    Text(String),
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    RedactedThinking(String),
+    ToolUse {
+        id: String,
+        name: String,
+        input_json: String,
+    },
+}
+
+impl ContentBlock {
+    fn into_response_item(self) -> Option<ResponseItem> {
+        match self {
+            ContentBlock::Text(text) => (!text.is_empty()).then(|| ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText { text }],
+            }),
+            ContentBlock::Thinking {
+                thinking,
+                signature,
+            } => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: vec![ReasoningItemReasoningSummary::SummaryText { text: thinking }],
+                content: None,
+                encrypted_content: (!signature.is_empty()).then_some(signature),
+            }),
+            ContentBlock::RedactedThinking(data) => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: None,
+                encrypted_content: Some(data),
+            }),
+            ContentBlock::ToolUse {
+                id,
+                name,
+                input_json,
+            } => Some(ResponseItem::FunctionCall {
+                id: None,
+                name,
+                arguments: if input_json.is_empty() {
+                    "{}".to_string()
+                } else {
+                    input_json
+                },
+                call_id: id,
+            }),
+        }
+    }
+}
+
+/// SSE processor for the Messages streaming format. Text and thinking deltas
+/// are forwarded as they arrive and every content block is emitted as a
+/// single [`ResponseEvent::OutputItemDone`] once it is complete, like the
+/// Responses API does.
+pub(crate) async fn process_messages_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+
+    let mut response_id = String::new();
+    let mut usage = Usage::default();
+    let mut blocks: HashMap<usize, ContentBlock> = HashMap::new();
+
+    loop {
+        let sse = match timeout(idle_timeout, stream.next()).await {
+            Ok(Some(Ok(sse))) => sse,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "stream closed before message_stop".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "idle timeout waiting for SSE".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        trace!("messages SSE event: {}", sse.data);
+
+        let event: MessagesSseEvent = match serde_json::from_str(&sse.data) {
+            Ok(event) => event,
+            Err(e) => {
+                debug!("Failed to parse SSE event: {e}, data: {}", &sse.data);
+                continue;
+            }
+        };
+
+        let response_event = match event {
+            MessagesSseEvent::MessageStart { message } => {
+                response_id = message.id;
+                usage = message.usage;
+                Some(ResponseEvent::Created)
+            }
+            MessagesSseEvent::ContentBlockStart {
+                index,
+                content_block,
+            } => {
+                let block = match content_block {
+                    ContentBlockStart::Text => ContentBlock::Text(String::new()),
+                    ContentBlockStart::Thinking => ContentBlock::Thinking {
+                        thinking: String::new(),
+                        signature: String::new(),
+                    },
+                    ContentBlockStart::RedactedThinking { data } => {
+                        ContentBlock::RedactedThinking(data)
+                    }
+                    ContentBlockStart::ToolUse { id, name } => ContentBlock::ToolUse {
+                        id,
+                        name,
+                        input_json: String::new(),
+                    },
+                    ContentBlockStart::Other => continue,
+                };
+                blocks.insert(index, block);
+                None
+            }
+            MessagesSseEvent::ContentBlockDelta { index, delta } => {
+                match (blocks.get_mut(&index), delta) {
+                    (
+                        Some(ContentBlock::Text(text)),
+                        ContentBlockDelta::TextDelta { text: delta },
+                    ) => {
+                        text.push_str(&delta);
+                        Some(ResponseEvent::OutputTextDelta(delta))
+                    }
+                    (
+                        Some(ContentBlock::Thinking { thinking, .. }),
+                        ContentBlockDelta::ThinkingDelta { thinking: delta },
+                    ) => {
+                        thinking.push_str(&delta);
+                        Some(ResponseEvent::ReasoningSummaryDelta(delta))
+                    }
+                    (
+                        Some(ContentBlock::Thinking { signature, .. }),
+                        ContentBlockDelta::SignatureDelta { signature: delta },
+                    ) => {
+                        signature.push_str(&delta);
+                        None
+                    }
+                    (
+                        Some(ContentBlock::ToolUse { input_json, .. }),
+                        ContentBlockDelta::InputJsonDelta { partial_json },
+                    ) => {
+                        input_json.push_str(&partial_json);
+                        None
+                    }
+                    (_, delta) => {
+                        debug!("unexpected delta for content block {index}: {delta:?}");
+                        None
+                    }
+                }
+            }
+            MessagesSseEvent::ContentBlockStop { index } => blocks
+                .remove(&index)
+                .and_then(ContentBlock::into_response_item)
+                .map(ResponseEvent::OutputItemDone),
+            MessagesSseEvent::MessageDelta { usage: Some(delta) } => {
+                // `output_tokens` is cumulative.
+                usage.output_tokens = delta.output_tokens;
+                if delta.input_tokens > 0 {
+                    usage.input_tokens = delta.input_tokens;
+                }
+                None
+            }
+            MessagesSseEvent::MessageStop => {
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::Completed {
+                        response_id,
+                        token_usage: Some(TokenUsage::from(&usage)),
+                    }))
+                    .await;
+                return;
+            }
+            MessagesSseEvent::Error { error } => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(error.message, None)))
+                    .await;
+                return;
+            }
+            MessagesSseEvent::MessageDelta { usage: None } | MessagesSseEvent::Other => None,
+        };
+
+        if let Some(response_event) = response_event
+            && tx_event.send(Ok(response_event)).await.is_err()
+        {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+    use tokio_util::io::ReaderStream;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/anthropic_messages_fixture.sse");
+
+    async fn run_sse(body: &str) -> Vec<Result<ResponseEvent>> {
+        let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(64);
+        let stream =
+            ReaderStream::new(std::io::Cursor::new(body.to_string())).map_err(CodexErr::Io);
+        tokio::spawn(process_messages_sse(stream, tx, Duration::from_secs(1)));
+
+        let mut events = Vec::new();
+        while let Some(ev) = rx.recv().await {
+            events.push(ev);
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn parses_recorded_stream() {
+        let events = run_sse(FIXTURE).await;
+        let events: Vec<ResponseEvent> = events.into_iter().map(|ev| ev.unwrap()).collect();
+
+        assert!(matches!(events[0], ResponseEvent::Created));
+
+        let reasoning_deltas: Vec<&str> = events
+            .iter()
+            .filter_map(|ev| match ev {
+                ResponseEvent::ReasoningSummaryDelta(delta) => Some(delta.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec!["Let me list ", "the files."], reasoning_deltas);
+
+        let text_deltas: Vec<&str> = events
+            .iter()
+            .filter_map(|ev| match ev {
+                ResponseEvent::OutputTextDelta(delta) => Some(delta.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec!["Listing ", "files now."], text_deltas);
+
+        let items: Vec<&ResponseItem> = events
+            .iter()
+            .filter_map(|ev| match ev {
+                ResponseEvent::OutputItemDone(item) => Some(item),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                &ResponseItem::Reasoning {
+                    id: String::new(),
+                    summary: vec![ReasoningItemReasoningSummary::SummaryText {
+                        text: "Let me list the files.".to_string()
+                    }],
+                    content: None,
+                    encrypted_content: Some("sig-abc".to_string()),
+                },
+                &ResponseItem::Message {
+                    id: None,
+                    role: "assistant".to_string(),
+                    content: vec![ContentItem::OutputText {
+                        text: "Listing files now.".to_string()
+                    }],
+                },
+                &ResponseItem::FunctionCall {
+                    id: None,
+                    name: "shell".to_string(),
+                    arguments: "{\"command\": [\"ls\"]}".to_string(),
+                    call_id: "toolu_01".to_string(),
+                },
+            ],
+            items
+        );
+
+        match events.last() {
+            Some(ResponseEvent::Completed {
+                response_id,
+                token_usage: Some(token_usage),
+            }) => {
+                assert_eq!("msg_01", response_id);
+                assert_eq!(150, token_usage.input_tokens);
+                assert_eq!(Some(30), token_usage.cached_input_tokens);
+                assert_eq!(42, token_usage.output_tokens);
+                assert_eq!(192, token_usage.total_tokens);
+            }
+            other => panic!("unexpected last event: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn error_event_and_missing_stop_are_errors() {
+        let error =
+            r#"data: {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        let events = run_sse(&format!("{error}\n\n")).await;
+        match &events[..] {
+            [Err(CodexErr::Stream(message, None))] => assert_eq!("Overloaded", message),
+            other => panic!("unexpected events: {other:?}"),
+        }
+
+        let start = r#"data: {"type":"message_start","message":{"id":"msg_01","usage":{"input_tokens":1,"output_tokens":1}}}"#;
+        let events = run_sse(&format!("{start}\n\n")).await;
+        match &events[..] {
+            [
+                Ok(ResponseEvent::Created),
+                Err(CodexErr::Stream(message, None)),
+            ] => {
+                assert_eq!("stream closed before message_stop", message)
+            }
+            other => panic!("unexpected events: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn maps_history_to_alternating_messages() {
+        let prompt = Prompt {
+            input: vec![
+                ResponseItem::Message {
+                    id: None,
+                    role: "user".to_string(),
+                    content: vec![
+                        ContentItem::InputText {
+                            text: "what is in this image?".to_string(),
+                        },
+                        ContentItem::InputImage {
+                            image_url: "data:image/png;base64,AAAA".to_string(),
+                        },
+                    ],
+                },
+                ResponseItem::Reasoning {
+                    id: String::new(),
+                    summary: vec![ReasoningItemReasoningSummary::SummaryText {
+                        text: "Look closer.".to_string(),
+                    }],
+                    content: None,
+                    encrypted_content: Some("sig".to_string()),
+                },
+                ResponseItem::FunctionCall {
+                    id: None,
+                    name: "shell".to_string(),
+                    arguments: "{\"command\":[\"ls\"]}".to_string(),
+                    call_id: "toolu_01".to_string(),
+                },
+                ResponseItem::FunctionCallOutput {
+                    call_id: "toolu_01".to_string(),
+                    output: FunctionCallOutputPayload {
+                        content: "no such file".to_string(),
+                        success: Some(false),
+                    },
+                },
+                ResponseItem::Message {
+                    id: None,
+                    role: "assistant".to_string(),
+                    content: vec![ContentItem::OutputText {
+                        text: "A cat.".to_string(),
+                    }],
+                },
+            ],
+            ..Default::default()
+        };
+        let model_family = crate::model_family::find_family_for_model("gpt-4o").unwrap();
+
+        let payload = create_messages_request(&prompt, &model_family, Some(1024)).unwrap();
+
+        assert_eq!(json!(1024), payload["max_tokens"]);
+        assert_eq!(json!(true), payload["stream"]);
+        assert!(payload["system"].as_str().is_some());
+        assert_eq!(
+            json!([
+                {
+                    "role": "user",
+                    "content": [
+                        {"type": "text", "text": "what is in this image?"},
+                        {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}},
+                    ],
+                },
+                {
+                    "role": "assistant",
+                    "content": [
+                        {"type": "thinking", "thinking": "Look closer.", "signature": "sig"},
+                        {"type": "tool_use", "id": "toolu_01", "name": "shell", "input": {"command": ["ls"]}},
+                    ],
+                },
+                {
+                    "role": "user",
+                    "content": [
+                        {"type": "tool_result", "tool_use_id": "toolu_01", "content": "no such file", "is_error": true},
+                    ],
+                },
+                {
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "A cat."}],
+                },
+            ]),
+            payload["messages"]
+        );
+    }
+}
@@ -0,0 +1,653 @@
+//! Commands the model runs in the background with `start_background_job`,
+//! such as dev servers, watchers or long test runs. A job keeps running while
+//! the agent continues to work; the model reads its output (optionally
+//! waiting for it to exit) with `read_background_job` and stops it with
+//! `kill_background_job`. Jobs run under the same sandbox as a `shell` call,
+//! in their own process group, and are killed when the session shuts down.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+use async_channel::Sender;
+use serde::Deserialize;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::process::Child;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::exec::ExecParams;
+use crate::exec::SandboxType;
+use crate::landlock::spawn_command_under_linux_sandbox;
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+use crate::protocol::BackgroundJobFinishedEvent;
+use crate::protocol::BackgroundJobStartedEvent;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::SandboxPolicy;
+use crate::seatbelt::spawn_command_under_seatbelt;
+use crate::spawn::StdioPolicy;
+use crate::spawn::exit_code_of;
+use crate::spawn::kill_process_group;
+use crate::spawn::spawn_child_async;
+use crate::util::MutexExt;
+
+pub(crate) const START_BACKGROUND_JOB_TOOL_NAME: &str = "start_background_job";
+pub(crate) const READ_BACKGROUND_JOB_TOOL_NAME: &str = "read_background_job";
+pub(crate) const KILL_BACKGROUND_JOB_TOOL_NAME: &str = "kill_background_job";
+
+/// Output a job produces between two reads is buffered up to this many
+/// bytes; older output is dropped first.
+const MAX_BUFFERED_OUTPUT: usize = 1024 * 1024;
+
+/// At most this much of the buffered output is returned by one read; the
+/// rest stays buffered for the next read.
+const MAX_READ_OUTPUT: usize = 16 * 1024;
+
+/// Upper bound for `wait_ms`, so that a read cannot hold up the turn for
+/// longer than a slow `shell` call would.
+const MAX_WAIT: Duration = Duration::from_secs(120);
+
+/// Once a job has exited, how long to wait for the rest of its output (e.g.
+/// when a process it started still holds the pipes).
+const EXIT_GRACE_PERIOD: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct StartBackgroundJobParams {
+    pub(crate) command: Vec<String>,
+    #[serde(default)]
+    pub(crate) workdir: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReadBackgroundJobParams {
+    pub(crate) job_id: u32,
+    #[serde(default)]
+    pub(crate) wait_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct KillBackgroundJobParams {
+    pub(crate) job_id: u32,
+}
+
+pub(crate) fn wait_time(wait_ms: Option<u64>) -> Duration {
+    Duration::from_millis(wait_ms.unwrap_or(0)).min(MAX_WAIT)
+}
+
+fn job_id_property() -> (String, JsonSchema) {
+    (
+        "job_id".to_string(),
+        JsonSchema::Number {
+            description: Some("The job ID returned by start_background_job".to_string()),
+        },
+    )
+}
+
+pub(crate) static START_BACKGROUND_JOB_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let properties = BTreeMap::from([
+        (
+            "command".to_string(),
+            JsonSchema::Array {
+                items: Box::new(JsonSchema::String { description: None }),
+                description: Some("The command to run".to_string()),
+            },
+        ),
+        (
+            "workdir".to_string(),
+            JsonSchema::String {
+                description: Some("The working directory to run the command in".to_string()),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: START_BACKGROUND_JOB_TOOL_NAME.to_string(),
+        description: r#"Starts a command in the background and returns its job ID right away.
+Use it for dev servers, watchers and long builds or test runs that would exceed the shell tool's timeout; use the shell tool for commands that finish quickly.
+Read the job's output with read_background_job and stop it with kill_background_job. Jobs are killed when the session ends.
+"#
+        .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["command".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+pub(crate) static READ_BACKGROUND_JOB_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let properties = BTreeMap::from([
+        job_id_property(),
+        (
+            "wait_ms".to_string(),
+            JsonSchema::Number {
+                description: Some(format!(
+                    "Wait up to this many milliseconds (at most {}) for the job to exit before returning",
+                    MAX_WAIT.as_millis()
+                )),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: READ_BACKGROUND_JOB_TOOL_NAME.to_string(),
+        description: format!(
+            "Returns the output a background job produced since it was last read (at most {} KiB; read again for the rest) and whether it is still running.",
+            MAX_READ_OUTPUT / 1024
+        ),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["job_id".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+pub(crate) static KILL_BACKGROUND_JOB_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    OpenAiTool::Function(ResponsesApiTool {
+        name: KILL_BACKGROUND_JOB_TOOL_NAME.to_string(),
+        description: "Kills a background job and every process it started.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties: BTreeMap::from([job_id_property()]),
+            required: Some(vec!["job_id".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+/// Result of `read_background_job`.
+#[derive(Debug, PartialEq)]
+pub(crate) struct BackgroundJobOutput {
+    pub(crate) job_id: u32,
+    /// Output produced since the previous read.
+    pub(crate) output: String,
+    /// Bytes of output that were dropped because nobody read them in time.
+    pub(crate) dropped_bytes: usize,
+    /// Bytes of output still buffered because they did not fit in this read.
+    pub(crate) remaining_bytes: usize,
+    /// `None` if the job is still running.
+    pub(crate) exit_code: Option<i32>,
+    pub(crate) elapsed: Duration,
+}
+
+impl BackgroundJobOutput {
+    pub(crate) fn format_for_model(&self) -> String {
+        let elapsed = self.elapsed.as_secs_f64();
+        let mut text = match self.exit_code {
+            Some(code) => format!(
+                "Job {} exited with code {code} after {elapsed:.1} seconds",
+                self.job_id
+            ),
+            None => format!(
+                "Job {} is still running after {elapsed:.1} seconds",
+                self.job_id
+            ),
+        };
+        if self.dropped_bytes > 0 {
+            text.push_str(&format!(
+                "\n({} bytes of earlier output were dropped)",
+                self.dropped_bytes
+            ));
+        }
+        text.push_str("\nOutput:\n");
+        text.push_str(&self.output);
+        if self.remaining_bytes > 0 {
+            text.push_str(&format!(
+                "\n({} more bytes of output; read the job again to get them)",
+                self.remaining_bytes
+            ));
+        }
+        text
+    }
+}
+
+/// The background jobs of one Codex session.
+pub(crate) struct BackgroundJobManager {
+    tx_event: Sender<Event>,
+    next_job_id: AtomicU32,
+    jobs: Mutex<BTreeMap<u32, Arc<BackgroundJob>>>,
+}
+
+impl BackgroundJobManager {
+    pub(crate) fn new(tx_event: Sender<Event>) -> Self {
+        Self {
+            tx_event,
+            next_job_id: AtomicU32::new(1),
+            jobs: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Spawns `params.command` and returns the ID of the new job without
+    /// waiting for it.
+    pub(crate) async fn start(
+        &self,
+        params: ExecParams,
+        sandbox_type: SandboxType,
+        sandbox_policy: &SandboxPolicy,
+        codex_linux_sandbox_exe: &Option<PathBuf>,
+        sub_id: &str,
+        call_id: &str,
+    ) -> Result<u32> {
+        let ExecParams {
+            command, cwd, env, ..
+        } = params;
+        let child = spawn_job(
+            command.clone(),
+            cwd.clone(),
+            env,
+            sandbox_type,
+            sandbox_policy,
+            codex_linux_sandbox_exe,
+        )
+        .await?;
+        let job_id = self.next_job_id.fetch_add(1, Ordering::SeqCst);
+        let job = BackgroundJob::watch(child, job_id, sub_id, self.tx_event.clone())?;
+        self.jobs.lock_unchecked().insert(job_id, Arc::new(job));
+
+        let event = Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::BackgroundJobStarted(BackgroundJobStartedEvent {
+                job_id,
+                call_id: call_id.to_string(),
+                command,
+                cwd,
+            }),
+        };
+        let _ = self.tx_event.send(event).await;
+        Ok(job_id)
+    }
+
+    /// Returns up to `MAX_READ_OUTPUT` of the output produced since the
+    /// previous read, first waiting up to `wait` for the job to exit. Jobs are
+    /// forgotten once a read has reported their exit and the last of their
+    /// output. Returns `None` for an unknown job.
+    pub(crate) async fn read(&self, job_id: u32, wait: Duration) -> Option<BackgroundJobOutput> {
+        let job = self.jobs.lock_unchecked().get(&job_id).cloned()?;
+        let mut exit_code = job.exit_code.clone();
+        if !wait.is_zero() {
+            let _ = tokio::time::timeout(wait, exit_code.wait_for(Option::is_some)).await;
+        }
+
+        let exit_code = *exit_code.borrow();
+        let (output, dropped_bytes, remaining_bytes) = job.output.take(MAX_READ_OUTPUT);
+        if exit_code.is_some() && remaining_bytes == 0 {
+            self.jobs.lock_unchecked().remove(&job_id);
+        }
+        Some(BackgroundJobOutput {
+            job_id,
+            output: String::from_utf8_lossy(&output).into_owned(),
+            dropped_bytes,
+            remaining_bytes,
+            exit_code,
+            elapsed: job.started.elapsed(),
+        })
+    }
+
+    /// Returns `false` for an unknown job.
+    pub(crate) fn kill(&self, job_id: u32) -> bool {
+        match self.jobs.lock_unchecked().remove(&job_id) {
+            Some(job) => {
+                job.kill();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Kills every job.
+    pub(crate) fn shutdown(&self) {
+        for job in std::mem::take(&mut *self.jobs.lock_unchecked()).into_values() {
+            job.kill();
+        }
+    }
+}
+
+async fn spawn_job(
+    command: Vec<String>,
+    cwd: PathBuf,
+    env: std::collections::HashMap<String, String>,
+    sandbox_type: SandboxType,
+    sandbox_policy: &SandboxPolicy,
+    codex_linux_sandbox_exe: &Option<PathBuf>,
+) -> Result<Child> {
+    let stdio_policy = StdioPolicy::RedirectForBackgroundJob;
+    let child = match sandbox_type {
+        SandboxType::None => {
+            let (program, args) = command.split_first().ok_or_else(|| {
+                CodexErr::Io(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "command args are empty",
+                ))
+            })?;
+            spawn_child_async(
+                PathBuf::from(program),
+                args.into(),
+                None,
+                cwd,
+                sandbox_policy,
+                stdio_policy,
+                env,
+            )
+            .await?
+        }
+        SandboxType::MacosSeatbelt => {
+            spawn_command_under_seatbelt(command, sandbox_policy, cwd, stdio_policy, env).await?
+        }
+        SandboxType::LinuxSeccomp => {
+            let codex_linux_sandbox_exe = codex_linux_sandbox_exe
+                .as_ref()
+                .ok_or(CodexErr::LandlockSandboxExecutableNotProvided)?;
+            spawn_command_under_linux_sandbox(
+                codex_linux_sandbox_exe,
+                command,
+                sandbox_policy,
+                cwd,
+                stdio_policy,
+                env,
+            )
+            .await?
+        }
+    };
+    Ok(child)
+}
+
+#[derive(Default)]
+struct JobOutput {
+    bytes: Vec<u8>,
+    dropped_bytes: usize,
+}
+
+#[derive(Default)]
+struct SharedJobOutput(Mutex<JobOutput>);
+
+impl SharedJobOutput {
+    fn push(&self, bytes: &[u8]) {
+        let mut output = self.0.lock_unchecked();
+        output.bytes.extend_from_slice(bytes);
+        let len = output.bytes.len();
+        if len > MAX_BUFFERED_OUTPUT {
+            let excess = len - MAX_BUFFERED_OUTPUT;
+            output.bytes.drain(..excess);
+            output.dropped_bytes += excess;
+        }
+    }
+
+    /// Takes the oldest `max` bytes of buffered output, without splitting a
+    /// UTF-8 character, and returns them with the number of bytes dropped
+    /// since the previous call and the number still buffered.
+    fn take(&self, max: usize) -> (Vec<u8>, usize, usize) {
+        let mut output = self.0.lock_unchecked();
+        let dropped_bytes = std::mem::take(&mut output.dropped_bytes);
+        let mut end = output.bytes.len().min(max);
+        if end < output.bytes.len() {
+            let boundary = (end.saturating_sub(3)..=end)
+                .rev()
+                .find(|&i| !is_utf8_continuation(output.bytes[i]));
+            end = boundary.filter(|&i| i > 0).unwrap_or(end);
+        }
+        let taken = output.bytes.drain(..end).collect();
+        (taken, dropped_bytes, output.bytes.len())
+    }
+}
+
+fn is_utf8_continuation(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
+
+struct BackgroundJob {
+    started: Instant,
+    output: Arc<SharedJobOutput>,
+    exit_code: watch::Receiver<Option<i32>>,
+    /// Also the ID of the job's process group.
+    pid: Option<u32>,
+    killed: Arc<AtomicBool>,
+}
+
+impl BackgroundJob {
+    /// Starts collecting the output of `child` and reports its exit as a
+    /// `BackgroundJobFinished` event.
+    fn watch(mut child: Child, job_id: u32, sub_id: &str, tx_event: Sender<Event>) -> Result<Self> {
+        let stdout = child.stdout.take().ok_or_else(|| {
+            CodexErr::Io(io::Error::other(
+                "stdout pipe was unexpectedly not available",
+            ))
+        })?;
+        let stderr = child.stderr.take().ok_or_else(|| {
+            CodexErr::Io(io::Error::other(
+                "stderr pipe was unexpectedly not available",
+            ))
+        })?;
+
+        let output = Arc::new(SharedJobOutput::default());
+        let readers = vec![
+            tokio::spawn(read_into(stdout, Arc::clone(&output))),
+            tokio::spawn(read_into(stderr, Arc::clone(&output))),
+        ];
+
+        let started = Instant::now();
+        let pid = child.id();
+        let killed = Arc::new(AtomicBool::new(false));
+        let (exit_tx, exit_code) = watch::channel(None);
+        tokio::spawn(wait_for_exit(
+            child,
+            readers,
+            exit_tx,
+            Arc::clone(&killed),
+            FinishedEvent {
+                tx_event,
+                sub_id: sub_id.to_string(),
+                job_id,
+                started,
+            },
+        ));
+
+        Ok(Self {
+            started,
+            output,
+            exit_code,
+            pid,
+            killed,
+        })
+    }
+
+    fn kill(&self) {
+        if self.exit_code.borrow().is_some() {
+            return;
+        }
+        self.killed.store(true, Ordering::SeqCst);
+        if let Some(pid) = self.pid {
+            kill_process_group(pid);
+        }
+    }
+}
+
+impl Drop for BackgroundJob {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+async fn read_into<R: AsyncRead + Unpin>(mut reader: R, output: Arc<SharedJobOutput>) {
+    let mut buf = [0u8; 8192];
+    loop {
+        match reader.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => output.push(&buf[..n]),
+        }
+    }
+}
+
+/// What `wait_for_exit` needs to report the end of a job.
+struct FinishedEvent {
+    tx_event: Sender<Event>,
+    sub_id: String,
+    job_id: u32,
+    started: Instant,
+}
+
+async fn wait_for_exit(
+    mut child: Child,
+    readers: Vec<JoinHandle<()>>,
+    exit_tx: watch::Sender<Option<i32>>,
+    killed: Arc<AtomicBool>,
+    finished: FinishedEvent,
+) {
+    let exit_code = match child.wait().await {
+        Ok(status) => exit_code_of(status),
+        Err(_) => -1,
+    };
+    let duration = finished.started.elapsed();
+    let _ = tokio::time::timeout(EXIT_GRACE_PERIOD, futures::future::join_all(readers)).await;
+    let _ = exit_tx.send(Some(exit_code));
+
+    let event = Event {
+        id: finished.sub_id,
+        msg: EventMsg::BackgroundJobFinished(BackgroundJobFinishedEvent {
+            job_id: finished.job_id,
+            exit_code,
+            killed: killed.load(Ordering::SeqCst),
+            duration,
+        }),
+    };
+    let _ = finished.tx_event.send(event).await;
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    fn params(script: &str) -> ExecParams {
+        ExecParams {
+            command: vec!["bash".to_string(), "-c".to_string(), script.to_string()],
+            cwd: std::env::temp_dir(),
+            timeout_ms: None,
+            env: HashMap::from([(
+                "PATH".to_string(),
+                std::env::var("PATH").unwrap_or_default(),
+            )]),
+            with_escalated_permissions: None,
+            justification: None,
+        }
+    }
+
+    async fn start(manager: &BackgroundJobManager, script: &str) -> u32 {
+        manager
+            .start(
+                params(script),
+                SandboxType::None,
+                &SandboxPolicy::DangerFullAccess,
+                &None,
+                "sub",
+                "call",
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn reads_incremental_output_and_waits_for_exit() {
+        let (tx_event, rx_event) = async_channel::unbounded();
+        let manager = BackgroundJobManager::new(tx_event);
+        let job_id = start(&manager, "echo first; sleep 0.5; echo second >&2; exit 4").await;
+
+        let started = rx_event.recv().await.unwrap();
+        assert!(matches!(
+            started.msg,
+            EventMsg::BackgroundJobStarted(BackgroundJobStartedEvent { job_id: 1, .. })
+        ));
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let first = manager.read(job_id, Duration::ZERO).await.unwrap();
+        assert_eq!(None, first.exit_code);
+        assert_eq!("first\n", first.output);
+
+        let second = manager.read(job_id, Duration::from_secs(10)).await.unwrap();
+        assert_eq!(Some(4), second.exit_code);
+        assert_eq!("second\n", second.output);
+        assert_eq!(None, manager.read(job_id, Duration::ZERO).await);
+
+        let finished = rx_event.recv().await.unwrap();
+        let EventMsg::BackgroundJobFinished(finished) = finished.msg else {
+            panic!("unexpected event: {finished:?}");
+        };
+        assert_eq!(
+            (1, 4, false),
+            (finished.job_id, finished.exit_code, finished.killed)
+        );
+    }
+
+    #[tokio::test]
+    async fn kill_stops_the_job_and_its_children() {
+        let (tx_event, rx_event) = async_channel::unbounded();
+        let manager = BackgroundJobManager::new(tx_event);
+        let job_id = start(&manager, "sleep 600 & wait").await;
+        let _started = rx_event.recv().await.unwrap();
+
+        assert!(manager.kill(job_id));
+        assert!(!manager.kill(job_id));
+
+        let finished = tokio::time::timeout(Duration::from_secs(10), rx_event.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let EventMsg::BackgroundJobFinished(finished) = finished.msg else {
+            panic!("unexpected event: {finished:?}");
+        };
+        assert!(finished.killed);
+        assert_eq!(128 + libc::SIGKILL, finished.exit_code);
+    }
+
+    #[test]
+    fn formats_output_for_the_model() {
+        let output = BackgroundJobOutput {
+            job_id: 2,
+            output: "listening on :3000\n".to_string(),
+            dropped_bytes: 10,
+            remaining_bytes: 0,
+            exit_code: None,
+            elapsed: Duration::from_millis(2500),
+        };
+        assert_eq!(
+            "Job 2 is still running after 2.5 seconds\n(10 bytes of earlier output were dropped)\nOutput:\nlistening on :3000\n",
+            output.format_for_model()
+        );
+    }
+
+    #[test]
+    fn takes_at_most_max_bytes_and_keeps_the_rest() {
+        let output = SharedJobOutput::default();
+        output.push("abcé".as_bytes());
+
+        // Four bytes would split the two-byte 'é'.
+        assert_eq!((b"abc".to_vec(), 0, 2), output.take(4));
+        assert_eq!(("é".as_bytes().to_vec(), 0, 0), output.take(4));
+        assert_eq!((Vec::new(), 0, 0), output.take(4));
+    }
+
+    #[test]
+    fn wait_time_is_capped() {
+        assert_eq!(Duration::ZERO, wait_time(None));
+        assert_eq!(Duration::from_millis(500), wait_time(Some(500)));
+        assert_eq!(MAX_WAIT, wait_time(Some(u64::MAX)));
+    }
+}
@@ -0,0 +1,655 @@
+//! Checkpoints of the working tree, taken during each task right before the
+//! first tool call that may change files, so that the file changes made by a
+//! turn can be undone. Tasks that only talk or read files take none.
+//!
+//! A checkpoint is a git tree written to a shadow repository in
+//! `CODEX_HOME/checkpoints/<session id>`, using the git work tree that
+//! contains the session's `cwd` as its work tree. The user's own index, refs
+//! and stash are never touched. The shadow repository borrows the objects of
+//! the user's repository so that unchanged files are not stored twice.
+//!
+//! Files ignored by the repository's `.gitignore` or `.git/info/exclude` are
+//! not part of a checkpoint and are left alone when one is restored. Directories that are not inside a
+//! git repository are not checkpointed.
+//!
+//! Only the latest [`MAX_CHECKPOINTS`] checkpoints of a session are kept, and
+//! the shadow repositories of sessions that have not taken a checkpoint for
+//! [`SHADOW_REPO_RETENTION`] are deleted.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::Context;
+use anyhow::bail;
+use tokio::process::Command;
+use tracing::warn;
+
+use crate::codex::Session;
+use crate::models::ContentItem;
+use crate::models::ResponseItem;
+use crate::protocol::CheckpointCreatedEvent;
+use crate::protocol::CheckpointRestoredEvent;
+use crate::protocol::ErrorEvent;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::util::MutexExt;
+use crate::util::remove_stale_dirs;
+
+/// Namespace of the refs that keep checkpoint trees reachable in the shadow
+/// repository.
+const CHECKPOINT_REF_PREFIX: &str = "refs/codex/checkpoints";
+
+/// Number of checkpoints kept per session. Older ones can no longer be
+/// restored.
+const MAX_CHECKPOINTS: usize = 50;
+
+/// How long the shadow repository of another session is kept after its last
+/// checkpoint. Checkpoints only live as long as their session, so an older
+/// repository is of no use unless its session is still open and comes back to
+/// undo a turn after all that time.
+const SHADOW_REPO_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+#[derive(Debug, Clone)]
+struct Checkpoint {
+    id: String,
+    work_tree: PathBuf,
+    tree: String,
+}
+
+/// Checkpoint of the running task.
+#[derive(Debug, Default, PartialEq)]
+enum TaskCheckpoint {
+    /// No task is running, or its checkpoint could not be taken.
+    #[default]
+    None,
+    /// The task has not called a tool that may change files yet.
+    Pending,
+    Taken(String),
+}
+
+#[derive(Default)]
+struct Checkpoints {
+    last_id: u64,
+    /// Oldest first.
+    list: Vec<Checkpoint>,
+    task: TaskCheckpoint,
+}
+
+pub(crate) struct CheckpointStore {
+    git_dir: PathBuf,
+    max_checkpoints: usize,
+    checkpoints: Mutex<Checkpoints>,
+}
+
+impl CheckpointStore {
+    pub(crate) fn new(git_dir: PathBuf) -> Self {
+        Self {
+            git_dir,
+            max_checkpoints: MAX_CHECKPOINTS,
+            checkpoints: Mutex::new(Checkpoints::default()),
+        }
+    }
+
+    /// Called when a task starts. The task's checkpoint is only taken once
+    /// [`CheckpointStore::checkpoint_task`] is called.
+    pub(crate) fn begin_task(&self) {
+        self.checkpoints.lock_unchecked().task = TaskCheckpoint::Pending;
+    }
+
+    /// Takes the checkpoint of the running task, unless it was already taken.
+    pub(crate) async fn checkpoint_task(&self, cwd: &Path) -> anyhow::Result<()> {
+        {
+            let mut checkpoints = self.checkpoints.lock_unchecked();
+            if checkpoints.task != TaskCheckpoint::Pending {
+                return Ok(());
+            }
+            checkpoints.task = TaskCheckpoint::None;
+        }
+        if let Some(id) = self.create(cwd).await? {
+            self.checkpoints.lock_unchecked().task = TaskCheckpoint::Taken(id);
+        }
+        Ok(())
+    }
+
+    /// Called when a task is over. Returns the id of its checkpoint, if one
+    /// was taken.
+    pub(crate) fn end_task(&self) -> Option<String> {
+        match std::mem::take(&mut self.checkpoints.lock_unchecked().task) {
+            TaskCheckpoint::Taken(id) => Some(id),
+            TaskCheckpoint::None | TaskCheckpoint::Pending => None,
+        }
+    }
+
+    /// Snapshots the git work tree containing `cwd` and returns the id of the
+    /// new checkpoint, or `None` if `cwd` is not inside a git repository.
+    pub(crate) async fn create(&self, cwd: &Path) -> anyhow::Result<Option<String>> {
+        let Some(work_tree) = repository_toplevel(cwd).await else {
+            return Ok(None);
+        };
+        self.ensure_initialized(cwd).await?;
+        let tree = self.snapshot(&work_tree).await?;
+
+        let id = {
+            let mut checkpoints = self.checkpoints.lock_unchecked();
+            checkpoints.last_id += 1;
+            checkpoints.last_id.to_string()
+        };
+        self.git(
+            &work_tree,
+            &[
+                "update-ref",
+                &format!("{CHECKPOINT_REF_PREFIX}/{id}"),
+                &tree,
+            ],
+        )
+        .await?;
+        let expired = {
+            let list = &mut self.checkpoints.lock_unchecked().list;
+            list.push(Checkpoint {
+                id: id.clone(),
+                work_tree,
+                tree,
+            });
+            let excess = list.len().saturating_sub(self.max_checkpoints);
+            list.drain(..excess).collect::<Vec<_>>()
+        };
+        for checkpoint in expired {
+            self.delete_ref(&checkpoint).await;
+        }
+        Ok(Some(id))
+    }
+
+    /// Called once the task that created checkpoint `id` is over. Discards
+    /// the checkpoint if the task did not change any files and otherwise
+    /// returns the changed files.
+    pub(crate) async fn finish(&self, id: &str) -> anyhow::Result<Option<Vec<PathBuf>>> {
+        let Some(checkpoint) = self.get(id) else {
+            return Ok(None);
+        };
+        let current = self.snapshot(&checkpoint.work_tree).await?;
+        if current == checkpoint.tree {
+            self.discard_from(&checkpoint).await;
+            return Ok(None);
+        }
+        let changed = self.changed_files(&checkpoint, &current).await?;
+        Ok(Some(changed))
+    }
+
+    /// Id of the most recent checkpoint.
+    pub(crate) fn latest(&self) -> Option<String> {
+        self.checkpoints
+            .lock_unchecked()
+            .list
+            .last()
+            .map(|checkpoint| checkpoint.id.clone())
+    }
+
+    /// Restores the work tree to checkpoint `id`, including deleted files and
+    /// file modes, and returns the files that were touched. The checkpoint
+    /// and all later ones are discarded.
+    pub(crate) async fn restore(&self, id: &str) -> anyhow::Result<Vec<PathBuf>> {
+        let Some(checkpoint) = self.get(id) else {
+            bail!("unknown checkpoint: {id}");
+        };
+        // Bring the shadow index up to date first: `read-tree --reset -u`
+        // deletes the files that are in the index but not in the checkpoint.
+        let current = self.snapshot(&checkpoint.work_tree).await?;
+        let restored = self.changed_files(&checkpoint, &current).await?;
+        self.git(
+            &checkpoint.work_tree,
+            &["read-tree", "--reset", "-u", &checkpoint.tree],
+        )
+        .await?;
+        self.discard_from(&checkpoint).await;
+        Ok(restored)
+    }
+
+    fn get(&self, id: &str) -> Option<Checkpoint> {
+        self.checkpoints
+            .lock_unchecked()
+            .list
+            .iter()
+            .find(|checkpoint| checkpoint.id == id)
+            .cloned()
+    }
+
+    /// Drops `checkpoint` and every checkpoint taken after it.
+    async fn discard_from(&self, checkpoint: &Checkpoint) {
+        let discarded = {
+            let list = &mut self.checkpoints.lock_unchecked().list;
+            match list.iter().position(|c| c.id == checkpoint.id) {
+                Some(pos) => list.split_off(pos),
+                None => Vec::new(),
+            }
+        };
+        for checkpoint in discarded {
+            self.delete_ref(&checkpoint).await;
+        }
+    }
+
+    async fn delete_ref(&self, checkpoint: &Checkpoint) {
+        let git_ref = format!("{CHECKPOINT_REF_PREFIX}/{}", checkpoint.id);
+        if let Err(e) = self
+            .git(&checkpoint.work_tree, &["update-ref", "-d", &git_ref])
+            .await
+        {
+            warn!("failed to delete {git_ref}: {e:#}");
+        }
+    }
+
+    /// Creates the shadow repository on first use.
+    async fn ensure_initialized(&self, cwd: &Path) -> anyhow::Result<()> {
+        if self.git_dir.exists() {
+            return Ok(());
+        }
+        if let Some(parent) = self.git_dir.parent() {
+            remove_stale_dirs(parent, SHADOW_REPO_RETENTION).await;
+        }
+        let output = Command::new("git")
+            .arg("init")
+            .arg("--bare")
+            .arg("--quiet")
+            .arg(&self.git_dir)
+            .output()
+            .await
+            .context("failed to run git init")?;
+        if !output.status.success() {
+            bail!(
+                "git init failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        if let Some(objects) = repository_objects_dir(cwd).await {
+            let alternates = self.git_dir.join("objects").join("info").join("alternates");
+            tokio::fs::write(&alternates, format!("{}\n", objects.display()))
+                .await
+                .with_context(|| format!("failed to write {}", alternates.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Writes the current state of `work_tree` as a tree and returns its id.
+    async fn snapshot(&self, work_tree: &Path) -> anyhow::Result<String> {
+        self.sync_info_exclude(work_tree).await?;
+        self.git(work_tree, &["add", "--all"]).await?;
+        let tree = self.git(work_tree, &["write-tree"]).await?;
+        Ok(tree.trim().to_string())
+    }
+
+    /// Copies the user's `info/exclude` into the shadow repository, where git
+    /// looks for it instead, so that files excluded there are ignored too.
+    async fn sync_info_exclude(&self, work_tree: &Path) -> anyhow::Result<()> {
+        let exclude = match user_git(work_tree, &["rev-parse", "--git-path", "info/exclude"]).await
+        {
+            Some(path) => tokio::fs::read(work_tree.join(path))
+                .await
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+        let info_dir = self.git_dir.join("info");
+        tokio::fs::create_dir_all(&info_dir)
+            .await
+            .with_context(|| format!("failed to create {}", info_dir.display()))?;
+        let shadow_exclude = info_dir.join("exclude");
+        tokio::fs::write(&shadow_exclude, exclude)
+            .await
+            .with_context(|| format!("failed to write {}", shadow_exclude.display()))
+    }
+
+    async fn changed_files(
+        &self,
+        checkpoint: &Checkpoint,
+        tree: &str,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let output = self
+            .git(
+                &checkpoint.work_tree,
+                &[
+                    "diff-tree",
+                    "-r",
+                    "-z",
+                    "--no-renames",
+                    "--name-only",
+                    &checkpoint.tree,
+                    tree,
+                ],
+            )
+            .await?;
+        Ok(output
+            .split('\0')
+            .filter(|name| !name.is_empty())
+            .map(|name| checkpoint.work_tree.join(name))
+            .collect())
+    }
+
+    async fn git(&self, work_tree: &Path, args: &[&str]) -> anyhow::Result<String> {
+        let output = Command::new("git")
+            .env("GIT_DIR", &self.git_dir)
+            .env("GIT_WORK_TREE", work_tree)
+            .env_remove("GIT_INDEX_FILE")
+            .current_dir(work_tree)
+            .args(args)
+            .output()
+            .await
+            .with_context(|| format!("failed to run git {}", args.join(" ")))?;
+        if !output.status.success() {
+            bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Root of the user's git work tree containing `cwd`.
+async fn repository_toplevel(cwd: &Path) -> Option<PathBuf> {
+    user_git(cwd, &["rev-parse", "--show-toplevel"])
+        .await
+        .map(PathBuf::from)
+}
+
+/// Object directory of the user's repository containing `cwd`.
+async fn repository_objects_dir(cwd: &Path) -> Option<PathBuf> {
+    let common_dir = user_git(cwd, &["rev-parse", "--git-common-dir"]).await?;
+    Some(cwd.join(common_dir).join("objects"))
+}
+
+/// Runs git against the user's repository and returns its trimmed output.
+async fn user_git(cwd: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Records a checkpoint of `cwd` before the running task first calls a tool
+/// that may change files. Failures are logged and only mean that the task
+/// cannot be undone.
+pub(crate) async fn ensure_checkpoint(sess: &Session, cwd: &Path) {
+    if let Err(e) = sess.checkpoints().checkpoint_task(cwd).await {
+        warn!("failed to create checkpoint: {e:#}");
+    }
+}
+
+/// Keeps the checkpoint taken during the task if the task changed any files
+/// and tells the client about it.
+pub(crate) async fn finish_checkpoint(sess: &Session, sub_id: &str) {
+    let Some(checkpoint_id) = sess.checkpoints().end_task() else {
+        return;
+    };
+    match sess.checkpoints().finish(&checkpoint_id).await {
+        Ok(Some(changed_files)) => {
+            sess.send_event(Event {
+                id: sub_id.to_string(),
+                msg: EventMsg::CheckpointCreated(CheckpointCreatedEvent {
+                    checkpoint_id,
+                    changed_files,
+                }),
+            })
+            .await;
+        }
+        Ok(None) => {}
+        Err(e) => warn!("failed to finish checkpoint {checkpoint_id}: {e:#}"),
+    }
+}
+
+/// Handles `Op::UndoTurn` (no `checkpoint_id`) and `Op::RestoreCheckpoint`.
+/// The model is told which files were reverted so it does not rely on edits
+/// that no longer exist.
+pub(crate) async fn handle_restore_checkpoint(
+    sess: &Session,
+    sub_id: &str,
+    checkpoint_id: Option<String>,
+) {
+    let result = if sess.has_running_task() {
+        Err("Cannot restore a checkpoint while a task is running".to_string())
+    } else {
+        match checkpoint_id.or_else(|| sess.checkpoints().latest()) {
+            None => Err("Nothing to undo: no turn has modified files yet".to_string()),
+            Some(checkpoint_id) => sess
+                .checkpoints()
+                .restore(&checkpoint_id)
+                .await
+                .map(|restored_files| (checkpoint_id, restored_files))
+                .map_err(|e| format!("Failed to restore checkpoint: {e:#}")),
+        }
+    };
+
+    let msg = match result {
+        Ok((checkpoint_id, restored_files)) => {
+            sess.record_conversation_items(&[restored_files_message(&restored_files)])
+                .await;
+            EventMsg::CheckpointRestored(CheckpointRestoredEvent {
+                checkpoint_id,
+                restored_files,
+            })
+        }
+        Err(message) => EventMsg::Error(ErrorEvent { message }),
+    };
+    sess.send_event(Event {
+        id: sub_id.to_string(),
+        msg,
+    })
+    .await;
+}
+
+fn restored_files_message(restored_files: &[PathBuf]) -> ResponseItem {
+    let mut text =
+        "The user restored the working tree to a checkpoint taken before an earlier turn, \
+                    undoing the file changes made since then."
+            .to_string();
+    if restored_files.is_empty() {
+        text.push_str(" No files had to be changed.");
+    } else {
+        text.push_str(" These files were restored to their previous state:");
+        for path in restored_files {
+            text.push_str(&format!("\n- {}", path.display()));
+        }
+    }
+    ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText { text }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    async fn init_repo(dir: &Path) {
+        for args in [
+            &["init", "--quiet"][..],
+            &["config", "user.email", "test@example.com"],
+            &["config", "user.name", "Test"],
+        ] {
+            assert!(user_git(dir, args).await.is_some(), "git {args:?} failed");
+        }
+        std::fs::write(dir.join("kept.txt"), "kept\n").unwrap();
+        std::fs::write(dir.join("edited.txt"), "before\n").unwrap();
+        std::fs::write(dir.join("deleted.txt"), "deleted\n").unwrap();
+        std::fs::write(dir.join(".gitignore"), "ignored/\n").unwrap();
+        user_git(dir, &["add", "--all"]).await.unwrap();
+        user_git(dir, &["commit", "--quiet", "-m", "initial"])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn restores_edited_deleted_and_created_files() {
+        let codex_home = TempDir::new().unwrap();
+        let repo = TempDir::new().unwrap();
+        let root = repo.path().canonicalize().unwrap();
+        init_repo(&root).await;
+        // Uncommitted changes are part of the checkpoint.
+        std::fs::write(root.join("uncommitted.txt"), "draft\n").unwrap();
+
+        let store = CheckpointStore::new(codex_home.path().join("checkpoints"));
+        let id = store.create(&root).await.unwrap().unwrap();
+
+        std::fs::write(root.join("edited.txt"), "after\n").unwrap();
+        std::fs::remove_file(root.join("deleted.txt")).unwrap();
+        std::fs::create_dir(root.join("new_dir")).unwrap();
+        std::fs::write(root.join("new_dir/created.txt"), "created\n").unwrap();
+        std::fs::create_dir(root.join("ignored")).unwrap();
+        std::fs::write(root.join("ignored/build.log"), "log\n").unwrap();
+
+        let changed = store.finish(&id).await.unwrap().unwrap();
+        assert_eq!(
+            vec![
+                root.join("deleted.txt"),
+                root.join("edited.txt"),
+                root.join("new_dir/created.txt"),
+            ],
+            changed
+        );
+
+        let restored = store.restore(&id).await.unwrap();
+        assert_eq!(changed, restored);
+        let read = |name: &str| std::fs::read_to_string(root.join(name)).unwrap();
+        assert_eq!("before\n", read("edited.txt"));
+        assert_eq!("deleted\n", read("deleted.txt"));
+        assert_eq!("draft\n", read("uncommitted.txt"));
+        assert!(!root.join("new_dir").exists());
+        assert_eq!("log\n", read("ignored/build.log"));
+        assert_eq!(None, store.latest());
+
+        // The user's repository is left alone.
+        assert_eq!(
+            Some("?? uncommitted.txt".to_string()),
+            user_git(&root, &["status", "--porcelain"]).await
+        );
+    }
+
+    #[tokio::test]
+    async fn leaves_files_excluded_by_the_user_alone() {
+        let codex_home = TempDir::new().unwrap();
+        let repo = TempDir::new().unwrap();
+        let root = repo.path().canonicalize().unwrap();
+        init_repo(&root).await;
+        std::fs::write(root.join(".git/info/exclude"), "scratch/\n").unwrap();
+
+        let store = CheckpointStore::new(codex_home.path().join("checkpoints"));
+        let id = store.create(&root).await.unwrap().unwrap();
+        std::fs::create_dir(root.join("scratch")).unwrap();
+        std::fs::write(root.join("scratch/notes.txt"), "notes\n").unwrap();
+        std::fs::write(root.join("edited.txt"), "after\n").unwrap();
+
+        let changed = store.finish(&id).await.unwrap().unwrap();
+        assert_eq!(vec![root.join("edited.txt")], changed);
+        store.restore(&id).await.unwrap();
+        assert_eq!(
+            "notes\n",
+            std::fs::read_to_string(root.join("scratch/notes.txt")).unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn restores_file_modes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let codex_home = TempDir::new().unwrap();
+        let repo = TempDir::new().unwrap();
+        let root = repo.path().canonicalize().unwrap();
+        init_repo(&root).await;
+        let script = root.join("kept.txt");
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let store = CheckpointStore::new(codex_home.path().join("checkpoints"));
+        let id = store.create(&root).await.unwrap().unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o644)).unwrap();
+        store.restore(&id).await.unwrap();
+
+        let mode = std::fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(0o755, mode & 0o777);
+    }
+
+    #[tokio::test]
+    async fn unchanged_turns_are_discarded_and_non_repositories_skipped() {
+        let codex_home = TempDir::new().unwrap();
+        let repo = TempDir::new().unwrap();
+        init_repo(repo.path()).await;
+        let store = CheckpointStore::new(codex_home.path().join("checkpoints"));
+
+        let id = store.create(repo.path()).await.unwrap().unwrap();
+        assert_eq!(None, store.finish(&id).await.unwrap());
+        assert_eq!(None, store.latest());
+
+        let not_a_repo = TempDir::new().unwrap();
+        assert_eq!(None, store.create(not_a_repo.path()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn tasks_only_checkpoint_when_asked_to() {
+        let codex_home = TempDir::new().unwrap();
+        let repo = TempDir::new().unwrap();
+        let root = repo.path().canonicalize().unwrap();
+        init_repo(&root).await;
+        let git_dir = codex_home.path().join("checkpoints");
+        let store = CheckpointStore::new(git_dir.clone());
+
+        store.begin_task();
+        assert_eq!(None, store.end_task());
+        assert!(!git_dir.exists());
+
+        store.begin_task();
+        store.checkpoint_task(&root).await.unwrap();
+        std::fs::write(root.join("edited.txt"), "after\n").unwrap();
+        // Later tool calls of the same task keep the first checkpoint.
+        store.checkpoint_task(&root).await.unwrap();
+        let id = store.end_task().unwrap();
+        assert_eq!(
+            Some(vec![root.join("edited.txt")]),
+            store.finish(&id).await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_only_the_latest_checkpoints() {
+        let codex_home = TempDir::new().unwrap();
+        let repo = TempDir::new().unwrap();
+        init_repo(repo.path()).await;
+        let mut store = CheckpointStore::new(codex_home.path().join("checkpoints"));
+        store.max_checkpoints = 2;
+
+        let first = store.create(repo.path()).await.unwrap().unwrap();
+        store.create(repo.path()).await.unwrap().unwrap();
+        let third = store.create(repo.path()).await.unwrap().unwrap();
+
+        assert!(store.restore(&first).await.is_err());
+        assert_eq!(Some(third), store.latest());
+        let refs = store
+            .git(repo.path(), &["for-each-ref", "--format=%(refname)"])
+            .await
+            .unwrap();
+        assert_eq!(2, refs.lines().count(), "{refs}");
+    }
+
+    #[tokio::test]
+    async fn prunes_stale_shadow_repositories() {
+        let checkpoints = TempDir::new().unwrap();
+        std::fs::create_dir(checkpoints.path().join("old-session")).unwrap();
+        std::fs::write(checkpoints.path().join("stray-file"), "").unwrap();
+
+        remove_stale_dirs(checkpoints.path(), SHADOW_REPO_RETENTION).await;
+        assert!(checkpoints.path().join("old-session").exists());
+
+        remove_stale_dirs(checkpoints.path(), Duration::ZERO).await;
+        assert!(!checkpoints.path().join("old-session").exists());
+        assert!(checkpoints.path().join("stray-file").exists());
+    }
+}
@@ -21,6 +21,8 @@ use tracing::trace;
 use tracing::warn;
 use uuid::Uuid;
 
+use crate::anthropic_messages::process_messages_sse;
+use crate::anthropic_messages::stream_messages;
 use crate::chat_completions::AggregateStreamExt;
 use crate::chat_completions::stream_chat_completions;
 use crate::client_common::Prompt;
@@ -88,8 +90,8 @@ impl ModelClient {
         }
     }
 
-    /// Dispatches to either the Responses or Chat implementation depending on
-    /// the provider config.  Public callers always invoke `stream()` – the
+    /// Dispatches to the Responses, Chat or Messages implementation depending
+    /// on the provider config.  Public callers always invoke `stream()` – the
     /// specialised helpers are private to avoid accidental misuse.
     pub async fn stream(&self, prompt: &Prompt) -> Result<ResponseStream> {
         match self.provider.wire_api {
@@ -103,33 +105,53 @@ impl ModelClient {
                     &self.provider,
                 )
                 .await?;
+                Ok(self.aggregate(response_stream))
+            }
+            WireApi::Messages => {
+                if let Some(path) = &*CODEX_RS_SSE_FIXTURE {
+                    // short circuit for tests
+                    warn!(path, "Streaming from fixture");
+                    return stream_from_fixture(path, self.provider.clone()).await;
+                }
+                let response_stream = stream_messages(
+                    prompt,
+                    &self.config.model_family,
+                    self.config.model_max_output_tokens,
+                    &self.client,
+                    &self.provider,
+                    &self.auth,
+                )
+                .await?;
+                Ok(self.aggregate(response_stream))
+            }
+        }
+    }
 
-                // Wrap it with the aggregation adapter so callers see *only*
-                // the final assistant message per turn (matching the
-                // behaviour of the Responses API).
-                let mut aggregated = if self.config.show_raw_agent_reasoning {
-                    crate::chat_completions::AggregatedChatStream::streaming_mode(response_stream)
-                } else {
-                    response_stream.aggregate()
-                };
-
-                // Bridge the aggregated stream back into a standard
-                // `ResponseStream` by forwarding events through a channel.
-                let (tx, rx) = mpsc::channel::<Result<ResponseEvent>>(16);
+    /// Wraps `response_stream` with the aggregation adapter so callers see
+    /// *only* the final assistant message per turn (matching the behaviour of
+    /// the Responses API).
+    fn aggregate(&self, response_stream: ResponseStream) -> ResponseStream {
+        let mut aggregated = if self.config.show_raw_agent_reasoning {
+            crate::chat_completions::AggregatedChatStream::streaming_mode(response_stream)
+        } else {
+            response_stream.aggregate()
+        };
 
-                tokio::spawn(async move {
-                    use futures::StreamExt;
-                    while let Some(ev) = aggregated.next().await {
-                        // Exit early if receiver hung up.
-                        if tx.send(ev).await.is_err() {
-                            break;
-                        }
-                    }
-                });
+        // Bridge the aggregated stream back into a standard
+        // `ResponseStream` by forwarding events through a channel.
+        let (tx, rx) = mpsc::channel::<Result<ResponseEvent>>(16);
 
-                Ok(ResponseStream { rx_event: rx })
+        tokio::spawn(async move {
+            use futures::StreamExt;
+            while let Some(ev) = aggregated.next().await {
+                // Exit early if receiver hung up.
+                if tx.send(ev).await.is_err() {
+                    break;
+                }
             }
-        }
+        });
+
+        ResponseStream { rx_event: rx }
     }
 
     /// Implementation for the OpenAI *Responses* experimental API.
@@ -327,6 +349,14 @@ impl ModelClient {
         self.config.model_family.clone()
     }
 
+    /// Returns the token usage at which the history should be compacted.
+    pub fn get_auto_compact_token_limit(&self) -> Option<u64> {
+        crate::compact::auto_compact_token_limit(
+            self.config.model_auto_compact_token_limit,
+            self.config.model_context_window,
+        )
+    }
+
     /// Returns the current reasoning effort setting.
     pub fn get_reasoning_effort(&self) -> ReasoningEffortConfig {
         self.effort
@@ -594,11 +624,15 @@ async fn stream_from_fixture(
 
     let rdr = std::io::Cursor::new(content);
     let stream = ReaderStream::new(rdr).map_err(CodexErr::Io);
-    tokio::spawn(process_sse(
-        stream,
-        tx_event,
-        provider.stream_idle_timeout(),
-    ));
+    let idle_timeout = provider.stream_idle_timeout();
+    match provider.wire_api {
+        WireApi::Messages => {
+            tokio::spawn(process_messages_sse(stream, tx_event, idle_timeout));
+        }
+        WireApi::Responses | WireApi::Chat => {
+            tokio::spawn(process_sse(stream, tx_event, idle_timeout));
+        }
+    }
     Ok(ResponseStream { rx_event })
 }
 
@@ -20,7 +20,7 @@ use tokio::sync::mpsc;
 const BASE_INSTRUCTIONS: &str = include_str!("../prompt.md");
 
 /// wraps user instructions message in a tag for the model to parse more easily.
-const USER_INSTRUCTIONS_START: &str = "<user_instructions>\n\n";
+pub(crate) const USER_INSTRUCTIONS_START: &str = "<user_instructions>\n\n";
 const USER_INSTRUCTIONS_END: &str = "\n\n</user_instructions>";
 
 /// API request payload for a single model turn
@@ -4,9 +4,10 @@ use std::collections::HashSet;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::sync::MutexGuard;
+use std::sync::Weak;
 use std::sync::atomic::AtomicU64;
 use std::time::Duration;
+use std::time::Instant;
 
 use async_channel::Receiver;
 use async_channel::Sender;
@@ -18,8 +19,11 @@ use codex_protocol::protocol::TurnAbortReason;
 use codex_protocol::protocol::TurnAbortedEvent;
 use futures::prelude::*;
 use mcp_types::CallToolResult;
+use mcp_types::ReadResourceResult;
 use serde::Serialize;
+use serde::de::DeserializeOwned;
 use serde_json;
+use tokio::sync::mpsc;
 use tokio::sync::oneshot;
 use tokio::task::AbortHandle;
 use tracing::debug;
@@ -35,10 +39,24 @@ use crate::apply_patch::ApplyPatchExec;
 use crate::apply_patch::CODEX_APPLY_PATCH_ARG1;
 use crate::apply_patch::InternalApplyPatchInvocation;
 use crate::apply_patch::convert_apply_patch_to_protocol;
+use crate::background_jobs;
+use crate::background_jobs::BackgroundJobManager;
+use crate::background_jobs::KILL_BACKGROUND_JOB_TOOL_NAME;
+use crate::background_jobs::KillBackgroundJobParams;
+use crate::background_jobs::READ_BACKGROUND_JOB_TOOL_NAME;
+use crate::background_jobs::ReadBackgroundJobParams;
+use crate::background_jobs::START_BACKGROUND_JOB_TOOL_NAME;
+use crate::background_jobs::StartBackgroundJobParams;
+use crate::checkpoint::CheckpointStore;
+use crate::checkpoint::ensure_checkpoint;
+use crate::checkpoint::finish_checkpoint;
+use crate::checkpoint::handle_restore_checkpoint;
 use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
+use crate::compact;
 use crate::config::Config;
+use crate::config_types::McpToolConfig;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::conversation_history::ConversationHistory;
 use crate::environment_context::EnvironmentContext;
@@ -53,7 +71,31 @@ use crate::exec::StdoutStream;
 use crate::exec::StreamOutput;
 use crate::exec::process_exec_tool_call;
 use crate::exec_env::create_env;
+use crate::exec_output::ExecOutputStore;
+use crate::exec_output::READ_EXEC_OUTPUT_TOOL_NAME;
+use crate::exec_output::ReadExecOutputParams;
+use crate::exec_policy::ExecPolicy;
+use crate::exec_session;
+use crate::exec_session::EXEC_COMMAND_TOOL_NAME;
+use crate::exec_session::ExecCommandParams;
+use crate::exec_session::ExecSessionManager;
+use crate::exec_session::ExecSessionOutput;
+use crate::exec_session::KILL_SESSION_TOOL_NAME;
+use crate::exec_session::KillSessionParams;
+use crate::exec_session::WRITE_STDIN_TOOL_NAME;
+use crate::exec_session::WriteStdinParams;
+use crate::file_tools::GREP_TOOL_NAME;
+use crate::file_tools::LIST_DIR_TOOL_NAME;
+use crate::file_tools::READ_FILE_TOOL_NAME;
+use crate::file_tools::parse_file_tool_call;
+use crate::file_tools::run_file_tool;
+use crate::is_safe_command::is_known_safe_command;
 use crate::mcp_connection_manager::McpConnectionManager;
+use crate::mcp_resources::GET_MCP_PROMPT_TIMEOUT;
+use crate::mcp_resources::PromptTurn;
+use crate::mcp_resources::attach_mentioned_resources;
+use crate::mcp_resources::handle_read_mcp_resource;
+use crate::mcp_resources::prompt_to_turn;
 use crate::mcp_tool_call::handle_mcp_tool_call;
 use crate::model_family::find_family_for_model;
 use crate::models::ContentItem;
@@ -64,10 +106,18 @@ use crate::models::ReasoningItemReasoningSummary;
 use crate::models::ResponseInputItem;
 use crate::models::ResponseItem;
 use crate::models::ShellToolCallParams;
+use crate::network_proxy::CODEX_NETWORK_PROXY_SOCKET_ENV_VAR;
+use crate::network_proxy::NETWORK_ALLOWLIST_SUPPORTED;
+use crate::network_proxy::NetworkProxy;
+use crate::network_proxy::network_namespaces_unavailable;
 use crate::openai_tools::ApplyPatchToolArgs;
+use crate::openai_tools::READ_MCP_RESOURCE_TOOL_NAME;
 use crate::openai_tools::ToolsConfig;
 use crate::openai_tools::get_openai_tools;
 use crate::parse_command::parse_command;
+use crate::plan_tool::PlanProgress;
+use crate::plan_tool::UpdatePlanArgs;
+use crate::plan_tool::handle_get_plan;
 use crate::plan_tool::handle_update_plan;
 use crate::project_doc::get_user_instructions;
 use crate::protocol::AgentMessageDeltaEvent;
@@ -80,6 +130,7 @@ use crate::protocol::AgentReasoningSectionBreakEvent;
 use crate::protocol::ApplyPatchApprovalRequestEvent;
 use crate::protocol::AskForApproval;
 use crate::protocol::BackgroundEventEvent;
+use crate::protocol::BudgetApprovalRequestEvent;
 use crate::protocol::ErrorEvent;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
@@ -87,7 +138,10 @@ use crate::protocol::ExecApprovalRequestEvent;
 use crate::protocol::ExecCommandBeginEvent;
 use crate::protocol::ExecCommandEndEvent;
 use crate::protocol::FileChange;
+use crate::protocol::FileToolCallBeginEvent;
+use crate::protocol::FileToolCallEndEvent;
 use crate::protocol::InputItem;
+use crate::protocol::NetworkAccessEvent;
 use crate::protocol::Op;
 use crate::protocol::PatchApplyBeginEvent;
 use crate::protocol::PatchApplyEndEvent;
@@ -96,39 +150,41 @@ use crate::protocol::SandboxPolicy;
 use crate::protocol::SessionConfiguredEvent;
 use crate::protocol::Submission;
 use crate::protocol::TaskCompleteEvent;
+use crate::protocol::TokenUsage;
 use crate::protocol::TurnDiffEvent;
 use crate::rollout::RolloutRecorder;
+use crate::rollout::SessionStateSnapshot;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_command_safety;
 use crate::safety::assess_safety_for_untrusted_command;
+use crate::safety::assess_write_stdin_safety;
+use crate::session_budget::SessionBudget;
+use crate::session_budget::enforce_session_budget;
 use crate::shell;
 use crate::turn_diff_tracker::TurnDiffTracker;
+use crate::user_notification::Notifier;
 use crate::user_notification::UserNotification;
+use crate::util::MutexExt;
 use crate::util::backoff;
+use crate::util::find_git_root;
 use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
 use codex_protocol::config_types::ReasoningSummary as ReasoningSummaryConfig;
 
-// A convenience extension trait for acquiring mutex locks where poisoning is
-// unrecoverable and should abort the program. This avoids scattered `.unwrap()`
-// calls on `lock()` while still surfacing a clear panic message when a lock is
-// poisoned.
-trait MutexExt<T> {
-    fn lock_unchecked(&self) -> MutexGuard<'_, T>;
-}
-
-impl<T> MutexExt<T> for Mutex<T> {
-    fn lock_unchecked(&self) -> MutexGuard<'_, T> {
-        #[expect(clippy::expect_used)]
-        self.lock().expect("poisoned lock")
-    }
-}
-
 /// The high-level interface to the Codex system.
 /// It operates as a queue pair where you send submissions and receive events.
 pub struct Codex {
     next_id: AtomicU64,
     tx_sub: Sender<Submission>,
     rx_event: Receiver<Event>,
+    /// Used to read the state of the session, e.g. to fork it. Does not keep
+    /// the session alive after it shuts down.
+    session: Weak<Session>,
+}
+
+/// History that a forked conversation starts from.
+pub(crate) struct ForkedHistory {
+    pub(crate) parent_id: Uuid,
+    pub(crate) items: Vec<ResponseItem>,
 }
 
 /// Wrapper returned by [`Codex::spawn`] containing the spawned [`Codex`],
@@ -144,6 +200,24 @@ pub(crate) const INITIAL_SUBMIT_ID: &str = "";
 impl Codex {
     /// Spawn a new [`Codex`] and initialize the session.
     pub async fn spawn(config: Config, auth: Option<CodexAuth>) -> CodexResult<CodexSpawnOk> {
+        Self::spawn_with_history(config, auth, None).await
+    }
+
+    /// Spawn a new [`Codex`] whose session starts with the given history
+    /// instead of an empty one.
+    pub(crate) async fn spawn_forked(
+        config: Config,
+        auth: Option<CodexAuth>,
+        forked_from: ForkedHistory,
+    ) -> CodexResult<CodexSpawnOk> {
+        Self::spawn_with_history(config, auth, Some(forked_from)).await
+    }
+
+    async fn spawn_with_history(
+        config: Config,
+        auth: Option<CodexAuth>,
+        forked_from: Option<ForkedHistory>,
+    ) -> CodexResult<CodexSpawnOk> {
         let (tx_sub, rx_sub) = async_channel::bounded(64);
         let (tx_event, rx_event) = async_channel::unbounded();
 
@@ -165,6 +239,7 @@ impl Codex {
             notify: config.notify.clone(),
             cwd: config.cwd.clone(),
             resume_path,
+            forked_from,
         };
 
         // Generate a unique ID for the lifetime of this Codex session.
@@ -176,6 +251,7 @@ impl Codex {
                     CodexErr::InternalAgentDied
                 })?;
         let session_id = session.session_id;
+        let weak_session = Arc::downgrade(&session);
 
         // This task will run until Op::Shutdown is received.
         tokio::spawn(submission_loop(session, turn_context, config, rx_sub));
@@ -183,6 +259,7 @@ impl Codex {
             next_id: AtomicU64::new(0),
             tx_sub,
             rx_event,
+            session: weak_session,
         };
 
         Ok(CodexSpawnOk { codex, session_id })
@@ -217,16 +294,29 @@ impl Codex {
             .map_err(|_| CodexErr::InternalAgentDied)?;
         Ok(event)
     }
+
+    /// Returns the conversation history and the latest state snapshot, or
+    /// `None` if the session has shut down.
+    pub(crate) fn history_and_snapshot(&self) -> Option<(Vec<ResponseItem>, SessionStateSnapshot)> {
+        let session = self.session.upgrade()?;
+        let state = session.state.lock_unchecked();
+        Some((state.history.contents(), state.snapshot.clone()))
+    }
 }
 
 /// Mutable state of the agent
 #[derive(Default)]
 struct State {
     approved_commands: HashSet<Vec<String>>,
+    /// `(server, tool)` pairs the user approved for the rest of the session.
+    approved_mcp_tools: HashSet<(String, String)>,
     current_task: Option<AgentTask>,
     pending_approvals: HashMap<String, oneshot::Sender<ReviewDecision>>,
     pending_input: Vec<ResponseInputItem>,
     history: ConversationHistory,
+    /// Written to the rollout alongside the conversation items so the session
+    /// can be resumed with the same turn context, plan and token usage.
+    snapshot: SessionStateSnapshot,
 }
 
 /// Context for an initialized model agent
@@ -239,9 +329,18 @@ pub(crate) struct Session {
     /// Manager for external MCP servers/tools.
     mcp_connection_manager: McpConnectionManager,
 
-    /// External notifier command (will be passed as args to exec()). When
-    /// `None` this feature is disabled.
-    notify: Option<Vec<String>>,
+    /// Rules used to auto-approve or forbid commands proposed by the model.
+    /// `None` if not even the default policy could be loaded.
+    exec_policy: Option<ExecPolicy>,
+
+    /// Proxy through which sandboxed commands reach the hosts on the sandbox
+    /// policy's network allowlist. `None` if there is no allowlist.
+    network_proxy: Mutex<Option<NetworkProxy>>,
+    tx_network_access: mpsc::UnboundedSender<NetworkAccessEvent>,
+
+    /// Delivers notifications to the external notifier command and the
+    /// built-in sinks configured under `[notifications]`.
+    notifier: Notifier,
 
     /// Optional rollout recorder for persisting the conversation transcript so
     /// sessions can be replayed or inspected later.
@@ -250,6 +349,23 @@ pub(crate) struct Session {
     codex_linux_sandbox_exe: Option<PathBuf>,
     user_shell: shell::Shell,
     show_raw_agent_reasoning: bool,
+
+    /// Snapshots of the working tree taken before each task so that its file
+    /// changes can be undone.
+    checkpoints: CheckpointStore,
+
+    /// Tokens and cost used so far, checked against the session budget.
+    budget: SessionBudget,
+
+    /// Terminal sessions started with `exec_command` that are still running.
+    exec_sessions: ExecSessionManager,
+
+    /// Commands started with `start_background_job` that have not been
+    /// reaped yet.
+    background_jobs: BackgroundJobManager,
+
+    /// Truncates `shell` output for the model and saves the full output.
+    exec_output: ExecOutputStore,
 }
 
 /// The context needed for a single turn of the conversation.
@@ -316,6 +432,9 @@ struct ConfigureSession {
     cwd: PathBuf,
 
     resume_path: Option<PathBuf>,
+
+    /// Set when the session is a fork of another conversation.
+    forked_from: Option<ForkedHistory>,
 }
 
 impl Session {
@@ -338,6 +457,7 @@ impl Session {
             notify,
             cwd,
             resume_path,
+            forked_from,
         } = configure_session;
         debug!("Configuring session: model={model}; provider={provider:?}");
         if !cwd.is_absolute() {
@@ -360,14 +480,16 @@ impl Session {
                     .map(|(rec, saved)| (saved.session_id, Some(saved), rec)),
                 None => {
                     let session_id = Uuid::new_v4();
-                    RolloutRecorder::new(&config, session_id, user_instructions.clone())
+                    let parent_id = forked_from.as_ref().map(|forked| forked.parent_id);
+                    RolloutRecorder::new(&config, session_id, user_instructions.clone(), parent_id)
                         .await
                         .map(|rec| (session_id, None, rec))
                 }
             }
         };
 
-        let mcp_fut = McpConnectionManager::new(config.mcp_servers.clone());
+        let (tx_mcp_status, rx_mcp_status) = mpsc::unbounded_channel();
+        let mcp_fut = McpConnectionManager::new(config.mcp_servers.clone(), tx_mcp_status);
         let default_shell_fut = shell::default_user_shell();
         let history_meta_fut = crate::message_history::history_metadata(&config);
 
@@ -380,21 +502,27 @@ impl Session {
             session_id: Uuid,
             rollout_recorder: Option<RolloutRecorder>,
             restored_items: Option<Vec<ResponseItem>>,
+            restored_plan: Option<UpdatePlanArgs>,
+            restored_token_usage: Option<TokenUsage>,
         }
         let rollout_result = match rollout_res {
             Ok((session_id, maybe_saved, recorder)) => {
-                let restored_items: Option<Vec<ResponseItem>> =
-                    maybe_saved.and_then(|saved_session| {
-                        if saved_session.items.is_empty() {
-                            None
-                        } else {
-                            Some(saved_session.items)
-                        }
-                    });
+                let (restored_items, restored_plan, restored_token_usage) = match maybe_saved {
+                    Some(saved_session) => (
+                        (!saved_session.items.is_empty()).then_some(saved_session.items),
+                        saved_session.state.plan,
+                        saved_session.state.last_token_usage,
+                    ),
+                    None => (None, None, None),
+                };
+                let restored_items =
+                    restored_items.or_else(|| forked_from.as_ref().map(|f| f.items.clone()));
                 RolloutResult {
                     session_id,
                     rollout_recorder: Some(recorder),
                     restored_items,
+                    restored_plan,
+                    restored_token_usage,
                 }
             }
             Err(e) => {
@@ -416,7 +544,9 @@ impl Session {
                 RolloutResult {
                     session_id: Uuid::new_v4(),
                     rollout_recorder: None,
-                    restored_items: None,
+                    restored_items: forked_from.as_ref().map(|f| f.items.clone()),
+                    restored_plan: None,
+                    restored_token_usage: None,
                 }
             }
         };
@@ -425,16 +555,33 @@ impl Session {
             session_id,
             rollout_recorder,
             restored_items,
+            restored_plan,
+            restored_token_usage,
         } = rollout_result;
 
         // Create the mutable state for the Session.
         let mut state = State {
             history: ConversationHistory::new(),
+            snapshot: SessionStateSnapshot {
+                model: Some(model.clone()),
+                approval_policy: Some(approval_policy),
+                sandbox_policy: Some(sandbox_policy.clone()),
+                cwd: Some(cwd.clone()),
+                plan: restored_plan.clone(),
+                last_token_usage: restored_token_usage,
+            },
             ..Default::default()
         };
         if let Some(restored_items) = restored_items {
             state.history.record_items(&restored_items);
         }
+        // Show the plan the resumed session left off with.
+        if let Some(plan) = restored_plan {
+            post_session_configured_error_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg: EventMsg::PlanUpdate(plan),
+            });
+        }
 
         // Handle MCP manager result and record any startup failures.
         let (mcp_connection_manager, failed_clients) = match mcp_res {
@@ -462,6 +609,28 @@ impl Session {
             }
         }
 
+        // Problems with user or project policy files are reported but do not
+        // prevent the remaining policy files from being used.
+        let exec_policy =
+            match ExecPolicy::load(&config.codex_home, &cwd, config.is_project_trusted(&cwd)) {
+                Ok((exec_policy, problems)) => {
+                    for message in problems {
+                        warn!("{message}");
+                        post_session_configured_error_events.push(Event {
+                            id: INITIAL_SUBMIT_ID.to_owned(),
+                            msg: EventMsg::BackgroundEvent(BackgroundEventEvent { message }),
+                        });
+                    }
+                    Some(exec_policy)
+                }
+                Err(e) => {
+                    error!("failed to load exec policy: {e:#}");
+                    None
+                }
+            };
+
+        let (tx_network_access, rx_network_access) = mpsc::unbounded_channel();
+
         // Now that `session_id` is final (may have been updated by resume),
         // construct the model client.
         let client = ModelClient::new(
@@ -480,6 +649,7 @@ impl Session {
                 sandbox_policy.clone(),
                 config.include_plan_tool,
                 config.include_apply_patch_tool,
+                config.use_experimental_streamable_shell_tool,
             ),
             user_instructions,
             base_instructions,
@@ -493,26 +663,66 @@ impl Session {
             session_id,
             tx_event: tx_event.clone(),
             mcp_connection_manager,
-            notify,
+            exec_policy,
+            network_proxy: Mutex::new(None),
+            tx_network_access,
+            notifier: Notifier::new(notify, config.notifications.clone()),
             state: Mutex::new(state),
             rollout: Mutex::new(rollout_recorder),
             codex_linux_sandbox_exe: config.codex_linux_sandbox_exe.clone(),
             user_shell: default_shell,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
+            checkpoints: CheckpointStore::new(
+                config
+                    .codex_home
+                    .join("checkpoints")
+                    .join(session_id.to_string()),
+            ),
+            budget: SessionBudget::new(
+                &config,
+                session_id,
+                find_git_root(&turn_context.cwd).unwrap_or_else(|| turn_context.cwd.clone()),
+            ),
+            exec_sessions: ExecSessionManager::default(),
+            background_jobs: BackgroundJobManager::new(tx_event.clone()),
+            exec_output: ExecOutputStore::new(&config.codex_home, session_id, &config.exec_output),
         });
 
-        // record the initial user instructions and environment context,
-        // regardless of whether we restored items.
-        let mut conversation_items = Vec::<ResponseItem>::with_capacity(2);
-        if let Some(user_instructions) = turn_context.user_instructions.as_deref() {
-            conversation_items.push(Prompt::format_user_instructions_message(user_instructions));
+        if let Some(msg) = sess.update_network_proxy(&turn_context.sandbox_policy) {
+            post_session_configured_error_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg,
+            });
+        }
+        tokio::spawn(forward_network_access_events(
+            Arc::downgrade(&sess),
+            rx_network_access,
+        ));
+        tokio::spawn(forward_mcp_server_status(
+            Arc::downgrade(&sess),
+            rx_mcp_status,
+        ));
+
+        if let Some(forked_from) = forked_from {
+            // A forked history already starts with the instructions and
+            // environment context; it only needs to go into the new rollout.
+            sess.record_state_snapshot(&forked_from.items).await;
+        } else {
+            // record the initial user instructions and environment context,
+            // regardless of whether we restored items.
+            let mut conversation_items = Vec::<ResponseItem>::with_capacity(2);
+            if let Some(user_instructions) = turn_context.user_instructions.as_deref() {
+                conversation_items
+                    .push(Prompt::format_user_instructions_message(user_instructions));
+            }
+            conversation_items.push(ResponseItem::from(EnvironmentContext::new(
+                turn_context.cwd.to_path_buf(),
+                turn_context.approval_policy,
+                turn_context.sandbox_policy.clone(),
+                sess.user_shell.name(),
+            )));
+            sess.record_conversation_items(&conversation_items).await;
         }
-        conversation_items.push(ResponseItem::from(EnvironmentContext::new(
-            turn_context.cwd.to_path_buf(),
-            turn_context.approval_policy,
-            turn_context.sandbox_policy.clone(),
-        )));
-        sess.record_conversation_items(&conversation_items).await;
 
         // Dispatch the SessionConfiguredEvent first and then report any errors.
         let events = std::iter::once(Event {
@@ -542,6 +752,18 @@ impl Session {
         state.current_task = Some(task);
     }
 
+    pub(crate) fn has_running_task(&self) -> bool {
+        self.state.lock_unchecked().current_task.is_some()
+    }
+
+    pub(crate) fn budget(&self) -> &SessionBudget {
+        &self.budget
+    }
+
+    pub(crate) fn checkpoints(&self) -> &CheckpointStore {
+        &self.checkpoints
+    }
+
     pub fn remove_task(&self, sub_id: &str) {
         let mut state = self.state.lock_unchecked();
         if let Some(task) = &state.current_task
@@ -568,6 +790,12 @@ impl Session {
         reason: Option<String>,
     ) -> oneshot::Receiver<ReviewDecision> {
         let (tx_approve, rx_approve) = oneshot::channel();
+        self.maybe_notify(UserNotification::ApprovalRequested {
+            turn_id: sub_id.clone(),
+            command: command.clone(),
+            files: Vec::new(),
+            reason: reason.clone(),
+        });
         let event = Event {
             id: sub_id.clone(),
             msg: EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
@@ -594,11 +822,18 @@ impl Session {
         grant_root: Option<PathBuf>,
     ) -> oneshot::Receiver<ReviewDecision> {
         let (tx_approve, rx_approve) = oneshot::channel();
+        let changes = convert_apply_patch_to_protocol(action);
+        self.maybe_notify(UserNotification::ApprovalRequested {
+            turn_id: sub_id.clone(),
+            command: Vec::new(),
+            files: changes.keys().cloned().collect(),
+            reason: reason.clone(),
+        });
         let event = Event {
             id: sub_id.clone(),
             msg: EventMsg::ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent {
                 call_id,
-                changes: convert_apply_patch_to_protocol(action),
+                changes,
                 reason,
                 grant_root,
             }),
@@ -611,6 +846,26 @@ impl Session {
         rx_approve
     }
 
+    pub(crate) async fn request_budget_approval(
+        &self,
+        sub_id: String,
+        request: BudgetApprovalRequestEvent,
+    ) -> oneshot::Receiver<ReviewDecision> {
+        let (tx_approve, rx_approve) = oneshot::channel();
+        // Register the approval first: clients such as the MCP server may
+        // answer as soon as they receive the event.
+        self.state
+            .lock_unchecked()
+            .pending_approvals
+            .insert(sub_id.clone(), tx_approve);
+        let event = Event {
+            id: sub_id,
+            msg: EventMsg::BudgetApprovalRequest(request),
+        };
+        let _ = self.tx_event.send(event).await;
+        rx_approve
+    }
+
     pub fn notify_approval(&self, sub_id: &str, decision: ReviewDecision) {
         let mut state = self.state.lock_unchecked();
         if let Some(tx_approve) = state.pending_approvals.remove(sub_id) {
@@ -623,17 +878,105 @@ impl Session {
         state.approved_commands.insert(cmd);
     }
 
+    pub(crate) fn is_mcp_tool_approved(&self, server: &str, tool: &str) -> bool {
+        let state = self.state.lock_unchecked();
+        state
+            .approved_mcp_tools
+            .contains(&(server.to_string(), tool.to_string()))
+    }
+
+    pub(crate) fn add_approved_mcp_tool(&self, server: String, tool: String) {
+        let mut state = self.state.lock_unchecked();
+        state.approved_mcp_tools.insert((server, tool));
+    }
+
+    pub(crate) fn mcp_tool_config(&self, server: &str, tool: &str) -> McpToolConfig {
+        self.mcp_connection_manager.tool_config(server, tool)
+    }
+
     /// Records items to both the rollout and the chat completions/ZDR
     /// transcript, if enabled.
-    async fn record_conversation_items(&self, items: &[ResponseItem]) {
+    pub(crate) async fn record_conversation_items(&self, items: &[ResponseItem]) {
         debug!("Recording items for conversation: {items:?}");
         self.record_state_snapshot(items).await;
 
         self.state.lock_unchecked().history.record_items(items);
     }
 
+    /// Starts, replaces or stops the network proxy so that it serves the
+    /// allowlist of `sandbox_policy`. Returns an event explaining why the
+    /// allowlist cannot be honored, if it cannot; commands then run without
+    /// network access.
+    fn update_network_proxy(&self, sandbox_policy: &SandboxPolicy) -> Option<EventMsg> {
+        let allowlist = sandbox_policy.get_network_allowlist();
+        let mut network_proxy = self.network_proxy.lock_unchecked();
+        let current = network_proxy
+            .as_ref()
+            .map(NetworkProxy::allowlist)
+            .unwrap_or_default();
+        if current == allowlist {
+            return None;
+        }
+
+        *network_proxy = None;
+        if allowlist.is_empty() {
+            return None;
+        }
+        let message = if !NETWORK_ALLOWLIST_SUPPORTED {
+            "`network_allowlist` is not supported on this platform; commands run without network access."
+                .to_string()
+        } else if let Some(reason) = network_namespaces_unavailable() {
+            // A property of the host rather than a failure, so it is not
+            // reported as an error.
+            let message = format!(
+                "`network_allowlist` requires unprivileged user namespaces, which are unavailable ({reason}); commands run without network access."
+            );
+            warn!("{message}");
+            return Some(EventMsg::BackgroundEvent(BackgroundEventEvent { message }));
+        } else {
+            match NetworkProxy::start(allowlist, self.tx_network_access.clone()) {
+                Ok(proxy) => {
+                    *network_proxy = Some(proxy);
+                    return None;
+                }
+                Err(e) => format!("Failed to start network proxy: {e:#}"),
+            }
+        };
+        error!("{message}");
+        Some(EventMsg::Error(ErrorEvent { message }))
+    }
+
+    /// Updates the turn context that is recorded in the rollout so a resumed
+    /// session starts with the most recent one.
+    fn set_snapshot_turn_context(&self, turn_context: &TurnContext) {
+        let mut state = self.state.lock_unchecked();
+        state.snapshot.model = Some(turn_context.client.get_model());
+        state.snapshot.approval_policy = Some(turn_context.approval_policy);
+        state.snapshot.sandbox_policy = Some(turn_context.sandbox_policy.clone());
+        state.snapshot.cwd = Some(turn_context.cwd.clone());
+    }
+
+    /// Updates the plan that is recorded in the rollout.
+    pub(crate) fn set_snapshot_plan(&self, plan: UpdatePlanArgs) {
+        self.state.lock_unchecked().snapshot.plan = Some(plan);
+    }
+
+    /// The plan most recently recorded with `update_plan`, if any.
+    pub(crate) fn snapshot_plan(&self) -> Option<UpdatePlanArgs> {
+        self.state.lock_unchecked().snapshot.plan.clone()
+    }
+
+    fn plan_progress(&self) -> Option<PlanProgress> {
+        self.state
+            .lock_unchecked()
+            .snapshot
+            .plan
+            .as_ref()
+            .map(UpdatePlanArgs::progress)
+    }
+
     async fn record_state_snapshot(&self, items: &[ResponseItem]) {
-        let snapshot = { crate::rollout::SessionStateSnapshot {} };
+        let snapshot = self.state.lock_unchecked().snapshot.clone();
 
         let recorder = {
             let guard = self.rollout.lock_unchecked();
@@ -700,6 +1043,7 @@ impl Session {
         call_id: &str,
         output: &ExecToolCallOutput,
         is_apply_patch: bool,
+        output_file: Option<PathBuf>,
     ) {
         let ExecToolCallOutput {
             stdout,
@@ -707,9 +1051,10 @@ impl Session {
             duration,
             exit_code,
         } = output;
-        // Because stdout and stderr could each be up to 100 KiB, we send
-        // truncated versions.
-        const MAX_STREAM_OUTPUT: usize = 5 * 1024; // 5KiB
+        // stdout and stderr can each be up to 8 MiB (`MAX_CAPTURED_OUTPUT`),
+        // far more than clients show, so we send their beginnings only.
+        // Output too long for the model is saved in full to `output_file`.
+        const MAX_STREAM_OUTPUT: usize = 5 * 1024; // 5K characters
         let stdout = stdout.text.chars().take(MAX_STREAM_OUTPUT).collect();
         let stderr = stderr.text.chars().take(MAX_STREAM_OUTPUT).collect();
 
@@ -727,6 +1072,7 @@ impl Session {
                 stderr,
                 duration: *duration,
                 exit_code: *exit_code,
+                output_file,
             })
         };
 
@@ -789,22 +1135,59 @@ impl Session {
                 &output_stderr
             }
         };
+        let output_file = match &result {
+            Ok(output) if !is_apply_patch => {
+                self.exec_output
+                    .spill(&call_id, model_visible_output(output))
+                    .await
+            }
+            _ => None,
+        };
         self.on_exec_command_end(
             turn_diff_tracker,
             &sub_id,
             &call_id,
             borrowed,
             is_apply_patch,
+            output_file,
         )
         .await;
 
+        if !is_apply_patch && borrowed.duration >= self.notifier.long_command_threshold() {
+            self.maybe_notify(UserNotification::LongCommandFinished {
+                turn_id: sub_id,
+                command: begin_ctx.command_for_display,
+                exit_code: borrowed.exit_code,
+                duration_secs: borrowed.duration.as_secs(),
+            });
+        }
+
         result
     }
 
+    /// Records the token usage reported for the most recent request, in the
+    /// rollout too so a resumed session knows whether to compact.
+    async fn set_last_token_usage(&self, token_usage: &TokenUsage) {
+        self.state.lock_unchecked().snapshot.last_token_usage = Some(token_usage.clone());
+        self.record_state_snapshot(&[]).await;
+    }
+
+    /// Returns the tokens used by the last request if they reached `limit`.
+    fn tokens_over_limit(&self, limit: Option<u64>) -> Option<u64> {
+        let limit = limit?;
+        let state = self.state.lock_unchecked();
+        let used = state
+            .snapshot
+            .last_token_usage
+            .as_ref()?
+            .tokens_in_context_window();
+        (used >= limit).then_some(used)
+    }
+
     /// Helper that emits a BackgroundEvent with the given message. This keeps
     /// the call‑sites terse so adding more diagnostics does not clutter the
     /// core agent logic.
-    async fn notify_background_event(&self, sub_id: &str, message: impl Into<String>) {
+    pub(crate) async fn notify_background_event(&self, sub_id: &str, message: impl Into<String>) {
         let event = Event {
             id: sub_id.to_string(),
             msg: EventMsg::BackgroundEvent(BackgroundEventEvent {
@@ -854,6 +1237,21 @@ impl Session {
             .await
     }
 
+    pub(crate) async fn read_mcp_resource(
+        &self,
+        server: &str,
+        uri: &str,
+        timeout: Option<Duration>,
+    ) -> anyhow::Result<ReadResourceResult> {
+        self.mcp_connection_manager
+            .read_resource(server, uri, timeout)
+            .await
+    }
+
+    pub(crate) fn has_mcp_server(&self, server: &str) -> bool {
+        self.mcp_connection_manager.has_server(server)
+    }
+
     fn interrupt_task(&self) {
         info!("interrupt received: abort current task, if any");
         let mut state = self.state.lock_unchecked();
@@ -864,33 +1262,11 @@ impl Session {
         }
     }
 
-    /// Spawn the configured notifier (if any) with the given JSON payload as
-    /// the last argument. Failures are logged but otherwise ignored so that
-    /// notification issues do not interfere with the main workflow.
-    fn maybe_notify(&self, notification: UserNotification) {
-        let Some(notify_command) = &self.notify else {
-            return;
-        };
-
-        if notify_command.is_empty() {
-            return;
-        }
-
-        let Ok(json) = serde_json::to_string(&notification) else {
-            error!("failed to serialise notification payload");
-            return;
-        };
-
-        let mut command = std::process::Command::new(&notify_command[0]);
-        if notify_command.len() > 1 {
-            command.args(&notify_command[1..]);
-        }
-        command.arg(json);
-
-        // Fire-and-forget – we do not wait for completion.
-        if let Err(e) = command.spawn() {
-            warn!("failed to spawn notifier '{}': {e}", notify_command[0]);
-        }
+    /// Delivers the notification to the configured sinks. Failures are
+    /// logged but otherwise ignored so that notification issues do not
+    /// interfere with the main workflow.
+    pub(crate) fn maybe_notify(&self, notification: UserNotification) {
+        self.notifier.notify(notification);
     }
 }
 
@@ -982,6 +1358,58 @@ impl AgentTask {
     }
 }
 
+/// Reports changes in the health of MCP servers as background events.
+async fn forward_mcp_server_status(
+    sess: Weak<Session>,
+    mut rx_status: mpsc::UnboundedReceiver<String>,
+) {
+    while let Some(message) = rx_status.recv().await {
+        let Some(sess) = sess.upgrade() else {
+            break;
+        };
+        let sub_id = sess
+            .state
+            .lock_unchecked()
+            .current_task
+            .as_ref()
+            .map(|task| task.sub_id.clone())
+            .unwrap_or_else(|| INITIAL_SUBMIT_ID.to_owned());
+        sess.send_event(Event {
+            id: sub_id,
+            msg: EventMsg::BackgroundEvent(BackgroundEventEvent { message }),
+        })
+        .await;
+    }
+}
+
+/// Reports each connection attempt seen by the network proxy. Attempts are
+/// attributed to the running task, if any, because that is almost always the
+/// one whose command made them.
+async fn forward_network_access_events(
+    sess: Weak<Session>,
+    mut rx_access: mpsc::UnboundedReceiver<NetworkAccessEvent>,
+) {
+    while let Some(access) = rx_access.recv().await {
+        let Some(sess) = sess.upgrade() else {
+            break;
+        };
+        let sub_id = sess
+            .state
+            .lock_unchecked()
+            .current_task
+            .as_ref()
+            .map(|task| task.sub_id.clone())
+            .unwrap_or_else(|| INITIAL_SUBMIT_ID.to_owned());
+        let event = Event {
+            id: sub_id,
+            msg: EventMsg::NetworkAccess(access),
+        };
+        if let Err(e) = sess.tx_event.send(event).await {
+            error!("failed to send network access event: {e:?}");
+        }
+    }
+}
+
 async fn submission_loop(
     sess: Arc<Session>,
     turn_context: TurnContext,
@@ -1049,6 +1477,7 @@ async fn submission_loop(
                     new_sandbox_policy.clone(),
                     config.include_plan_tool,
                     config.include_apply_patch_tool,
+                    config.use_experimental_streamable_shell_tool,
                 );
 
                 let new_turn_context = TurnContext {
@@ -1063,18 +1492,31 @@ async fn submission_loop(
                     disable_response_storage: prev.disable_response_storage,
                 };
 
+                if sandbox_policy.is_some()
+                    && let Some(msg) = sess.update_network_proxy(&new_sandbox_policy)
+                {
+                    sess.send_event(Event {
+                        id: sub.id.clone(),
+                        msg,
+                    })
+                    .await;
+                }
+
                 // Install the new persistent context for subsequent tasks/turns.
+                sess.set_snapshot_turn_context(&new_turn_context);
                 turn_context = Arc::new(new_turn_context);
                 if cwd.is_some() || approval_policy.is_some() || sandbox_policy.is_some() {
                     sess.record_conversation_items(&[ResponseItem::from(EnvironmentContext::new(
                         new_cwd,
                         new_approval_policy,
                         new_sandbox_policy,
+                        sess.user_shell.name(),
                     ))])
                     .await;
                 }
             }
             Op::UserInput { items } => {
+                let items = attach_mentioned_resources(&sess, &sub.id, items).await;
                 // attempt to inject input into current task
                 if let Err(items) = sess.inject_input(items) {
                     // no current task, spawn a new one
@@ -1092,6 +1534,7 @@ async fn submission_loop(
                 effort,
                 summary,
             } => {
+                let items = attach_mentioned_resources(&sess, &sub.id, items).await;
                 // attempt to inject input into current task
                 if let Err(items) = sess.inject_input(items) {
                     // Derive a fresh TurnContext for this turn using the provided overrides.
@@ -1125,6 +1568,7 @@ async fn submission_loop(
                             sandbox_policy.clone(),
                             config.include_plan_tool,
                             config.include_apply_patch_tool,
+                            config.use_experimental_streamable_shell_tool,
                         ),
                         user_instructions: turn_context.user_instructions.clone(),
                         base_instructions: turn_context.base_instructions.clone(),
@@ -1134,6 +1578,7 @@ async fn submission_loop(
                         cwd,
                         disable_response_storage: turn_context.disable_response_storage,
                     };
+                    sess.set_snapshot_turn_context(&fresh_turn_context);
                     // TODO: record the new environment context in the conversation history
                     // no current task, spawn a new one with the per‑turn context
                     let task =
@@ -1153,6 +1598,12 @@ async fn submission_loop(
                 }
                 other => sess.notify_approval(&id, other),
             },
+            Op::BudgetApproval { id, decision } => match decision {
+                ReviewDecision::Abort => {
+                    sess.interrupt_task();
+                }
+                other => sess.notify_approval(&id, other),
+            },
             Op::AddToHistory { text } => {
                 let id = sess.session_id;
                 let config = config.clone();
@@ -1205,36 +1656,109 @@ async fn submission_loop(
 
                 // This is a cheap lookup from the connection manager's cache.
                 let tools = sess.mcp_connection_manager.list_all_tools();
+                let servers = sess.mcp_connection_manager.server_status();
                 let event = Event {
                     id: sub_id,
                     msg: EventMsg::McpListToolsResponse(
-                        crate::protocol::McpListToolsResponseEvent { tools },
+                        crate::protocol::McpListToolsResponseEvent { tools, servers },
                     ),
                 };
                 if let Err(e) = tx_event.send(event).await {
                     warn!("failed to send McpListToolsResponse event: {e}");
                 }
             }
+            Op::ListMcpResources => {
+                let event = Event {
+                    id: sub.id.clone(),
+                    msg: EventMsg::McpListResourcesResponse(
+                        crate::protocol::McpListResourcesResponseEvent {
+                            resources: sess.mcp_connection_manager.list_all_resources(),
+                        },
+                    ),
+                };
+                sess.send_event(event).await;
+            }
+            Op::ListMcpPrompts => {
+                let event = Event {
+                    id: sub.id.clone(),
+                    msg: EventMsg::McpListPromptsResponse(
+                        crate::protocol::McpListPromptsResponseEvent {
+                            prompts: sess.mcp_connection_manager.list_all_prompts(),
+                        },
+                    ),
+                };
+                sess.send_event(event).await;
+            }
+            Op::RunMcpPrompt {
+                server,
+                prompt,
+                arguments,
+            } => {
+                let turn = sess
+                    .mcp_connection_manager
+                    .get_prompt(&server, &prompt, arguments, Some(GET_MCP_PROMPT_TIMEOUT))
+                    .await
+                    .map_err(|e| format!("{e:#}"))
+                    .and_then(prompt_to_turn)
+                    .and_then(|turn| {
+                        // Earlier messages cannot be slipped into a running
+                        // task's history.
+                        if !turn.history.is_empty()
+                            && sess.state.lock_unchecked().current_task.is_some()
+                        {
+                            Err("it has assistant messages and a task is running".to_string())
+                        } else {
+                            Ok(turn)
+                        }
+                    });
+                let PromptTurn { history, input } = match turn {
+                    Ok(turn) => turn,
+                    Err(e) => {
+                        let event = Event {
+                            id: sub.id.clone(),
+                            msg: EventMsg::Error(ErrorEvent {
+                                message: format!("failed to get prompt {server}/{prompt}: {e}"),
+                            }),
+                        };
+                        sess.send_event(event).await;
+                        continue;
+                    }
+                };
+                if !history.is_empty() {
+                    sess.record_conversation_items(&history).await;
+                }
+                if let Err(items) = sess.inject_input(input) {
+                    let task =
+                        AgentTask::spawn(sess.clone(), Arc::clone(&turn_context), sub.id, items);
+                    sess.set_task(task);
+                }
+            }
             Op::Compact => {
-                // Create a summarization request as user input
-                const SUMMARIZATION_PROMPT: &str = include_str!("prompt_for_compact_command.md");
-
                 // Attempt to inject input into current task
                 if let Err(items) = sess.inject_input(vec![InputItem::Text {
-                    text: "Start Summarization".to_string(),
+                    text: compact::SUMMARIZE_TRIGGER.to_string(),
                 }]) {
                     let task = AgentTask::compact(
                         sess.clone(),
                         Arc::clone(&turn_context),
                         sub.id,
                         items,
-                        SUMMARIZATION_PROMPT.to_string(),
+                        compact::SUMMARIZATION_PROMPT.to_string(),
                     );
                     sess.set_task(task);
                 }
             }
+            Op::UndoTurn => {
+                handle_restore_checkpoint(&sess, &sub.id, None).await;
+            }
+            Op::RestoreCheckpoint { checkpoint_id } => {
+                handle_restore_checkpoint(&sess, &sub.id, Some(checkpoint_id)).await;
+            }
             Op::Shutdown => {
                 info!("Shutting down Codex instance");
+                sess.exec_sessions.shutdown();
+                sess.background_jobs.shutdown();
+                sess.exec_output.remove_all().await;
 
                 // Gracefully flush and shutdown rollout recorder on session end so tests
                 // that inspect the rollout file do not race with the background writer.
@@ -1301,16 +1825,30 @@ async fn run_task(
         return;
     }
 
+    sess.checkpoints().begin_task();
+
+    // Compact before recording the new input so that the summary only covers
+    // the earlier turns.
+    maybe_auto_compact(&sess, turn_context, &sub_id, None).await;
+
     let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
-    sess.record_conversation_items(&[initial_input_for_turn.clone().into()])
+    let task_input = ResponseItem::from(initial_input_for_turn);
+    sess.record_conversation_items(std::slice::from_ref(&task_input))
         .await;
 
     let mut last_agent_message: Option<String> = None;
     // Although from the perspective of codex.rs, TurnDiffTracker has the lifecycle of a Task which contains
     // many turns, from the perspective of the user, it is a single turn.
     let mut turn_diff_tracker = TurnDiffTracker::new();
+    let mut budget_approved_for_task = false;
 
     loop {
+        if !enforce_session_budget(&sess, turn_context, &sub_id, &mut budget_approved_for_task)
+            .await
+        {
+            break;
+        }
+
         // Note that pending_input would be something like a message the user
         // submitted through the UI while the model was running. Though the UI
         // may support this, the model might not.
@@ -1453,9 +1991,17 @@ async fn run_task(
                     });
                     break;
                 }
+
+                // The model is not done yet; make room for the next request
+                // if the context window is nearly full.
+                maybe_auto_compact(&sess, turn_context, &sub_id, Some(task_input.clone())).await;
             }
             Err(e) => {
                 info!("Turn error: {e:#}");
+                sess.maybe_notify(UserNotification::TurnFailed {
+                    turn_id: sub_id.clone(),
+                    error: e.to_string(),
+                });
                 let event = Event {
                     id: sub_id.clone(),
                     msg: EventMsg::Error(ErrorEvent {
@@ -1468,10 +2014,14 @@ async fn run_task(
             }
         }
     }
+    finish_checkpoint(&sess, &sub_id).await;
     sess.remove_task(&sub_id);
     let event = Event {
         id: sub_id,
-        msg: EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }),
+        msg: EventMsg::TaskComplete(TaskCompleteEvent {
+            last_agent_message,
+            plan_progress: sess.plan_progress(),
+        }),
     };
     sess.tx_event.send(event).await.ok();
 }
@@ -1486,6 +2036,7 @@ async fn run_turn(
     let tools = get_openai_tools(
         &turn_context.tools_config,
         Some(sess.mcp_connection_manager.list_all_tools()),
+        &sess.mcp_connection_manager.list_all_resources(),
     );
 
     let prompt = Prompt {
@@ -1654,6 +2205,9 @@ async fn try_run_turn(
                 token_usage,
             } => {
                 if let Some(token_usage) = token_usage {
+                    sess.set_last_token_usage(&token_usage).await;
+                    sess.budget
+                        .record(&turn_context.client.get_model(), &token_usage);
                     sess.tx_event
                         .send(Event {
                             id: sub_id.to_string(),
@@ -1731,32 +2285,111 @@ async fn run_compact_task(
         return;
     }
 
+    match compact_history(
+        &sess,
+        turn_context,
+        &sub_id,
+        input,
+        &compact_instructions,
+        None,
+    )
+    .await
+    {
+        Ok(()) => {}
+        Err(CodexErr::Interrupted) => return,
+        Err(e) => {
+            let event = Event {
+                id: sub_id.clone(),
+                msg: EventMsg::Error(ErrorEvent {
+                    message: e.to_string(),
+                }),
+            };
+            sess.send_event(event).await;
+            return;
+        }
+    }
+
+    sess.remove_task(&sub_id);
+    sess.notify_background_event(&sub_id, "compacted the conversation")
+        .await;
+    let event = Event {
+        id: sub_id.clone(),
+        msg: EventMsg::TaskComplete(TaskCompleteEvent {
+            last_agent_message: None,
+            plan_progress: sess.plan_progress(),
+        }),
+    };
+    sess.send_event(event).await;
+}
+
+/// Compacts the history if the last request used at least the model's
+/// auto-compact token limit. `task_input` is the message that started the
+/// running task, if the task is already under way.
+async fn maybe_auto_compact(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    task_input: Option<ResponseItem>,
+) {
+    let limit = turn_context.client.get_auto_compact_token_limit();
+    let Some(used) = sess.tokens_over_limit(limit) else {
+        return;
+    };
+
+    let input = vec![InputItem::Text {
+        text: compact::SUMMARIZE_TRIGGER.to_string(),
+    }];
+    let message = match compact_history(
+        sess,
+        turn_context,
+        sub_id,
+        input,
+        compact::SUMMARIZATION_PROMPT,
+        task_input,
+    )
+    .await
+    {
+        Ok(()) => format!("context window nearly full ({used} tokens); compacted the conversation"),
+        Err(e) => format!("failed to compact the conversation: {e}"),
+    };
+    sess.notify_background_event(sub_id, message).await;
+}
+
+/// Asks the model to summarize the conversation so far, then replaces the
+/// history with the user instructions and environment context, `task_input`
+/// and the summary (which also lists the current plan).
+async fn compact_history(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    input: Vec<InputItem>,
+    compact_instructions: &str,
+    task_input: Option<ResponseItem>,
+) -> CodexResult<()> {
     let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
     let turn_input: Vec<ResponseItem> =
-        sess.turn_input_with_history(vec![initial_input_for_turn.clone().into()]);
+        sess.turn_input_with_history(vec![initial_input_for_turn.into()]);
 
     let prompt = Prompt {
         input: turn_input,
         store: !turn_context.disable_response_storage,
         tools: Vec::new(),
-        base_instructions_override: Some(compact_instructions.clone()),
+        base_instructions_override: Some(compact_instructions.to_string()),
     };
 
     let max_retries = turn_context.client.get_provider().stream_max_retries();
     let mut retries = 0;
 
-    loop {
-        let attempt_result = drain_to_completed(&sess, turn_context, &sub_id, &prompt).await;
-
-        match attempt_result {
-            Ok(()) => break,
-            Err(CodexErr::Interrupted) => return,
+    let output = loop {
+        match drain_to_completed(sess, turn_context, sub_id, &prompt).await {
+            Ok(output) => break output,
+            Err(CodexErr::Interrupted) => return Err(CodexErr::Interrupted),
             Err(e) => {
                 if retries < max_retries {
                     retries += 1;
                     let delay = backoff(retries);
                     sess.notify_background_event(
-                        &sub_id,
+                        sub_id,
                         format!(
                             "stream error: {e}; retrying {retries}/{max_retries} in {delay:?}…"
                         ),
@@ -1765,37 +2398,39 @@ async fn run_compact_task(
                     tokio::time::sleep(delay).await;
                     continue;
                 } else {
-                    let event = Event {
-                        id: sub_id.clone(),
-                        msg: EventMsg::Error(ErrorEvent {
-                            message: e.to_string(),
-                        }),
-                    };
-                    sess.send_event(event).await;
-                    return;
+                    return Err(e);
                 }
             }
         }
-    }
+    };
 
-    sess.remove_task(&sub_id);
-    let event = Event {
-        id: sub_id.clone(),
-        msg: EventMsg::AgentMessage(AgentMessageEvent {
-            message: "Compact task completed".to_string(),
-        }),
+    let Some(summary) = get_last_assistant_message_from_turn(&output) else {
+        return Err(CodexErr::Stream(
+            "summary turn completed without a message".into(),
+            None,
+        ));
     };
-    sess.send_event(event).await;
-    let event = Event {
-        id: sub_id.clone(),
-        msg: EventMsg::TaskComplete(TaskCompleteEvent {
-            last_agent_message: None,
-        }),
+    let compacted = {
+        let mut state = sess.state.lock_unchecked();
+        let mut kept: Vec<ResponseItem> = task_input.into_iter().collect();
+        kept.push(compact::summary_message(
+            &summary,
+            state.snapshot.plan.as_ref(),
+        ));
+        state.history.compact(kept);
+        state.snapshot.last_token_usage = None;
+        state.history.contents()
     };
-    sess.send_event(event).await;
 
-    let mut state = sess.state.lock_unchecked();
-    state.history.keep_last_messages(1);
+    // Record the compacted history so resuming or forking the session does
+    // not replay the transcript it replaced.
+    let recorder = sess.rollout.lock_unchecked().as_ref().cloned();
+    if let Some(rec) = recorder
+        && let Err(e) = rec.record_compaction(&compacted).await
+    {
+        error!("failed to record rollout compaction: {e:#}");
+    }
+    Ok(())
 }
 
 async fn handle_response_item(
@@ -1935,6 +2570,13 @@ async fn handle_function_call(
     arguments: String,
     call_id: String,
 ) -> ResponseInputItem {
+    if matches!(
+        name.as_str(),
+        EXEC_COMMAND_TOOL_NAME | WRITE_STDIN_TOOL_NAME | START_BACKGROUND_JOB_TOOL_NAME
+    ) || sess.mcp_connection_manager.parse_tool_name(&name).is_some()
+    {
+        ensure_checkpoint(sess, &turn_context.cwd).await;
+    }
     match name.as_str() {
         "container.exec" | "shell" => {
             let params = match parse_container_exec_arguments(arguments, turn_context, &call_id) {
@@ -1985,13 +2627,53 @@ async fn handle_function_call(
             .await
         }
         "update_plan" => handle_update_plan(sess, arguments, sub_id, call_id).await,
-        _ => {
+        "get_plan" => handle_get_plan(sess, call_id),
+        EXEC_COMMAND_TOOL_NAME => {
+            handle_exec_command(
+                sess,
+                turn_context,
+                turn_diff_tracker,
+                sub_id,
+                call_id,
+                arguments,
+            )
+            .await
+        }
+        WRITE_STDIN_TOOL_NAME => {
+            handle_write_stdin(
+                sess,
+                turn_context,
+                turn_diff_tracker,
+                sub_id,
+                call_id,
+                arguments,
+            )
+            .await
+        }
+        KILL_SESSION_TOOL_NAME => handle_kill_session(sess, call_id, arguments),
+        START_BACKGROUND_JOB_TOOL_NAME => {
+            handle_start_background_job(sess, turn_context, sub_id, call_id, arguments).await
+        }
+        READ_BACKGROUND_JOB_TOOL_NAME => handle_read_background_job(sess, call_id, arguments).await,
+        KILL_BACKGROUND_JOB_TOOL_NAME => handle_kill_background_job(sess, call_id, arguments),
+        READ_EXEC_OUTPUT_TOOL_NAME => handle_read_exec_output(sess, call_id, arguments).await,
+        READ_FILE_TOOL_NAME | GREP_TOOL_NAME | LIST_DIR_TOOL_NAME => {
+            handle_file_tool_call(sess, turn_context, &sub_id, call_id, &name, arguments).await
+        }
+        READ_MCP_RESOURCE_TOOL_NAME => {
+            handle_read_mcp_resource(sess, &sub_id, call_id, arguments).await
+        }
+        _ => {
             match sess.mcp_connection_manager.parse_tool_name(&name) {
                 Some((server, tool_name)) => {
-                    // TODO(mbolin): Determine appropriate timeout for tool call.
-                    let timeout = None;
                     handle_mcp_tool_call(
-                        sess, &sub_id, call_id, server, tool_name, arguments, timeout,
+                        sess,
+                        turn_context,
+                        &sub_id,
+                        call_id,
+                        server,
+                        tool_name,
+                        arguments,
                     )
                     .await
                 }
@@ -2075,6 +2757,9 @@ async fn handle_container_exec_with_params(
     sub_id: String,
     call_id: String,
 ) -> ResponseInputItem {
+    if !is_known_safe_command(&params.command) {
+        ensure_checkpoint(sess, &turn_context.cwd).await;
+    }
     // check if this was a patch, and apply it if so
     let apply_patch_exec = match maybe_parse_apply_patch_verified(&params.command, &params.cwd) {
         MaybeApplyPatchVerified::Body(changes) => {
@@ -2160,6 +2845,8 @@ async fn handle_container_exec_with_params(
                     &turn_context.sandbox_policy,
                     &state.approved_commands,
                     params.with_escalated_permissions.unwrap_or(false),
+                    sess.exec_policy.as_ref(),
+                    &params.cwd,
                 )
             };
             let command_for_display = params.command.clone();
@@ -2167,48 +2854,9 @@ async fn handle_container_exec_with_params(
         }
     };
 
-    let sandbox_type = match safety {
-        SafetyCheck::AutoApprove { sandbox_type } => sandbox_type,
-        SafetyCheck::AskUser => {
-            let rx_approve = sess
-                .request_command_approval(
-                    sub_id.clone(),
-                    call_id.clone(),
-                    params.command.clone(),
-                    params.cwd.clone(),
-                    params.justification.clone(),
-                )
-                .await;
-            match rx_approve.await.unwrap_or_default() {
-                ReviewDecision::Approved => (),
-                ReviewDecision::ApprovedForSession => {
-                    sess.add_approved_command(params.command.clone());
-                }
-                ReviewDecision::Denied | ReviewDecision::Abort => {
-                    return ResponseInputItem::FunctionCallOutput {
-                        call_id,
-                        output: FunctionCallOutputPayload {
-                            content: "exec command rejected by user".to_string(),
-                            success: None,
-                        },
-                    };
-                }
-            }
-            // No sandboxing is applied because the user has given
-            // explicit approval. Often, we end up in this case because
-            // the command cannot be run in a sandbox, such as
-            // installing a new dependency that requires network access.
-            SandboxType::None
-        }
-        SafetyCheck::Reject { reason } => {
-            return ResponseInputItem::FunctionCallOutput {
-                call_id,
-                output: FunctionCallOutputPayload {
-                    content: format!("exec command rejected: {reason}"),
-                    success: None,
-                },
-            };
-        }
+    let sandbox_type = match sandbox_type_for_exec(sess, safety, &params, &sub_id, &call_id).await {
+        Ok(sandbox_type) => sandbox_type,
+        Err(output) => return *output,
     };
 
     let exec_command_context = ExecCommandContext {
@@ -2227,7 +2875,7 @@ async fn handle_container_exec_with_params(
         ),
     };
 
-    let params = maybe_run_with_user_profile(params, sess, turn_context);
+    let params = finalize_exec_params(params, sess, turn_context);
     let output_result = sess
         .run_exec_with_events(
             turn_diff_tracker,
@@ -2251,7 +2899,7 @@ async fn handle_container_exec_with_params(
             let ExecToolCallOutput { exit_code, .. } = &output;
 
             let is_success = *exit_code == 0;
-            let content = format_exec_output(output);
+            let content = format_exec_output(output, &sess.exec_output, &call_id);
             ResponseInputItem::FunctionCallOutput {
                 call_id: call_id.clone(),
                 output: FunctionCallOutputPayload {
@@ -2282,6 +2930,530 @@ async fn handle_container_exec_with_params(
     }
 }
 
+/// Wraps `params` in the user's shell profile and points it at the network
+/// proxy, if any. Applied after approval so that the user is asked about the
+/// command the model wrote.
+fn finalize_exec_params(
+    params: ExecParams,
+    sess: &Session,
+    turn_context: &TurnContext,
+) -> ExecParams {
+    let mut params = maybe_run_with_user_profile(params, sess, turn_context);
+    // The sandbox lets commands connect to the socket named here, so never
+    // pass on a value inherited from the environment.
+    params.env.remove(CODEX_NETWORK_PROXY_SOCKET_ENV_VAR);
+    if let Some(proxy) = sess.network_proxy.lock_unchecked().as_ref() {
+        params.env.insert(
+            CODEX_NETWORK_PROXY_SOCKET_ENV_VAR.to_string(),
+            proxy.socket_path().to_string_lossy().into_owned(),
+        );
+    }
+    params
+}
+
+/// Resolves `safety` into the sandbox to run `params` in, asking the user for
+/// approval if needed. Returns the output to send to the model instead if the
+/// command must not run.
+async fn sandbox_type_for_exec(
+    sess: &Session,
+    safety: SafetyCheck,
+    params: &ExecParams,
+    sub_id: &str,
+    call_id: &str,
+) -> Result<SandboxType, Box<ResponseInputItem>> {
+    match safety {
+        SafetyCheck::AutoApprove { sandbox_type } => Ok(sandbox_type),
+        SafetyCheck::AskUser => {
+            let rx_approve = sess
+                .request_command_approval(
+                    sub_id.to_string(),
+                    call_id.to_string(),
+                    params.command.clone(),
+                    params.cwd.clone(),
+                    params.justification.clone(),
+                )
+                .await;
+            match rx_approve.await.unwrap_or_default() {
+                ReviewDecision::Approved => (),
+                ReviewDecision::ApprovedForSession => {
+                    sess.add_approved_command(params.command.clone());
+                }
+                ReviewDecision::Denied | ReviewDecision::Abort => {
+                    return Err(Box::new(ResponseInputItem::FunctionCallOutput {
+                        call_id: call_id.to_string(),
+                        output: FunctionCallOutputPayload {
+                            content: "exec command rejected by user".to_string(),
+                            success: None,
+                        },
+                    }));
+                }
+            }
+            // No sandboxing is applied because the user has given
+            // explicit approval. Often, we end up in this case because
+            // the command cannot be run in a sandbox, such as
+            // installing a new dependency that requires network access.
+            Ok(SandboxType::None)
+        }
+        SafetyCheck::Reject { reason } => Err(Box::new(ResponseInputItem::FunctionCallOutput {
+            call_id: call_id.to_string(),
+            output: FunctionCallOutputPayload {
+                content: format!("exec command rejected: {reason}"),
+                success: None,
+            },
+        })),
+    }
+}
+
+async fn handle_exec_command(
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: String,
+    call_id: String,
+    arguments: String,
+) -> ResponseInputItem {
+    let args = match parse_tool_arguments::<ExecCommandParams>(&arguments, &call_id) {
+        Ok(args) => args,
+        Err(output) => return *output,
+    };
+    // Commands are written for bash, as with the `shell` tool.
+    let command = vec!["bash".to_string(), "-lc".to_string(), args.cmd];
+    let params = ExecParams {
+        command: command.clone(),
+        cwd: turn_context.resolve_path(args.workdir),
+        timeout_ms: None,
+        env: create_env(&turn_context.shell_environment_policy),
+        with_escalated_permissions: None,
+        justification: None,
+    };
+    let safety = {
+        let state = sess.state.lock_unchecked();
+        assess_command_safety(
+            &params.command,
+            turn_context.approval_policy,
+            &turn_context.sandbox_policy,
+            &state.approved_commands,
+            false,
+            sess.exec_policy.as_ref(),
+            &params.cwd,
+        )
+    };
+    let sandbox_type = match sandbox_type_for_exec(sess, safety, &params, &sub_id, &call_id).await {
+        Ok(sandbox_type) => sandbox_type,
+        Err(output) => return *output,
+    };
+
+    let exec_command_context = ExecCommandContext {
+        sub_id: sub_id.clone(),
+        call_id: call_id.clone(),
+        command_for_display: command,
+        cwd: params.cwd.clone(),
+        apply_patch: None,
+    };
+    let params = finalize_exec_params(params, sess, turn_context);
+
+    sess.on_exec_command_begin(turn_diff_tracker, exec_command_context)
+        .await;
+    let result = sess
+        .exec_sessions
+        .exec_command(
+            params,
+            sandbox_type,
+            &turn_context.sandbox_policy,
+            &sess.codex_linux_sandbox_exe,
+            exec_session::yield_time(args.yield_time_ms),
+            Some(StdoutStream {
+                sub_id: sub_id.clone(),
+                call_id: call_id.clone(),
+                tx_event: sess.tx_event.clone(),
+            }),
+        )
+        .await;
+    exec_session_call_output(
+        sess,
+        turn_diff_tracker,
+        &sub_id,
+        call_id,
+        result,
+        args.max_output_tokens,
+    )
+    .await
+}
+
+async fn handle_write_stdin(
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: String,
+    call_id: String,
+    arguments: String,
+) -> ResponseInputItem {
+    let args = match parse_tool_arguments::<WriteStdinParams>(&arguments, &call_id) {
+        Ok(args) => args,
+        Err(output) => return *output,
+    };
+    let command_for_display = vec![
+        WRITE_STDIN_TOOL_NAME.to_string(),
+        args.session_id.to_string(),
+        args.chars.clone(),
+    ];
+    if !args.chars.is_empty()
+        && let Some(session_sandbox_type) = sess.exec_sessions.sandbox_type(args.session_id)
+    {
+        let safety = assess_write_stdin_safety(
+            session_sandbox_type,
+            turn_context.approval_policy,
+            &turn_context.sandbox_policy,
+        );
+        let params = ExecParams {
+            command: command_for_display.clone(),
+            cwd: turn_context.cwd.clone(),
+            timeout_ms: None,
+            env: HashMap::new(),
+            with_escalated_permissions: None,
+            justification: Some(format!(
+                "Session {} runs outside the sandbox.",
+                args.session_id
+            )),
+        };
+        if let Err(output) = sandbox_type_for_exec(sess, safety, &params, &sub_id, &call_id).await {
+            return *output;
+        }
+    }
+    let exec_command_context = ExecCommandContext {
+        sub_id: sub_id.clone(),
+        call_id: call_id.clone(),
+        command_for_display,
+        cwd: turn_context.cwd.clone(),
+        apply_patch: None,
+    };
+    sess.on_exec_command_begin(turn_diff_tracker, exec_command_context)
+        .await;
+    let result = sess
+        .exec_sessions
+        .write_stdin(
+            args.session_id,
+            &args.chars,
+            exec_session::yield_time(args.yield_time_ms),
+            Some(StdoutStream {
+                sub_id: sub_id.clone(),
+                call_id: call_id.clone(),
+                tx_event: sess.tx_event.clone(),
+            }),
+        )
+        .await;
+    exec_session_call_output(
+        sess,
+        turn_diff_tracker,
+        &sub_id,
+        call_id,
+        result,
+        args.max_output_tokens,
+    )
+    .await
+}
+
+/// Reports the end of an `exec_command` or `write_stdin` call to the UI and
+/// builds the output for the model.
+async fn exec_session_call_output(
+    sess: &Session,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: &str,
+    call_id: String,
+    result: CodexResult<ExecSessionOutput>,
+    max_output_tokens: Option<u64>,
+) -> ResponseInputItem {
+    let output = match result {
+        Ok(output) => output,
+        Err(e) => {
+            let end = ExecToolCallOutput {
+                exit_code: -1,
+                stdout: StreamOutput::new(String::new()),
+                stderr: StreamOutput::new(get_error_message_ui(&e)),
+                duration: Duration::default(),
+            };
+            sess.on_exec_command_end(turn_diff_tracker, sub_id, &call_id, &end, false, None)
+                .await;
+            return ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content: format!("execution error: {e}"),
+                    success: None,
+                },
+            };
+        }
+    };
+
+    // A process that is still running has not failed (yet).
+    let exit_code = output.exit_code.unwrap_or(0);
+    let end = ExecToolCallOutput {
+        exit_code,
+        stdout: StreamOutput::new(output.output.clone()),
+        stderr: StreamOutput::new(String::new()),
+        duration: output.wall_time,
+    };
+    sess.on_exec_command_end(turn_diff_tracker, sub_id, &call_id, &end, false, None)
+        .await;
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content: output.format_for_model(max_output_tokens),
+            success: Some(exit_code == 0),
+        },
+    }
+}
+
+fn handle_kill_session(sess: &Session, call_id: String, arguments: String) -> ResponseInputItem {
+    let args = match parse_tool_arguments::<KillSessionParams>(&arguments, &call_id) {
+        Ok(args) => args,
+        Err(output) => return *output,
+    };
+    let (content, success) = if sess.exec_sessions.kill(args.session_id) {
+        (format!("Killed session {}", args.session_id), true)
+    } else {
+        (
+            format!("No running session with ID {}", args.session_id),
+            false,
+        )
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(success),
+        },
+    }
+}
+
+async fn handle_start_background_job(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: String,
+    call_id: String,
+    arguments: String,
+) -> ResponseInputItem {
+    let args = match parse_tool_arguments::<StartBackgroundJobParams>(&arguments, &call_id) {
+        Ok(args) => args,
+        Err(output) => return *output,
+    };
+    let params = ExecParams {
+        command: args.command,
+        cwd: turn_context.resolve_path(args.workdir),
+        timeout_ms: None,
+        env: create_env(&turn_context.shell_environment_policy),
+        with_escalated_permissions: None,
+        justification: None,
+    };
+    let safety = {
+        let state = sess.state.lock_unchecked();
+        assess_command_safety(
+            &params.command,
+            turn_context.approval_policy,
+            &turn_context.sandbox_policy,
+            &state.approved_commands,
+            false,
+            sess.exec_policy.as_ref(),
+            &params.cwd,
+        )
+    };
+    let sandbox_type = match sandbox_type_for_exec(sess, safety, &params, &sub_id, &call_id).await {
+        Ok(sandbox_type) => sandbox_type,
+        Err(output) => return *output,
+    };
+
+    let params = finalize_exec_params(params, sess, turn_context);
+    let result = sess
+        .background_jobs
+        .start(
+            params,
+            sandbox_type,
+            &turn_context.sandbox_policy,
+            &sess.codex_linux_sandbox_exe,
+            &sub_id,
+            &call_id,
+        )
+        .await;
+    let (content, success) = match result {
+        Ok(job_id) => (format!("Started background job {job_id}"), Some(true)),
+        Err(e) => (format!("execution error: {e}"), None),
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload { content, success },
+    }
+}
+
+async fn handle_read_background_job(
+    sess: &Session,
+    call_id: String,
+    arguments: String,
+) -> ResponseInputItem {
+    let args = match parse_tool_arguments::<ReadBackgroundJobParams>(&arguments, &call_id) {
+        Ok(args) => args,
+        Err(output) => return *output,
+    };
+    let wait = background_jobs::wait_time(args.wait_ms);
+    let (content, success) = match sess.background_jobs.read(args.job_id, wait).await {
+        Some(output) => (
+            output.format_for_model(),
+            output.exit_code.is_none_or(|code| code == 0),
+        ),
+        None => (format!("No background job with ID {}", args.job_id), false),
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(success),
+        },
+    }
+}
+
+fn handle_kill_background_job(
+    sess: &Session,
+    call_id: String,
+    arguments: String,
+) -> ResponseInputItem {
+    let args = match parse_tool_arguments::<KillBackgroundJobParams>(&arguments, &call_id) {
+        Ok(args) => args,
+        Err(output) => return *output,
+    };
+    let (content, success) = if sess.background_jobs.kill(args.job_id) {
+        (format!("Killed background job {}", args.job_id), true)
+    } else {
+        (
+            format!("No running background job with ID {}", args.job_id),
+            false,
+        )
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(success),
+        },
+    }
+}
+
+async fn handle_read_exec_output(
+    sess: &Session,
+    call_id: String,
+    arguments: String,
+) -> ResponseInputItem {
+    let args = match parse_tool_arguments::<ReadExecOutputParams>(&arguments, &call_id) {
+        Ok(args) => args,
+        Err(output) => return *output,
+    };
+    let result = sess
+        .exec_output
+        .read(
+            &args.output_id,
+            args.start_line.unwrap_or(1),
+            args.max_lines,
+        )
+        .await;
+    let (content, success) = match result {
+        Ok(page) => (page, true),
+        Err(e) => (
+            format!("failed to read output `{}`: {e}", args.output_id),
+            false,
+        ),
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(success),
+        },
+    }
+}
+
+/// Runs one of the built-in file tools. They only read, within the sandbox's
+/// readable roots, so they run without approval.
+async fn handle_file_tool_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    call_id: String,
+    tool_name: &str,
+    arguments: String,
+) -> ResponseInputItem {
+    let invocation = match parse_file_tool_call(tool_name, &arguments, &turn_context.cwd) {
+        Ok(invocation) => invocation,
+        Err(e) => {
+            // allow model to re-sample
+            return ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content: format!("failed to parse function arguments: {e}"),
+                    success: None,
+                },
+            };
+        }
+    };
+
+    sess.send_event(Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::FileToolCallBegin(FileToolCallBeginEvent {
+            call_id: call_id.clone(),
+            invocation: invocation.clone(),
+        }),
+    })
+    .await;
+
+    let start = Instant::now();
+    let result = {
+        let invocation = invocation.clone();
+        let sandbox_policy = turn_context.sandbox_policy.clone();
+        let cwd = turn_context.cwd.clone();
+        tokio::task::spawn_blocking(move || run_file_tool(&invocation, &sandbox_policy, &cwd))
+            .await
+            .unwrap_or_else(|e| Err(format!("file tool failed: {e}")))
+    };
+
+    sess.send_event(Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::FileToolCallEnd(FileToolCallEndEvent {
+            call_id: call_id.clone(),
+            invocation,
+            duration: start.elapsed(),
+            result: result
+                .as_ref()
+                .map(|output| output.summary.clone())
+                .map_err(Clone::clone),
+        }),
+    })
+    .await;
+
+    let (content, success) = match result {
+        Ok(output) => (output.content, true),
+        Err(e) => (e, false),
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(success),
+        },
+    }
+}
+
+fn parse_tool_arguments<T: DeserializeOwned>(
+    arguments: &str,
+    call_id: &str,
+) -> Result<T, Box<ResponseInputItem>> {
+    serde_json::from_str::<T>(arguments).map_err(|e| {
+        // allow model to re-sample
+        Box::new(ResponseInputItem::FunctionCallOutput {
+            call_id: call_id.to_string(),
+            output: FunctionCallOutputPayload {
+                content: format!("failed to parse function arguments: {e}"),
+                success: None,
+            },
+        })
+    })
+}
+
 async fn handle_sandbox_error(
     turn_diff_tracker: &mut TurnDiffTracker,
     params: ExecParams,
@@ -2384,7 +3556,7 @@ async fn handle_sandbox_error(
                     let ExecToolCallOutput { exit_code, .. } = &retry_output;
 
                     let is_success = *exit_code == 0;
-                    let content = format_exec_output(retry_output);
+                    let content = format_exec_output(retry_output, &sess.exec_output, &call_id);
 
                     ResponseInputItem::FunctionCallOutput {
                         call_id: call_id.clone(),
@@ -2416,14 +3588,26 @@ async fn handle_sandbox_error(
     }
 }
 
+/// The stream of `output` that is sent to the model.
+fn model_visible_output(output: &ExecToolCallOutput) -> &StreamOutput<String> {
+    if output.exit_code == 0 {
+        &output.stdout
+    } else {
+        &output.stderr
+    }
+}
+
 /// Exec output is a pre-serialized JSON payload
-fn format_exec_output(exec_output: ExecToolCallOutput) -> String {
+fn format_exec_output(
+    exec_output: ExecToolCallOutput,
+    store: &ExecOutputStore,
+    call_id: &str,
+) -> String {
     let ExecToolCallOutput {
         exit_code,
-        stdout,
-        stderr,
         duration,
-    } = exec_output;
+        ..
+    } = &exec_output;
 
     #[derive(Serialize)]
     struct ExecMetadata {
@@ -2440,20 +3624,12 @@ fn format_exec_output(exec_output: ExecToolCallOutput) -> String {
     // round to 1 decimal place
     let duration_seconds = ((duration.as_secs_f32()) * 10.0).round() / 10.0;
 
-    let is_success = exit_code == 0;
-    let output = if is_success { stdout } else { stderr };
-
-    let mut formatted_output = output.text;
-    if let Some(truncated_after_lines) = output.truncated_after_lines {
-        formatted_output.push_str(&format!(
-            "\n\n[Output truncated after {truncated_after_lines} lines: too many lines or bytes.]",
-        ));
-    }
+    let formatted_output = store.format_for_model(call_id, model_visible_output(&exec_output));
 
     let payload = ExecOutput {
         output: &formatted_output,
         metadata: ExecMetadata {
-            exit_code,
+            exit_code: *exit_code,
             duration_seconds,
         },
     };
@@ -2482,13 +3658,16 @@ fn get_last_assistant_message_from_turn(responses: &[ResponseItem]) -> Option<St
     })
 }
 
+/// Streams a response without handling tool calls and returns its output
+/// items. Nothing is recorded in the conversation history.
 async fn drain_to_completed(
     sess: &Session,
     turn_context: &TurnContext,
     sub_id: &str,
     prompt: &Prompt,
-) -> CodexResult<()> {
+) -> CodexResult<Vec<ResponseItem>> {
     let mut stream = turn_context.client.clone().stream(prompt).await?;
+    let mut output = Vec::new();
     loop {
         let maybe_event = stream.next().await;
         let Some(event) = maybe_event else {
@@ -2499,9 +3678,7 @@ async fn drain_to_completed(
         };
         match event {
             Ok(ResponseEvent::OutputItemDone(item)) => {
-                // Record only to in-memory conversation history; avoid state snapshot.
-                let mut state = sess.state.lock_unchecked();
-                state.history.record_items(std::slice::from_ref(&item));
+                output.push(item);
             }
             Ok(ResponseEvent::Completed {
                 response_id: _,
@@ -2516,6 +3693,8 @@ async fn drain_to_completed(
                         ));
                     }
                 };
+                sess.budget
+                    .record(&turn_context.client.get_model(), &token_usage);
                 sess.tx_event
                     .send(Event {
                         id: sub_id.to_string(),
@@ -2523,7 +3702,7 @@ async fn drain_to_completed(
                     })
                     .await
                     .ok();
-                return Ok(());
+                return Ok(output);
             }
             Ok(_) => continue,
             Err(e) => return Err(e),
@@ -1,18 +1,23 @@
 use crate::codex::Codex;
+use crate::config::Config;
 use crate::error::Result as CodexResult;
+use crate::models::ResponseItem;
 use crate::protocol::Event;
 use crate::protocol::Op;
 use crate::protocol::Submission;
+use crate::rollout::SessionStateSnapshot;
 
 pub struct CodexConversation {
     codex: Codex,
+    /// Config the conversation was created with; a fork starts from it.
+    config: Config,
 }
 
 /// Conduit for the bidirectional stream of messages that compose a conversation
 /// in Codex.
 impl CodexConversation {
-    pub(crate) fn new(codex: Codex) -> Self {
-        Self { codex }
+    pub(crate) fn new(codex: Codex, config: Config) -> Self {
+        Self { codex, config }
     }
 
     pub async fn submit(&self, op: Op) -> CodexResult<String> {
@@ -27,4 +32,12 @@ impl CodexConversation {
     pub async fn next_event(&self) -> CodexResult<Event> {
         self.codex.next_event().await
     }
+
+    pub(crate) fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub(crate) fn history_and_snapshot(&self) -> Option<(Vec<ResponseItem>, SessionStateSnapshot)> {
+        self.codex.history_and_snapshot()
+    }
 }
@@ -0,0 +1,101 @@
+//! Helpers for compacting the conversation history into a summary, either on
+//! request (`Op::Compact`) or automatically when the context window is
+//! nearly full.
+
+use crate::models::ContentItem;
+use crate::models::ResponseItem;
+use crate::plan_tool::StepStatus;
+use crate::plan_tool::UpdatePlanArgs;
+
+/// Instructions used in place of the base instructions for the summary turn.
+pub(crate) const SUMMARIZATION_PROMPT: &str = include_str!("prompt_for_compact_command.md");
+
+/// User message that asks the model for the summary.
+pub(crate) const SUMMARIZE_TRIGGER: &str = "Start Summarization";
+
+/// Share of the model's context window that may be used before the history
+/// is compacted, when `model_auto_compact_token_limit` is not set.
+const DEFAULT_AUTO_COMPACT_PERCENT: u64 = 90;
+
+/// Number of tokens at which the conversation is compacted automatically, or
+/// `None` if neither a limit nor the context window is known.
+pub(crate) fn auto_compact_token_limit(
+    configured_limit: Option<u64>,
+    context_window: Option<u64>,
+) -> Option<u64> {
+    configured_limit
+        .or_else(|| context_window.map(|window| window * DEFAULT_AUTO_COMPACT_PERCENT / 100))
+}
+
+/// Builds the history item that stands in for the compacted conversation.
+/// The current plan is appended verbatim so it survives even if the summary
+/// leaves it out.
+pub(crate) fn summary_message(summary: &str, plan: Option<&UpdatePlanArgs>) -> ResponseItem {
+    let mut text = format!(
+        "Summary of the conversation so far (earlier messages were compacted to save context):\n\n{}",
+        summary.trim()
+    );
+    if let Some(plan) = plan.filter(|plan| !plan.plan.is_empty()) {
+        text.push_str("\n\nCurrent plan:");
+        for item in &plan.plan {
+            let status = match item.status {
+                StepStatus::Pending => "pending",
+                StepStatus::InProgress => "in_progress",
+                StepStatus::Completed => "completed",
+            };
+            text.push_str(&format!("\n- [{status}] {}", item.step));
+        }
+    }
+    ResponseItem::Message {
+        id: None,
+        role: "assistant".to_string(),
+        content: vec![ContentItem::OutputText { text }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan_tool::PlanItemArg;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn limit_defaults_to_share_of_context_window() {
+        assert_eq!(Some(180_000), auto_compact_token_limit(None, Some(200_000)));
+        assert_eq!(
+            Some(50_000),
+            auto_compact_token_limit(Some(50_000), Some(200_000))
+        );
+        assert_eq!(None, auto_compact_token_limit(None, None));
+    }
+
+    #[test]
+    fn summary_message_includes_plan() {
+        let plan = UpdatePlanArgs {
+            explanation: None,
+            plan: vec![
+                PlanItemArg {
+                    step: "write tests".to_string(),
+                    status: StepStatus::Completed,
+                },
+                PlanItemArg {
+                    step: "fix bug".to_string(),
+                    status: StepStatus::InProgress,
+                },
+            ],
+        };
+
+        let ResponseItem::Message { role, content, .. } =
+            summary_message("did things\n", Some(&plan))
+        else {
+            panic!("expected a message");
+        };
+        assert_eq!("assistant", role);
+        assert_eq!(
+            vec![ContentItem::OutputText {
+                text: "Summary of the conversation so far (earlier messages were compacted to save context):\n\ndid things\n\nCurrent plan:\n- [completed] write tests\n- [in_progress] fix bug".to_string()
+            }],
+            content
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use crate::config_profile::ConfigProfile;
+use crate::config_types::ExecOutputConfig;
 use crate::config_types::History;
 use crate::config_types::McpServerConfig;
+use crate::config_types::McpServerSettings;
+use crate::config_types::NotificationsConfig;
 use crate::config_types::SandboxWorkspaceWrite;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyToml;
@@ -11,6 +14,7 @@ use crate::model_family::find_family_for_model;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::built_in_model_providers;
 use crate::openai_model_info::get_model_info;
+use crate::openai_model_pricing::ModelPricing;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
 use codex_login::AuthMode;
@@ -51,6 +55,10 @@ pub struct Config {
     /// Maximum number of output tokens.
     pub model_max_output_tokens: Option<u64>,
 
+    /// Token usage at which the conversation history is compacted
+    /// automatically. Defaults to 90% of `model_context_window`.
+    pub model_auto_compact_token_limit: Option<u64>,
+
     /// Key into the model_providers map that specifies which provider to use.
     pub model_provider_id: String,
 
@@ -64,6 +72,10 @@ pub struct Config {
 
     pub shell_environment_policy: ShellEnvironmentPolicy,
 
+    /// Projects marked `trust_level = "trusted"` under `[projects]`. Policy
+    /// files shipped with a project are only loaded inside these.
+    pub trusted_projects: Vec<PathBuf>,
+
     /// When `true`, `AgentReasoning` events emitted by the backend will be
     /// suppressed from the frontend output. This can reduce visual noise when
     /// users are only interested in the final agent responses.
@@ -134,6 +146,27 @@ pub struct Config {
     /// Collection of settings that are specific to the TUI.
     pub tui: Tui,
 
+    /// Settings that apply when Codex runs as an MCP server.
+    pub mcp_server: McpServerSettings,
+
+    /// Built-in notification sinks and which notifications they receive.
+    pub notifications: NotificationsConfig,
+
+    /// Limits on the `shell` output sent to the model.
+    pub exec_output: ExecOutputConfig,
+
+    /// Model prices (per million tokens) that override or extend the built-in
+    /// table used to estimate the cost of a session.
+    pub model_pricing: HashMap<String, ModelPricing>,
+
+    /// Pause the agent and ask for approval to continue once the session has
+    /// used this many tokens (non-cached input plus output).
+    pub max_session_tokens: Option<u64>,
+
+    /// Pause the agent and ask for approval to continue once the estimated
+    /// cost of the session reaches this many US dollars.
+    pub max_session_cost: Option<f64>,
+
     /// Path to the `codex-linux-sandbox` executable. This must be set if
     /// [`crate::exec::SandboxType::LinuxSeccomp`] is used. Note that this
     /// cannot be set in the config file: it must be set in code via
@@ -164,6 +197,11 @@ pub struct Config {
     /// model family's default preference.
     pub include_apply_patch_tool: bool,
 
+    /// Replace the `shell` tool with the experimental `exec_command`,
+    /// `write_stdin` and `kill_session` tools, which run commands in terminal
+    /// sessions that persist between calls.
+    pub use_experimental_streamable_shell_tool: bool,
+
     /// The value for the `originator` header included with Responses API requests.
     pub responses_originator_header: String,
 
@@ -335,6 +373,10 @@ pub struct ConfigToml {
     /// Maximum number of output tokens.
     pub model_max_output_tokens: Option<u64>,
 
+    /// Token usage at which the conversation history is compacted
+    /// automatically.
+    pub model_auto_compact_token_limit: Option<u64>,
+
     /// Default approval policy for executing commands.
     pub approval_policy: Option<AskForApproval>,
 
@@ -388,6 +430,25 @@ pub struct ConfigToml {
     /// Collection of settings that are specific to the TUI.
     pub tui: Option<Tui>,
 
+    /// Settings that apply when Codex runs as an MCP server.
+    pub mcp_server: Option<McpServerSettings>,
+
+    /// Built-in notification sinks.
+    pub notifications: Option<NotificationsConfig>,
+
+    /// Limits on the `shell` output sent to the model.
+    pub exec_output: Option<ExecOutputConfig>,
+
+    /// Per-model prices that override or extend the built-in table.
+    #[serde(default)]
+    pub model_pricing: HashMap<String, ModelPricing>,
+
+    /// Token budget for a single session.
+    pub max_session_tokens: Option<u64>,
+
+    /// Cost budget for a single session, in US dollars.
+    pub max_session_cost: Option<f64>,
+
     /// When set to `true`, `AgentReasoning` events will be hidden from the
     /// UI/output. Defaults to `false`.
     pub hide_agent_reasoning: Option<bool>,
@@ -411,6 +472,10 @@ pub struct ConfigToml {
     /// Experimental path to a file whose contents replace the built-in BASE_INSTRUCTIONS.
     pub experimental_instructions_file: Option<PathBuf>,
 
+    /// Experimental: use the `exec_command`/`write_stdin` tools, which keep
+    /// terminal sessions alive between calls, instead of `shell`.
+    pub experimental_use_exec_command_tool: Option<bool>,
+
     /// The value for the `originator` header included with Responses API requests.
     pub responses_originator_header_internal_override: Option<String>,
 
@@ -436,12 +501,16 @@ impl ConfigToml {
             SandboxMode::WorkspaceWrite => match self.sandbox_workspace_write.as_ref() {
                 Some(SandboxWorkspaceWrite {
                     writable_roots,
+                    readable_roots,
                     network_access,
+                    network_allowlist,
                     exclude_tmpdir_env_var,
                     exclude_slash_tmp,
                 }) => SandboxPolicy::WorkspaceWrite {
                     writable_roots: writable_roots.clone(),
+                    readable_roots: readable_roots.clone(),
                     network_access: *network_access,
+                    network_allowlist: network_allowlist.clone(),
                     exclude_tmpdir_env_var: *exclude_tmpdir_env_var,
                     exclude_slash_tmp: *exclude_slash_tmp,
                 },
@@ -563,6 +632,14 @@ impl Config {
 
         let shell_environment_policy = cfg.shell_environment_policy.into();
 
+        let trusted_projects = cfg
+            .projects
+            .iter()
+            .flatten()
+            .filter(|(_, project)| project.trust_level.as_deref() == Some("trusted"))
+            .map(|(path, _)| PathBuf::from(path))
+            .collect();
+
         let resolved_cwd = {
             use std::env;
 
@@ -636,6 +713,7 @@ impl Config {
             model_family,
             model_context_window,
             model_max_output_tokens,
+            model_auto_compact_token_limit: cfg.model_auto_compact_token_limit,
             model_provider_id,
             model_provider,
             cwd: resolved_cwd,
@@ -645,6 +723,7 @@ impl Config {
                 .unwrap_or_else(AskForApproval::default),
             sandbox_policy,
             shell_environment_policy,
+            trusted_projects,
             disable_response_storage: config_profile
                 .disable_response_storage
                 .or(cfg.disable_response_storage)
@@ -660,6 +739,12 @@ impl Config {
             history,
             file_opener: cfg.file_opener.unwrap_or(UriBasedFileOpener::VsCode),
             tui: cfg.tui.unwrap_or_default(),
+            mcp_server: cfg.mcp_server.unwrap_or_default(),
+            notifications: cfg.notifications.unwrap_or_default(),
+            exec_output: cfg.exec_output.unwrap_or_default(),
+            model_pricing: cfg.model_pricing,
+            max_session_tokens: cfg.max_session_tokens,
+            max_session_cost: cfg.max_session_cost,
             codex_linux_sandbox_exe,
 
             hide_agent_reasoning: cfg.hide_agent_reasoning.unwrap_or(false),
@@ -684,12 +769,21 @@ impl Config {
             experimental_resume,
             include_plan_tool: include_plan_tool.unwrap_or(false),
             include_apply_patch_tool: include_apply_patch_tool_val,
+            use_experimental_streamable_shell_tool: cfg
+                .experimental_use_exec_command_tool
+                .unwrap_or(false),
             responses_originator_header,
             preferred_auth_method: cfg.preferred_auth_method.unwrap_or(AuthMode::ChatGPT),
         };
         Ok(config)
     }
 
+    /// Whether `path` is, or is inside, a project the user has trusted.
+    pub fn is_project_trusted(&self, path: &Path) -> bool {
+        path.ancestors()
+            .any(|dir| self.trusted_projects.iter().any(|project| project == dir))
+    }
+
     fn load_instructions(codex_dir: Option<&Path>) -> Option<String> {
         let mut p = match codex_dir {
             Some(p) => p.to_path_buf(),
@@ -877,12 +971,38 @@ exclude_slash_tmp = true
         assert_eq!(
             SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![PathBuf::from("/my/workspace")],
+                readable_roots: None,
                 network_access: false,
+                network_allowlist: vec![],
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
             },
             sandbox_workspace_write_cfg.derive_sandbox_policy(sandbox_mode_override)
         );
+
+        let sandbox_workspace_write_restricted_reads = r#"
+sandbox_mode = "workspace-write"
+
+[sandbox_workspace_write]
+readable_roots = ["/my/docs"]
+"#;
+
+        let sandbox_workspace_write_restricted_reads_cfg =
+            toml::from_str::<ConfigToml>(sandbox_workspace_write_restricted_reads)
+                .expect("TOML deserialization should succeed");
+        let sandbox_mode_override = None;
+        assert_eq!(
+            SandboxPolicy::WorkspaceWrite {
+                writable_roots: vec![],
+                readable_roots: Some(vec![PathBuf::from("/my/docs")]),
+                network_access: false,
+                network_allowlist: vec![],
+                exclude_tmpdir_env_var: false,
+                exclude_slash_tmp: false,
+            },
+            sandbox_workspace_write_restricted_reads_cfg
+                .derive_sandbox_policy(sandbox_mode_override)
+        );
     }
 
     struct PrecedenceTestFixture {
@@ -1023,11 +1143,13 @@ disable_response_storage = true
                 model_family: find_family_for_model("o3").expect("known model slug"),
                 model_context_window: Some(200_000),
                 model_max_output_tokens: Some(100_000),
+                model_auto_compact_token_limit: None,
                 model_provider_id: "openai".to_string(),
                 model_provider: fixture.openai_provider.clone(),
                 approval_policy: AskForApproval::Never,
                 sandbox_policy: SandboxPolicy::new_read_only_policy(),
                 shell_environment_policy: ShellEnvironmentPolicy::default(),
+                trusted_projects: Vec::new(),
                 disable_response_storage: false,
                 user_instructions: None,
                 notify: None,
@@ -1039,6 +1161,12 @@ disable_response_storage = true
                 history: History::default(),
                 file_opener: UriBasedFileOpener::VsCode,
                 tui: Tui::default(),
+                mcp_server: McpServerSettings::default(),
+                notifications: NotificationsConfig::default(),
+                exec_output: ExecOutputConfig::default(),
+                model_pricing: HashMap::new(),
+                max_session_tokens: None,
+                max_session_cost: None,
                 codex_linux_sandbox_exe: None,
                 hide_agent_reasoning: false,
                 show_raw_agent_reasoning: false,
@@ -1049,6 +1177,7 @@ disable_response_storage = true
                 base_instructions: None,
                 include_plan_tool: false,
                 include_apply_patch_tool: false,
+                use_experimental_streamable_shell_tool: false,
                 responses_originator_header: "codex_cli_rs".to_string(),
                 preferred_auth_method: AuthMode::ChatGPT,
             },
@@ -1076,11 +1205,13 @@ disable_response_storage = true
             model_family: find_family_for_model("gpt-3.5-turbo").expect("known model slug"),
             model_context_window: Some(16_385),
             model_max_output_tokens: Some(4_096),
+            model_auto_compact_token_limit: None,
             model_provider_id: "openai-chat-completions".to_string(),
             model_provider: fixture.openai_chat_completions_provider.clone(),
             approval_policy: AskForApproval::UnlessTrusted,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            trusted_projects: Vec::new(),
             disable_response_storage: false,
             user_instructions: None,
             notify: None,
@@ -1092,6 +1223,12 @@ disable_response_storage = true
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
             tui: Tui::default(),
+            mcp_server: McpServerSettings::default(),
+            notifications: NotificationsConfig::default(),
+            exec_output: ExecOutputConfig::default(),
+            model_pricing: HashMap::new(),
+            max_session_tokens: None,
+            max_session_cost: None,
             codex_linux_sandbox_exe: None,
             hide_agent_reasoning: false,
             show_raw_agent_reasoning: false,
@@ -1102,6 +1239,7 @@ disable_response_storage = true
             base_instructions: None,
             include_plan_tool: false,
             include_apply_patch_tool: false,
+            use_experimental_streamable_shell_tool: false,
             responses_originator_header: "codex_cli_rs".to_string(),
             preferred_auth_method: AuthMode::ChatGPT,
         };
@@ -1144,11 +1282,13 @@ disable_response_storage = true
             model_family: find_family_for_model("o3").expect("known model slug"),
             model_context_window: Some(200_000),
             model_max_output_tokens: Some(100_000),
+            model_auto_compact_token_limit: None,
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            trusted_projects: Vec::new(),
             disable_response_storage: true,
             user_instructions: None,
             notify: None,
@@ -1160,6 +1300,12 @@ disable_response_storage = true
             history: History::default(),
             file_opener: UriBasedFileOpener::VsCode,
             tui: Tui::default(),
+            mcp_server: McpServerSettings::default(),
+            notifications: NotificationsConfig::default(),
+            exec_output: ExecOutputConfig::default(),
+            model_pricing: HashMap::new(),
+            max_session_tokens: None,
+            max_session_cost: None,
             codex_linux_sandbox_exe: None,
             hide_agent_reasoning: false,
             show_raw_agent_reasoning: false,
@@ -1170,6 +1316,7 @@ disable_response_storage = true
             base_instructions: None,
             include_plan_tool: false,
             include_apply_patch_tool: false,
+            use_experimental_streamable_shell_tool: false,
             responses_originator_header: "codex_cli_rs".to_string(),
             preferred_auth_method: AuthMode::ChatGPT,
         };
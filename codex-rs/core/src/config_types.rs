@@ -5,19 +5,63 @@
 
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Duration;
 use wildmatch::WildMatchPattern;
 
+use crate::protocol::ReviewDecision;
 use serde::Deserialize;
 
+/// Either `command` (for servers launched as a subprocess speaking MCP over
+/// stdio) or `url` (for servers reached over Streamable HTTP or the legacy
+/// HTTP+SSE transport) must be set.
 #[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct McpServerConfig {
-    pub command: String,
+    #[serde(default)]
+    pub command: Option<String>,
 
     #[serde(default)]
     pub args: Vec<String>,
 
     #[serde(default)]
     pub env: Option<HashMap<String, String>>,
+
+    /// Endpoint of a server that is reached over HTTP.
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// Extra HTTP headers sent with every request to `url`.
+    #[serde(default)]
+    pub headers: Option<HashMap<String, String>>,
+
+    /// Name of the environment variable holding a token to send as
+    /// `Authorization: Bearer <token>` to `url`.
+    #[serde(default)]
+    pub bearer_token_env_var: Option<String>,
+
+    /// Glob patterns (`*` and `?`) of the tools exposed to the model. When
+    /// unset, every tool of the server is exposed.
+    #[serde(default)]
+    pub enabled_tools: Option<Vec<String>>,
+
+    /// Glob patterns of tools that are never exposed to the model. Takes
+    /// precedence over `enabled_tools`.
+    #[serde(default)]
+    pub disabled_tools: Vec<String>,
+
+    /// Settings for individual tools, keyed by the name the server reports.
+    #[serde(default)]
+    pub tools: HashMap<String, McpToolConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct McpToolConfig {
+    /// Whether calls to the tool must be approved by the user. When unset,
+    /// approval is required only under the `untrusted` approval policy.
+    pub require_approval: Option<bool>,
+
+    /// Seconds after which a call to the tool is abandoned. No timeout
+    /// applies when unset.
+    pub timeout_sec: Option<u64>,
 }
 
 #[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
@@ -76,12 +120,129 @@ pub enum HistoryPersistence {
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct Tui {}
 
+/// Settings that apply when Codex itself runs as an MCP server (`codex mcp`).
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct McpServerSettings {
+    /// Seconds to wait for the client to answer an approval request before
+    /// the request is denied.
+    pub approval_timeout_secs: Option<u64>,
+
+    /// Decision applied to approval requests when the client does not
+    /// support elicitation. Defaults to `denied`.
+    pub default_approval_decision: Option<ReviewDecision>,
+}
+
+/// Built-in notification sinks, configured under `[notifications]`. The
+/// `notify` program receives the same notifications, except that it only
+/// receives `agent-turn-complete` ones while `events` is unset.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct NotificationsConfig {
+    /// Notification types to deliver. When unset, the built-in sinks receive
+    /// all types and the `notify` program only `agent-turn-complete`, the one
+    /// type it received before the others existed.
+    pub events: Option<Vec<NotificationKind>>,
+
+    /// Commands that run at least this many seconds trigger a
+    /// `long-command-finished` notification. Defaults to 60.
+    pub long_command_secs: Option<u64>,
+
+    /// Show desktop notifications through the freedesktop notification
+    /// service on D-Bus.
+    #[serde(default)]
+    pub desktop: bool,
+
+    /// Escape sequence the TUI writes to ask the terminal emulator for a
+    /// notification.
+    pub terminal: Option<TerminalNotificationMethod>,
+
+    /// POST every notification as JSON to this endpoint.
+    pub webhook: Option<WebhookConfig>,
+}
+
+impl NotificationsConfig {
+    pub fn is_enabled(&self, kind: NotificationKind) -> bool {
+        self.events
+            .as_ref()
+            .is_none_or(|events| events.contains(&kind))
+    }
+
+    /// Whether the `notify` program receives notifications of type `kind`.
+    pub fn is_enabled_for_program(&self, kind: NotificationKind) -> bool {
+        match &self.events {
+            Some(events) => events.contains(&kind),
+            None => kind == NotificationKind::AgentTurnComplete,
+        }
+    }
+
+    pub fn long_command_threshold(&self) -> Duration {
+        Duration::from_secs(self.long_command_secs.unwrap_or(60))
+    }
+}
+
+/// Limits on the output of a `shell` call sent to the model, configured under
+/// `[exec_output]`. Longer output is cut down to its first and last lines and
+/// saved in full for the model to page through.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ExecOutputConfig {
+    /// Defaults to 10 KiB.
+    pub max_bytes: Option<usize>,
+
+    /// Defaults to 256. Values below 1 are treated as 1.
+    pub max_lines: Option<usize>,
+}
+
+impl ExecOutputConfig {
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes.unwrap_or(10 * 1024)
+    }
+
+    pub fn max_lines(&self) -> usize {
+        self.max_lines.unwrap_or(256).max(1)
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotificationKind {
+    AgentTurnComplete,
+    ApprovalRequested,
+    TurnFailed,
+    LongCommandFinished,
+    BudgetExceeded,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TerminalNotificationMethod {
+    /// `OSC 9`, understood by iTerm2, WezTerm, kitty and Windows Terminal.
+    Osc9,
+    /// `OSC 777`, understood by urxvt, foot and VTE-based terminals.
+    Osc777,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct WebhookConfig {
+    pub url: String,
+
+    /// Extra request headers, e.g. for authentication.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct SandboxWorkspaceWrite {
     #[serde(default)]
     pub writable_roots: Vec<PathBuf>,
+    /// When set, restricts read access to these folders plus the writable
+    /// roots and the default system and toolchain folders.
+    #[serde(default)]
+    pub readable_roots: Option<Vec<PathBuf>>,
     #[serde(default)]
     pub network_access: bool,
+    /// Hosts reachable through the network proxy when `network_access` is
+    /// `false`.
+    #[serde(default)]
+    pub network_allowlist: Vec<String>,
     #[serde(default)]
     pub exclude_tmpdir_env_var: bool,
     #[serde(default)]
@@ -1,3 +1,6 @@
+use crate::client_common::USER_INSTRUCTIONS_START;
+use crate::environment_context::ENVIRONMENT_CONTEXT_START;
+use crate::models::ContentItem;
 use crate::models::ResponseItem;
 
 /// Transcript of conversation history
@@ -74,33 +77,69 @@ impl ConversationHistory {
         }
     }
 
-    pub(crate) fn keep_last_messages(&mut self, n: usize) {
-        if n == 0 {
-            self.items.clear();
-            return;
-        }
+    /// Replaces the transcript with the messages Codex added on its own (user
+    /// instructions and environment context) followed by `items`.
+    pub(crate) fn compact(&mut self, items: Vec<ResponseItem>) {
+        self.items.retain(is_context_message);
+        self.items.extend(items);
+    }
+}
 
-        // Collect the last N message items (assistant/user), newest to oldest.
-        let mut kept: Vec<ResponseItem> = Vec::with_capacity(n);
-        for item in self.items.iter().rev() {
-            if let ResponseItem::Message { role, content, .. } = item {
-                kept.push(ResponseItem::Message {
-                    // we need to remove the id or the model will complain that messages are sent without
-                    // their reasonings
-                    id: None,
-                    role: role.clone(),
-                    content: content.clone(),
-                });
-                if kept.len() == n {
-                    break;
-                }
-            }
+/// Returns the text of a message typed by the user, skipping the messages
+/// Codex adds on its own (user instructions and environment context).
+pub(crate) fn user_message_text(item: &ResponseItem) -> Option<String> {
+    let ResponseItem::Message { role, content, .. } = item else {
+        return None;
+    };
+    if role != "user" {
+        return None;
+    }
+    content.iter().find_map(|c| match c {
+        ContentItem::InputText { text }
+            if !text.starts_with(USER_INSTRUCTIONS_START)
+                && !text.starts_with(ENVIRONMENT_CONTEXT_START) =>
+        {
+            Some(text.clone())
         }
+        _ => None,
+    })
+}
+
+/// True for the user instructions and environment context messages.
+fn is_context_message(item: &ResponseItem) -> bool {
+    let ResponseItem::Message { role, content, .. } = item else {
+        return false;
+    };
+    role == "user"
+        && content.iter().any(|c| {
+            matches!(c, ContentItem::InputText { text }
+                if text.starts_with(USER_INSTRUCTIONS_START)
+                    || text.starts_with(ENVIRONMENT_CONTEXT_START))
+        })
+}
 
-        // Preserve chronological order (oldest to newest) within the kept slice.
-        kept.reverse();
-        self.items = kept;
+/// Returns `items` without their last `n` turns. A turn starts with a message
+/// typed by the user and runs until the next one, so everything recorded
+/// before the first such message (instructions, environment context) is
+/// always kept.
+pub(crate) fn drop_last_turns(mut items: Vec<ResponseItem>, n: usize) -> Vec<ResponseItem> {
+    if n == 0 {
+        return items;
+    }
+    let turn_starts: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| user_message_text(item).is_some())
+        .map(|(idx, _)| idx)
+        .collect();
+    let cut = match turn_starts.len().checked_sub(n) {
+        Some(idx) => turn_starts.get(idx).copied(),
+        None => turn_starts.first().copied(),
+    };
+    if let Some(cut) = cut {
+        items.truncate(cut);
     }
+    items
 }
 
 /// Anything that is not a system message or "reasoning" message is considered
@@ -169,6 +208,52 @@ mod tests {
         }
     }
 
+    fn input_msg(text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn drop_last_turns_keeps_context_and_earlier_turns() {
+        let items = vec![
+            input_msg("<user_instructions>\n\nbe nice\n\n</user_instructions>"),
+            input_msg("<environment_context>\ncwd\n</environment_context>"),
+            input_msg("first"),
+            assistant_msg("first answer"),
+            input_msg("second"),
+            assistant_msg("second answer"),
+        ];
+
+        assert_eq!(items, drop_last_turns(items.clone(), 0));
+        assert_eq!(items[..4].to_vec(), drop_last_turns(items.clone(), 1));
+        assert_eq!(items[..2].to_vec(), drop_last_turns(items.clone(), 2));
+        assert_eq!(items[..2].to_vec(), drop_last_turns(items.clone(), 5));
+    }
+
+    #[test]
+    fn compact_keeps_context_messages() {
+        let mut h = ConversationHistory::default();
+        let items = vec![
+            input_msg("<user_instructions>\n\nbe nice\n\n</user_instructions>"),
+            input_msg("<environment_context>\ncwd\n</environment_context>"),
+            input_msg("first"),
+            assistant_msg("first answer"),
+        ];
+        h.record_items(items.iter());
+
+        h.compact(vec![assistant_msg("summary")]);
+
+        assert_eq!(
+            vec![items[0].clone(), items[1].clone(), assistant_msg("summary")],
+            h.contents()
+        );
+    }
+
     #[test]
     fn merges_adjacent_assistant_messages() {
         let mut h = ConversationHistory::default();
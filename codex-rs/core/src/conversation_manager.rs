@@ -7,11 +7,14 @@ use uuid::Uuid;
 
 use crate::codex::Codex;
 use crate::codex::CodexSpawnOk;
+use crate::codex::ForkedHistory;
 use crate::codex::INITIAL_SUBMIT_ID;
 use crate::codex_conversation::CodexConversation;
 use crate::config::Config;
+use crate::conversation_history::drop_last_turns;
 use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
+use crate::model_family::find_family_for_model;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::SessionConfiguredEvent;
@@ -50,11 +53,76 @@ impl ConversationManager {
         &self,
         config: Config,
         auth: Option<CodexAuth>,
+    ) -> CodexResult<NewConversation> {
+        let codex_spawn_ok = Codex::spawn(config.clone(), auth).await?;
+        self.finalize_spawn(codex_spawn_ok, config).await
+    }
+
+    /// Starts a new conversation with a copy of the history of
+    /// `conversation_id`, minus its last `drop_last_n_turns` turns (a turn
+    /// being a user message and everything that followed it). The fork uses
+    /// the source's most recent model, approval and sandbox policy, and cwd,
+    /// and is recorded in a new rollout file that names the source as parent.
+    pub async fn fork_conversation(
+        &self,
+        conversation_id: Uuid,
+        drop_last_n_turns: usize,
+    ) -> CodexResult<NewConversation> {
+        let source = self.get_conversation(conversation_id).await?;
+        let config = source.config();
+        let auth = CodexAuth::from_codex_home(&config.codex_home, config.preferred_auth_method)?;
+        self.fork_conversation_with_auth(conversation_id, drop_last_n_turns, auth)
+            .await
+    }
+
+    /// Used for integration tests: should not be used by ordinary business
+    /// logic.
+    pub async fn fork_conversation_with_auth(
+        &self,
+        conversation_id: Uuid,
+        drop_last_n_turns: usize,
+        auth: Option<CodexAuth>,
+    ) -> CodexResult<NewConversation> {
+        let source = self.get_conversation(conversation_id).await?;
+        let (history, snapshot) = source
+            .history_and_snapshot()
+            .ok_or(CodexErr::ConversationNotFound(conversation_id))?;
+
+        let mut config = source.config().clone();
+        if let Some(model) = snapshot.model {
+            config.model_family =
+                find_family_for_model(&model).unwrap_or_else(|| config.model_family.clone());
+            config.model = model;
+        }
+        if let Some(approval_policy) = snapshot.approval_policy {
+            config.approval_policy = approval_policy;
+        }
+        if let Some(sandbox_policy) = snapshot.sandbox_policy {
+            config.sandbox_policy = sandbox_policy;
+        }
+        if let Some(cwd) = snapshot.cwd {
+            config.cwd = cwd;
+        }
+        // The fork must not pick up the rollout the source was resumed from.
+        config.experimental_resume = None;
+
+        let forked_from = ForkedHistory {
+            parent_id: conversation_id,
+            items: drop_last_turns(history, drop_last_n_turns),
+        };
+        let codex_spawn_ok = Codex::spawn_forked(config.clone(), auth, forked_from).await?;
+        self.finalize_spawn(codex_spawn_ok, config).await
+    }
+
+    async fn finalize_spawn(
+        &self,
+        codex_spawn_ok: CodexSpawnOk,
+        config: Config,
     ) -> CodexResult<NewConversation> {
         let CodexSpawnOk {
             codex,
             session_id: conversation_id,
-        } = Codex::spawn(config, auth).await?;
+        } = codex_spawn_ok;
 
         // The first event must be `SessionInitialized`. Validate and forward it
         // to the caller so that they can display it in the conversation
@@ -70,7 +138,7 @@ impl ConversationManager {
             }
         };
 
-        let conversation = Arc::new(CodexConversation::new(codex));
+        let conversation = Arc::new(CodexConversation::new(codex, config));
         self.conversations
             .write()
             .await
@@ -28,6 +28,9 @@ pub(crate) struct EnvironmentContext {
     pub approval_policy: AskForApproval,
     pub sandbox_mode: SandboxMode,
     pub network_access: NetworkAccess,
+    /// The user's login shell, when it could be detected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
 }
 
 impl EnvironmentContext {
@@ -35,6 +38,7 @@ impl EnvironmentContext {
         cwd: PathBuf,
         approval_policy: AskForApproval,
         sandbox_policy: SandboxPolicy,
+        shell: Option<&str>,
     ) -> Self {
         Self {
             cwd,
@@ -55,6 +59,7 @@ impl EnvironmentContext {
                     }
                 }
             },
+            shell: shell.map(str::to_string),
         }
     }
 }
@@ -69,6 +74,9 @@ impl Display for EnvironmentContext {
         writeln!(f, "Approval policy: {}", self.approval_policy)?;
         writeln!(f, "Sandbox mode: {}", self.sandbox_mode)?;
         writeln!(f, "Network access: {}", self.network_access)?;
+        if let Some(shell) = &self.shell {
+            writeln!(f, "Shell: {shell}")?;
+        }
         Ok(())
     }
 }
@@ -28,11 +28,9 @@ use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
 use serde_bytes::ByteBuf;
 
-// Maximum we send for each stream, which is either:
-// - 10KiB OR
-// - 256 lines
-const MAX_STREAM_OUTPUT: usize = 10 * 1024;
-const MAX_STREAM_OUTPUT_LINES: usize = 256;
+// Maximum we capture for each stream. The full output is kept so that it can
+// be saved when it is too long for the model; see `crate::exec_output`.
+const MAX_CAPTURED_OUTPUT: usize = 8 * 1024 * 1024;
 
 const DEFAULT_TIMEOUT_MS: u64 = 10_000;
 
@@ -186,6 +184,7 @@ fn is_likely_sandbox_denied(sandbox_type: SandboxType, exit_code: i32) -> bool {
 #[derive(Debug)]
 pub struct StreamOutput<T> {
     pub text: T,
+    /// Set if the stream produced more output than is captured.
     pub truncated_after_lines: Option<u32>,
 }
 #[derive(Debug)]
@@ -251,8 +250,8 @@ async fn exec(
     consume_truncated_output(child, timeout, stdout_stream).await
 }
 
-/// Consumes the output of a child process, truncating it so it is suitable for
-/// use as the output of a `shell` tool call. Also enforces specified timeout.
+/// Consumes the output of a child process, capturing up to
+/// `MAX_CAPTURED_OUTPUT` bytes of each stream. Also enforces specified timeout.
 pub(crate) async fn consume_truncated_output(
     mut child: Child,
     timeout: Duration,
@@ -275,15 +274,13 @@ pub(crate) async fn consume_truncated_output(
 
     let stdout_handle = tokio::spawn(read_capped(
         BufReader::new(stdout_reader),
-        MAX_STREAM_OUTPUT,
-        MAX_STREAM_OUTPUT_LINES,
+        MAX_CAPTURED_OUTPUT,
         stdout_stream.clone(),
         false,
     ));
     let stderr_handle = tokio::spawn(read_capped(
         BufReader::new(stderr_reader),
-        MAX_STREAM_OUTPUT,
-        MAX_STREAM_OUTPUT_LINES,
+        MAX_CAPTURED_OUTPUT,
         stdout_stream.clone(),
         true,
     ));
@@ -320,7 +317,6 @@ pub(crate) async fn consume_truncated_output(
 async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
     mut reader: R,
     max_output: usize,
-    max_lines: usize,
     stream: Option<StdoutStream>,
     is_stderr: bool,
 ) -> io::Result<StreamOutput<Vec<u8>>> {
@@ -328,7 +324,7 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
     let mut tmp = [0u8; 8192];
 
     let mut remaining_bytes = max_output;
-    let mut remaining_lines = max_lines;
+    let mut lines = 0;
 
     loop {
         let n = reader.read(&mut tmp).await?;
@@ -355,33 +351,21 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
             let _ = stream.tx_event.send(event).await;
         }
 
-        // Copy into the buffer only while we still have byte and line budget.
-        if remaining_bytes > 0 && remaining_lines > 0 {
-            let mut copy_len = 0;
-            for &b in &tmp[..n] {
-                if remaining_bytes == 0 || remaining_lines == 0 {
-                    break;
-                }
-                copy_len += 1;
-                remaining_bytes -= 1;
-                if b == b'\n' {
-                    remaining_lines -= 1;
-                }
-            }
+        // Copy into the buffer only while we still have byte budget.
+        if remaining_bytes > 0 {
+            let copy_len = n.min(remaining_bytes);
             buf.extend_from_slice(&tmp[..copy_len]);
+            remaining_bytes -= copy_len;
+            lines += tmp[..copy_len].iter().filter(|&&b| b == b'\n').count();
         }
-        // Continue reading to EOF to avoid back-pressure, but discard once caps are hit.
+        // Continue reading to EOF to avoid back-pressure, but discard once the cap is hit.
     }
 
-    let truncated = remaining_lines == 0 || remaining_bytes == 0;
+    let truncated = remaining_bytes == 0;
 
     Ok(StreamOutput {
         text: buf,
-        truncated_after_lines: if truncated {
-            Some((max_lines - remaining_lines) as u32)
-        } else {
-            None
-        },
+        truncated_after_lines: truncated.then_some(lines as u32),
     })
 }
 
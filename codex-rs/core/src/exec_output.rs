@@ -0,0 +1,420 @@
+//! Truncation of `shell` output for the model. Output that exceeds the limits
+//! configured under `[exec_output]` is cut down to its first and last lines,
+//! and the full output is spilled to a file in the session's artifact
+//! directory (`~/.codex/artifacts/<session_id>/`). The model pages through
+//! that file with the `read_exec_output` tool, so an error at the end of a
+//! long build is never lost.
+//!
+//! The full output may contain secrets, so the directory is deleted when the
+//! session shuts down. Directories left behind by sessions that did not shut
+//! down cleanly are deleted after [`ARTIFACT_RETENTION`].
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::config_types::ExecOutputConfig;
+use crate::exec::StreamOutput;
+use crate::exec_session::truncate_middle;
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+use crate::util::remove_stale_dirs;
+
+pub(crate) const READ_EXEC_OUTPUT_TOOL_NAME: &str = "read_exec_output";
+
+/// Directory inside `~/.codex` that holds the artifacts of each session.
+const ARTIFACTS_SUBDIR: &str = "artifacts";
+
+/// How long the artifact directory of another session is kept after it was
+/// last written to.
+const ARTIFACT_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReadExecOutputParams {
+    pub(crate) output_id: String,
+    /// 1-based line to start at.
+    #[serde(default)]
+    pub(crate) start_line: Option<usize>,
+    #[serde(default)]
+    pub(crate) max_lines: Option<usize>,
+}
+
+pub(crate) static READ_EXEC_OUTPUT_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let properties = BTreeMap::from([
+        (
+            "output_id".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "The ID of the saved output, as given in the truncated shell output"
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "start_line".to_string(),
+            JsonSchema::Number {
+                description: Some("The 1-based line to start reading at".to_string()),
+            },
+        ),
+        (
+            "max_lines".to_string(),
+            JsonSchema::Number {
+                description: Some("Maximum number of lines to return".to_string()),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: READ_EXEC_OUTPUT_TOOL_NAME.to_string(),
+        description: "Reads a range of lines from the full output of a shell command whose output was truncated.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["output_id".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+/// Truncates command output for the model and keeps the full output of the
+/// commands that were truncated.
+pub(crate) struct ExecOutputStore {
+    dir: PathBuf,
+    max_bytes: usize,
+    max_lines: usize,
+}
+
+impl ExecOutputStore {
+    pub(crate) fn new(codex_home: &Path, session_id: Uuid, config: &ExecOutputConfig) -> Self {
+        Self {
+            dir: codex_home
+                .join(ARTIFACTS_SUBDIR)
+                .join(session_id.to_string()),
+            max_bytes: config.max_bytes(),
+            max_lines: config.max_lines(),
+        }
+    }
+
+    /// Writes `output` to the artifact file of `call_id` if it is too long to
+    /// be sent to the model in full. Returns the path of the file.
+    pub(crate) async fn spill(
+        &self,
+        call_id: &str,
+        output: &StreamOutput<String>,
+    ) -> Option<PathBuf> {
+        if !self.exceeds_limits(&output.text) {
+            return None;
+        }
+        let path = self.output_path(call_id);
+        if !self.dir.exists()
+            && let Some(parent) = self.dir.parent()
+        {
+            remove_stale_dirs(parent, ARTIFACT_RETENTION).await;
+        }
+        let result = async {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            tokio::fs::write(&path, &output.text).await
+        }
+        .await;
+        match result {
+            Ok(()) => Some(path),
+            Err(e) => {
+                warn!("failed to save the output of {call_id}: {e}");
+                None
+            }
+        }
+    }
+
+    /// Deletes the saved outputs of this session.
+    pub(crate) async fn remove_all(&self) {
+        match tokio::fs::remove_dir_all(&self.dir).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => warn!("failed to delete {}: {e}", self.dir.display()),
+        }
+    }
+
+    /// Returns `output` as sent to the model: in full if it is within the
+    /// limits, otherwise its first and last lines with a pointer to the full
+    /// output if [`Self::spill`] saved it.
+    pub(crate) fn format_for_model(&self, call_id: &str, output: &StreamOutput<String>) -> String {
+        let text = &output.text;
+        let mut formatted = if self.exceeds_limits(text) {
+            let output_id = output_id(call_id);
+            let mut formatted = truncate_head_tail(text, self.max_bytes, self.max_lines);
+            let total_lines = text.lines().count();
+            if self.output_path(&output_id).is_file() {
+                push_note(
+                    &mut formatted,
+                    &format!(
+                        "[Full output ({total_lines} lines) saved as `{output_id}`; use {READ_EXEC_OUTPUT_TOOL_NAME} to page through it.]"
+                    ),
+                );
+            }
+            formatted
+        } else {
+            text.clone()
+        };
+        if output.truncated_after_lines.is_some() {
+            push_note(
+                &mut formatted,
+                "[Output beyond this point was not captured: too many bytes.]",
+            );
+        }
+        formatted
+    }
+
+    /// Returns up to `max_lines` lines of a saved output, starting at the
+    /// 1-based `start_line`. Pages are held to the same limits as the output
+    /// of a command.
+    pub(crate) async fn read(
+        &self,
+        output_id: &str,
+        start_line: usize,
+        max_lines: Option<usize>,
+    ) -> io::Result<String> {
+        let text = tokio::fs::read_to_string(self.output_path(output_id)).await?;
+        let lines: Vec<&str> = text.lines().collect();
+        let total = lines.len();
+        let start = start_line.max(1);
+        if start > total {
+            return Ok(format!(
+                "No lines at {start}: the output has {total} lines."
+            ));
+        }
+
+        let max_lines = max_lines.unwrap_or(self.max_lines).clamp(1, self.max_lines);
+        let mut page = String::new();
+        let mut end = start - 1;
+        for line in lines.iter().skip(start - 1).take(max_lines) {
+            if end >= start && page.len() + line.len() + 1 > self.max_bytes {
+                break;
+            }
+            page.push_str(line);
+            page.push('\n');
+            end += 1;
+        }
+        Ok(format!(
+            "Lines {start}-{end} of {total}:\n{}",
+            truncate_middle(&page, self.max_bytes)
+        ))
+    }
+
+    fn exceeds_limits(&self, text: &str) -> bool {
+        text.len() > self.max_bytes || text.lines().count() > self.max_lines
+    }
+
+    fn output_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.txt", output_id(id)))
+    }
+}
+
+/// Appends `note` to `text`, separated by a blank line.
+fn push_note(text: &mut String, note: &str) {
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push('\n');
+    text.push_str(note);
+}
+
+/// The ID under which the output of a call is saved: `id` with every
+/// character that is not safe in a file name replaced, so that an ID from the
+/// model cannot point outside of the artifact directory.
+fn output_id(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Keeps the first and last lines of `text`, each half taking at most half of
+/// `max_bytes` and `max_lines`, and replaces the lines in between with a
+/// marker. Falls back to cutting bytes out of the middle when the lines are
+/// too long to keep any of them whole.
+pub(crate) fn truncate_head_tail(text: &str, max_bytes: usize, max_lines: usize) -> String {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    if text.len() <= max_bytes && lines.len() <= max_lines {
+        return text.to_string();
+    }
+
+    // Number of whole lines from `lines` that fit in the limits.
+    fn take_lines<'a>(
+        lines: impl Iterator<Item = &'a &'a str>,
+        max_lines: usize,
+        max_bytes: usize,
+    ) -> usize {
+        let mut count = 0;
+        let mut bytes = 0;
+        for line in lines.take(max_lines) {
+            if bytes + line.len() > max_bytes {
+                break;
+            }
+            bytes += line.len();
+            count += 1;
+        }
+        count
+    }
+    let head = take_lines(lines.iter(), max_lines / 2, max_bytes / 2);
+    let tail = take_lines(
+        lines.iter().skip(head).rev(),
+        max_lines - max_lines / 2,
+        max_bytes - max_bytes / 2,
+    );
+    if head == 0 && tail == 0 {
+        return truncate_middle(text, max_bytes);
+    }
+
+    let elided = lines.len() - head - tail;
+    let mut truncated = lines[..head].concat();
+    if !truncated.is_empty() && !truncated.ends_with('\n') {
+        truncated.push('\n');
+    }
+    truncated.push_str(&format!("[... {elided} lines elided ...]\n"));
+    truncated.push_str(&lines[lines.len() - tail..].concat());
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn numbered_lines(count: usize) -> String {
+        (1..=count).map(|i| format!("line {i}\n")).collect()
+    }
+
+    fn store(codex_home: &Path) -> ExecOutputStore {
+        ExecOutputStore::new(
+            codex_home,
+            Uuid::new_v4(),
+            &ExecOutputConfig {
+                max_bytes: Some(1024),
+                max_lines: Some(4),
+            },
+        )
+    }
+
+    #[test]
+    fn keeps_output_within_limits() {
+        let text = numbered_lines(4);
+        assert_eq!(text, truncate_head_tail(&text, 1024, 4));
+    }
+
+    #[test]
+    fn keeps_head_and_tail_lines() {
+        assert_eq!(
+            "line 1\nline 2\n[... 6 lines elided ...]\nline 9\nline 10\n",
+            truncate_head_tail(&numbered_lines(10), 1024, 4)
+        );
+    }
+
+    #[test]
+    fn keeps_head_and_tail_within_byte_limit() {
+        // Every line is 7 or 8 bytes long, so 16 bytes per half fit two lines.
+        assert_eq!(
+            "line 1\nline 2\n[... 6 lines elided ...]\nline 9\nline 10\n",
+            truncate_head_tail(&numbered_lines(10), 32, 100)
+        );
+    }
+
+    #[test]
+    fn cuts_bytes_when_no_line_fits() {
+        let text = "x".repeat(100);
+        assert_eq!(
+            format!(
+                "{}\n…80 bytes truncated…\n{}",
+                "x".repeat(10),
+                "x".repeat(10)
+            ),
+            truncate_head_tail(&text, 20, 4)
+        );
+    }
+
+    #[tokio::test]
+    async fn spills_truncated_output_and_reads_it_back() {
+        let codex_home = TempDir::new().unwrap();
+        let store = store(codex_home.path());
+
+        let short = StreamOutput::new(numbered_lines(3));
+        assert_eq!(None, store.spill("call_short", &short).await);
+        assert_eq!(short.text, store.format_for_model("call_short", &short));
+
+        let long = StreamOutput::new(numbered_lines(10));
+        let path = store.spill("call/long", &long).await.unwrap();
+        assert_eq!(long.text, std::fs::read_to_string(path).unwrap());
+        assert_eq!(
+            "line 1\nline 2\n[... 6 lines elided ...]\nline 9\nline 10\n\n[Full output (10 lines) saved as `call_long`; use read_exec_output to page through it.]",
+            store.format_for_model("call/long", &long)
+        );
+
+        assert_eq!(
+            "Lines 5-7 of 10:\nline 5\nline 6\nline 7\n",
+            store.read("call_long", 5, Some(3)).await.unwrap()
+        );
+        // Pages are capped at the configured number of lines.
+        assert_eq!(
+            "Lines 7-10 of 10:\nline 7\nline 8\nline 9\nline 10\n",
+            store.read("call_long", 7, None).await.unwrap()
+        );
+        assert_eq!(
+            "No lines at 11: the output has 10 lines.",
+            store.read("call_long", 11, None).await.unwrap()
+        );
+        assert!(store.read("../call_long", 1, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn zero_max_lines_is_treated_as_one() {
+        let codex_home = TempDir::new().unwrap();
+        let store = ExecOutputStore::new(
+            codex_home.path(),
+            Uuid::new_v4(),
+            &ExecOutputConfig {
+                max_bytes: Some(1024),
+                max_lines: Some(0),
+            },
+        );
+
+        let long = StreamOutput::new(numbered_lines(3));
+        store.spill("call_long", &long).await.unwrap();
+        assert_eq!(
+            "Lines 2-2 of 3:\nline 2\n",
+            store.read("call_long", 2, None).await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn removes_saved_outputs() {
+        let codex_home = TempDir::new().unwrap();
+        let artifacts = codex_home.path().join(ARTIFACTS_SUBDIR);
+        std::fs::create_dir_all(artifacts.join("old-session")).unwrap();
+        let store = store(codex_home.path());
+
+        store
+            .spill("call_long", &StreamOutput::new(numbered_lines(10)))
+            .await
+            .unwrap();
+        // Recent directories of other sessions are left alone.
+        assert!(artifacts.join("old-session").exists());
+
+        store.remove_all().await;
+        assert!(!store.dir.exists());
+        assert!(store.read("call_long", 1, None).await.is_err());
+        store.remove_all().await;
+    }
+}
@@ -0,0 +1,614 @@
+//! Consults the `codex-execpolicy` checker when deciding whether a command
+//! proposed by the model can run without asking the user.
+//!
+//! The checker is built from the policy bundled with `codex-execpolicy`
+//! followed by any `*.policy` files found in `$CODEX_HOME/policy` and in the
+//! `.codex/policy` folder of the project being worked on, if the user trusts
+//! that project. Whatever the policy says, it can only approve a command to
+//! run in the sandbox (see `safety.rs`), so a project policy never widens
+//! what a command may do.
+
+use std::ffi::OsString;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_execpolicy::ArgType;
+use codex_execpolicy::ExecCall;
+use codex_execpolicy::ExecvChecker;
+use codex_execpolicy::MatchedExec;
+use codex_execpolicy::PolicyParser;
+use codex_execpolicy::ValidExec;
+use codex_execpolicy::get_default_policy_extended_by;
+
+use crate::bash::try_parse_bash;
+use crate::bash::try_parse_word_only_commands_sequence;
+use crate::protocol::SandboxPolicy;
+use crate::protocol::WritableRoot;
+
+/// Name of the folder under `CODEX_HOME` that holds user policy files.
+const POLICY_DIR: &str = "policy";
+
+/// Folder, relative to a project directory, that holds project policy files.
+const PROJECT_POLICY_DIR: &str = ".codex/policy";
+
+/// Only files with this extension are loaded from [`POLICY_DIR`].
+const POLICY_FILE_EXTENSION: &str = "policy";
+
+/// Outcome of checking a command against the exec policy.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ExecPolicyAssessment {
+    /// Every command matched a rule and none of them can write files.
+    ReadOnly,
+
+    /// Every command matched a rule and every file it may write resolves to a
+    /// path inside the sandbox's writable roots.
+    WritesWithinWritableRoots,
+
+    /// At least one command is explicitly forbidden by the policy.
+    Forbidden { reason: String },
+
+    /// The policy could not vouch for the command.
+    Unverified,
+}
+
+pub(crate) struct ExecPolicy {
+    checker: ExecvChecker,
+}
+
+impl ExecPolicy {
+    /// Loads the default policy extended by user and project policy files.
+    /// Sources are applied in the following order, with rules from later
+    /// sources taking precedence over earlier ones:
+    ///
+    /// 1.  `$CODEX_HOME/policy/*.policy`
+    /// 2.  `.codex/policy/*.policy` at the root of the Git repository that
+    ///     contains `cwd`, if any
+    /// 3.  `.codex/policy/*.policy` in `cwd`
+    ///
+    /// The project policies (2 and 3) are only loaded if `project_trusted`:
+    /// otherwise any checkout could ship rules that approve its own commands.
+    ///
+    /// Files in the same folder are applied in lexicographic order of their
+    /// file names. A file that fails to parse, or whose `should_match` /
+    /// `should_not_match` examples do not hold, is skipped; a description of
+    /// each such problem is returned alongside the policy so the caller can
+    /// surface it.
+    pub(crate) fn load(
+        codex_home: &Path,
+        cwd: &Path,
+        project_trusted: bool,
+    ) -> anyhow::Result<(Self, Vec<String>)> {
+        let mut project_dirs = Vec::new();
+        if let Some(git_root) = find_git_root(cwd)
+            && git_root != cwd
+        {
+            project_dirs.push(git_root.join(PROJECT_POLICY_DIR));
+        }
+        project_dirs.push(cwd.join(PROJECT_POLICY_DIR));
+
+        let mut problems = Vec::new();
+        let mut policy_dirs = vec![codex_home.join(POLICY_DIR)];
+        if project_trusted {
+            policy_dirs.extend(project_dirs);
+        } else {
+            for dir in project_dirs.iter().filter(|dir| dir.is_dir()) {
+                problems.push(format!(
+                    "ignoring exec policy folder {}: the project is not trusted",
+                    dir.display()
+                ));
+            }
+        }
+
+        let mut parsers = Vec::new();
+        for dir in policy_dirs {
+            match read_policy_dir(&dir) {
+                Ok(dir_parsers) => {
+                    for (path, parser) in dir_parsers {
+                        match validate_policy_file(&parser) {
+                            Ok(()) => parsers.push(parser),
+                            Err(problem) => problems.push(format!(
+                                "ignoring exec policy file {}: {problem}",
+                                path.display()
+                            )),
+                        }
+                    }
+                }
+                Err(e) => problems.push(format!(
+                    "failed to read exec policy folder {}: {e}",
+                    dir.display()
+                )),
+            }
+        }
+
+        let policy = get_default_policy_extended_by(parsers).map_err(|e| e.into_anyhow())?;
+        Ok((
+            Self {
+                checker: ExecvChecker::new(policy),
+            },
+            problems,
+        ))
+    }
+
+    /// Checks `command` (either a plain argv or a `bash -lc` script made up
+    /// of word-only commands) against the policy, resolving file arguments
+    /// against `cwd`.
+    pub(crate) fn assess(
+        &self,
+        command: &[String],
+        sandbox_policy: &SandboxPolicy,
+        cwd: &Path,
+    ) -> ExecPolicyAssessment {
+        let Some(commands) = split_into_exec_calls(command) else {
+            return ExecPolicyAssessment::Unverified;
+        };
+
+        let writable_roots = sandbox_policy.get_writable_roots_with_cwd(cwd);
+        let writeable_folders: Vec<PathBuf> = writable_roots
+            .iter()
+            .map(|root| root.root.clone())
+            .collect();
+        let readable_folders = readable_folders(sandbox_policy, cwd);
+        let checker_cwd: Option<OsString> = Some(cwd.as_os_str().to_os_string());
+
+        let mut verified = true;
+        let mut writes_files = false;
+        for exec_call in commands {
+            // Keep going after an unverified command so that a forbidden
+            // command later in the script is still reported as such.
+            let valid_exec = match self.checker.r#match(&exec_call) {
+                Ok(MatchedExec::Match { exec }) => exec,
+                Ok(MatchedExec::Forbidden { reason, .. }) => {
+                    return ExecPolicyAssessment::Forbidden { reason };
+                }
+                Err(_) => {
+                    verified = false;
+                    continue;
+                }
+            };
+
+            writes_files |= valid_exec.might_write_files();
+            if self
+                .checker
+                .check(
+                    valid_exec.clone(),
+                    &checker_cwd,
+                    &readable_folders,
+                    &writeable_folders,
+                )
+                .is_err()
+                || !writes_avoid_read_only_subpaths(&valid_exec, &writable_roots, cwd)
+            {
+                verified = false;
+            }
+        }
+
+        match (verified, writes_files) {
+            (false, _) => ExecPolicyAssessment::Unverified,
+            (true, false) => ExecPolicyAssessment::ReadOnly,
+            (true, true) => ExecPolicyAssessment::WritesWithinWritableRoots,
+        }
+    }
+}
+
+fn read_policy_dir(dir: &Path) -> std::io::Result<Vec<(PathBuf, PolicyParser)>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file()
+            && path.extension().and_then(|ext| ext.to_str()) == Some(POLICY_FILE_EXTENSION)
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let contents = std::fs::read_to_string(&path)?;
+            let parser = PolicyParser::new(&path.to_string_lossy(), &contents);
+            Ok((path, parser))
+        })
+        .collect()
+}
+
+/// Parses a policy file on its own and runs the `should_match` and
+/// `should_not_match` examples of every program it defines.
+fn validate_policy_file(parser: &PolicyParser) -> Result<(), String> {
+    let policy = parser.parse().map_err(|e| e.to_string())?;
+
+    let mut failures = Vec::new();
+    for failed in policy.check_each_good_list_individually() {
+        failures.push(format!(
+            "`{}` is listed in should_match but does not match: {:?}",
+            join_command(&failed.program, &failed.args),
+            failed.error
+        ));
+    }
+    for passed in policy.check_each_bad_list_individually() {
+        failures.push(format!(
+            "`{}` is listed in should_not_match but matches",
+            join_command(&passed.program, &passed.args)
+        ));
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures.join("; "))
+    }
+}
+
+fn join_command(program: &str, args: &[String]) -> String {
+    std::iter::once(program)
+        .chain(args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Walks up from `cwd` looking for the directory that contains `.git`
+/// (either a directory or, for worktrees and submodules, a file).
+fn find_git_root(cwd: &Path) -> Option<PathBuf> {
+    cwd.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Returns the individual `execv(3)` calls that make up `command`, or `None`
+/// if `command` cannot be reduced to a sequence of plain commands.
+fn split_into_exec_calls(command: &[String]) -> Option<Vec<ExecCall>> {
+    let commands = match command {
+        [bash, flag, script] if bash == "bash" && flag == "-lc" => {
+            let tree = try_parse_bash(script)?;
+            try_parse_word_only_commands_sequence(&tree, script)?
+        }
+        _ => vec![command.to_vec()],
+    };
+
+    commands
+        .into_iter()
+        .map(|argv| {
+            let (program, args) = argv.split_first()?;
+            Some(ExecCall {
+                program: program.clone(),
+                args: args.to_vec(),
+            })
+        })
+        .collect()
+}
+
+fn readable_folders(sandbox_policy: &SandboxPolicy, cwd: &Path) -> Vec<PathBuf> {
+    if sandbox_policy.has_full_disk_read_access() {
+        vec![PathBuf::from("/")]
+    } else {
+        sandbox_policy.get_readable_roots_with_cwd(cwd)
+    }
+}
+
+/// `ExecvChecker` only knows about the writable roots themselves, so also
+/// make sure no writeable argument targets a read-only subpath such as `.git`.
+fn writes_avoid_read_only_subpaths(
+    valid_exec: &ValidExec,
+    writable_roots: &[WritableRoot],
+    cwd: &Path,
+) -> bool {
+    valid_exec
+        .args
+        .iter()
+        .map(|arg| (&arg.r#type, &arg.value))
+        .chain(valid_exec.opts.iter().map(|opt| (&opt.r#type, &opt.value)))
+        .filter(|(arg_type, _)| matches!(arg_type, ArgType::WriteableFile))
+        .all(|(_, value)| {
+            let path = normalize(&cwd.join(value));
+            writable_roots
+                .iter()
+                .any(|root| root.is_path_writable(&path))
+        })
+}
+
+/// Removes `.` and resolves `..` without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for comp in path.components() {
+        match comp {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn workspace_write_policy() -> SandboxPolicy {
+        SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            readable_roots: None,
+            network_access: false,
+            network_allowlist: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        }
+    }
+
+    fn vec_str(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn write_policy_file(dir: &Path, file_name: &str, contents: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join(file_name), contents).unwrap();
+    }
+
+    fn default_policy() -> ExecPolicy {
+        let codex_home = TempDir::new().unwrap();
+        let (policy, problems) =
+            ExecPolicy::load(codex_home.path(), codex_home.path(), true).unwrap();
+        assert_eq!(Vec::<String>::new(), problems);
+        policy
+    }
+
+    fn load_with_user_policy(codex_home: &TempDir, contents: &str) -> ExecPolicy {
+        write_policy_file(&codex_home.path().join(POLICY_DIR), "user.policy", contents);
+        let (policy, problems) =
+            ExecPolicy::load(codex_home.path(), codex_home.path(), true).unwrap();
+        assert_eq!(Vec::<String>::new(), problems);
+        policy
+    }
+
+    #[test]
+    fn read_only_command_matches_default_policy() {
+        let cwd = TempDir::new().unwrap();
+        let policy = default_policy();
+        assert_eq!(
+            ExecPolicyAssessment::ReadOnly,
+            policy.assess(
+                &vec_str(&["head", "-n", "10", "README.md"]),
+                &workspace_write_policy(),
+                cwd.path(),
+            )
+        );
+    }
+
+    #[test]
+    fn writes_are_checked_against_writable_roots() {
+        let cwd = TempDir::new().unwrap();
+        let policy = default_policy();
+        let sandbox_policy = workspace_write_policy();
+
+        assert_eq!(
+            ExecPolicyAssessment::WritesWithinWritableRoots,
+            policy.assess(
+                &vec_str(&["cp", "a.txt", "b.txt"]),
+                &sandbox_policy,
+                cwd.path()
+            )
+        );
+        assert_eq!(
+            ExecPolicyAssessment::Unverified,
+            policy.assess(
+                &vec_str(&["cp", "a.txt", "../b.txt"]),
+                &sandbox_policy,
+                cwd.path()
+            )
+        );
+        assert_eq!(
+            ExecPolicyAssessment::Unverified,
+            policy.assess(
+                &vec_str(&["cp", "a.txt", "b.txt"]),
+                &SandboxPolicy::ReadOnly,
+                cwd.path()
+            )
+        );
+    }
+
+    #[test]
+    fn writes_into_dot_git_are_unverified() {
+        let cwd = TempDir::new().unwrap();
+        std::fs::create_dir(cwd.path().join(".git")).unwrap();
+        let policy = default_policy();
+        assert_eq!(
+            ExecPolicyAssessment::Unverified,
+            policy.assess(
+                &vec_str(&["cp", "a.txt", ".git/config"]),
+                &workspace_write_policy(),
+                cwd.path()
+            )
+        );
+    }
+
+    #[test]
+    fn user_policy_can_forbid_programs() {
+        let codex_home = TempDir::new().unwrap();
+        let cwd = TempDir::new().unwrap();
+        let policy = load_with_user_policy(
+            &codex_home,
+            r#"
+define_program(
+    program="git",
+    args=["push", ARG_UNVERIFIED_VARARGS],
+    forbidden="pushing is reserved for humans",
+)
+"#,
+        );
+
+        let script = vec_str(&["bash", "-lc", "ls && git push origin main"]);
+        assert_eq!(
+            ExecPolicyAssessment::Forbidden {
+                reason: "pushing is reserved for humans".to_string()
+            },
+            policy.assess(&script, &workspace_write_policy(), cwd.path())
+        );
+    }
+
+    #[test]
+    fn bash_script_is_verified_only_if_every_command_matches() {
+        let cwd = TempDir::new().unwrap();
+        let policy = default_policy();
+        let sandbox_policy = workspace_write_policy();
+
+        assert_eq!(
+            ExecPolicyAssessment::ReadOnly,
+            policy.assess(
+                &vec_str(&["bash", "-lc", "ls && cat foo.txt"]),
+                &sandbox_policy,
+                cwd.path()
+            )
+        );
+        assert_eq!(
+            ExecPolicyAssessment::Unverified,
+            policy.assess(
+                &vec_str(&["bash", "-lc", "ls && rm foo.txt"]),
+                &sandbox_policy,
+                cwd.path()
+            )
+        );
+        assert_eq!(
+            ExecPolicyAssessment::Unverified,
+            policy.assess(
+                &vec_str(&["bash", "-lc", "cat foo.txt > bar.txt"]),
+                &sandbox_policy,
+                cwd.path()
+            )
+        );
+    }
+
+    #[test]
+    fn project_policy_takes_precedence_over_user_policy() {
+        let codex_home = TempDir::new().unwrap();
+        let repo = TempDir::new().unwrap();
+        std::fs::create_dir(repo.path().join(".git")).unwrap();
+        let cwd = repo.path().join("crate");
+        std::fs::create_dir(&cwd).unwrap();
+
+        write_policy_file(
+            &codex_home.path().join(POLICY_DIR),
+            "cargo.policy",
+            r#"define_program(program="cargo", args=["test"])"#,
+        );
+        write_policy_file(
+            &repo.path().join(PROJECT_POLICY_DIR),
+            "cargo.policy",
+            r#"define_program(program="cargo", args=["test"], forbidden="run `just test`")"#,
+        );
+
+        let (policy, problems) = ExecPolicy::load(codex_home.path(), &cwd, true).unwrap();
+        assert_eq!(Vec::<String>::new(), problems);
+        assert_eq!(
+            ExecPolicyAssessment::Forbidden {
+                reason: "run `just test`".to_string()
+            },
+            policy.assess(
+                &vec_str(&["cargo", "test"]),
+                &workspace_write_policy(),
+                &cwd
+            )
+        );
+
+        // A policy in `cwd` itself is applied last.
+        write_policy_file(
+            &cwd.join(PROJECT_POLICY_DIR),
+            "cargo.policy",
+            r#"define_program(program="cargo", args=["test"])"#,
+        );
+        let (policy, problems) = ExecPolicy::load(codex_home.path(), &cwd, true).unwrap();
+        assert_eq!(Vec::<String>::new(), problems);
+        assert_eq!(
+            ExecPolicyAssessment::ReadOnly,
+            policy.assess(
+                &vec_str(&["cargo", "test"]),
+                &workspace_write_policy(),
+                &cwd
+            )
+        );
+    }
+
+    #[test]
+    fn untrusted_project_policy_is_ignored() {
+        let codex_home = TempDir::new().unwrap();
+        let cwd = TempDir::new().unwrap();
+        write_policy_file(
+            &cwd.path().join(PROJECT_POLICY_DIR),
+            "make.policy",
+            r#"define_program(program="make", args=["lint"])"#,
+        );
+        let command = vec_str(&["make", "lint"]);
+
+        let (policy, problems) = ExecPolicy::load(codex_home.path(), cwd.path(), false).unwrap();
+        assert_eq!(1, problems.len(), "{problems:?}");
+        assert!(problems[0].contains("not trusted"), "{problems:?}");
+        assert_eq!(
+            ExecPolicyAssessment::Unverified,
+            policy.assess(&command, &workspace_write_policy(), cwd.path())
+        );
+
+        let (policy, problems) = ExecPolicy::load(codex_home.path(), cwd.path(), true).unwrap();
+        assert_eq!(Vec::<String>::new(), problems);
+        assert_eq!(
+            ExecPolicyAssessment::ReadOnly,
+            policy.assess(&command, &workspace_write_policy(), cwd.path())
+        );
+    }
+
+    #[test]
+    fn invalid_policy_files_are_reported_and_skipped() {
+        let codex_home = TempDir::new().unwrap();
+        let cwd = TempDir::new().unwrap();
+        let policy_dir = codex_home.path().join(POLICY_DIR);
+        write_policy_file(&policy_dir, "a_syntax.policy", "define_program(");
+        write_policy_file(
+            &policy_dir,
+            "b_examples.policy",
+            r#"
+define_program(
+    program="pnpm",
+    args=["lint"],
+    should_match=[["test"]],
+    should_not_match=[["lint"]],
+)
+"#,
+        );
+        write_policy_file(
+            &policy_dir,
+            "c_valid.policy",
+            r#"define_program(program="cargo", args=["check"])"#,
+        );
+
+        let (policy, problems) = ExecPolicy::load(codex_home.path(), cwd.path(), true).unwrap();
+        assert_eq!(2, problems.len(), "{problems:?}");
+        assert!(problems[0].contains("a_syntax.policy"), "{problems:?}");
+        assert!(problems[1].contains("b_examples.policy"), "{problems:?}");
+        assert!(
+            problems[1].contains("`pnpm test` is listed in should_match"),
+            "{problems:?}"
+        );
+        assert!(
+            problems[1].contains("`pnpm lint` is listed in should_not_match"),
+            "{problems:?}"
+        );
+
+        let sandbox_policy = workspace_write_policy();
+        assert_eq!(
+            ExecPolicyAssessment::Unverified,
+            policy.assess(&vec_str(&["pnpm", "lint"]), &sandbox_policy, cwd.path())
+        );
+        assert_eq!(
+            ExecPolicyAssessment::ReadOnly,
+            policy.assess(&vec_str(&["cargo", "check"]), &sandbox_policy, cwd.path())
+        );
+    }
+}
@@ -0,0 +1,815 @@
+//! Long-lived interactive sessions behind the experimental `exec_command`,
+//! `write_stdin` and `kill_session` tools.
+//!
+//! Unlike the `shell` tool, which spawns a fresh process per call,
+//! `exec_command` starts a process on a pseudo-terminal and returns after
+//! `yield_time_ms` even if it is still running. The model can keep talking to
+//! it with `write_stdin`, so an interactive shell keeps its working directory,
+//! exported variables and activated virtualenvs, and servers keep running
+//! between calls. The process runs under the same sandbox as a `shell` call
+//! would, and input for a process that runs outside the sandbox needs the
+//! same approval as a command. The process is killed once it has been idle
+//! for too long, on `kill_session` or when the session shuts down.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_bytes::ByteBuf;
+use tokio::process::Child;
+use tokio::sync::Notify;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::exec::ExecParams;
+use crate::exec::SandboxType;
+use crate::exec::StdoutStream;
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::ExecCommandOutputDeltaEvent;
+use crate::protocol::ExecOutputStream;
+use crate::protocol::SandboxPolicy;
+use crate::spawn::exit_code_of;
+use crate::spawn::kill_process_group;
+use crate::util::MutexExt;
+
+pub(crate) const EXEC_COMMAND_TOOL_NAME: &str = "exec_command";
+pub(crate) const WRITE_STDIN_TOOL_NAME: &str = "write_stdin";
+pub(crate) const KILL_SESSION_TOOL_NAME: &str = "kill_session";
+
+const DEFAULT_YIELD_TIME_MS: u64 = 10_000;
+
+/// Upper bound for `yield_time_ms`, so that a call cannot hold up the turn
+/// for longer than a slow `shell` call would.
+const MAX_YIELD_TIME: Duration = Duration::from_secs(120);
+const DEFAULT_MAX_OUTPUT_TOKENS: u64 = 10_000;
+
+/// Rough number of bytes per token used to turn `max_output_tokens` into a
+/// byte limit.
+const BYTES_PER_TOKEN: u64 = 4;
+
+/// Sessions that receive no `exec_command` or `write_stdin` call for this long
+/// are killed.
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Output produced between two calls is buffered up to this many bytes; older
+/// output is dropped first.
+const MAX_BUFFERED_OUTPUT: usize = 1024 * 1024;
+
+/// Once the process has exited, how long to wait for the rest of its output
+/// (e.g. when a background child still holds the terminal).
+const EXIT_GRACE_PERIOD: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ExecCommandParams {
+    pub(crate) cmd: String,
+    #[serde(default)]
+    pub(crate) workdir: Option<String>,
+    #[serde(default)]
+    pub(crate) yield_time_ms: Option<u64>,
+    #[serde(default)]
+    pub(crate) max_output_tokens: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct WriteStdinParams {
+    pub(crate) session_id: u32,
+    pub(crate) chars: String,
+    #[serde(default)]
+    pub(crate) yield_time_ms: Option<u64>,
+    #[serde(default)]
+    pub(crate) max_output_tokens: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct KillSessionParams {
+    pub(crate) session_id: u32,
+}
+
+pub(crate) fn yield_time(yield_time_ms: Option<u64>) -> Duration {
+    Duration::from_millis(yield_time_ms.unwrap_or(DEFAULT_YIELD_TIME_MS)).min(MAX_YIELD_TIME)
+}
+
+fn output_properties() -> BTreeMap<String, JsonSchema> {
+    BTreeMap::from([
+        (
+            "yield_time_ms".to_string(),
+            JsonSchema::Number {
+                description: Some(format!(
+                    "How long to wait for output before returning, in milliseconds (default {DEFAULT_YIELD_TIME_MS}, at most {}). The process keeps running afterwards.",
+                    MAX_YIELD_TIME.as_millis()
+                )),
+            },
+        ),
+        (
+            "max_output_tokens".to_string(),
+            JsonSchema::Number {
+                description: Some(format!(
+                    "Maximum number of tokens of output to return (default {DEFAULT_MAX_OUTPUT_TOKENS}). Longer output is truncated in the middle."
+                )),
+            },
+        ),
+    ])
+}
+
+pub(crate) static EXEC_COMMAND_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let mut properties = output_properties();
+    properties.insert(
+        "cmd".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "The shell command to run, e.g. `npm run dev` or `bash -i`".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "workdir".to_string(),
+        JsonSchema::String {
+            description: Some("The working directory to run the command in".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: EXEC_COMMAND_TOOL_NAME.to_string(),
+        description: r#"Runs a command in a new terminal session and returns its output.
+If the command is still running after yield_time_ms, the result includes a session ID: use write_stdin to send it input or to read more output, and kill_session to stop it.
+To keep the working directory, environment variables or an activated virtualenv across commands, start an interactive shell (e.g. `bash -i`) and send it commands with write_stdin.
+"#
+        .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["cmd".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+pub(crate) static WRITE_STDIN_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let mut properties = output_properties();
+    properties.insert(
+        "session_id".to_string(),
+        JsonSchema::Number {
+            description: Some("The session ID returned by exec_command".to_string()),
+        },
+    );
+    properties.insert(
+        "chars".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Characters to write to the terminal; end commands with \"\\n\". May be empty to only read new output. Use \"\\u0003\" to send Ctrl-C."
+                    .to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: WRITE_STDIN_TOOL_NAME.to_string(),
+        description: "Writes characters to a running exec_command session and returns the output it produced since the last call.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["session_id".to_string(), "chars".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+pub(crate) static KILL_SESSION_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let properties = BTreeMap::from([(
+        "session_id".to_string(),
+        JsonSchema::Number {
+            description: Some("The session ID returned by exec_command".to_string()),
+        },
+    )]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: KILL_SESSION_TOOL_NAME.to_string(),
+        description: "Kills a running exec_command session and every process it started."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["session_id".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+/// Result of an `exec_command` or `write_stdin` call.
+#[derive(Debug)]
+pub(crate) struct ExecSessionOutput {
+    pub(crate) session_id: u32,
+    /// Output produced during the call.
+    pub(crate) output: String,
+    /// `None` if the process is still running.
+    pub(crate) exit_code: Option<i32>,
+    pub(crate) wall_time: Duration,
+}
+
+impl ExecSessionOutput {
+    pub(crate) fn format_for_model(&self, max_output_tokens: Option<u64>) -> String {
+        let max_bytes = max_output_tokens
+            .unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS)
+            .saturating_mul(BYTES_PER_TOKEN);
+        let status = match self.exit_code {
+            Some(code) => format!("Process exited with code {code}"),
+            None => format!("Process running with session ID {}", self.session_id),
+        };
+        format!(
+            "Wall time: {:.3} seconds\n{status}\nOutput:\n{}",
+            self.wall_time.as_secs_f64(),
+            truncate_middle(
+                &self.output,
+                usize::try_from(max_bytes).unwrap_or(usize::MAX)
+            )
+        )
+    }
+}
+
+/// Keeps the start and the end of `text`, which together are at most
+/// `max_bytes` long.
+pub(crate) fn truncate_middle(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+    let mut head_end = max_bytes / 2;
+    while !text.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    let mut tail_start = text.len() - max_bytes / 2;
+    while !text.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    format!(
+        "{}\n…{} bytes truncated…\n{}",
+        &text[..head_end],
+        tail_start - head_end,
+        &text[tail_start..]
+    )
+}
+
+/// The live sessions of one Codex session.
+pub(crate) struct ExecSessionManager {
+    next_session_id: AtomicU32,
+    sessions: Mutex<HashMap<u32, Arc<ExecSession>>>,
+    idle_timeout: Duration,
+}
+
+impl Default for ExecSessionManager {
+    fn default() -> Self {
+        Self::new(DEFAULT_IDLE_TIMEOUT)
+    }
+}
+
+impl ExecSessionManager {
+    pub(crate) fn new(idle_timeout: Duration) -> Self {
+        Self {
+            next_session_id: AtomicU32::new(1),
+            sessions: Mutex::new(HashMap::new()),
+            idle_timeout,
+        }
+    }
+
+    /// Starts `params.command` on a new terminal and collects its output for
+    /// up to `yield_time`.
+    pub(crate) async fn exec_command(
+        &self,
+        params: ExecParams,
+        sandbox_type: SandboxType,
+        sandbox_policy: &SandboxPolicy,
+        codex_linux_sandbox_exe: &Option<std::path::PathBuf>,
+        yield_time: Duration,
+        stdout_stream: Option<StdoutStream>,
+    ) -> Result<ExecSessionOutput> {
+        let start = Instant::now();
+        let session = Arc::new(
+            ExecSession::spawn(
+                params,
+                sandbox_type,
+                sandbox_policy,
+                codex_linux_sandbox_exe,
+                self.idle_timeout,
+            )
+            .await?,
+        );
+        let session_id = self.next_session_id.fetch_add(1, Ordering::SeqCst);
+        self.sessions
+            .lock_unchecked()
+            .insert(session_id, Arc::clone(&session));
+        Ok(self
+            .collect(session_id, &session, start, yield_time, stdout_stream)
+            .await)
+    }
+
+    /// Writes `chars` to the terminal of `session_id` and collects the output
+    /// produced since the previous call for up to `yield_time`.
+    pub(crate) async fn write_stdin(
+        &self,
+        session_id: u32,
+        chars: &str,
+        yield_time: Duration,
+        stdout_stream: Option<StdoutStream>,
+    ) -> Result<ExecSessionOutput> {
+        let start = Instant::now();
+        let session = self
+            .sessions
+            .lock_unchecked()
+            .get(&session_id)
+            .cloned()
+            .ok_or_else(|| {
+                CodexErr::Io(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no running session with ID {session_id}"),
+                ))
+            })?;
+        session.touch();
+        if !chars.is_empty() {
+            let master = Arc::clone(&session.master);
+            let bytes = chars.as_bytes().to_vec();
+            tokio::task::spawn_blocking(move || (&*master).write_all(&bytes)).await??;
+        }
+        Ok(self
+            .collect(session_id, &session, start, yield_time, stdout_stream)
+            .await)
+    }
+
+    /// Returns the sandbox `session_id` runs under, or `None` if there is no
+    /// session with this ID.
+    pub(crate) fn sandbox_type(&self, session_id: u32) -> Option<SandboxType> {
+        self.sessions
+            .lock_unchecked()
+            .get(&session_id)
+            .map(|session| session.sandbox_type)
+    }
+
+    /// Returns `false` if there is no session with this ID.
+    pub(crate) fn kill(&self, session_id: u32) -> bool {
+        // Dropping the last reference kills the process group.
+        self.sessions.lock_unchecked().remove(&session_id).is_some()
+    }
+
+    /// Kills every session.
+    pub(crate) fn shutdown(&self) {
+        self.sessions.lock_unchecked().clear();
+    }
+
+    async fn collect(
+        &self,
+        session_id: u32,
+        session: &ExecSession,
+        start: Instant,
+        yield_time: Duration,
+        stdout_stream: Option<StdoutStream>,
+    ) -> ExecSessionOutput {
+        let (output, exit_code) = session
+            .collect_output(start + yield_time, stdout_stream.as_ref())
+            .await;
+        session.touch();
+        if exit_code.is_some() {
+            self.sessions.lock_unchecked().remove(&session_id);
+        }
+        ExecSessionOutput {
+            session_id,
+            output: String::from_utf8_lossy(&output).replace("\r\n", "\n"),
+            exit_code,
+            wall_time: start.elapsed(),
+        }
+    }
+}
+
+/// Output read from the terminal that no call has returned yet.
+#[derive(Default)]
+struct OutputBuffer {
+    bytes: Vec<u8>,
+    /// The terminal was closed: no more output will arrive.
+    closed: bool,
+}
+
+#[derive(Default)]
+struct SessionOutput {
+    buffer: Mutex<OutputBuffer>,
+    notify: Notify,
+}
+
+impl SessionOutput {
+    fn push(&self, bytes: &[u8]) {
+        {
+            let mut buffer = self.buffer.lock_unchecked();
+            buffer.bytes.extend_from_slice(bytes);
+            let len = buffer.bytes.len();
+            if len > MAX_BUFFERED_OUTPUT {
+                buffer.bytes.drain(..len - MAX_BUFFERED_OUTPUT);
+            }
+        }
+        self.notify.notify_one();
+    }
+
+    fn close(&self) {
+        self.buffer.lock_unchecked().closed = true;
+        self.notify.notify_one();
+    }
+
+    fn take(&self) -> (Vec<u8>, bool) {
+        let mut buffer = self.buffer.lock_unchecked();
+        (std::mem::take(&mut buffer.bytes), buffer.closed)
+    }
+}
+
+struct ExecSession {
+    master: Arc<File>,
+    output: Arc<SessionOutput>,
+    exit_code: watch::Receiver<Option<i32>>,
+    /// Also the ID of the process group, which the process leads.
+    pid: Option<u32>,
+    last_used: Arc<Mutex<Instant>>,
+    sandbox_type: SandboxType,
+}
+
+impl ExecSession {
+    #[cfg(unix)]
+    async fn spawn(
+        params: ExecParams,
+        sandbox_type: SandboxType,
+        sandbox_policy: &SandboxPolicy,
+        codex_linux_sandbox_exe: &Option<std::path::PathBuf>,
+        idle_timeout: Duration,
+    ) -> Result<Self> {
+        use std::os::fd::AsRawFd;
+
+        use crate::landlock::spawn_command_under_linux_sandbox;
+        use crate::seatbelt::spawn_command_under_seatbelt;
+        use crate::spawn::StdioPolicy;
+        use crate::spawn::spawn_child_async;
+
+        let pty = crate::pty::open_pty()?;
+        let stdio_policy = StdioPolicy::Pty(pty.slave.as_raw_fd());
+        let ExecParams {
+            command, cwd, env, ..
+        } = params;
+        let child = match sandbox_type {
+            SandboxType::None => {
+                let (program, args) = command.split_first().ok_or_else(|| {
+                    CodexErr::Io(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "command args are empty",
+                    ))
+                })?;
+                spawn_child_async(
+                    program.into(),
+                    args.into(),
+                    None,
+                    cwd,
+                    sandbox_policy,
+                    stdio_policy,
+                    env,
+                )
+                .await?
+            }
+            SandboxType::MacosSeatbelt => {
+                spawn_command_under_seatbelt(command, sandbox_policy, cwd, stdio_policy, env)
+                    .await?
+            }
+            SandboxType::LinuxSeccomp => {
+                let codex_linux_sandbox_exe = codex_linux_sandbox_exe
+                    .as_ref()
+                    .ok_or(CodexErr::LandlockSandboxExecutableNotProvided)?;
+                spawn_command_under_linux_sandbox(
+                    codex_linux_sandbox_exe,
+                    command,
+                    sandbox_policy,
+                    cwd,
+                    stdio_policy,
+                    env,
+                )
+                .await?
+            }
+        };
+        // Only the child may hold the slave, so that reading the master fails
+        // once every process on the terminal has exited.
+        drop(pty.slave);
+
+        let master = File::from(pty.master);
+        let output = Arc::new(SessionOutput::default());
+        spawn_reader(master.try_clone()?, Arc::clone(&output));
+
+        let pid = child.id();
+        let last_used = Arc::new(Mutex::new(Instant::now()));
+        let (exit_tx, exit_code) = watch::channel(None);
+        tokio::spawn(wait_for_exit(
+            child,
+            pid,
+            Arc::clone(&last_used),
+            idle_timeout,
+            exit_tx,
+        ));
+
+        Ok(Self {
+            master: Arc::new(master),
+            output,
+            exit_code,
+            pid,
+            last_used,
+            sandbox_type,
+        })
+    }
+
+    #[cfg(not(unix))]
+    async fn spawn(
+        _params: ExecParams,
+        _sandbox_type: SandboxType,
+        _sandbox_policy: &SandboxPolicy,
+        _codex_linux_sandbox_exe: &Option<std::path::PathBuf>,
+        _idle_timeout: Duration,
+    ) -> Result<Self> {
+        Err(CodexErr::Io(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "terminal sessions are only supported on Unix",
+        )))
+    }
+
+    fn touch(&self) {
+        *self.last_used.lock_unchecked() = Instant::now();
+    }
+
+    /// Returns the output that arrives until `deadline` or until the process
+    /// exits, streaming it as `ExecCommandOutputDelta` events, along with the
+    /// exit code if the process has exited.
+    async fn collect_output(
+        &self,
+        mut deadline: Instant,
+        stdout_stream: Option<&StdoutStream>,
+    ) -> (Vec<u8>, Option<i32>) {
+        let mut exit_code = self.exit_code.clone();
+        let mut collected = Vec::new();
+        let mut exited = false;
+        loop {
+            let (chunk, closed) = self.output.take();
+            if !chunk.is_empty() {
+                if let Some(stream) = stdout_stream {
+                    send_output_delta(stream, &chunk).await;
+                }
+                collected.extend_from_slice(&chunk);
+            }
+
+            if !exited && exit_code.borrow().is_some() {
+                exited = true;
+                deadline = deadline.min(Instant::now() + EXIT_GRACE_PERIOD);
+            }
+            if exited && closed {
+                break;
+            }
+
+            tokio::select! {
+                _ = self.output.notify.notified() => {}
+                _ = exit_code.changed(), if !exited => {}
+                _ = tokio::time::sleep_until(deadline) => break,
+            }
+        }
+
+        let (chunk, _) = self.output.take();
+        if !chunk.is_empty() {
+            if let Some(stream) = stdout_stream {
+                send_output_delta(stream, &chunk).await;
+            }
+            collected.extend_from_slice(&chunk);
+        }
+        let exit_code = *exit_code.borrow();
+        (collected, exit_code)
+    }
+}
+
+impl Drop for ExecSession {
+    fn drop(&mut self) {
+        if self.exit_code.borrow().is_none()
+            && let Some(pid) = self.pid
+        {
+            kill_process_group(pid);
+        }
+    }
+}
+
+/// Moves everything written to the terminal into `output` until the terminal
+/// is closed. Reads block, so this runs on its own thread.
+fn spawn_reader(mut master: File, output: Arc<SessionOutput>) {
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            match master.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => output.push(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                // Linux reports EIO once no process holds the terminal.
+                Err(_) => break,
+            }
+        }
+        output.close();
+    });
+}
+
+/// Waits for the process to exit, killing it once it has been idle for
+/// `idle_timeout`, and publishes its exit code.
+async fn wait_for_exit(
+    mut child: Child,
+    pid: Option<u32>,
+    last_used: Arc<Mutex<Instant>>,
+    idle_timeout: Duration,
+    exit_tx: watch::Sender<Option<i32>>,
+) {
+    let status = loop {
+        let idle_deadline = *last_used.lock_unchecked() + idle_timeout;
+        tokio::select! {
+            status = child.wait() => break status,
+            _ = tokio::time::sleep_until(idle_deadline) => {
+                if last_used.lock_unchecked().elapsed() >= idle_timeout {
+                    if let Some(pid) = pid {
+                        kill_process_group(pid);
+                    }
+                    break child.wait().await;
+                }
+            }
+        }
+    };
+    let exit_code = match status {
+        Ok(status) => exit_code_of(status),
+        Err(_) => -1,
+    };
+    let _ = exit_tx.send(Some(exit_code));
+}
+
+async fn send_output_delta(stream: &StdoutStream, chunk: &[u8]) {
+    let event = Event {
+        id: stream.sub_id.clone(),
+        msg: EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent {
+            call_id: stream.call_id.clone(),
+            stream: ExecOutputStream::Stdout,
+            chunk: ByteBuf::from(chunk.to_vec()),
+        }),
+    };
+    let _ = stream.tx_event.send(event).await;
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn params(cmd: &str) -> ExecParams {
+        ExecParams {
+            command: vec!["bash".to_string(), "-c".to_string(), cmd.to_string()],
+            cwd: std::env::temp_dir(),
+            timeout_ms: None,
+            env: HashMap::from([
+                (
+                    "PATH".to_string(),
+                    std::env::var("PATH").unwrap_or_default(),
+                ),
+                ("PS1".to_string(), "$ ".to_string()),
+            ]),
+            with_escalated_permissions: None,
+            justification: None,
+        }
+    }
+
+    async fn exec(manager: &ExecSessionManager, cmd: &str, yield_ms: u64) -> ExecSessionOutput {
+        manager
+            .exec_command(
+                params(cmd),
+                SandboxType::None,
+                &SandboxPolicy::DangerFullAccess,
+                &None,
+                Duration::from_millis(yield_ms),
+                None,
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn returns_once_the_command_exits() {
+        let manager = ExecSessionManager::default();
+        let output = exec(&manager, "echo hello; exit 3", 10_000).await;
+
+        assert_eq!(Some(3), output.exit_code);
+        assert_eq!("hello\n", output.output);
+        assert!(output.wall_time < Duration::from_secs(5));
+        assert!(manager.sessions.lock_unchecked().is_empty());
+    }
+
+    #[tokio::test]
+    async fn interactive_shell_keeps_state_between_calls() {
+        let manager = ExecSessionManager::default();
+        let started = exec(&manager, "exec bash --norc --noprofile -i", 300).await;
+        assert_eq!(None, started.exit_code);
+        let session_id = started.session_id;
+
+        let write = |chars: &'static str| {
+            manager.write_stdin(session_id, chars, Duration::from_millis(500), None)
+        };
+        write("cd /tmp && export GREETING=persisted\n")
+            .await
+            .unwrap();
+        let output = write("echo \"$PWD $GREETING\"\n").await.unwrap();
+        assert!(
+            output.output.contains("/tmp persisted"),
+            "output: {:?}",
+            output.output
+        );
+
+        let output = write("exit 7\n").await.unwrap();
+        assert_eq!(Some(7), output.exit_code);
+        assert!(
+            manager
+                .write_stdin(session_id, "", Duration::ZERO, None)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn kill_stops_the_whole_process_group() {
+        let manager = ExecSessionManager::default();
+        let started = exec(&manager, "sleep 600 & sleep 600", 100).await;
+        assert_eq!(None, started.exit_code);
+
+        let session = manager
+            .sessions
+            .lock_unchecked()
+            .get(&started.session_id)
+            .cloned()
+            .unwrap();
+        let mut exit_code = session.exit_code.clone();
+        assert!(manager.kill(started.session_id));
+        drop(session);
+
+        exit_code.wait_for(Option::is_some).await.unwrap();
+        assert_eq!(Some(128 + libc::SIGKILL), *exit_code.borrow());
+        assert!(!manager.kill(started.session_id));
+    }
+
+    #[tokio::test]
+    async fn remembers_the_sandbox_of_each_session() {
+        let manager = ExecSessionManager::default();
+        let started = exec(&manager, "sleep 600", 50).await;
+
+        assert_eq!(
+            Some(SandboxType::None),
+            manager.sandbox_type(started.session_id)
+        );
+        assert!(manager.kill(started.session_id));
+        assert_eq!(None, manager.sandbox_type(started.session_id));
+    }
+
+    #[tokio::test]
+    async fn idle_sessions_are_killed() {
+        let manager = ExecSessionManager::new(Duration::from_millis(200));
+        let started = exec(&manager, "sleep 600", 50).await;
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let output = manager
+            .write_stdin(started.session_id, "", Duration::from_secs(5), None)
+            .await
+            .unwrap();
+        assert_eq!(Some(128 + libc::SIGKILL), output.exit_code);
+    }
+
+    #[test]
+    fn formats_output_for_the_model() {
+        let output = ExecSessionOutput {
+            session_id: 4,
+            output: "0123456789".to_string(),
+            exit_code: None,
+            wall_time: Duration::from_millis(1500),
+        };
+        assert_eq!(
+            "Wall time: 1.500 seconds\nProcess running with session ID 4\nOutput:\n0123\n…2 bytes truncated…\n6789",
+            output.format_for_model(Some(2))
+        );
+    }
+
+    #[test]
+    fn yield_time_is_capped() {
+        assert_eq!(
+            Duration::from_millis(DEFAULT_YIELD_TIME_MS),
+            yield_time(None)
+        );
+        assert_eq!(Duration::from_millis(500), yield_time(Some(500)));
+        assert_eq!(MAX_YIELD_TIME, yield_time(Some(u64::MAX)));
+    }
+}
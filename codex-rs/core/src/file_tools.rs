@@ -0,0 +1,746 @@
+//! Built-in `read_file`, `grep` and `list_dir` tools. They run in-process
+//! instead of spawning a shell, so they need no approval; in exchange they
+//! only read paths that are readable under the session's sandbox policy.
+//! Each call is reported as a `FileToolCallBegin`/`FileToolCallEnd` pair so
+//! that clients can show exactly what was read.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use grep_regex::RegexMatcher;
+use grep_searcher::BinaryDetection;
+use grep_searcher::SearcherBuilder;
+use grep_searcher::sinks::Lossy;
+use ignore::WalkBuilder;
+use ignore::overrides::OverrideBuilder;
+use serde::Deserialize;
+
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+use crate::protocol::FileToolCallSummary;
+use crate::protocol::FileToolInvocation;
+use crate::protocol::SandboxPolicy;
+
+pub(crate) const READ_FILE_TOOL_NAME: &str = "read_file";
+pub(crate) const GREP_TOOL_NAME: &str = "grep";
+pub(crate) const LIST_DIR_TOOL_NAME: &str = "list_dir";
+
+/// Maximum number of lines returned by one `read_file` call.
+const MAX_READ_LINES: usize = 2000;
+
+/// Maximum number of bytes returned by one call of any of the tools.
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// Longer lines are cut off in the output.
+const MAX_LINE_LENGTH: usize = 500;
+
+/// `read_file` keeps this many bytes of each line: enough for
+/// [`MAX_LINE_LENGTH`] characters and one more to tell that the line is
+/// longer.
+const MAX_LINE_BYTES: usize = (MAX_LINE_LENGTH + 1) * 4;
+
+const MAX_GREP_MATCHES: usize = 200;
+
+/// Larger files are skipped by `grep`.
+const MAX_GREP_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+const MAX_LIST_ENTRIES: usize = 500;
+
+const DEFAULT_LIST_DEPTH: usize = 1;
+const MAX_LIST_DEPTH: usize = 5;
+
+#[derive(Debug, Deserialize)]
+struct ReadFileParams {
+    path: String,
+    #[serde(default)]
+    start_line: Option<usize>,
+    #[serde(default)]
+    end_line: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GrepParams {
+    pattern: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    include: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListDirParams {
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    depth: Option<usize>,
+}
+
+pub(crate) static READ_FILE_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let properties = BTreeMap::from([
+        (
+            "path".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "Path of the file, relative to the working directory".to_string(),
+                ),
+            },
+        ),
+        (
+            "start_line".to_string(),
+            JsonSchema::Number {
+                description: Some("1-based line to start reading at".to_string()),
+            },
+        ),
+        (
+            "end_line".to_string(),
+            JsonSchema::Number {
+                description: Some("1-based line to stop reading at (inclusive)".to_string()),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: READ_FILE_TOOL_NAME.to_string(),
+        description: format!(
+            "Reads a text file and returns its lines prefixed with their line numbers. Returns at most {MAX_READ_LINES} lines per call; use start_line and end_line to read a specific range. Prefer this over running cat, head or sed in the shell."
+        ),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["path".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+pub(crate) static GREP_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let properties = BTreeMap::from([
+        (
+            "pattern".to_string(),
+            JsonSchema::String {
+                description: Some("Regular expression to search for".to_string()),
+            },
+        ),
+        (
+            "path".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "File or directory to search, relative to the working directory. Defaults to the working directory"
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "include".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "Only search files whose names match this glob, e.g. \"*.rs\"".to_string(),
+                ),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: GREP_TOOL_NAME.to_string(),
+        description: format!(
+            "Searches files for lines matching a regular expression and returns them as path:line:text. Files ignored by .gitignore are skipped. Returns at most {MAX_GREP_MATCHES} matches. Prefer this over running grep or rg in the shell."
+        ),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["pattern".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+pub(crate) static LIST_DIR_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let properties = BTreeMap::from([
+        (
+            "path".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "Directory to list, relative to the working directory. Defaults to the working directory"
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "depth".to_string(),
+            JsonSchema::Number {
+                description: Some(format!(
+                    "How many levels of subdirectories to list, from 1 to {MAX_LIST_DEPTH}. Defaults to {DEFAULT_LIST_DEPTH}"
+                )),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: LIST_DIR_TOOL_NAME.to_string(),
+        description: "Lists the files and directories in a directory; directories end with a slash. Files ignored by .gitignore are skipped. Prefer this over running ls or find in the shell.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false),
+        },
+    })
+});
+
+/// Result of a successful call.
+#[derive(Debug, PartialEq)]
+pub(crate) struct FileToolOutput {
+    /// Sent to the model.
+    pub(crate) content: String,
+    pub(crate) summary: FileToolCallSummary,
+}
+
+/// Parses the arguments of a call to `tool_name` and resolves its paths
+/// against `cwd`.
+pub(crate) fn parse_file_tool_call(
+    tool_name: &str,
+    arguments: &str,
+    cwd: &Path,
+) -> Result<FileToolInvocation, serde_json::Error> {
+    let resolve = |path: Option<String>| path.map_or_else(|| cwd.to_path_buf(), |p| cwd.join(p));
+    let invocation = match tool_name {
+        READ_FILE_TOOL_NAME => {
+            let params: ReadFileParams = serde_json::from_str(arguments)?;
+            FileToolInvocation::ReadFile {
+                path: resolve(Some(params.path)),
+                start_line: params.start_line,
+                end_line: params.end_line,
+            }
+        }
+        GREP_TOOL_NAME => {
+            let params: GrepParams = serde_json::from_str(arguments)?;
+            FileToolInvocation::Grep {
+                pattern: params.pattern,
+                path: resolve(params.path),
+                include: params.include,
+            }
+        }
+        LIST_DIR_TOOL_NAME => {
+            let params: ListDirParams = serde_json::from_str(arguments)?;
+            FileToolInvocation::ListDir {
+                path: resolve(params.path),
+                depth: params
+                    .depth
+                    .unwrap_or(DEFAULT_LIST_DEPTH)
+                    .clamp(1, MAX_LIST_DEPTH),
+            }
+        }
+        other => {
+            return Err(serde::de::Error::custom(format!(
+                "unknown file tool: {other}"
+            )));
+        }
+    };
+    Ok(invocation)
+}
+
+/// Runs `invocation`. Blocks on file-system access, so call it from a
+/// blocking task.
+pub(crate) fn run_file_tool(
+    invocation: &FileToolInvocation,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+) -> Result<FileToolOutput, String> {
+    match invocation {
+        FileToolInvocation::ReadFile {
+            path,
+            start_line,
+            end_line,
+        } => {
+            check_readable(path, sandbox_policy, cwd)?;
+            read_file(path, *start_line, *end_line)
+        }
+        FileToolInvocation::Grep {
+            pattern,
+            path,
+            include,
+        } => {
+            check_readable(path, sandbox_policy, cwd)?;
+            grep(pattern, path, include.as_deref(), cwd)
+        }
+        FileToolInvocation::ListDir { path, depth } => {
+            check_readable(path, sandbox_policy, cwd)?;
+            list_dir(path, *depth)
+        }
+    }
+}
+
+/// Fails unless `path` exists and, once symlinks are resolved, lies within
+/// the folders readable under `sandbox_policy`.
+fn check_readable(path: &Path, sandbox_policy: &SandboxPolicy, cwd: &Path) -> Result<(), String> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    if sandbox_policy.has_full_disk_read_access() {
+        return Ok(());
+    }
+    let readable = sandbox_policy
+        .get_readable_roots_with_cwd(cwd)
+        .iter()
+        .any(|root| {
+            root.canonicalize()
+                .is_ok_and(|root| canonical.starts_with(root))
+        });
+    if readable {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} is not readable under the sandbox policy",
+            path.display()
+        ))
+    }
+}
+
+/// Reads the requested lines without loading the whole file: reading stops
+/// at `end_line` or once the output is full, so the total number of lines is
+/// only reported when the end of the file was reached.
+fn read_file(
+    path: &Path,
+    start_line: Option<usize>,
+    end_line: Option<usize>,
+) -> Result<FileToolOutput, String> {
+    if path.is_dir() {
+        return Err(format!(
+            "{} is a directory; use {LIST_DIR_TOOL_NAME} to list it",
+            path.display()
+        ));
+    }
+    let io_error = |e: io::Error| format!("{}: {e}", path.display());
+    let mut reader = BufReader::new(File::open(path).map_err(io_error)?);
+    if is_binary(reader.fill_buf().map_err(io_error)?) {
+        return Err(format!("{} is a binary file", path.display()));
+    }
+
+    let start = start_line.unwrap_or(1).max(1);
+    let end = end_line.unwrap_or(usize::MAX);
+    let no_lines = |content: String| FileToolOutput {
+        content,
+        summary: FileToolCallSummary {
+            count: 0,
+            truncated: false,
+        },
+    };
+    if start > end {
+        return Ok(no_lines(format!(
+            "No lines in range: start_line {start} is after end_line {end}."
+        )));
+    }
+
+    let mut content = String::new();
+    let mut line = Vec::new();
+    let mut line_number = 0;
+    let mut last = start - 1;
+    // Whether lines after `last` were left unread.
+    let mut more = false;
+    while read_line(&mut reader, &mut line).map_err(io_error)? {
+        line_number += 1;
+        if line_number < start {
+            continue;
+        }
+        if line_number > end
+            || line_number - start >= MAX_READ_LINES
+            || content.len() >= MAX_OUTPUT_BYTES
+        {
+            more = true;
+            break;
+        }
+        let text = String::from_utf8_lossy(&line);
+        content.push_str(&format!(
+            "{line_number:>6}\t{}\n",
+            truncate_line(text.trim_end_matches('\r'))
+        ));
+        last = line_number;
+    }
+    if last < start {
+        return Ok(no_lines(format!(
+            "No lines in range: {} has {line_number} lines.",
+            path.display()
+        )));
+    }
+
+    if more {
+        content.push_str(&format!("[Lines {start}-{last}; more lines follow]"));
+    } else if start > 1 {
+        content.push_str(&format!("[Lines {start}-{last} of {last}]"));
+    }
+    Ok(FileToolOutput {
+        content,
+        summary: FileToolCallSummary {
+            count: last + 1 - start,
+            truncated: more && last < end,
+        },
+    })
+}
+
+/// Reads the next line into `line` without its line break, keeping at most
+/// [`MAX_LINE_BYTES`] of it. Returns `false` at the end of the file.
+fn read_line(reader: &mut impl BufRead, line: &mut Vec<u8>) -> io::Result<bool> {
+    line.clear();
+    let mut read_any = false;
+    loop {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
+            return Ok(read_any);
+        }
+        read_any = true;
+        let newline = available.iter().position(|&b| b == b'\n');
+        let chunk = &available[..newline.unwrap_or(available.len())];
+        let room = MAX_LINE_BYTES.saturating_sub(line.len());
+        line.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        let consumed = newline.map_or(available.len(), |index| index + 1);
+        reader.consume(consumed);
+        if newline.is_some() {
+            return Ok(true);
+        }
+    }
+}
+
+fn grep(
+    pattern: &str,
+    path: &Path,
+    include: Option<&str>,
+    cwd: &Path,
+) -> Result<FileToolOutput, String> {
+    let matcher =
+        RegexMatcher::new_line_matcher(pattern).map_err(|e| format!("invalid pattern: {e}"))?;
+    let mut walker = walk_builder(path);
+    if let Some(include) = include {
+        let overrides = OverrideBuilder::new(path)
+            .add(include)
+            .and_then(|builder| builder.build())
+            .map_err(|e| format!("invalid include glob: {e}"))?;
+        walker.overrides(overrides);
+    }
+    // Like git, skip files with a NUL byte near the start.
+    let mut searcher = SearcherBuilder::new()
+        .binary_detection(BinaryDetection::quit(0))
+        .line_number(true)
+        .build();
+
+    let mut content = String::new();
+    let mut count = 0;
+    let mut truncated = false;
+    for entry in walker.build().flatten() {
+        if !entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file())
+            || entry
+                .metadata()
+                .is_ok_and(|metadata| metadata.len() > MAX_GREP_FILE_SIZE)
+        {
+            continue;
+        }
+        let display = display_path(entry.path(), cwd).display().to_string();
+        let sink = Lossy(|line_number, line: &str| {
+            if count >= MAX_GREP_MATCHES || content.len() >= MAX_OUTPUT_BYTES {
+                truncated = true;
+                return Ok(false);
+            }
+            content.push_str(&format!(
+                "{display}:{line_number}:{}\n",
+                truncate_line(line.trim_end_matches(['\r', '\n']))
+            ));
+            count += 1;
+            Ok(true)
+        });
+        // Unreadable files are skipped.
+        let _ = searcher.search_path(&matcher, entry.path(), sink);
+        if truncated {
+            break;
+        }
+    }
+
+    if count == 0 {
+        content.push_str("No matches found.");
+    } else if truncated {
+        content.push_str(&format!("[Stopped after {count} matches]"));
+    }
+    Ok(FileToolOutput {
+        content,
+        summary: FileToolCallSummary { count, truncated },
+    })
+}
+
+fn list_dir(path: &Path, depth: usize) -> Result<FileToolOutput, String> {
+    if !path.is_dir() {
+        return Err(format!("{} is not a directory", path.display()));
+    }
+    let mut walker = walk_builder(path);
+    walker.max_depth(Some(depth));
+
+    let mut content = String::new();
+    let mut count = 0;
+    let mut truncated = false;
+    for entry in walker.build().flatten() {
+        if entry.depth() == 0 {
+            continue;
+        }
+        if count >= MAX_LIST_ENTRIES || content.len() >= MAX_OUTPUT_BYTES {
+            truncated = true;
+            break;
+        }
+        let relative = entry.path().strip_prefix(path).unwrap_or(entry.path());
+        let suffix = if entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_dir())
+        {
+            "/"
+        } else {
+            ""
+        };
+        content.push_str(&format!("{}{suffix}\n", relative.display()));
+        count += 1;
+    }
+
+    if count == 0 {
+        content.push_str("The directory is empty.");
+    } else if truncated {
+        content.push_str(&format!("[Stopped after {count} entries]"));
+    }
+    Ok(FileToolOutput {
+        content,
+        summary: FileToolCallSummary { count, truncated },
+    })
+}
+
+/// Walks `path` in a stable order, skipping `.git` and files ignored by git
+/// but not other hidden files.
+fn walk_builder(path: &Path) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(path);
+    builder
+        .hidden(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .sort_by_file_name(|a, b| a.cmp(b));
+    builder
+}
+
+fn display_path<'a>(path: &'a Path, cwd: &Path) -> &'a Path {
+    path.strip_prefix(cwd).unwrap_or(path)
+}
+
+/// Like git, treats content with a NUL byte near the start as binary.
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8192).any(|&b| b == 0)
+}
+
+fn truncate_line(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_LENGTH) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn workspace() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/nested")).unwrap();
+        std::fs::write(root.join("README.md"), "# demo\nfn main is in src\n").unwrap();
+        std::fs::write(
+            root.join("src/main.rs"),
+            "fn main() {\n    println!(\"hi\");\n}\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/nested/lib.rs"), "pub fn lib() {}\n").unwrap();
+        std::fs::write(root.join("image.bin"), b"fn \0\x01").unwrap();
+        dir
+    }
+
+    fn run(tool_name: &str, arguments: &str, cwd: &Path) -> Result<FileToolOutput, String> {
+        let invocation = parse_file_tool_call(tool_name, arguments, cwd).unwrap();
+        run_file_tool(&invocation, &SandboxPolicy::DangerFullAccess, cwd)
+    }
+
+    fn summary(count: usize, truncated: bool) -> FileToolCallSummary {
+        FileToolCallSummary { count, truncated }
+    }
+
+    #[test]
+    fn reads_numbered_lines_in_a_range() {
+        let dir = workspace();
+        assert_eq!(
+            FileToolOutput {
+                content: "     1\tfn main() {\n     2\t    println!(\"hi\");\n     3\t}\n"
+                    .to_string(),
+                summary: summary(3, false),
+            },
+            run(
+                READ_FILE_TOOL_NAME,
+                r#"{"path": "src/main.rs"}"#,
+                dir.path()
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            FileToolOutput {
+                content: "     2\t    println!(\"hi\");\n[Lines 2-2; more lines follow]"
+                    .to_string(),
+                summary: summary(1, false),
+            },
+            run(
+                READ_FILE_TOOL_NAME,
+                r#"{"path": "src/main.rs", "start_line": 2, "end_line": 2}"#,
+                dir.path()
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            Err(format!(
+                "{} is a binary file",
+                dir.path().join("image.bin").display()
+            )),
+            run(READ_FILE_TOOL_NAME, r#"{"path": "image.bin"}"#, dir.path())
+        );
+    }
+
+    #[test]
+    fn stops_reading_at_the_line_limit() {
+        let dir = TempDir::new().unwrap();
+        let text: String = (1..=MAX_READ_LINES + 5)
+            .map(|i| format!("line {i}\r\n"))
+            .collect();
+        std::fs::write(dir.path().join("long.txt"), text).unwrap();
+
+        let output = run(READ_FILE_TOOL_NAME, r#"{"path": "long.txt"}"#, dir.path()).unwrap();
+        assert_eq!(summary(MAX_READ_LINES, true), output.summary);
+        assert!(
+            output.content.ends_with(&format!(
+                "\tline {MAX_READ_LINES}\n[Lines 1-{MAX_READ_LINES}; more lines follow]"
+            )),
+            "{}",
+            output.content
+        );
+
+        let arguments = format!(r#"{{"path": "long.txt", "start_line": {MAX_READ_LINES}}}"#);
+        let output = run(READ_FILE_TOOL_NAME, &arguments, dir.path()).unwrap();
+        assert_eq!(summary(6, false), output.summary);
+        assert!(
+            output.content.ends_with(&format!(
+                "\tline {}\n[Lines {MAX_READ_LINES}-{} of {}]",
+                MAX_READ_LINES + 5,
+                MAX_READ_LINES + 5,
+                MAX_READ_LINES + 5
+            )),
+            "{}",
+            output.content
+        );
+    }
+
+    #[test]
+    fn cuts_off_long_lines() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("wide.txt"),
+            format!("{}\nnext\n", "é".repeat(MAX_LINE_BYTES)),
+        )
+        .unwrap();
+
+        assert_eq!(
+            format!("     1\t{}…\n     2\tnext\n", "é".repeat(MAX_LINE_LENGTH)),
+            run(READ_FILE_TOOL_NAME, r#"{"path": "wide.txt"}"#, dir.path())
+                .unwrap()
+                .content
+        );
+    }
+
+    #[test]
+    fn greps_text_files_in_order() {
+        let dir = workspace();
+        assert_eq!(
+            FileToolOutput {
+                content: "README.md:2:fn main is in src\nsrc/main.rs:1:fn main() {\nsrc/nested/lib.rs:1:pub fn lib() {}\n".to_string(),
+                summary: summary(3, false),
+            },
+            run(GREP_TOOL_NAME, r#"{"pattern": "fn \\w+"}"#, dir.path()).unwrap()
+        );
+        assert_eq!(
+            FileToolOutput {
+                content: "src/main.rs:1:fn main() {\nsrc/nested/lib.rs:1:pub fn lib() {}\n"
+                    .to_string(),
+                summary: summary(2, false),
+            },
+            run(
+                GREP_TOOL_NAME,
+                r#"{"pattern": "fn", "path": "src", "include": "*.rs"}"#,
+                dir.path()
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            "No matches found.",
+            run(GREP_TOOL_NAME, r#"{"pattern": "missing"}"#, dir.path())
+                .unwrap()
+                .content
+        );
+    }
+
+    #[test]
+    fn lists_directories_to_the_requested_depth() {
+        let dir = workspace();
+        assert_eq!(
+            FileToolOutput {
+                content: "README.md\nimage.bin\nsrc/\n".to_string(),
+                summary: summary(3, false),
+            },
+            run(LIST_DIR_TOOL_NAME, "{}", dir.path()).unwrap()
+        );
+        assert_eq!(
+            FileToolOutput {
+                content: "main.rs\nnested/\nnested/lib.rs\n".to_string(),
+                summary: summary(3, false),
+            },
+            run(
+                LIST_DIR_TOOL_NAME,
+                r#"{"path": "src", "depth": 2}"#,
+                dir.path()
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn refuses_paths_outside_of_the_readable_roots() {
+        let cwd = workspace();
+        let outside = workspace();
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            readable_roots: Some(vec![]),
+            network_access: false,
+            network_allowlist: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+        let read = |path: &Path| {
+            let arguments = serde_json::json!({ "path": path }).to_string();
+            let invocation =
+                parse_file_tool_call(READ_FILE_TOOL_NAME, &arguments, cwd.path()).unwrap();
+            run_file_tool(&invocation, &policy, cwd.path())
+        };
+
+        assert!(read(&cwd.path().join("src/main.rs")).is_ok());
+        let outside_file = outside.path().join("src/main.rs");
+        assert_eq!(
+            Err(format!(
+                "{} is not readable under the sandbox policy",
+                outside_file.display()
+            )),
+            read(&outside_file)
+        );
+    }
+}
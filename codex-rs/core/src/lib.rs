@@ -5,13 +5,17 @@
 // the TUI or the tracing stack).
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
+mod anthropic_messages;
 mod apply_patch;
+mod background_jobs;
 mod bash;
 mod chat_completions;
+mod checkpoint;
 mod client;
 mod client_common;
 pub mod codex;
 mod codex_conversation;
+mod compact;
 pub use codex_conversation::CodexConversation;
 pub mod config;
 pub mod config_profile;
@@ -21,14 +25,21 @@ mod environment_context;
 pub mod error;
 pub mod exec;
 pub mod exec_env;
+mod exec_output;
+mod exec_policy;
+mod exec_session;
+mod file_tools;
 mod flags;
 pub mod git_info;
 mod is_safe_command;
 pub mod landlock;
 mod mcp_connection_manager;
+mod mcp_resources;
 mod mcp_tool_call;
 mod message_history;
 mod model_provider_info;
+mod network_proxy;
+pub use network_proxy::CODEX_NETWORK_PROXY_SOCKET_ENV_VAR;
 pub mod parse_command;
 pub use model_provider_info::BUILT_IN_OSS_MODEL_PROVIDER_ID;
 pub use model_provider_info::ModelProviderInfo;
@@ -41,15 +52,22 @@ pub use conversation_manager::NewConversation;
 pub mod model_family;
 mod models;
 mod openai_model_info;
+mod openai_model_pricing;
+pub use openai_model_pricing::ModelPricing;
+pub use openai_model_pricing::get_model_pricing;
 mod openai_tools;
 pub mod plan_tool;
-mod project_doc;
-mod rollout;
+pub mod project_doc;
+#[cfg(unix)]
+mod pty;
+pub mod rollout;
 pub(crate) mod safety;
 pub mod seatbelt;
+mod session_budget;
 pub mod shell;
 pub mod spawn;
 pub mod turn_diff_tracker;
+pub mod usage_ledger;
 pub mod user_agent;
 mod user_notification;
 pub mod util;
@@ -5,10 +5,16 @@
 //! helpers to query the available tools across *all* servers and returns them
 //! in a single aggregated map using the fully-qualified tool name
 //! `"<server><MCP_TOOL_NAME_DELIMITER><tool>"` as the key.
+//!
+//! Each server is supervised for the rest of the session: a server that
+//! exits is restarted with exponential backoff, and a server that sends
+//! `notifications/tools/list_changed` has its tools listed again.
 
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::ffi::OsString;
+use std::sync::Arc;
+use std::sync::Mutex;
 use std::time::Duration;
 
 use anyhow::Context;
@@ -16,17 +22,32 @@ use anyhow::Result;
 use anyhow::anyhow;
 use codex_mcp_client::McpClient;
 use mcp_types::ClientCapabilities;
+use mcp_types::GetPromptResult;
 use mcp_types::Implementation;
+use mcp_types::ListToolsResult;
+use mcp_types::ModelContextProtocolNotification;
+use mcp_types::Prompt;
+use mcp_types::ReadResourceResult;
+use mcp_types::Resource;
+use mcp_types::ServerCapabilities;
 use mcp_types::Tool;
+use mcp_types::ToolListChangedNotification;
 
 use serde_json::json;
 use sha1::Digest;
 use sha1::Sha1;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
 use tokio::task::JoinSet;
 use tracing::info;
 use tracing::warn;
+use wildmatch::WildMatchPattern;
 
 use crate::config_types::McpServerConfig;
+use crate::config_types::McpToolConfig;
+use crate::protocol::McpServerStatus;
+use crate::util::MutexExt;
 
 /// Delimiter used to separate the server name from the tool name in a fully
 /// qualified tool name.
@@ -39,6 +60,20 @@ const MAX_TOOL_NAME_LENGTH: usize = 64;
 /// Timeout for the `tools/list` request.
 const LIST_TOOLS_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Timeout for the `resources/list` and `prompts/list` requests.
+const LIST_RESOURCES_AND_PROMPTS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Timeout for the `initialize` request.
+const INITIALIZE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay before the first attempt to restart a server that exited. Doubles
+/// with every failed attempt, up to `RESTART_MAX_BACKOFF`.
+const RESTART_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const RESTART_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A server that still fails to start after this many attempts is given up.
+const MAX_RESTART_ATTEMPTS: u32 = 5;
+
 /// Map that holds a startup error for every MCP server that could **not** be
 /// spawned successfully.
 pub type ClientStartErrors = HashMap<String, anyhow::Error>;
@@ -75,23 +110,130 @@ fn qualify_tools(tools: Vec<ToolInfo>) -> HashMap<String, ToolInfo> {
     qualified_tools
 }
 
+#[derive(Clone)]
 struct ToolInfo {
     server_name: String,
     tool_name: String,
     tool: Tool,
 }
 
-/// A thin wrapper around a set of running [`McpClient`] instances.
+type ToolNamePattern = WildMatchPattern<'*', '?'>;
+
+/// Which tools of a server are exposed to the model, per the server's
+/// `enabled_tools` and `disabled_tools`.
+struct ToolFilter {
+    enabled: Option<Vec<ToolNamePattern>>,
+    disabled: Vec<ToolNamePattern>,
+}
+
+impl ToolFilter {
+    fn new(cfg: &McpServerConfig) -> Self {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| ToolNamePattern::new(pattern))
+                .collect::<Vec<_>>()
+        };
+        Self {
+            enabled: cfg.enabled_tools.as_deref().map(compile),
+            disabled: compile(&cfg.disabled_tools),
+        }
+    }
+
+    fn allows(&self, tool_name: &str) -> bool {
+        let enabled = self
+            .enabled
+            .as_ref()
+            .is_none_or(|patterns| patterns.iter().any(|pattern| pattern.matches(tool_name)));
+        enabled
+            && !self
+                .disabled
+                .iter()
+                .any(|pattern| pattern.matches(tool_name))
+    }
+}
+
+/// The running servers and their tools, shared with the supervisor tasks.
 #[derive(Default)]
-pub(crate) struct McpConnectionManager {
+struct Servers {
     /// Server-name -> client instance.
     ///
     /// The server name originates from the keys of the `mcp_servers` map in
     /// the user configuration.
-    clients: HashMap<String, std::sync::Arc<McpClient>>,
+    clients: HashMap<String, Arc<McpClient>>,
+
+    /// Server name -> tools of that server exposed to the model.
+    server_tools: HashMap<String, Vec<ToolInfo>>,
 
-    /// Fully qualified tool name -> tool instance.
+    /// Fully qualified tool name -> tool instance, derived from
+    /// `server_tools`.
     tools: HashMap<String, ToolInfo>,
+
+    /// Server name -> resources listed by that server when it (re)started.
+    resources: HashMap<String, Vec<Resource>>,
+
+    /// Server name -> prompts listed by that server when it (re)started.
+    prompts: HashMap<String, Vec<Prompt>>,
+
+    /// Server name -> health of that server.
+    status: HashMap<String, McpServerStatus>,
+}
+
+impl Servers {
+    fn set_tools(&mut self, server_name: &str, tools: Vec<ToolInfo>) {
+        self.server_tools.insert(server_name.to_string(), tools);
+        self.qualify_tools();
+    }
+
+    fn remove_tools(&mut self, server_name: &str) {
+        self.server_tools.remove(server_name);
+        self.qualify_tools();
+    }
+
+    /// Records what `server_name` offers besides tools. Servers that offer
+    /// nothing are left out of the maps.
+    fn set_resources_and_prompts(
+        &mut self,
+        server_name: &str,
+        resources: Vec<Resource>,
+        prompts: Vec<Prompt>,
+    ) {
+        self.resources.remove(server_name);
+        self.prompts.remove(server_name);
+        if !resources.is_empty() {
+            self.resources.insert(server_name.to_string(), resources);
+        }
+        if !prompts.is_empty() {
+            self.prompts.insert(server_name.to_string(), prompts);
+        }
+    }
+
+    fn qualify_tools(&mut self) {
+        // Visit servers in a fixed order so that name collisions are always
+        // resolved the same way.
+        let mut server_names: Vec<&String> = self.server_tools.keys().collect();
+        server_names.sort();
+        let tools = server_names
+            .into_iter()
+            .flat_map(|server_name| self.server_tools[server_name].iter().cloned())
+            .collect();
+        self.tools = qualify_tools(tools);
+    }
+}
+
+type SharedServers = Arc<Mutex<Servers>>;
+
+/// A thin wrapper around a set of running [`McpClient`] instances.
+#[derive(Default)]
+pub(crate) struct McpConnectionManager {
+    servers: SharedServers,
+
+    /// Server name -> settings of individual tools of that server.
+    tool_configs: HashMap<String, HashMap<String, McpToolConfig>>,
+
+    /// One task per server that restarts it and refreshes what it offers. Aborted
+    /// when the manager is dropped.
+    supervisors: Vec<AbortHandle>,
 }
 
 impl McpConnectionManager {
@@ -100,11 +242,14 @@ impl McpConnectionManager {
     /// * `mcp_servers` – Map loaded from the user configuration where *keys*
     ///   are human-readable server identifiers and *values* are the spawn
     ///   instructions.
+    /// * `status_tx` – Receives a message whenever a server exits, is
+    ///   restarted or changes its tools.
     ///
     /// Servers that fail to start are reported in `ClientStartErrors`: the
     /// user should be informed about these errors.
     pub async fn new(
         mcp_servers: HashMap<String, McpServerConfig>,
+        status_tx: mpsc::UnboundedSender<String>,
     ) -> Result<(Self, ClientStartErrors)> {
         // Early exit if no servers are configured.
         if mcp_servers.is_empty() {
@@ -114,6 +259,7 @@ impl McpConnectionManager {
         // Launch all configured servers concurrently.
         let mut join_set = JoinSet::new();
         let mut errors = ClientStartErrors::new();
+        let mut configs: HashMap<String, McpServerConfig> = HashMap::new();
 
         for (server_name, cfg) in mcp_servers {
             // Validate server name before spawning
@@ -126,57 +272,21 @@ impl McpConnectionManager {
                 continue;
             }
 
-            join_set.spawn(async move {
-                let McpServerConfig { command, args, env } = cfg;
-                let client_res = McpClient::new_stdio_client(
-                    command.into(),
-                    args.into_iter().map(OsString::from).collect(),
-                    env,
-                )
-                .await;
-                match client_res {
-                    Ok(client) => {
-                        // Initialize the client.
-                        let params = mcp_types::InitializeRequestParams {
-                            capabilities: ClientCapabilities {
-                                experimental: None,
-                                roots: None,
-                                sampling: None,
-                                // https://modelcontextprotocol.io/specification/2025-06-18/client/elicitation#capabilities
-                                // indicates this should be an empty object.
-                                elicitation: Some(json!({})),
-                            },
-                            client_info: Implementation {
-                                name: "codex-mcp-client".to_owned(),
-                                version: env!("CARGO_PKG_VERSION").to_owned(),
-                                title: Some("Codex".into()),
-                            },
-                            protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
-                        };
-                        let initialize_notification_params = None;
-                        let timeout = Some(Duration::from_secs(10));
-                        match client
-                            .initialize(params, initialize_notification_params, timeout)
-                            .await
-                        {
-                            Ok(_response) => (server_name, Ok(client)),
-                            Err(e) => (server_name, Err(e)),
-                        }
-                    }
-                    Err(e) => (server_name, Err(e.into())),
-                }
-            });
+            configs.insert(server_name.clone(), cfg.clone());
+            join_set.spawn(async move { (server_name, start_server(cfg).await) });
         }
 
-        let mut clients: HashMap<String, std::sync::Arc<McpClient>> =
+        let mut clients: HashMap<String, Arc<McpClient>> = HashMap::with_capacity(join_set.len());
+        let mut capabilities: HashMap<String, ServerCapabilities> =
             HashMap::with_capacity(join_set.len());
 
         while let Some(res) = join_set.join_next().await {
             let (server_name, client_res) = res?; // JoinError propagation
 
             match client_res {
-                Ok(client) => {
-                    clients.insert(server_name, std::sync::Arc::new(client));
+                Ok((client, server_capabilities)) => {
+                    clients.insert(server_name.clone(), Arc::new(client));
+                    capabilities.insert(server_name, server_capabilities);
                 }
                 Err(e) => {
                     errors.insert(server_name, e);
@@ -184,22 +294,86 @@ impl McpConnectionManager {
             }
         }
 
-        let all_tools = list_all_tools(&clients).await?;
+        let mut servers = Servers::default();
+        for tool in list_all_tools(&clients).await? {
+            let Some(cfg) = configs.get(&tool.server_name) else {
+                continue;
+            };
+            if ToolFilter::new(cfg).allows(&tool.tool_name) {
+                servers
+                    .server_tools
+                    .entry(tool.server_name.clone())
+                    .or_default()
+                    .push(tool);
+            }
+        }
+        servers.qualify_tools();
 
-        let tools = qualify_tools(all_tools);
+        (servers.resources, servers.prompts) =
+            list_all_resources_and_prompts(&clients, &capabilities).await;
 
-        Ok((Self { clients, tools }, errors))
+        let tool_configs = configs
+            .iter()
+            .map(|(server_name, cfg)| (server_name.clone(), cfg.tools.clone()))
+            .collect();
+
+        servers.status = clients
+            .keys()
+            .map(|server_name| (server_name.clone(), McpServerStatus::Running))
+            .collect();
+        servers.clients = clients.clone();
+        let servers = Arc::new(Mutex::new(servers));
+
+        let supervisors = clients
+            .into_iter()
+            .filter_map(|(server_name, client)| {
+                let cfg = configs.remove(&server_name)?;
+                let task = tokio::spawn(supervise_server(
+                    server_name,
+                    cfg,
+                    client,
+                    servers.clone(),
+                    status_tx.clone(),
+                ));
+                Some(task.abort_handle())
+            })
+            .collect();
+
+        Ok((
+            Self {
+                servers,
+                tool_configs,
+                supervisors,
+            },
+            errors,
+        ))
+    }
+
+    fn client(&self, server: &str) -> Result<Arc<McpClient>> {
+        self.servers
+            .lock_unchecked()
+            .clients
+            .get(server)
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))
     }
 
     /// Returns a single map that contains **all** tools. Each key is the
     /// fully-qualified name for the tool.
     pub fn list_all_tools(&self) -> HashMap<String, Tool> {
-        self.tools
+        self.servers
+            .lock_unchecked()
+            .tools
             .iter()
             .map(|(name, tool)| (name.clone(), tool.tool.clone()))
             .collect()
     }
 
+    /// Returns the health of every server that started.
+    pub fn server_status(&self) -> HashMap<String, McpServerStatus> {
+        self.servers.lock_unchecked().status.clone()
+    }
+
     /// Invoke the tool indicated by the (server, tool) pair.
     pub async fn call_tool(
         &self,
@@ -208,11 +382,7 @@ impl McpConnectionManager {
         arguments: Option<serde_json::Value>,
         timeout: Option<Duration>,
     ) -> Result<mcp_types::CallToolResult> {
-        let client = self
-            .clients
-            .get(server)
-            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?
-            .clone();
+        let client = self.client(server)?;
 
         client
             .call_tool(tool.to_string(), arguments, timeout)
@@ -220,31 +390,307 @@ impl McpConnectionManager {
             .with_context(|| format!("tool call failed for `{server}/{tool}`"))
     }
 
+    /// Returns the resources listed by each server that supports them.
+    pub fn list_all_resources(&self) -> HashMap<String, Vec<Resource>> {
+        self.servers.lock_unchecked().resources.clone()
+    }
+
+    /// Returns the prompts listed by each server that supports them.
+    pub fn list_all_prompts(&self) -> HashMap<String, Vec<Prompt>> {
+        self.servers.lock_unchecked().prompts.clone()
+    }
+
+    /// Whether `server` is the name of a running MCP server.
+    pub fn has_server(&self, server: &str) -> bool {
+        self.servers.lock_unchecked().clients.contains_key(server)
+    }
+
+    /// Read the resource at `uri` from `server`.
+    pub async fn read_resource(
+        &self,
+        server: &str,
+        uri: &str,
+        timeout: Option<Duration>,
+    ) -> Result<ReadResourceResult> {
+        let client = self.client(server)?;
+
+        client
+            .read_resource(uri.to_string(), timeout)
+            .await
+            .with_context(|| format!("failed to read resource `{uri}` from `{server}`"))
+    }
+
+    /// Fetch the prompt `name` from `server`, filled in with `arguments`.
+    pub async fn get_prompt(
+        &self,
+        server: &str,
+        name: &str,
+        arguments: HashMap<String, String>,
+        timeout: Option<Duration>,
+    ) -> Result<GetPromptResult> {
+        let client = self.client(server)?;
+
+        let arguments = (!arguments.is_empty()).then(|| json!(arguments));
+        client
+            .get_prompt(name.to_string(), arguments, timeout)
+            .await
+            .with_context(|| format!("failed to get prompt `{name}` from `{server}`"))
+    }
+
+    /// Returns the settings configured for `tool` of `server`, or the
+    /// defaults if there are none.
+    pub fn tool_config(&self, server: &str, tool: &str) -> McpToolConfig {
+        self.tool_configs
+            .get(server)
+            .and_then(|tools| tools.get(tool))
+            .cloned()
+            .unwrap_or_default()
+    }
+
     pub fn parse_tool_name(&self, tool_name: &str) -> Option<(String, String)> {
-        self.tools
+        self.servers
+            .lock_unchecked()
+            .tools
             .get(tool_name)
             .map(|tool| (tool.server_name.clone(), tool.tool_name.clone()))
     }
 }
 
+impl Drop for McpConnectionManager {
+    fn drop(&mut self) {
+        // The supervisors hold on to the clients, so they must stop for the
+        // servers to be shut down.
+        for supervisor in &self.supervisors {
+            supervisor.abort();
+        }
+    }
+}
+
+/// Watches a server for the rest of the session, restarting it when it exits
+/// and listing its tools again when it reports that they changed. A restarted
+/// server's resources and prompts are listed again too.
+async fn supervise_server(
+    server_name: String,
+    cfg: McpServerConfig,
+    mut client: Arc<McpClient>,
+    servers: SharedServers,
+    status_tx: mpsc::UnboundedSender<String>,
+) {
+    let filter = ToolFilter::new(&cfg);
+    loop {
+        watch_server(&server_name, &client, &filter, &servers, &status_tx).await;
+
+        {
+            let mut servers = servers.lock_unchecked();
+            servers.remove_tools(&server_name);
+            servers.set_resources_and_prompts(&server_name, Vec::new(), Vec::new());
+            servers
+                .status
+                .insert(server_name.clone(), McpServerStatus::Restarting);
+        }
+        report_status(
+            &status_tx,
+            format!("MCP server `{server_name}` exited; restarting it"),
+        );
+
+        match restart_server(&server_name, &cfg, &filter).await {
+            Ok((new_client, capabilities, tools)) => {
+                let count = tools.len();
+                let (resources, prompts) =
+                    list_server_resources_and_prompts(&server_name, &new_client, &capabilities)
+                        .await;
+                {
+                    let mut servers = servers.lock_unchecked();
+                    servers
+                        .clients
+                        .insert(server_name.clone(), new_client.clone());
+                    servers.set_tools(&server_name, tools);
+                    servers.set_resources_and_prompts(&server_name, resources, prompts);
+                    servers
+                        .status
+                        .insert(server_name.clone(), McpServerStatus::Running);
+                }
+                report_status(
+                    &status_tx,
+                    format!("MCP server `{server_name}` restarted with {count} tools"),
+                );
+                client = new_client;
+            }
+            Err(e) => {
+                servers
+                    .lock_unchecked()
+                    .status
+                    .insert(server_name.clone(), McpServerStatus::Failed);
+                report_status(
+                    &status_tx,
+                    format!("MCP server `{server_name}` could not be restarted: {e:#}"),
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// Returns once `client` is closed, refreshing the server's tools whenever
+/// it sends `notifications/tools/list_changed`.
+async fn watch_server(
+    server_name: &str,
+    client: &McpClient,
+    filter: &ToolFilter,
+    servers: &SharedServers,
+    status_tx: &mpsc::UnboundedSender<String>,
+) {
+    let mut notifications = client.subscribe_notifications();
+    loop {
+        tokio::select! {
+            _ = client.closed() => return,
+            notification = notifications.recv() => {
+                match notification {
+                    Ok(notification) if notification.method == ToolListChangedNotification::METHOD => {}
+                    Ok(_) => continue,
+                    // A missed notification may have announced a change.
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+                match list_server_tools(server_name, client, filter).await {
+                    Ok(tools) => {
+                        let count = tools.len();
+                        servers.lock_unchecked().set_tools(server_name, tools);
+                        report_status(
+                            status_tx,
+                            format!("MCP server `{server_name}` now offers {count} tools"),
+                        );
+                    }
+                    Err(e) => warn!("failed to refresh tools of MCP server `{server_name}`: {e:#}"),
+                }
+            }
+        }
+    }
+}
+
+/// Starts the server again, waiting longer after every failed attempt.
+async fn restart_server(
+    server_name: &str,
+    cfg: &McpServerConfig,
+    filter: &ToolFilter,
+) -> Result<(Arc<McpClient>, ServerCapabilities, Vec<ToolInfo>)> {
+    let mut attempt = 0;
+    loop {
+        tokio::time::sleep(restart_backoff(attempt)).await;
+        let result = async {
+            let (client, capabilities) = start_server(cfg.clone()).await?;
+            let tools = list_server_tools(server_name, &client, filter).await?;
+            Ok((Arc::new(client), capabilities, tools))
+        }
+        .await;
+        attempt += 1;
+        match result {
+            Ok(restarted) => return Ok(restarted),
+            Err(e) if attempt >= MAX_RESTART_ATTEMPTS => return Err(e),
+            Err(e) => {
+                warn!("attempt {attempt} to restart MCP server `{server_name}` failed: {e:#}")
+            }
+        }
+    }
+}
+
+/// Delay before restart attempt number `attempt` (counting from zero).
+fn restart_backoff(attempt: u32) -> Duration {
+    RESTART_INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(RESTART_MAX_BACKOFF)
+}
+
+fn report_status(status_tx: &mpsc::UnboundedSender<String>, message: String) {
+    info!("{message}");
+    // The session may already be gone.
+    let _ = status_tx.send(message);
+}
+
+/// Spawn or connect to the server described by `cfg` and complete the
+/// `initialize` handshake.
+async fn start_server(cfg: McpServerConfig) -> Result<(McpClient, ServerCapabilities)> {
+    let client = new_client(cfg).await?;
+    let params = mcp_types::InitializeRequestParams {
+        capabilities: ClientCapabilities {
+            experimental: None,
+            roots: None,
+            sampling: None,
+            // https://modelcontextprotocol.io/specification/2025-06-18/client/elicitation#capabilities
+            // indicates this should be an empty object.
+            elicitation: Some(json!({})),
+        },
+        client_info: Implementation {
+            name: "codex-mcp-client".to_owned(),
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            title: Some("Codex".into()),
+        },
+        protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
+    };
+    let initialize_notification_params = None;
+    let response = client
+        .initialize(
+            params,
+            initialize_notification_params,
+            Some(INITIALIZE_TIMEOUT),
+        )
+        .await?;
+    Ok((client, response.capabilities))
+}
+
+/// Spawn or connect to the server described by `cfg`, depending on whether it
+/// is configured with a `command` or a `url`.
+async fn new_client(cfg: McpServerConfig) -> Result<McpClient> {
+    let McpServerConfig {
+        command,
+        args,
+        env,
+        url,
+        headers,
+        bearer_token_env_var,
+        ..
+    } = cfg;
+    match (command, url) {
+        (Some(command), None) => Ok(McpClient::new_stdio_client(
+            command.into(),
+            args.into_iter().map(OsString::from).collect(),
+            env,
+        )
+        .await?),
+        (None, Some(url)) => {
+            let bearer_token = match bearer_token_env_var {
+                Some(var) => match std::env::var(&var) {
+                    Ok(token) if !token.trim().is_empty() => Some(token),
+                    _ => {
+                        return Err(anyhow!(
+                            "environment variable `{var}` with the bearer token is not set"
+                        ));
+                    }
+                },
+                None => None,
+            };
+            McpClient::new_streamable_http_client(url, headers.unwrap_or_default(), bearer_token)
+                .await
+        }
+        (Some(_), Some(_)) => Err(anyhow!("only one of `command` and `url` may be set")),
+        (None, None) => Err(anyhow!("either `command` or `url` must be set")),
+    }
+}
+
 /// Query every server for its available tools and return a single map that
 /// contains **all** tools. Each key is the fully-qualified name for the tool.
-async fn list_all_tools(
-    clients: &HashMap<String, std::sync::Arc<McpClient>>,
-) -> Result<Vec<ToolInfo>> {
+async fn list_all_tools(clients: &HashMap<String, Arc<McpClient>>) -> Result<Vec<ToolInfo>> {
     let mut join_set = JoinSet::new();
 
     // Spawn one task per server so we can query them concurrently. This
     // keeps the overall latency roughly at the slowest server instead of
     // the cumulative latency.
     for (server_name, client) in clients {
-        let server_name_cloned = server_name.clone();
-        let client_clone = client.clone();
+        let server_name = server_name.clone();
+        let client = client.clone();
         join_set.spawn(async move {
-            let res = client_clone
-                .list_tools(None, Some(LIST_TOOLS_TIMEOUT))
-                .await;
-            (server_name_cloned, res)
+            let res = client.list_tools(None, Some(LIST_TOOLS_TIMEOUT)).await;
+            (server_name, res)
         });
     }
 
@@ -252,16 +698,7 @@ async fn list_all_tools(
 
     while let Some(join_res) = join_set.join_next().await {
         let (server_name, list_result) = join_res?;
-        let list_result = list_result?;
-
-        for tool in list_result.tools {
-            let tool_info = ToolInfo {
-                server_name: server_name.clone(),
-                tool_name: tool.name.clone(),
-                tool,
-            };
-            aggregated.push(tool_info);
-        }
+        aggregated.extend(tool_infos(&server_name, list_result?));
     }
 
     info!(
@@ -273,6 +710,114 @@ async fn list_all_tools(
     Ok(aggregated)
 }
 
+/// Lists the tools of a single server that pass `filter`.
+async fn list_server_tools(
+    server_name: &str,
+    client: &McpClient,
+    filter: &ToolFilter,
+) -> Result<Vec<ToolInfo>> {
+    let list_result = client.list_tools(None, Some(LIST_TOOLS_TIMEOUT)).await?;
+    Ok(tool_infos(server_name, list_result)
+        .into_iter()
+        .filter(|tool| filter.allows(&tool.tool_name))
+        .collect())
+}
+
+fn tool_infos(server_name: &str, list_result: ListToolsResult) -> Vec<ToolInfo> {
+    list_result
+        .tools
+        .into_iter()
+        .map(|tool| ToolInfo {
+            server_name: server_name.to_string(),
+            tool_name: tool.name.clone(),
+            tool,
+        })
+        .collect()
+}
+
+/// Query every server that advertises the `resources` or `prompts`
+/// capability for what it offers. Unlike tools, failures are only logged: a
+/// server whose listing fails simply contributes nothing.
+async fn list_all_resources_and_prompts(
+    clients: &HashMap<String, Arc<McpClient>>,
+    capabilities: &HashMap<String, ServerCapabilities>,
+) -> (HashMap<String, Vec<Resource>>, HashMap<String, Vec<Prompt>>) {
+    let mut join_set = JoinSet::new();
+
+    for (server_name, client) in clients {
+        let Some(server_capabilities) = capabilities.get(server_name) else {
+            continue;
+        };
+        if server_capabilities.resources.is_none() && server_capabilities.prompts.is_none() {
+            continue;
+        }
+
+        let server_name = server_name.clone();
+        let client = client.clone();
+        let server_capabilities = server_capabilities.clone();
+        join_set.spawn(async move {
+            let (resources, prompts) =
+                list_server_resources_and_prompts(&server_name, &client, &server_capabilities)
+                    .await;
+            (server_name, resources, prompts)
+        });
+    }
+
+    let mut all_resources = HashMap::new();
+    let mut all_prompts = HashMap::new();
+    while let Some(join_res) = join_set.join_next().await {
+        let (server_name, resources, prompts) = match join_res {
+            Ok(res) => res,
+            Err(e) => {
+                warn!("failed to join MCP listing task: {e}");
+                continue;
+            }
+        };
+        if !resources.is_empty() {
+            all_resources.insert(server_name.clone(), resources);
+        }
+        if !prompts.is_empty() {
+            all_prompts.insert(server_name, prompts);
+        }
+    }
+
+    (all_resources, all_prompts)
+}
+
+/// Lists the resources and prompts of a single server, according to the
+/// capabilities it advertised. Failures are logged and yield empty lists.
+async fn list_server_resources_and_prompts(
+    server_name: &str,
+    client: &McpClient,
+    capabilities: &ServerCapabilities,
+) -> (Vec<Resource>, Vec<Prompt>) {
+    let resources = if capabilities.resources.is_some() {
+        client
+            .list_resources(None, Some(LIST_RESOURCES_AND_PROMPTS_TIMEOUT))
+            .await
+            .map(|result| result.resources)
+            .unwrap_or_else(|e| {
+                warn!("failed to list resources for MCP server `{server_name}`: {e:#}");
+                Vec::new()
+            })
+    } else {
+        Vec::new()
+    };
+    let prompts = if capabilities.prompts.is_some() {
+        client
+            .list_prompts(None, Some(LIST_RESOURCES_AND_PROMPTS_TIMEOUT))
+            .await
+            .map(|result| result.prompts)
+            .unwrap_or_else(|e| {
+                warn!("failed to list prompts for MCP server `{server_name}`: {e:#}");
+                Vec::new()
+            })
+    } else {
+        Vec::new()
+    };
+    (resources, prompts)
+}
+
 fn is_valid_mcp_server_name(server_name: &str) -> bool {
     !server_name.is_empty()
         && server_name
@@ -366,4 +911,70 @@ mod tests {
             "my_server__yet_another_e1c3987bd9c50b826cbe1687966f79f0c602d19ca"
         );
     }
+
+    #[test]
+    fn test_restart_backoff() {
+        let delays: Vec<u64> = (0..7).map(|n| restart_backoff(n).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 30, 30]);
+    }
+
+    #[test]
+    fn test_set_resources_and_prompts() {
+        let resource = Resource {
+            annotations: None,
+            description: None,
+            mime_type: None,
+            name: "readme".to_string(),
+            size: None,
+            title: None,
+            uri: "file:///README.md".to_string(),
+        };
+        let prompt = Prompt {
+            arguments: None,
+            description: None,
+            name: "review".to_string(),
+            title: None,
+        };
+        let mut servers = Servers::default();
+
+        servers.set_resources_and_prompts("docs", vec![resource.clone()], vec![prompt]);
+        assert_eq!(Some(&vec![resource.clone()]), servers.resources.get("docs"));
+        assert_eq!(1, servers.prompts["docs"].len());
+
+        // A restarted server that no longer offers prompts loses them.
+        servers.set_resources_and_prompts("docs", vec![resource], Vec::new());
+        assert!(servers.resources.contains_key("docs"));
+        assert!(!servers.prompts.contains_key("docs"));
+
+        servers.set_resources_and_prompts("docs", Vec::new(), Vec::new());
+        assert!(servers.resources.is_empty());
+    }
+
+    #[test]
+    fn test_tool_filter() {
+        let cfg = McpServerConfig {
+            command: Some("server".to_string()),
+            args: Vec::new(),
+            env: None,
+            url: None,
+            headers: None,
+            bearer_token_env_var: None,
+            enabled_tools: Some(vec!["read_*".to_string(), "search".to_string()]),
+            disabled_tools: vec!["read_secret?".to_string()],
+            tools: HashMap::new(),
+        };
+        let filter = ToolFilter::new(&cfg);
+
+        assert!(filter.allows("read_file"));
+        assert!(filter.allows("search"));
+        assert!(!filter.allows("write_file"));
+        assert!(!filter.allows("read_secrets"));
+
+        let filter = ToolFilter::new(&McpServerConfig {
+            enabled_tools: None,
+            ..cfg
+        });
+        assert!(filter.allows("write_file"));
+        assert!(!filter.allows("read_secrets"));
+    }
 }
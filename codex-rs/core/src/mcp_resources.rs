@@ -0,0 +1,440 @@
+//! Resources and prompts offered by MCP servers: the `read_mcp_resource`
+//! tool, `@server:uri` mentions in user input and prompts submitted as a
+//! turn.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use mcp_types::CallToolResult;
+use mcp_types::ContentBlock;
+use mcp_types::EmbeddedResource;
+use mcp_types::EmbeddedResourceResource;
+use mcp_types::GetPromptResult;
+use mcp_types::ReadResourceResult;
+use mcp_types::ReadResourceResultContents;
+use mcp_types::Role;
+use mcp_types::TextContent;
+use serde_json::json;
+use tracing::warn;
+
+use crate::codex::Session;
+use crate::models::ContentItem;
+use crate::models::FunctionCallOutputPayload;
+use crate::models::ResponseInputItem;
+use crate::models::ResponseItem;
+use crate::openai_tools::ReadMcpResourceToolArgs;
+use crate::protocol::BackgroundEventEvent;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::InputItem;
+use crate::protocol::McpInvocation;
+use crate::protocol::McpToolCallBeginEvent;
+use crate::protocol::McpToolCallEndEvent;
+
+/// Timeout for reading a resource, whether for the model or for a mention.
+const READ_RESOURCE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Timeout for fetching a prompt submitted with `Op::RunMcpPrompt`.
+pub(crate) const GET_MCP_PROMPT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Name under which resource reads are reported in `McpToolCallBegin` and
+/// `McpToolCallEnd` events.
+const READ_RESOURCE_METHOD: &str = "resources/read";
+
+/// Handles a call to the `read_mcp_resource` tool. The read is reported to
+/// clients like any other MCP tool call.
+pub(crate) async fn handle_read_mcp_resource(
+    sess: &Session,
+    sub_id: &str,
+    call_id: String,
+    arguments: String,
+) -> ResponseInputItem {
+    let args = match serde_json::from_str::<ReadMcpResourceToolArgs>(&arguments) {
+        Ok(args) => args,
+        Err(e) => {
+            return ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content: format!("failed to parse function arguments: {e}"),
+                    success: None,
+                },
+            };
+        }
+    };
+
+    let invocation = McpInvocation {
+        server: args.server.clone(),
+        tool: READ_RESOURCE_METHOD.to_string(),
+        arguments: Some(json!({ "uri": args.uri })),
+    };
+    sess.send_event(Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::McpToolCallBegin(McpToolCallBeginEvent {
+            call_id: call_id.clone(),
+            invocation: invocation.clone(),
+        }),
+    })
+    .await;
+
+    let start = Instant::now();
+    let result = sess
+        .read_mcp_resource(&args.server, &args.uri, Some(READ_RESOURCE_TIMEOUT))
+        .await
+        .map(resource_to_tool_result)
+        .map_err(|e| format!("resource read error: {e:#}"));
+
+    sess.send_event(Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::McpToolCallEnd(McpToolCallEndEvent {
+            call_id: call_id.clone(),
+            invocation,
+            duration: start.elapsed(),
+            result: result.clone(),
+        }),
+    })
+    .await;
+
+    ResponseInputItem::McpToolCallOutput { call_id, result }
+}
+
+fn resource_to_tool_result(result: ReadResourceResult) -> CallToolResult {
+    let content = result
+        .contents
+        .into_iter()
+        .map(|contents| {
+            let text = match contents {
+                ReadResourceResultContents::TextResourceContents(text) => text.text,
+                ReadResourceResultContents::BlobResourceContents(blob) => {
+                    describe_blob(&blob.uri, blob.mime_type.as_deref(), &blob.blob)
+                }
+            };
+            ContentBlock::TextContent(TextContent {
+                annotations: None,
+                text,
+                r#type: "text".to_string(),
+            })
+        })
+        .collect();
+    CallToolResult {
+        content,
+        is_error: None,
+        structured_content: None,
+    }
+}
+
+fn describe_blob(uri: &str, mime_type: Option<&str>, blob: &str) -> String {
+    format!(
+        "[binary resource {uri} ({}), {} bytes of base64 omitted]",
+        mime_type.unwrap_or("unknown type"),
+        blob.len()
+    )
+}
+
+/// Returns the `(server, uri)` pairs mentioned as `@server:uri` in `text`,
+/// in order of appearance and without duplicates. Only mentions of servers
+/// for which `is_server` returns true are recognized, so e-mail addresses and
+/// `@file` mentions are left alone.
+pub(crate) fn parse_resource_mentions(
+    text: &str,
+    is_server: impl Fn(&str) -> bool,
+) -> Vec<(String, String)> {
+    let mut mentions: Vec<(String, String)> = Vec::new();
+    for token in text.split_whitespace() {
+        let Some(mention) = token.strip_prefix('@') else {
+            continue;
+        };
+        let Some((server, uri)) = mention.split_once(':') else {
+            continue;
+        };
+        // Trailing punctuation belongs to the sentence, not the URI.
+        let uri = uri.trim_end_matches([',', '.', ';', '!', '?']);
+        if uri.is_empty() || !is_server(server) {
+            continue;
+        }
+        let mention = (server.to_string(), uri.to_string());
+        if !mentions.contains(&mention) {
+            mentions.push(mention);
+        }
+    }
+    mentions
+}
+
+/// Reads every resource mentioned as `@server:uri` in the text of `items`
+/// and appends its contents to the input. Resources that cannot be read are
+/// reported with a background event and otherwise ignored.
+pub(crate) async fn attach_mentioned_resources(
+    sess: &Session,
+    sub_id: &str,
+    mut items: Vec<InputItem>,
+) -> Vec<InputItem> {
+    let mentions: Vec<(String, String)> = items
+        .iter()
+        .filter_map(|item| match item {
+            InputItem::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .flat_map(|text| parse_resource_mentions(text, |server| sess.has_mcp_server(server)))
+        .collect();
+
+    let mut attached: Vec<(String, String)> = Vec::new();
+    for (server, uri) in mentions {
+        if attached.contains(&(server.clone(), uri.clone())) {
+            continue;
+        }
+        match sess
+            .read_mcp_resource(&server, &uri, Some(READ_RESOURCE_TIMEOUT))
+            .await
+        {
+            Ok(result) => items.extend(resource_to_input_items(&server, result)),
+            Err(e) => {
+                warn!("failed to read MCP resource {server}:{uri}: {e:#}");
+                sess.send_event(Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::BackgroundEvent(BackgroundEventEvent {
+                        message: format!("Could not attach @{server}:{uri}: {e:#}"),
+                    }),
+                })
+                .await;
+            }
+        }
+        attached.push((server, uri));
+    }
+    items
+}
+
+fn resource_to_input_items(server: &str, result: ReadResourceResult) -> Vec<InputItem> {
+    result
+        .contents
+        .into_iter()
+        .map(|contents| match contents {
+            ReadResourceResultContents::TextResourceContents(text) => InputItem::Text {
+                text: format!(
+                    "<mcp_resource server=\"{server}\" uri=\"{}\">\n{}\n</mcp_resource>",
+                    text.uri, text.text
+                ),
+            },
+            ReadResourceResultContents::BlobResourceContents(blob) => {
+                blob_to_input_item(&blob.uri, blob.mime_type.as_deref(), blob.blob)
+            }
+        })
+        .collect()
+}
+
+/// Images are attached as images; other binary contents are only described.
+fn blob_to_input_item(uri: &str, mime_type: Option<&str>, blob: String) -> InputItem {
+    match mime_type {
+        Some(mime_type) if mime_type.starts_with("image/") => InputItem::Image {
+            image_url: format!("data:{mime_type};base64,{blob}"),
+        },
+        _ => InputItem::Text {
+            text: describe_blob(uri, mime_type, &blob),
+        },
+    }
+}
+
+/// An MCP prompt, split into the conversation it sets up and the user input
+/// that starts the turn.
+#[derive(Debug, PartialEq)]
+pub(crate) struct PromptTurn {
+    /// Messages up to the last non-user one, with their roles, to be recorded
+    /// in the history before the turn.
+    pub(crate) history: Vec<ResponseItem>,
+    /// The trailing user messages.
+    pub(crate) input: Vec<InputItem>,
+}
+
+/// Converts the messages of an MCP prompt into a turn. Assistant messages keep
+/// their role, so they must be followed by a user message and may only hold
+/// text.
+pub(crate) fn prompt_to_turn(result: GetPromptResult) -> Result<PromptTurn, String> {
+    let mut messages = result.messages;
+    let first_input = messages
+        .iter()
+        .rposition(|message| message.role != Role::User)
+        .map_or(0, |i| i + 1);
+    if first_input == messages.len() && first_input > 0 {
+        return Err("prompt must end with a user message".to_string());
+    }
+    let input = messages
+        .split_off(first_input)
+        .into_iter()
+        .filter_map(|message| content_to_input_item(message.content))
+        .collect();
+
+    let mut history = Vec::with_capacity(messages.len());
+    for message in messages {
+        let item = match message.role {
+            Role::User => {
+                let items: Vec<InputItem> =
+                    content_to_input_item(message.content).into_iter().collect();
+                ResponseInputItem::from(items).into()
+            }
+            Role::Assistant => {
+                let text = match message.content {
+                    ContentBlock::TextContent(text) => text.text,
+                    ContentBlock::EmbeddedResource(EmbeddedResource {
+                        resource: EmbeddedResourceResource::TextResourceContents(text),
+                        ..
+                    }) => text.text,
+                    _ => return Err("assistant messages may only contain text".to_string()),
+                };
+                ResponseItem::Message {
+                    id: None,
+                    role: "assistant".to_string(),
+                    content: vec![ContentItem::OutputText { text }],
+                }
+            }
+        };
+        history.push(item);
+    }
+    Ok(PromptTurn { history, input })
+}
+
+fn content_to_input_item(content: ContentBlock) -> Option<InputItem> {
+    match content {
+        ContentBlock::TextContent(text) => Some(InputItem::Text { text: text.text }),
+        ContentBlock::ImageContent(image) => Some(InputItem::Image {
+            image_url: format!("data:{};base64,{}", image.mime_type, image.data),
+        }),
+        ContentBlock::EmbeddedResource(embedded) => Some(match embedded.resource {
+            EmbeddedResourceResource::TextResourceContents(text) => {
+                InputItem::Text { text: text.text }
+            }
+            EmbeddedResourceResource::BlobResourceContents(blob) => {
+                blob_to_input_item(&blob.uri, blob.mime_type.as_deref(), blob.blob)
+            }
+        }),
+        ContentBlock::AudioContent(_) | ContentBlock::ResourceLink(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_types::PromptMessage;
+    use mcp_types::TextResourceContents;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_mentions_of_known_servers_only() {
+        let is_server = |server: &str| server == "docs";
+        assert_eq!(
+            vec![
+                ("docs".to_string(), "guide://intro".to_string()),
+                ("docs".to_string(), "file:///tmp/a.md".to_string()),
+            ],
+            parse_resource_mentions(
+                "see @docs:guide://intro, @docs:file:///tmp/a.md and @docs:guide://intro again. \
+                 Mail me@example.com, ask @other:thing or open @src/lib.rs",
+                is_server,
+            )
+        );
+        assert!(parse_resource_mentions("@docs: nothing", is_server).is_empty());
+    }
+
+    #[test]
+    fn text_resources_are_wrapped_and_images_attached() {
+        let result = ReadResourceResult {
+            contents: vec![
+                ReadResourceResultContents::TextResourceContents(TextResourceContents {
+                    mime_type: None,
+                    text: "hello".to_string(),
+                    uri: "docs://a".to_string(),
+                }),
+                ReadResourceResultContents::BlobResourceContents(mcp_types::BlobResourceContents {
+                    blob: "aGk=".to_string(),
+                    mime_type: Some("image/png".to_string()),
+                    uri: "docs://b".to_string(),
+                }),
+            ],
+        };
+        assert_eq!(
+            vec![
+                InputItem::Text {
+                    text: "<mcp_resource server=\"docs\" uri=\"docs://a\">\nhello\n</mcp_resource>"
+                        .to_string()
+                },
+                InputItem::Image {
+                    image_url: "data:image/png;base64,aGk=".to_string()
+                },
+            ],
+            resource_to_input_items("docs", result)
+        );
+    }
+
+    fn text_message(role: Role, text: &str) -> PromptMessage {
+        PromptMessage {
+            content: ContentBlock::TextContent(TextContent {
+                annotations: None,
+                text: text.to_string(),
+                r#type: "text".to_string(),
+            }),
+            role,
+        }
+    }
+
+    #[test]
+    fn prompt_messages_become_user_input() {
+        let result = GetPromptResult {
+            description: None,
+            messages: vec![text_message(Role::User, "Review the diff")],
+        };
+        assert_eq!(
+            Ok(PromptTurn {
+                history: vec![],
+                input: vec![InputItem::Text {
+                    text: "Review the diff".to_string()
+                }],
+            }),
+            prompt_to_turn(result)
+        );
+    }
+
+    #[test]
+    fn prompt_messages_keep_their_roles() {
+        let result = GetPromptResult {
+            description: None,
+            messages: vec![
+                text_message(Role::User, "Name a color"),
+                text_message(Role::Assistant, "Blue"),
+                text_message(Role::User, "Another one"),
+            ],
+        };
+        assert_eq!(
+            Ok(PromptTurn {
+                history: vec![
+                    ResponseItem::Message {
+                        id: None,
+                        role: "user".to_string(),
+                        content: vec![ContentItem::InputText {
+                            text: "Name a color".to_string()
+                        }],
+                    },
+                    ResponseItem::Message {
+                        id: None,
+                        role: "assistant".to_string(),
+                        content: vec![ContentItem::OutputText {
+                            text: "Blue".to_string()
+                        }],
+                    },
+                ],
+                input: vec![InputItem::Text {
+                    text: "Another one".to_string()
+                }],
+            }),
+            prompt_to_turn(result)
+        );
+
+        let result = GetPromptResult {
+            description: None,
+            messages: vec![
+                text_message(Role::User, "Name a color"),
+                text_message(Role::Assistant, "Blue"),
+            ],
+        };
+        assert_eq!(
+            Err("prompt must end with a user message".to_string()),
+            prompt_to_turn(result)
+        );
+    }
+}
@@ -4,24 +4,60 @@ use std::time::Instant;
 use tracing::error;
 
 use crate::codex::Session;
+use crate::codex::TurnContext;
 use crate::models::FunctionCallOutputPayload;
 use crate::models::ResponseInputItem;
+use crate::protocol::AskForApproval;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::McpInvocation;
 use crate::protocol::McpToolCallBeginEvent;
 use crate::protocol::McpToolCallEndEvent;
+use crate::protocol::ReviewDecision;
+
+/// Whether a call to an MCP tool may go ahead.
+#[derive(Debug, PartialEq)]
+enum McpToolCallSafety {
+    AutoApprove,
+    AskUser,
+    Reject { reason: String },
+}
+
+/// Tools without an explicit `require_approval` setting are treated like
+/// commands that are not known to be safe: they need approval only under the
+/// `untrusted` policy.
+fn assess_mcp_tool_call_safety(
+    require_approval: Option<bool>,
+    approval_policy: AskForApproval,
+    approved_for_session: bool,
+) -> McpToolCallSafety {
+    if approved_for_session {
+        return McpToolCallSafety::AutoApprove;
+    }
+    let require_approval =
+        require_approval.unwrap_or(approval_policy == AskForApproval::UnlessTrusted);
+    match (require_approval, approval_policy) {
+        (false, _) => McpToolCallSafety::AutoApprove,
+        (true, AskForApproval::Never) => McpToolCallSafety::Reject {
+            reason: "the tool requires approval, but the approval policy is `never`".to_string(),
+        },
+        (true, _) => McpToolCallSafety::AskUser,
+    }
+}
 
 /// Handles the specified tool call dispatches the appropriate
 /// `McpToolCallBegin` and `McpToolCallEnd` events to the `Session`.
+///
+/// Calls that need approval are put to the user as an `ExecApprovalRequest`
+/// for the command `server.tool arguments`.
 pub(crate) async fn handle_mcp_tool_call(
     sess: &Session,
+    turn_context: &TurnContext,
     sub_id: &str,
     call_id: String,
     server: String,
     tool_name: String,
     arguments: String,
-    timeout: Option<Duration>,
 ) -> ResponseInputItem {
     // Parse the `arguments` as JSON. An empty string is OK, but invalid JSON
     // is not.
@@ -43,6 +79,53 @@ pub(crate) async fn handle_mcp_tool_call(
         }
     };
 
+    let tool_config = sess.mcp_tool_config(&server, &tool_name);
+    let safety = assess_mcp_tool_call_safety(
+        tool_config.require_approval,
+        turn_context.approval_policy,
+        sess.is_mcp_tool_approved(&server, &tool_name),
+    );
+    let rejection = match safety {
+        McpToolCallSafety::AutoApprove => None,
+        McpToolCallSafety::AskUser => {
+            let mut command = vec![format!("{server}.{tool_name}")];
+            if !arguments.trim().is_empty() {
+                command.push(arguments.clone());
+            }
+            let rx_approve = sess
+                .request_command_approval(
+                    sub_id.to_string(),
+                    call_id.clone(),
+                    command,
+                    turn_context.cwd.clone(),
+                    Some(format!(
+                        "Call the `{tool_name}` tool of MCP server `{server}`"
+                    )),
+                )
+                .await;
+            match rx_approve.await.unwrap_or_default() {
+                ReviewDecision::Approved => None,
+                ReviewDecision::ApprovedForSession => {
+                    sess.add_approved_mcp_tool(server.clone(), tool_name.clone());
+                    None
+                }
+                ReviewDecision::Denied | ReviewDecision::Abort => {
+                    Some("MCP tool call rejected by user".to_string())
+                }
+            }
+        }
+        McpToolCallSafety::Reject { reason } => Some(format!("MCP tool call rejected: {reason}")),
+    };
+    if let Some(content) = rejection {
+        return ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content,
+                success: Some(false),
+            },
+        };
+    }
+
     let invocation = McpInvocation {
         server: server.clone(),
         tool: tool_name.clone(),
@@ -55,6 +138,7 @@ pub(crate) async fn handle_mcp_tool_call(
     });
     notify_mcp_tool_call_event(sess, sub_id, tool_call_begin_event).await;
 
+    let timeout = tool_config.timeout_sec.map(Duration::from_secs);
     let start = Instant::now();
     // Perform the tool call.
     let result = sess
@@ -80,3 +164,37 @@ async fn notify_mcp_tool_call_event(sess: &Session, sub_id: &str, event: EventMs
     })
     .await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn approval_follows_policy_unless_configured() {
+        assert_eq!(
+            McpToolCallSafety::AutoApprove,
+            assess_mcp_tool_call_safety(None, AskForApproval::OnRequest, false)
+        );
+        assert_eq!(
+            McpToolCallSafety::AskUser,
+            assess_mcp_tool_call_safety(None, AskForApproval::UnlessTrusted, false)
+        );
+        assert_eq!(
+            McpToolCallSafety::AutoApprove,
+            assess_mcp_tool_call_safety(Some(false), AskForApproval::UnlessTrusted, false)
+        );
+        assert_eq!(
+            McpToolCallSafety::AskUser,
+            assess_mcp_tool_call_safety(Some(true), AskForApproval::OnFailure, false)
+        );
+        assert_eq!(
+            McpToolCallSafety::AutoApprove,
+            assess_mcp_tool_call_safety(Some(true), AskForApproval::OnRequest, true)
+        );
+        assert!(matches!(
+            assess_mcp_tool_call_safety(Some(true), AskForApproval::Never, false),
+            McpToolCallSafety::Reject { .. }
+        ));
+    }
+}
@@ -17,6 +17,9 @@ use crate::error::EnvVarError;
 const DEFAULT_STREAM_IDLE_TIMEOUT_MS: u64 = 300_000;
 const DEFAULT_STREAM_MAX_RETRIES: u64 = 5;
 const DEFAULT_REQUEST_MAX_RETRIES: u64 = 4;
+const DEFAULT_ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com/v1";
+const ANTHROPIC_VERSION_HEADER: &str = "anthropic-version";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
 
 /// Wire protocol that the provider speaks. Most third-party services only
 /// implement the classic OpenAI Chat Completions JSON schema, whereas OpenAI
@@ -33,6 +36,9 @@ pub enum WireApi {
     /// Regular Chat Completions compatible with `/v1/chat/completions`.
     #[default]
     Chat,
+
+    /// The Anthropic Messages API at `/v1/messages`.
+    Messages,
 }
 
 /// Serializable representation of a provider definition.
@@ -85,7 +91,8 @@ impl ModelProviderInfo {
     /// Construct a `POST` RequestBuilder for the given URL using the provided
     /// reqwest Client applying:
     ///   • provider-specific headers (static + env based)
-    ///   • Bearer auth header when an API key is available.
+    ///   • Bearer auth header when an API key is available (`x-api-key` and
+    ///     `anthropic-version` for the Messages API).
     ///   • Auth token for OAuth.
     ///
     /// If the provider declares an `env_key` but the variable is missing/empty, returns an [`Err`] identical to the
@@ -112,7 +119,21 @@ impl ModelProviderInfo {
         let mut builder = client.post(url);
 
         if let Some(auth) = effective_auth.as_ref() {
-            builder = builder.bearer_auth(auth.get_token().await?);
+            let token = auth.get_token().await?;
+            builder = match self.wire_api {
+                WireApi::Messages => builder.header("x-api-key", token),
+                WireApi::Responses | WireApi::Chat => builder.bearer_auth(token),
+            };
+        }
+
+        if self.wire_api == WireApi::Messages
+            && !self.http_headers.as_ref().is_some_and(|headers| {
+                headers
+                    .keys()
+                    .any(|k| k.eq_ignore_ascii_case(ANTHROPIC_VERSION_HEADER))
+            })
+        {
+            builder = builder.header(ANTHROPIC_VERSION_HEADER, ANTHROPIC_VERSION);
         }
 
         Ok(self.apply_http_headers(builder))
@@ -132,7 +153,9 @@ impl ModelProviderInfo {
     }
 
     pub(crate) fn get_full_url(&self, auth: &Option<CodexAuth>) -> String {
-        let default_base_url = if matches!(
+        let default_base_url = if self.wire_api == WireApi::Messages {
+            DEFAULT_ANTHROPIC_BASE_URL
+        } else if matches!(
             auth,
             Some(CodexAuth {
                 mode: AuthMode::ChatGPT,
@@ -152,6 +175,7 @@ impl ModelProviderInfo {
         match self.wire_api {
             WireApi::Responses => format!("{base_url}/responses{query_string}"),
             WireApi::Chat => format!("{base_url}/chat/completions{query_string}"),
+            WireApi::Messages => format!("{base_url}/messages{query_string}"),
         }
     }
 
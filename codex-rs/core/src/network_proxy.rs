@@ -0,0 +1,449 @@
+//! Minimal HTTP `CONNECT` proxy that lets sandboxed commands reach an
+//! allowlist of hosts.
+//!
+//! Commands that run under a sandbox without network access cannot open
+//! TCP sockets, but they can still connect to Unix domain sockets. When the
+//! sandbox policy has a non-empty network allowlist, the session starts this
+//! proxy on a Unix socket and advertises its path to commands via
+//! [`CODEX_NETWORK_PROXY_SOCKET_ENV_VAR`], e.g.:
+//!
+//! ```shell
+//! curl --unix-socket "$CODEX_NETWORK_PROXY_SOCKET" --proxy http://localhost https://crates.io
+//! ```
+//!
+//! Every `CONNECT` request is reported as a [`NetworkAccessEvent`], whether
+//! or not the host was on the allowlist.
+//!
+//! The sandbox must let commands connect to the proxy's socket and to no
+//! other Unix socket (think `/var/run/docker.sock` or `$SSH_AUTH_SOCK`).
+//! Seatbelt can match the socket path. Seccomp cannot inspect the address
+//! passed to `connect(2)`, so on Linux `codex-linux-sandbox` runs the command
+//! in a private network namespace and bridges a loopback port to the socket
+//! instead; there commands find the proxy in `HTTPS_PROXY` and friends.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tempfile::TempDir;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::io::Take;
+use tokio::net::TcpStream;
+use tokio::net::UnixListener;
+use tokio::net::UnixStream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::debug;
+use tracing::warn;
+
+use crate::protocol::NetworkAccessEvent;
+
+/// Environment variable holding the path of the proxy's Unix socket.
+pub const CODEX_NETWORK_PROXY_SOCKET_ENV_VAR: &str = "CODEX_NETWORK_PROXY_SOCKET";
+
+/// Upper bound on the size of the request head sent by a client.
+const MAX_REQUEST_HEAD_BYTES: usize = 8 * 1024;
+
+/// Whether the platform sandbox can limit commands to the proxy's socket.
+pub(crate) const NETWORK_ALLOWLIST_SUPPORTED: bool =
+    cfg!(any(target_os = "macos", target_os = "linux"));
+
+/// Returns why the Linux sandbox cannot bridge commands to the proxy, if it
+/// cannot. The bridge runs commands in new user and network namespaces,
+/// which fails where unprivileged user namespaces are disabled. Probed once
+/// per process.
+pub(crate) fn network_namespaces_unavailable() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        static UNAVAILABLE: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+        UNAVAILABLE
+            .get_or_init(|| probe_network_namespaces().err().map(|e| e.to_string()))
+            .clone()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// Creates the namespaces in a child process that exits right away instead
+/// of running a program.
+#[cfg(target_os = "linux")]
+fn probe_network_namespaces() -> std::io::Result<()> {
+    use std::os::unix::process::CommandExt;
+    use std::process::Stdio;
+
+    let mut cmd = std::process::Command::new("true");
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // SAFETY: the child only makes async-signal-safe system calls before it
+    // exits, and it never returns to the parent's code.
+    unsafe {
+        cmd.pre_exec(|| {
+            if libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            libc::_exit(0)
+        });
+    }
+    cmd.status().map(|_| ())
+}
+
+/// Ports allowed for an allowlist entry that does not specify one.
+const DEFAULT_PORTS: &[u16] = &[80, 443];
+
+/// Running proxy. The listener is shut down and its socket removed on drop.
+pub(crate) struct NetworkProxy {
+    allowlist: Vec<String>,
+    socket_path: PathBuf,
+    accept_task: JoinHandle<()>,
+    /// Holds the folder containing the socket so it is cleaned up on drop.
+    _socket_dir: TempDir,
+}
+
+impl NetworkProxy {
+    /// Starts listening on a fresh Unix socket. Each connection attempt is
+    /// sent to `tx_access`.
+    pub(crate) fn start(
+        allowlist: &[String],
+        tx_access: mpsc::UnboundedSender<NetworkAccessEvent>,
+    ) -> std::io::Result<Self> {
+        let socket_dir = tempfile::Builder::new()
+            .prefix("codex-network-proxy")
+            .tempdir()?;
+        let socket_path = socket_dir.path().join("proxy.sock");
+        let listener = UnixListener::bind(&socket_path)?;
+        let host_allowlist = Arc::new(HostAllowlist::new(allowlist));
+
+        let accept_task = tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        warn!("network proxy failed to accept connection: {e}");
+                        continue;
+                    }
+                };
+                let allowlist = Arc::clone(&host_allowlist);
+                let tx_access = tx_access.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, &allowlist, &tx_access).await {
+                        debug!("network proxy connection failed: {e}");
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            allowlist: allowlist.to_vec(),
+            socket_path,
+            accept_task,
+            _socket_dir: socket_dir,
+        })
+    }
+
+    /// The allowlist the proxy was started with.
+    pub(crate) fn allowlist(&self) -> &[String] {
+        &self.allowlist
+    }
+
+    pub(crate) fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+}
+
+impl Drop for NetworkProxy {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+async fn handle_connection(
+    stream: UnixStream,
+    allowlist: &HostAllowlist,
+    tx_access: &mpsc::UnboundedSender<NetworkAccessEvent>,
+) -> std::io::Result<()> {
+    let mut client = BufReader::new(stream);
+    let request_line = read_request_head(&mut client).await?;
+
+    let Some((host, port)) = parse_connect_request(&request_line) else {
+        client
+            .get_mut()
+            .write_all(b"HTTP/1.1 405 Method Not Allowed\r\nConnection: close\r\n\r\n")
+            .await?;
+        return Ok(());
+    };
+
+    let allowed = allowlist.is_allowed(&host, port);
+    let _ = tx_access.send(NetworkAccessEvent {
+        host: host.clone(),
+        port,
+        allowed,
+    });
+    if !allowed {
+        client
+            .get_mut()
+            .write_all(b"HTTP/1.1 403 Forbidden\r\nConnection: close\r\n\r\n")
+            .await?;
+        return Ok(());
+    }
+
+    let mut upstream = match TcpStream::connect((host.as_str(), port)).await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            client
+                .get_mut()
+                .write_all(b"HTTP/1.1 502 Bad Gateway\r\nConnection: close\r\n\r\n")
+                .await?;
+            return Err(e);
+        }
+    };
+    client
+        .get_mut()
+        .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+        .await?;
+
+    // Forward anything the client sent right after the request head before
+    // splicing the two streams together.
+    let buffered = client.buffer().to_vec();
+    if !buffered.is_empty() {
+        upstream.write_all(&buffered).await?;
+    }
+    let mut client = client.into_inner();
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    Ok(())
+}
+
+/// Reads the request head (up to and including the empty line) and returns
+/// its first line.
+async fn read_request_head(client: &mut BufReader<UnixStream>) -> std::io::Result<String> {
+    let mut head = (&mut *client).take(MAX_REQUEST_HEAD_BYTES as u64);
+    let mut request_line = Vec::new();
+    read_head_line(&mut head, &mut request_line).await?;
+    loop {
+        let mut header = Vec::new();
+        let n = read_head_line(&mut head, &mut header).await?;
+        if n == 0 || header == b"\r\n" || header == b"\n" {
+            return Ok(String::from_utf8_lossy(&request_line).into_owned());
+        }
+    }
+}
+
+/// Reads one line of the request head, failing once the head has grown past
+/// [`MAX_REQUEST_HEAD_BYTES`] so that a client cannot make the proxy buffer
+/// an endless line.
+async fn read_head_line(
+    head: &mut Take<&mut BufReader<UnixStream>>,
+    line: &mut Vec<u8>,
+) -> std::io::Result<usize> {
+    let n = head.read_until(b'\n', line).await?;
+    if head.limit() == 0 && !line.ends_with(b"\n") {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "request head too large",
+        ));
+    }
+    Ok(n)
+}
+
+/// Parses `CONNECT host:port HTTP/1.1` into the target host and port.
+fn parse_connect_request(request_line: &str) -> Option<(String, u16)> {
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next()?;
+    let authority = parts.next()?;
+    if !method.eq_ignore_ascii_case("CONNECT") {
+        return None;
+    }
+    let (host, port) = authority.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let port = port.parse().ok()?;
+    if host.is_empty() {
+        return None;
+    }
+    Some((host.to_ascii_lowercase(), port))
+}
+
+/// Hosts that sandboxed commands may connect to. Entries are either a host
+/// name (`crates.io`) or a wildcard for its subdomains (`*.crates.io`),
+/// optionally followed by `:port`. Entries without a port allow ports 80 and
+/// 443.
+struct HostAllowlist {
+    entries: Vec<AllowedHost>,
+}
+
+struct AllowedHost {
+    host: String,
+    port: Option<u16>,
+}
+
+impl HostAllowlist {
+    fn new(allowlist: &[String]) -> Self {
+        let entries = allowlist
+            .iter()
+            .filter_map(|entry| {
+                let entry = entry.trim().to_ascii_lowercase();
+                let parsed = match entry.rsplit_once(':') {
+                    Some((host, port)) => port.parse().ok().map(|port| AllowedHost {
+                        host: host.to_string(),
+                        port: Some(port),
+                    }),
+                    None => Some(AllowedHost {
+                        host: entry.clone(),
+                        port: None,
+                    }),
+                };
+                if parsed.is_none() {
+                    warn!("ignoring invalid network allowlist entry: {entry}");
+                }
+                parsed
+            })
+            .collect();
+        Self { entries }
+    }
+
+    fn is_allowed(&self, host: &str, port: u16) -> bool {
+        let host = host.to_ascii_lowercase();
+        self.entries.iter().any(|entry| {
+            let port_matches = match entry.port {
+                Some(allowed_port) => allowed_port == port,
+                None => DEFAULT_PORTS.contains(&port),
+            };
+            let host_matches = match entry.host.strip_prefix("*.") {
+                Some(domain) => host
+                    .strip_suffix(domain)
+                    .is_some_and(|prefix| prefix.ends_with('.')),
+                None => entry.host == host,
+            };
+            port_matches && host_matches
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn allowlist_matches_hosts_and_ports() {
+        let allowlist = HostAllowlist::new(&[
+            "crates.io".to_string(),
+            "*.crates.io".to_string(),
+            "registry.internal:8443".to_string(),
+        ]);
+
+        assert!(allowlist.is_allowed("crates.io", 443));
+        assert!(allowlist.is_allowed("Static.Crates.io", 443));
+        assert!(allowlist.is_allowed("registry.internal", 8443));
+
+        assert!(!allowlist.is_allowed("crates.io", 22));
+        assert!(!allowlist.is_allowed("evilcrates.io", 443));
+        assert!(!allowlist.is_allowed("registry.internal", 443));
+        assert!(!allowlist.is_allowed("example.com", 443));
+    }
+
+    #[test]
+    fn parses_connect_requests() {
+        assert_eq!(
+            Some(("crates.io".to_string(), 443)),
+            parse_connect_request("CONNECT crates.io:443 HTTP/1.1\r\n")
+        );
+        assert_eq!(
+            Some(("::1".to_string(), 8080)),
+            parse_connect_request("CONNECT [::1]:8080 HTTP/1.1\r\n")
+        );
+        assert_eq!(
+            None,
+            parse_connect_request("GET http://crates.io/ HTTP/1.1\r\n")
+        );
+        assert_eq!(
+            None,
+            parse_connect_request("CONNECT crates.io HTTP/1.1\r\n")
+        );
+    }
+
+    async fn send_connect(proxy: &NetworkProxy, authority: &str) -> (String, UnixStream) {
+        let mut stream = UnixStream::connect(proxy.socket_path()).await.unwrap();
+        stream
+            .write_all(
+                format!("CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\n\r\n").as_bytes(),
+            )
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        while !response.ends_with(b"\r\n\r\n") {
+            let mut byte = [0u8; 1];
+            if stream.read(&mut byte).await.unwrap() == 0 {
+                break;
+            }
+            response.push(byte[0]);
+        }
+        (String::from_utf8(response).unwrap(), stream)
+    }
+
+    #[tokio::test]
+    async fn proxies_allowed_hosts_and_reports_every_attempt() {
+        let upstream = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = upstream.local_addr().unwrap().port();
+        let echo = tokio::spawn(async move {
+            let (mut socket, _) = upstream.accept().await.unwrap();
+            let mut buf = [0u8; 4];
+            socket.read_exact(&mut buf).await.unwrap();
+            socket.write_all(&buf).await.unwrap();
+        });
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let proxy = NetworkProxy::start(&[format!("127.0.0.1:{port}")], tx).unwrap();
+
+        let (response, mut stream) = send_connect(&proxy, &format!("127.0.0.1:{port}")).await;
+        assert_eq!("HTTP/1.1 200 Connection Established\r\n\r\n", response);
+        stream.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(b"ping", &buf);
+        echo.await.unwrap();
+
+        let (response, _) = send_connect(&proxy, "example.com:443").await;
+        assert!(response.starts_with("HTTP/1.1 403"), "{response}");
+
+        assert_eq!(
+            Some(NetworkAccessEvent {
+                host: "127.0.0.1".to_string(),
+                port,
+                allowed: true,
+            }),
+            rx.recv().await
+        );
+        assert_eq!(
+            Some(NetworkAccessEvent {
+                host: "example.com".to_string(),
+                port: 443,
+                allowed: false,
+            }),
+            rx.recv().await
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_request_heads_without_line_breaks() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let proxy = NetworkProxy::start(&["crates.io".to_string()], tx).unwrap();
+
+        let mut stream = UnixStream::connect(proxy.socket_path()).await.unwrap();
+        // The proxy may hang up before it has read everything.
+        let _ = stream
+            .write_all(&vec![b'a'; MAX_REQUEST_HEAD_BYTES * 4])
+            .await;
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response).await;
+        assert!(response.is_empty(), "{response:?}");
+
+        drop(proxy);
+        assert_eq!(None, rx.recv().await);
+    }
+}
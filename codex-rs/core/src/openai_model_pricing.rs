@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::protocol::TokenUsage;
+
+/// Price of a model in US dollars per million tokens. Prices change over
+/// time, so entries can be overridden (or added) under `[model_pricing]` in
+/// config.toml.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct ModelPricing {
+    pub input_per_million: f64,
+
+    /// Price of input tokens served from the prompt cache. Cached tokens are
+    /// billed as regular input when unset.
+    #[serde(default)]
+    pub cached_input_per_million: Option<f64>,
+
+    /// Price of output tokens, including reasoning tokens.
+    pub output_per_million: f64,
+}
+
+impl ModelPricing {
+    const fn new(input: f64, cached_input: Option<f64>, output: f64) -> Self {
+        Self {
+            input_per_million: input,
+            cached_input_per_million: cached_input,
+            output_per_million: output,
+        }
+    }
+
+    /// Cost of `usage` in US dollars.
+    pub fn cost_usd(&self, usage: &TokenUsage) -> f64 {
+        let cached_input_price = self
+            .cached_input_per_million
+            .unwrap_or(self.input_per_million);
+        (usage.non_cached_input() as f64 * self.input_per_million
+            + usage.cached_input() as f64 * cached_input_price
+            + usage.output_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Returns the price of `model`, preferring `overrides` from config.toml over
+/// the built-in table.
+pub fn get_model_pricing(
+    model: &str,
+    overrides: &HashMap<String, ModelPricing>,
+) -> Option<ModelPricing> {
+    if let Some(pricing) = overrides.get(model) {
+        return Some(*pricing);
+    }
+    match model {
+        // https://platform.openai.com/docs/pricing
+        "gpt-5" => Some(ModelPricing::new(1.25, Some(0.125), 10.0)),
+        "o3" => Some(ModelPricing::new(2.0, Some(0.5), 8.0)),
+        "o4-mini" => Some(ModelPricing::new(1.1, Some(0.275), 4.4)),
+        "codex-mini-latest" => Some(ModelPricing::new(1.5, Some(0.375), 6.0)),
+        "gpt-4.1" | "gpt-4.1-2025-04-14" => Some(ModelPricing::new(2.0, Some(0.5), 8.0)),
+        "gpt-4o" | "gpt-4o-2024-08-06" | "gpt-4o-2024-11-20" => {
+            Some(ModelPricing::new(2.5, Some(1.25), 10.0))
+        }
+        "gpt-4o-2024-05-13" => Some(ModelPricing::new(5.0, None, 15.0)),
+        "gpt-3.5-turbo" => Some(ModelPricing::new(0.5, None, 1.5)),
+
+        // The gpt-oss models are open-weight and usually run locally, so
+        // there is nothing to bill.
+        "gpt-oss-20b" | "gpt-oss-120b" => Some(ModelPricing::new(0.0, None, 0.0)),
+
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn cached_input_is_billed_at_the_cached_rate() {
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            cached_input_tokens: Some(400_000),
+            output_tokens: 100_000,
+            reasoning_output_tokens: Some(50_000),
+            total_tokens: 1_100_000,
+        };
+        let pricing = get_model_pricing("o3", &HashMap::new()).unwrap();
+        // 600k input at $2 + 400k cached at $0.50 + 100k output at $8.
+        assert_eq!(2.2, pricing.cost_usd(&usage));
+
+        let uncached = ModelPricing::new(2.0, None, 8.0);
+        assert_eq!(2.8, uncached.cost_usd(&usage));
+    }
+
+    #[test]
+    fn overrides_take_precedence() {
+        let custom = ModelPricing::new(1.0, None, 2.0);
+        let overrides = HashMap::from([("o3".to_string(), custom)]);
+        assert_eq!(Some(custom), get_model_pricing("o3", &overrides));
+        assert_eq!(None, get_model_pricing("my-local-model", &HashMap::new()));
+    }
+}
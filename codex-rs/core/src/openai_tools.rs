@@ -5,7 +5,18 @@ use serde_json::json;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 
+use crate::background_jobs::KILL_BACKGROUND_JOB_TOOL;
+use crate::background_jobs::READ_BACKGROUND_JOB_TOOL;
+use crate::background_jobs::START_BACKGROUND_JOB_TOOL;
+use crate::exec_output::READ_EXEC_OUTPUT_TOOL;
+use crate::exec_session::EXEC_COMMAND_TOOL;
+use crate::exec_session::KILL_SESSION_TOOL;
+use crate::exec_session::WRITE_STDIN_TOOL;
+use crate::file_tools::GREP_TOOL;
+use crate::file_tools::LIST_DIR_TOOL;
+use crate::file_tools::READ_FILE_TOOL;
 use crate::model_family::ModelFamily;
+use crate::plan_tool::GET_PLAN_TOOL;
 use crate::plan_tool::PLAN_TOOL;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
@@ -35,8 +46,13 @@ pub(crate) enum OpenAiTool {
 #[derive(Debug, Clone)]
 pub enum ConfigShellToolType {
     DefaultShell,
-    ShellWithRequest { sandbox_policy: SandboxPolicy },
+    ShellWithRequest {
+        sandbox_policy: SandboxPolicy,
+    },
     LocalShell,
+    /// `exec_command`, `write_stdin` and `kill_session`, which keep
+    /// processes running between calls.
+    StreamableShell,
 }
 
 #[derive(Debug, Clone)]
@@ -53,6 +69,7 @@ impl ToolsConfig {
         sandbox_policy: SandboxPolicy,
         include_plan_tool: bool,
         include_apply_patch_tool: bool,
+        use_streamable_shell_tool: bool,
     ) -> Self {
         let mut shell_type = if model_family.uses_local_shell_tool {
             ConfigShellToolType::LocalShell
@@ -64,6 +81,9 @@ impl ToolsConfig {
                 sandbox_policy: sandbox_policy.clone(),
             }
         }
+        if use_streamable_shell_tool {
+            shell_type = ConfigShellToolType::StreamableShell;
+        }
 
         Self {
             shell_type,
@@ -319,6 +339,69 @@ It is important to remember:
     })
 }
 
+/// Name of the built-in tool that reads a resource from an MCP server.
+pub(crate) const READ_MCP_RESOURCE_TOOL_NAME: &str = "read_mcp_resource";
+
+/// Upper bound on the number of resources enumerated in the description of
+/// the `read_mcp_resource` tool.
+const MAX_LISTED_MCP_RESOURCES: usize = 50;
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ReadMcpResourceToolArgs {
+    pub(crate) server: String,
+    pub(crate) uri: String,
+}
+
+/// The description lists the resources known at startup so the model can
+/// discover them without an extra round trip.
+fn create_read_mcp_resource_tool(
+    mcp_resources: &HashMap<String, Vec<mcp_types::Resource>>,
+) -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "server".to_string(),
+        JsonSchema::String {
+            description: Some("Name of the MCP server that offers the resource".to_string()),
+        },
+    );
+    properties.insert(
+        "uri".to_string(),
+        JsonSchema::String {
+            description: Some("URI of the resource to read".to_string()),
+        },
+    );
+
+    let mut servers: Vec<&String> = mcp_resources.keys().collect();
+    servers.sort();
+    let mut listed = Vec::new();
+    for server in servers {
+        for resource in &mcp_resources[server] {
+            let label = resource.description.as_deref().unwrap_or(&resource.name);
+            listed.push(format!("- {server}: {} ({label})", resource.uri));
+        }
+    }
+    let total = listed.len();
+    listed.truncate(MAX_LISTED_MCP_RESOURCES);
+    let mut description = String::from(
+        "Reads a resource (a file, document, record, ...) exposed by one of the connected MCP servers and returns its contents.\n\nAvailable resources:\n",
+    );
+    description.push_str(&listed.join("\n"));
+    if total > listed.len() {
+        description.push_str(&format!("\n... and {} more", total - listed.len()));
+    }
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: READ_MCP_RESOURCE_TOOL_NAME.to_string(),
+        description,
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["server".to_string(), "uri".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
+
 /// Returns JSON values that are compatible with Function Calling in the
 /// Responses API:
 /// https://platform.openai.com/docs/guides/function-calling?api-mode=responses
@@ -365,6 +448,37 @@ pub(crate) fn create_tools_json_for_chat_completions_api(
     Ok(tools_json)
 }
 
+/// Returns JSON values that are compatible with tool use in the Anthropic
+/// Messages API:
+/// https://docs.anthropic.com/en/docs/agents-and-tools/tool-use/overview
+pub(crate) fn create_tools_json_for_messages_api(
+    tools: &Vec<OpenAiTool>,
+) -> crate::error::Result<Vec<serde_json::Value>> {
+    let mut tools_json = Vec::new();
+    for tool in tools {
+        // The local shell tool is specific to the Responses API; offer the
+        // equivalent function tool instead.
+        let tool = match tool {
+            OpenAiTool::LocalShell {} => &create_shell_tool(),
+            tool => tool,
+        };
+        if let OpenAiTool::Function(ResponsesApiTool {
+            name,
+            description,
+            parameters,
+            ..
+        }) = tool
+        {
+            tools_json.push(json!({
+                "name": name,
+                "description": description,
+                "input_schema": serde_json::to_value(parameters)?,
+            }));
+        }
+    }
+    Ok(tools_json)
+}
+
 pub(crate) fn mcp_tool_to_openai_tool(
     fully_qualified_name: String,
     tool: mcp_types::Tool,
@@ -516,10 +630,12 @@ fn sanitize_json_schema(value: &mut JsonValue) {
 
 /// Returns a list of OpenAiTools based on the provided config and MCP tools.
 /// Note that the keys of mcp_tools should be fully qualified names. See
-/// [`McpConnectionManager`] for more details.
+/// [`McpConnectionManager`] for more details. The `read_mcp_resource` tool is
+/// only offered when at least one server has resources.
 pub(crate) fn get_openai_tools(
     config: &ToolsConfig,
     mcp_tools: Option<HashMap<String, mcp_types::Tool>>,
+    mcp_resources: &HashMap<String, Vec<mcp_types::Resource>>,
 ) -> Vec<OpenAiTool> {
     let mut tools: Vec<OpenAiTool> = Vec::new();
 
@@ -533,16 +649,38 @@ pub(crate) fn get_openai_tools(
         ConfigShellToolType::LocalShell => {
             tools.push(OpenAiTool::LocalShell {});
         }
+        ConfigShellToolType::StreamableShell => {
+            tools.push(EXEC_COMMAND_TOOL.clone());
+            tools.push(WRITE_STDIN_TOOL.clone());
+            tools.push(KILL_SESSION_TOOL.clone());
+        }
+    }
+
+    if !matches!(config.shell_type, ConfigShellToolType::StreamableShell) {
+        tools.push(READ_EXEC_OUTPUT_TOOL.clone());
     }
 
+    tools.push(READ_FILE_TOOL.clone());
+    tools.push(GREP_TOOL.clone());
+    tools.push(LIST_DIR_TOOL.clone());
+
+    tools.push(START_BACKGROUND_JOB_TOOL.clone());
+    tools.push(READ_BACKGROUND_JOB_TOOL.clone());
+    tools.push(KILL_BACKGROUND_JOB_TOOL.clone());
+
     if config.plan_tool {
         tools.push(PLAN_TOOL.clone());
+        tools.push(GET_PLAN_TOOL.clone());
     }
 
     if config.apply_patch_tool {
         tools.push(create_apply_patch_tool());
     }
 
+    if !mcp_resources.is_empty() {
+        tools.push(create_read_mcp_resource_tool(mcp_resources));
+    }
+
     if let Some(mcp_tools) = mcp_tools {
         for (name, tool) in mcp_tools {
             match mcp_tool_to_openai_tool(name.clone(), tool.clone()) {
@@ -597,10 +735,33 @@ mod tests {
             SandboxPolicy::ReadOnly,
             true,
             model_family.uses_apply_patch_tool,
+            false,
+        );
+        let tools = get_openai_tools(&config, Some(HashMap::new()), &HashMap::new());
+
+        assert_eq_tool_names(
+            &tools,
+            &[
+                "local_shell",
+                "read_exec_output",
+                "read_file",
+                "grep",
+                "list_dir",
+                "start_background_job",
+                "read_background_job",
+                "kill_background_job",
+                "update_plan",
+                "get_plan",
+            ],
         );
-        let tools = get_openai_tools(&config, Some(HashMap::new()));
+    }
 
-        assert_eq_tool_names(&tools, &["local_shell", "update_plan"]);
+    #[test]
+    fn test_messages_api_tools_replace_local_shell() {
+        let tools_json =
+            create_tools_json_for_messages_api(&vec![OpenAiTool::LocalShell {}]).unwrap();
+        let names: Vec<_> = tools_json.iter().map(|tool| &tool["name"]).collect();
+        assert_eq!(names, vec!["shell"]);
     }
 
     #[test]
@@ -612,10 +773,102 @@ mod tests {
             SandboxPolicy::ReadOnly,
             true,
             model_family.uses_apply_patch_tool,
+            false,
         );
-        let tools = get_openai_tools(&config, Some(HashMap::new()));
+        let tools = get_openai_tools(&config, Some(HashMap::new()), &HashMap::new());
+
+        assert_eq_tool_names(
+            &tools,
+            &[
+                "shell",
+                "read_exec_output",
+                "read_file",
+                "grep",
+                "list_dir",
+                "start_background_job",
+                "read_background_job",
+                "kill_background_job",
+                "update_plan",
+                "get_plan",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_get_openai_tools_streamable_shell() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(
+            &model_family,
+            AskForApproval::OnRequest,
+            SandboxPolicy::ReadOnly,
+            false,
+            false,
+            true,
+        );
+        let tools = get_openai_tools(&config, Some(HashMap::new()), &HashMap::new());
+
+        assert_eq_tool_names(
+            &tools,
+            &[
+                "exec_command",
+                "write_stdin",
+                "kill_session",
+                "read_file",
+                "grep",
+                "list_dir",
+                "start_background_job",
+                "read_background_job",
+                "kill_background_job",
+            ],
+        );
+    }
 
-        assert_eq_tool_names(&tools, &["shell", "update_plan"]);
+    #[test]
+    fn test_get_openai_tools_read_mcp_resource() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(
+            &model_family,
+            AskForApproval::Never,
+            SandboxPolicy::ReadOnly,
+            false,
+            model_family.uses_apply_patch_tool,
+            false,
+        );
+        let resources = HashMap::from([(
+            "docs".to_string(),
+            vec![mcp_types::Resource {
+                annotations: None,
+                description: Some("Getting started".to_string()),
+                mime_type: None,
+                name: "intro".to_string(),
+                size: None,
+                title: None,
+                uri: "docs://intro".to_string(),
+            }],
+        )]);
+        let tools = get_openai_tools(&config, Some(HashMap::new()), &resources);
+
+        assert_eq_tool_names(
+            &tools,
+            &[
+                "shell",
+                "read_exec_output",
+                "read_file",
+                "grep",
+                "list_dir",
+                "start_background_job",
+                "read_background_job",
+                "kill_background_job",
+                "read_mcp_resource",
+            ],
+        );
+        let OpenAiTool::Function(ResponsesApiTool { description, .. }) = &tools[8] else {
+            panic!("expected a function tool");
+        };
+        assert!(
+            description.ends_with("Available resources:\n- docs: docs://intro (Getting started)"),
+            "{description}"
+        );
     }
 
     #[test]
@@ -627,6 +880,7 @@ mod tests {
             SandboxPolicy::ReadOnly,
             false,
             model_family.uses_apply_patch_tool,
+            false,
         );
         let tools = get_openai_tools(
             &config,
@@ -664,12 +918,26 @@ mod tests {
                     description: Some("Do something cool".to_string()),
                 },
             )])),
+            &HashMap::new(),
         );
 
-        assert_eq_tool_names(&tools, &["shell", "test_server/do_something_cool"]);
+        assert_eq_tool_names(
+            &tools,
+            &[
+                "shell",
+                "read_exec_output",
+                "read_file",
+                "grep",
+                "list_dir",
+                "start_background_job",
+                "read_background_job",
+                "kill_background_job",
+                "test_server/do_something_cool",
+            ],
+        );
 
         assert_eq!(
-            tools[1],
+            tools[8],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "test_server/do_something_cool".to_string(),
                 parameters: JsonSchema::Object {
@@ -721,6 +989,7 @@ mod tests {
             SandboxPolicy::ReadOnly,
             false,
             model_family.uses_apply_patch_tool,
+            false,
         );
 
         let tools = get_openai_tools(
@@ -744,12 +1013,26 @@ mod tests {
                     description: Some("Search docs".to_string()),
                 },
             )])),
+            &HashMap::new(),
         );
 
-        assert_eq_tool_names(&tools, &["shell", "dash/search"]);
+        assert_eq_tool_names(
+            &tools,
+            &[
+                "shell",
+                "read_exec_output",
+                "read_file",
+                "grep",
+                "list_dir",
+                "start_background_job",
+                "read_background_job",
+                "kill_background_job",
+                "dash/search",
+            ],
+        );
 
         assert_eq!(
-            tools[1],
+            tools[8],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "dash/search".to_string(),
                 parameters: JsonSchema::Object {
@@ -777,6 +1060,7 @@ mod tests {
             SandboxPolicy::ReadOnly,
             false,
             model_family.uses_apply_patch_tool,
+            false,
         );
 
         let tools = get_openai_tools(
@@ -798,11 +1082,25 @@ mod tests {
                     description: Some("Pagination".to_string()),
                 },
             )])),
+            &HashMap::new(),
         );
 
-        assert_eq_tool_names(&tools, &["shell", "dash/paginate"]);
+        assert_eq_tool_names(
+            &tools,
+            &[
+                "shell",
+                "read_exec_output",
+                "read_file",
+                "grep",
+                "list_dir",
+                "start_background_job",
+                "read_background_job",
+                "kill_background_job",
+                "dash/paginate",
+            ],
+        );
         assert_eq!(
-            tools[1],
+            tools[8],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "dash/paginate".to_string(),
                 parameters: JsonSchema::Object {
@@ -828,6 +1126,7 @@ mod tests {
             SandboxPolicy::ReadOnly,
             false,
             model_family.uses_apply_patch_tool,
+            false,
         );
 
         let tools = get_openai_tools(
@@ -849,11 +1148,25 @@ mod tests {
                     description: Some("Tags".to_string()),
                 },
             )])),
+            &HashMap::new(),
         );
 
-        assert_eq_tool_names(&tools, &["shell", "dash/tags"]);
+        assert_eq_tool_names(
+            &tools,
+            &[
+                "shell",
+                "read_exec_output",
+                "read_file",
+                "grep",
+                "list_dir",
+                "start_background_job",
+                "read_background_job",
+                "kill_background_job",
+                "dash/tags",
+            ],
+        );
         assert_eq!(
-            tools[1],
+            tools[8],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "dash/tags".to_string(),
                 parameters: JsonSchema::Object {
@@ -882,6 +1195,7 @@ mod tests {
             SandboxPolicy::ReadOnly,
             false,
             model_family.uses_apply_patch_tool,
+            false,
         );
 
         let tools = get_openai_tools(
@@ -903,11 +1217,25 @@ mod tests {
                     description: Some("AnyOf Value".to_string()),
                 },
             )])),
+            &HashMap::new(),
         );
 
-        assert_eq_tool_names(&tools, &["shell", "dash/value"]);
+        assert_eq_tool_names(
+            &tools,
+            &[
+                "shell",
+                "read_exec_output",
+                "read_file",
+                "grep",
+                "list_dir",
+                "start_background_job",
+                "read_background_job",
+                "kill_background_job",
+                "dash/value",
+            ],
+        );
         assert_eq!(
-            tools[1],
+            tools[8],
             OpenAiTool::Function(ResponsesApiTool {
                 name: "dash/value".to_string(),
                 parameters: JsonSchema::Object {
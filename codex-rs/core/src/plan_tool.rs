@@ -13,6 +13,7 @@ use crate::protocol::EventMsg;
 // Use the canonical plan tool types from the protocol crate to ensure
 // type-identity matches events transported via `codex_protocol`.
 pub use codex_protocol::plan_tool::PlanItemArg;
+pub use codex_protocol::plan_tool::PlanProgress;
 pub use codex_protocol::plan_tool::StepStatus;
 pub use codex_protocol::plan_tool::UpdatePlanArgs;
 
@@ -60,6 +61,21 @@ At most one step can be in_progress at a time.
     })
 });
 
+pub(crate) static GET_PLAN_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    OpenAiTool::Function(ResponsesApiTool {
+        name: "get_plan".to_string(),
+        description: r#"Returns the current task plan as last set with update_plan, including the status of each step.
+"#
+        .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties: BTreeMap::new(),
+            required: None,
+            additional_properties: Some(false),
+        },
+    })
+});
+
 /// This function doesn't do anything useful. However, it gives the model a structured way to record its plan that clients can read and render.
 /// So it's the _inputs_ to this function that are useful to clients, not the outputs and neither are actually useful for the model other
 /// than forcing it to come up and document a plan (TBD how that affects performance).
@@ -71,6 +87,7 @@ pub(crate) async fn handle_update_plan(
 ) -> ResponseInputItem {
     match parse_update_plan_arguments(arguments, &call_id) {
         Ok(args) => {
+            session.set_snapshot_plan(args.clone());
             let output = ResponseInputItem::FunctionCallOutput {
                 call_id,
                 output: FunctionCallOutputPayload {
@@ -90,6 +107,33 @@ pub(crate) async fn handle_update_plan(
     }
 }
 
+/// Returns the plan recorded in the session state (which survives resume and
+/// compaction) so the model can read it back.
+pub(crate) fn handle_get_plan(session: &Session, call_id: String) -> ResponseInputItem {
+    let content = match session.snapshot_plan() {
+        Some(plan) => match serde_json::to_string(&plan) {
+            Ok(plan) => plan,
+            Err(e) => {
+                return ResponseInputItem::FunctionCallOutput {
+                    call_id,
+                    output: FunctionCallOutputPayload {
+                        content: format!("failed to serialize plan: {e}"),
+                        success: Some(false),
+                    },
+                };
+            }
+        },
+        None => "No plan has been recorded yet".to_string(),
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(true),
+        },
+    }
+}
+
 fn parse_update_plan_arguments(
     arguments: String,
     call_id: &str,
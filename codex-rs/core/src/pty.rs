@@ -0,0 +1,68 @@
+//! Pseudo-terminals for the interactive sessions in [`crate::exec_session`].
+//! Programs see a real terminal, so they line-buffer their output, show
+//! prompts and can be driven by writing to the master end.
+
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+
+/// Terminal size reported to programs running in a session. Wide enough that
+/// most tools do not wrap their output.
+const PTY_ROWS: u16 = 24;
+const PTY_COLS: u16 = 200;
+
+pub(crate) struct Pty {
+    /// Read to get the output of the session, write to send it input.
+    pub master: OwnedFd,
+    /// Becomes stdin, stdout and stderr of the spawned process.
+    pub slave: OwnedFd,
+}
+
+pub(crate) fn open_pty() -> io::Result<Pty> {
+    let mut master: libc::c_int = -1;
+    let mut slave: libc::c_int = -1;
+    let mut winsize = libc::winsize {
+        ws_row: PTY_ROWS,
+        ws_col: PTY_COLS,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: the out-pointers are valid, a null name and termios are
+    // allowed, and `winsize` outlives the call.
+    let rc = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            // `*const` on Linux, `*mut` on macOS.
+            &raw mut winsize,
+        )
+    };
+    if rc == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: openpty succeeded, so both descriptors are open and owned by us.
+    let pty = unsafe {
+        Pty {
+            master: OwnedFd::from_raw_fd(master),
+            slave: OwnedFd::from_raw_fd(slave),
+        }
+    };
+
+    // Neither end should leak into other processes spawned by Codex; the
+    // slave is duplicated onto the stdio of the session's process instead.
+    set_cloexec(&pty.master)?;
+    set_cloexec(&pty.slave)?;
+    Ok(pty)
+}
+
+fn set_cloexec(fd: &OwnedFd) -> io::Result<()> {
+    // SAFETY: `fd` is an open descriptor.
+    let rc = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) };
+    if rc == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -2,8 +2,11 @@
 
 use std::fs::File;
 use std::fs::{self};
+use std::io::BufRead;
+use std::io::BufReader;
 use std::io::Error as IoError;
 use std::path::Path;
+use std::path::PathBuf;
 
 use serde::Deserialize;
 use serde::Serialize;
@@ -20,9 +23,14 @@ use tracing::warn;
 use uuid::Uuid;
 
 use crate::config::Config;
+use crate::conversation_history::user_message_text;
 use crate::git_info::GitInfo;
 use crate::git_info::collect_git_info;
 use crate::models::ResponseItem;
+use crate::plan_tool::UpdatePlanArgs;
+use crate::protocol::AskForApproval;
+use crate::protocol::SandboxPolicy;
+use crate::protocol::TokenUsage;
 
 const SESSIONS_SUBDIR: &str = "sessions";
 
@@ -31,18 +39,52 @@ pub struct SessionMeta {
     pub id: Uuid,
     pub timestamp: String,
     pub instructions: Option<String>,
+    /// Set when this session was forked from another one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<Uuid>,
 }
 
-#[derive(Serialize)]
-struct SessionMetaWithGit {
+/// First line of every rollout file.
+#[derive(Serialize, Deserialize)]
+pub struct SessionMetaWithGit {
     #[serde(flatten)]
-    meta: SessionMeta,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    git: Option<GitInfo>,
+    pub meta: SessionMeta,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitInfo>,
 }
 
-#[derive(Serialize, Deserialize, Default, Clone)]
-pub struct SessionStateSnapshot {}
+/// State of the session that is not part of the conversation history but is
+/// needed to pick up where it left off. The latest snapshot in a rollout file
+/// wins.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct SessionStateSnapshot {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_policy: Option<AskForApproval>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox_policy: Option<SandboxPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// Most recent plan recorded with the `update_plan` tool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<UpdatePlanArgs>,
+    /// Usage reported for the most recent model request, used to decide when
+    /// to compact the history. Cleared once the history is compacted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_token_usage: Option<TokenUsage>,
+}
+
+/// Summary of a recorded session, as shown when choosing one to resume.
+#[derive(Debug, Clone)]
+pub struct SessionInfo {
+    pub path: PathBuf,
+    pub id: Uuid,
+    pub timestamp: String,
+    pub cwd: Option<PathBuf>,
+    pub git_branch: Option<String>,
+    pub first_user_message: Option<String>,
+}
 
 #[derive(Serialize, Deserialize, Default, Clone)]
 pub struct SavedSession {
@@ -70,8 +112,12 @@ pub(crate) struct RolloutRecorder {
 
 enum RolloutCmd {
     AddItems(Vec<ResponseItem>),
-    UpdateState(SessionStateSnapshot),
-    Shutdown { ack: oneshot::Sender<()> },
+    UpdateState(Box<SessionStateSnapshot>),
+    /// The history was compacted into these items.
+    Compacted(Vec<ResponseItem>),
+    Shutdown {
+        ack: oneshot::Sender<()>,
+    },
 }
 
 impl RolloutRecorder {
@@ -82,6 +128,7 @@ impl RolloutRecorder {
         config: &Config,
         uuid: Uuid,
         instructions: Option<String>,
+        parent_id: Option<Uuid>,
     ) -> std::io::Result<Self> {
         let LogFileInfo {
             file,
@@ -114,6 +161,7 @@ impl RolloutRecorder {
                 timestamp,
                 id: session_id,
                 instructions,
+                parent_id,
             }),
             cwd,
         ));
@@ -150,11 +198,26 @@ impl RolloutRecorder {
 
     pub(crate) async fn record_state(&self, state: SessionStateSnapshot) -> std::io::Result<()> {
         self.tx
-            .send(RolloutCmd::UpdateState(state))
+            .send(RolloutCmd::UpdateState(Box::new(state)))
             .await
             .map_err(|e| IoError::other(format!("failed to queue rollout state: {e}")))
     }
 
+    /// Records that the history was compacted into `items`, which replace
+    /// everything recorded before when the session is resumed.
+    pub(crate) async fn record_compaction(&self, items: &[ResponseItem]) -> std::io::Result<()> {
+        self.tx
+            .send(RolloutCmd::Compacted(
+                items
+                    .iter()
+                    .filter(|item| !matches!(item, ResponseItem::Other))
+                    .cloned()
+                    .collect(),
+            ))
+            .await
+            .map_err(|e| IoError::other(format!("failed to queue rollout compaction: {e}")))
+    }
+
     pub async fn resume(
         path: &Path,
         cwd: std::path::PathBuf,
@@ -178,15 +241,25 @@ impl RolloutRecorder {
                 Ok(v) => v,
                 Err(_) => continue,
             };
-            if v.get("record_type")
-                .and_then(|rt| rt.as_str())
-                .map(|s| s == "state")
-                .unwrap_or(false)
-            {
-                if let Ok(s) = serde_json::from_value::<SessionStateSnapshot>(v.clone()) {
-                    state = s
+            match v.get("record_type").and_then(|rt| rt.as_str()) {
+                Some("state") => {
+                    match serde_json::from_value::<SessionStateSnapshot>(v.clone()) {
+                        Ok(s) => state = s,
+                        Err(e) => warn!("failed to parse session state: {v:?}, error: {e}"),
+                    }
+                    continue;
                 }
-                continue;
+                Some(COMPACTED_RECORD_TYPE) => {
+                    match serde_json::from_value::<CompactedLine>(v.clone()) {
+                        Ok(compacted) => {
+                            items = compacted.items;
+                            state.last_token_usage = None;
+                        }
+                        Err(e) => warn!("failed to parse compaction: {v:?}, error: {e}"),
+                    }
+                    continue;
+                }
+                _ => {}
             }
             match serde_json::from_value::<ResponseItem>(v.clone()) {
                 Ok(item) => match item {
@@ -294,6 +367,7 @@ async fn rollout_writer(
     cwd: std::path::PathBuf,
 ) -> std::io::Result<()> {
     let mut writer = JsonlWriter { file };
+    let mut last_state_line: Option<String> = None;
 
     // If we have a meta, collect git info asynchronously and write meta first
     if let Some(session_meta) = meta.take() {
@@ -331,10 +405,22 @@ async fn rollout_writer(
                     #[serde(flatten)]
                     state: &'a SessionStateSnapshot,
                 }
+                let line = serde_json::to_string(&StateLine {
+                    record_type: "state",
+                    state: &state,
+                })?;
+                // The state is re-sent with every batch of items but rarely
+                // changes, so only write it when it does.
+                if last_state_line.as_ref() != Some(&line) {
+                    writer.write_raw_line(&line).await?;
+                    last_state_line = Some(line);
+                }
+            }
+            RolloutCmd::Compacted(items) => {
                 writer
-                    .write_line(&StateLine {
-                        record_type: "state",
-                        state: &state,
+                    .write_line(&CompactedLine {
+                        record_type: COMPACTED_RECORD_TYPE.to_string(),
+                        items,
                     })
                     .await?;
             }
@@ -347,16 +433,317 @@ async fn rollout_writer(
     Ok(())
 }
 
+/// `record_type` of the line written when the history is compacted.
+const COMPACTED_RECORD_TYPE: &str = "compacted";
+
+/// Written when the history is compacted. On resume, `items` replace the
+/// items recorded before it.
+#[derive(Serialize, Deserialize)]
+struct CompactedLine {
+    record_type: String,
+    items: Vec<ResponseItem>,
+}
+
 struct JsonlWriter {
     file: tokio::fs::File,
 }
 
 impl JsonlWriter {
     async fn write_line(&mut self, item: &impl serde::Serialize) -> std::io::Result<()> {
-        let mut json = serde_json::to_string(item)?;
+        let json = serde_json::to_string(item)?;
+        self.write_raw_line(&json).await
+    }
+
+    async fn write_raw_line(&mut self, json: &str) -> std::io::Result<()> {
+        let mut json = json.to_string();
         json.push('\n');
         let _ = self.file.write_all(json.as_bytes()).await;
         self.file.flush().await?;
         Ok(())
     }
 }
+
+/// Lists the sessions recorded under `$CODEX_HOME/sessions`, most recent
+/// first. Files that cannot be read or parsed are skipped.
+pub fn list_sessions(codex_home: &Path) -> std::io::Result<Vec<SessionInfo>> {
+    let mut paths = Vec::new();
+    collect_rollout_paths(&codex_home.join(SESSIONS_SUBDIR), &mut paths)?;
+    // Rollout files are named `rollout-<timestamp>-<id>.jsonl` and live in
+    // `YYYY/MM/DD` folders, so sorting by file name sorts by start time.
+    paths.sort_by(|a, b| b.file_name().cmp(&a.file_name()));
+
+    Ok(paths
+        .into_iter()
+        .filter_map(|path| match read_session_info(&path) {
+            Ok(info) => Some(info),
+            Err(e) => {
+                warn!("skipping rollout file {path:?}: {e}");
+                None
+            }
+        })
+        .collect())
+}
+
+/// Finds the session whose id is `id` or starts with `id`. Returns an error
+/// if the prefix matches more than one session.
+pub fn find_session(codex_home: &Path, id: &str) -> std::io::Result<Option<SessionInfo>> {
+    let id = id.to_ascii_lowercase();
+    let mut matches = list_sessions(codex_home)?
+        .into_iter()
+        .filter(|info| info.id.to_string().starts_with(&id));
+    let found = matches.next();
+    if found.is_some() && matches.next().is_some() {
+        return Err(IoError::other(format!(
+            "session id `{id}` is ambiguous; use more characters"
+        )));
+    }
+    Ok(found)
+}
+
+/// Reads the latest [`SessionStateSnapshot`] recorded in a rollout file.
+pub fn read_session_state(path: &Path) -> std::io::Result<SessionStateSnapshot> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut state = SessionStateSnapshot::default();
+    for line in reader.lines().skip(1) {
+        let line = line?;
+        if let Some(s) = parse_state_line(&line) {
+            state = s;
+        } else if is_compacted_line(&line) {
+            // The usage refers to the history before it was compacted.
+            state.last_token_usage = None;
+        }
+    }
+    Ok(state)
+}
+
+fn collect_rollout_paths(dir: &Path, paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_rollout_paths(&path, paths)?;
+        } else if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("rollout-") && name.ends_with(".jsonl"))
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Reads the session meta and scans the rollout only as far as the first
+/// message typed by the user.
+fn read_session_info(path: &Path) -> std::io::Result<SessionInfo> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let meta_line = lines
+        .next()
+        .ok_or_else(|| IoError::other("empty session file"))??;
+    let SessionMetaWithGit { meta, git } = serde_json::from_str(&meta_line)
+        .map_err(|e| IoError::other(format!("failed to parse session meta: {e}")))?;
+
+    let mut cwd = None;
+    let mut first_user_message = None;
+    for line in lines {
+        let line = line?;
+        if let Some(state) = parse_state_line(&line) {
+            cwd = cwd.or(state.cwd);
+            continue;
+        }
+        if let Ok(item) = serde_json::from_str::<ResponseItem>(&line)
+            && let Some(text) = user_message_text(&item)
+        {
+            first_user_message = Some(text);
+            break;
+        }
+    }
+
+    Ok(SessionInfo {
+        path: path.to_path_buf(),
+        id: meta.id,
+        timestamp: meta.timestamp,
+        cwd,
+        git_branch: git.and_then(|git| git.branch),
+        first_user_message,
+    })
+}
+
+fn is_compacted_line(line: &str) -> bool {
+    serde_json::from_str::<Value>(line).is_ok_and(|v| {
+        v.get("record_type").and_then(|rt| rt.as_str()) == Some(COMPACTED_RECORD_TYPE)
+    })
+}
+
+fn parse_state_line(line: &str) -> Option<SessionStateSnapshot> {
+    let v: Value = serde_json::from_str(line).ok()?;
+    if v.get("record_type").and_then(|rt| rt.as_str()) != Some("state") {
+        return None;
+    }
+    serde_json::from_value(v).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn write_rollout(codex_home: &Path, date: &str, id: &str, lines: &[Value]) -> PathBuf {
+        let dir = codex_home.join(SESSIONS_SUBDIR).join("2025").join("05");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("rollout-{date}-{id}.jsonl"));
+        let text: Vec<String> = lines.iter().map(Value::to_string).collect();
+        fs::write(&path, text.join("\n")).unwrap();
+        path
+    }
+
+    fn user_message(text: &str) -> Value {
+        json!({
+            "type": "message",
+            "role": "user",
+            "content": [{ "type": "input_text", "text": text }],
+        })
+    }
+
+    #[test]
+    fn lists_sessions_newest_first_with_summary() {
+        let codex_home = TempDir::new().unwrap();
+        let older_id = "5973b6c0-94b8-487b-a530-2aeb6098ae0e";
+        let newer_id = "0e8f1c2a-1111-4222-8333-444455556666";
+        write_rollout(
+            codex_home.path(),
+            "2025-05-07T17-24-21",
+            older_id,
+            &[json!({ "id": older_id, "timestamp": "2025-05-07T17:24:21.000Z" })],
+        );
+        let newer_path = write_rollout(
+            codex_home.path(),
+            "2025-05-08T09-00-00",
+            newer_id,
+            &[
+                json!({
+                    "id": newer_id,
+                    "timestamp": "2025-05-08T09:00:00.000Z",
+                    "git": { "branch": "main" },
+                }),
+                json!({ "record_type": "state", "cwd": "/work/project" }),
+                user_message(
+                    "<environment_context>\n<cwd>/work/project</cwd>\n</environment_context>",
+                ),
+                user_message("fix the flaky test"),
+            ],
+        );
+
+        let sessions = list_sessions(codex_home.path()).unwrap();
+        let ids: Vec<String> = sessions.iter().map(|s| s.id.to_string()).collect();
+        assert_eq!(vec![newer_id.to_string(), older_id.to_string()], ids);
+
+        let newest = &sessions[0];
+        assert_eq!(newer_path, newest.path);
+        assert_eq!(Some(PathBuf::from("/work/project")), newest.cwd);
+        assert_eq!(Some("main".to_string()), newest.git_branch);
+        assert_eq!(
+            Some("fix the flaky test".to_string()),
+            newest.first_user_message
+        );
+        assert_eq!(None, sessions[1].first_user_message);
+    }
+
+    #[test]
+    fn finds_sessions_by_id_prefix() {
+        let codex_home = TempDir::new().unwrap();
+        for id in [
+            "aaaa0000-0000-4000-8000-000000000001",
+            "aaaa0000-0000-4000-8000-000000000002",
+            "bbbb0000-0000-4000-8000-000000000003",
+        ] {
+            write_rollout(
+                codex_home.path(),
+                "2025-05-07T17-24-21",
+                id,
+                &[json!({ "id": id, "timestamp": "2025-05-07T17:24:21.000Z" })],
+            );
+        }
+
+        let found = find_session(codex_home.path(), "BBBB").unwrap().unwrap();
+        assert_eq!("bbbb0000-0000-4000-8000-000000000003", found.id.to_string());
+        assert!(find_session(codex_home.path(), "aaaa").is_err());
+        assert!(find_session(codex_home.path(), "cccc").unwrap().is_none());
+    }
+
+    #[test]
+    fn reads_latest_session_state() {
+        let codex_home = TempDir::new().unwrap();
+        let id = "5973b6c0-94b8-487b-a530-2aeb6098ae0e";
+        let path = write_rollout(
+            codex_home.path(),
+            "2025-05-07T17-24-21",
+            id,
+            &[
+                json!({ "id": id, "timestamp": "2025-05-07T17:24:21.000Z" }),
+                json!({ "record_type": "state", "model": "o3", "cwd": "/a" }),
+                user_message("hello"),
+                json!({
+                    "record_type": "state",
+                    "model": "gpt-5",
+                    "approval_policy": "on-request",
+                    "sandbox_policy": { "mode": "read-only" },
+                    "cwd": "/b",
+                    "plan": { "plan": [{ "step": "write tests", "status": "in_progress" }] },
+                }),
+            ],
+        );
+
+        let state = read_session_state(&path).unwrap();
+        assert_eq!(Some("gpt-5".to_string()), state.model);
+        assert_eq!(Some(AskForApproval::OnRequest), state.approval_policy);
+        assert_eq!(Some(SandboxPolicy::ReadOnly), state.sandbox_policy);
+        assert_eq!(Some(PathBuf::from("/b")), state.cwd);
+        assert_eq!(1, state.plan.unwrap().plan.len());
+    }
+
+    #[tokio::test]
+    async fn resumes_from_the_compacted_history() {
+        let codex_home = TempDir::new().unwrap();
+        let id = "5973b6c0-94b8-487b-a530-2aeb6098ae0e";
+        let usage = json!({
+            "input_tokens": 90_000,
+            "output_tokens": 1_000,
+            "total_tokens": 91_000,
+        });
+        let path = write_rollout(
+            codex_home.path(),
+            "2025-05-07T17-24-21",
+            id,
+            &[
+                json!({ "id": id, "timestamp": "2025-05-07T17:24:21.000Z" }),
+                user_message("a long conversation"),
+                json!({ "record_type": "state", "cwd": "/a", "last_token_usage": usage }),
+                json!({ "record_type": "compacted", "items": [user_message("summary")] }),
+                user_message("after compaction"),
+            ],
+        );
+
+        assert!(
+            read_session_state(&path)
+                .unwrap()
+                .last_token_usage
+                .is_none()
+        );
+
+        let (_recorder, saved) = RolloutRecorder::resume(&path, PathBuf::from("/a"))
+            .await
+            .unwrap();
+        let texts: Vec<String> = saved.items.iter().filter_map(user_message_text).collect();
+        assert_eq!(vec!["summary", "after compaction"], texts);
+        assert!(saved.state.last_token_usage.is_none());
+        assert_eq!(Some(PathBuf::from("/a")), saved.state.cwd);
+    }
+}
@@ -7,6 +7,8 @@ use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::ApplyPatchFileChange;
 
 use crate::exec::SandboxType;
+use crate::exec_policy::ExecPolicy;
+use crate::exec_policy::ExecPolicyAssessment;
 use crate::is_safe_command::is_known_safe_command;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
@@ -69,15 +71,31 @@ pub fn assess_patch_safety(
 /// true:
 ///
 /// - the user has explicitly approved the command
-/// - the command is on the "known safe" list
+/// - the command is on the "known safe" list and the sandbox policy allows
+///   reading the whole disk
 /// - `DangerFullAccess` was specified and `UnlessTrusted` was not
-pub fn assess_command_safety(
+///
+/// Commands that match an exec policy rule are auto-approved to run in the
+/// sandbox. Commands that the exec policy forbids are rejected before any of
+/// the above is considered.
+pub(crate) fn assess_command_safety(
     command: &[String],
     approval_policy: AskForApproval,
     sandbox_policy: &SandboxPolicy,
     approved: &HashSet<Vec<String>>,
     with_escalated_permissions: bool,
+    exec_policy: Option<&ExecPolicy>,
+    cwd: &Path,
 ) -> SafetyCheck {
+    let exec_policy_assessment = exec_policy
+        .map(|exec_policy| exec_policy.assess(command, sandbox_policy, cwd))
+        .unwrap_or(ExecPolicyAssessment::Unverified);
+    if let ExecPolicyAssessment::Forbidden { reason } = exec_policy_assessment {
+        return SafetyCheck::Reject {
+            reason: format!("forbidden by exec policy: {reason}"),
+        };
+    }
+
     // A command is "trusted" because either:
     // - it belongs to a set of commands we consider "safe" by default, or
     // - the user has explicitly approved the command for this session
@@ -91,11 +109,36 @@ pub fn assess_command_safety(
     // would probably be fine to run the command in a sandbox, but when
     // `approved.contains(command)` is `true`, the user may have approved it for
     // the session _because_ they know it needs to run outside a sandbox.
-    if is_known_safe_command(command) || approved.contains(command) {
+    if approved.contains(command) {
         return SafetyCheck::AutoApprove {
             sandbox_type: SandboxType::None,
         };
     }
+    if is_known_safe_command(command) {
+        // A "safe" command can still read any file, so it only skips the
+        // sandbox when the policy allows reading everything anyway.
+        if sandbox_policy.has_full_disk_read_access() {
+            return SafetyCheck::AutoApprove {
+                sandbox_type: SandboxType::None,
+            };
+        }
+        return match get_platform_sandbox() {
+            Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
+            None => SafetyCheck::AskUser,
+        };
+    }
+
+    // A policy match only vouches for the files named on the command line:
+    // the program may still run arbitrary code (think `cargo test`), so it is
+    // auto-approved only when it can run in the sandbox.
+    if matches!(
+        exec_policy_assessment,
+        ExecPolicyAssessment::ReadOnly | ExecPolicyAssessment::WritesWithinWritableRoots
+    ) && !with_escalated_permissions
+        && let Some(sandbox_type) = get_platform_sandbox()
+    {
+        return SafetyCheck::AutoApprove { sandbox_type };
+    }
 
     assess_safety_for_untrusted_command(approval_policy, sandbox_policy, with_escalated_permissions)
 }
@@ -159,6 +202,34 @@ pub(crate) fn assess_safety_for_untrusted_command(
     }
 }
 
+/// Input written to a terminal session started by `exec_command` is a command
+/// in its own right, so writing to a session that runs outside the sandbox
+/// (typically because the user approved the command that started it) needs
+/// approval as well, unless the user has turned sandboxing off.
+pub(crate) fn assess_write_stdin_safety(
+    session_sandbox_type: SandboxType,
+    approval_policy: AskForApproval,
+    sandbox_policy: &SandboxPolicy,
+) -> SafetyCheck {
+    if session_sandbox_type != SandboxType::None {
+        return SafetyCheck::AutoApprove {
+            sandbox_type: session_sandbox_type,
+        };
+    }
+
+    match (approval_policy, sandbox_policy) {
+        (AskForApproval::UnlessTrusted, _) => SafetyCheck::AskUser,
+        (_, SandboxPolicy::DangerFullAccess) => SafetyCheck::AutoApprove {
+            sandbox_type: SandboxType::None,
+        },
+        (AskForApproval::Never, _) => SafetyCheck::Reject {
+            reason: "the session runs outside the sandbox; rejected by user approval settings"
+                .to_string(),
+        },
+        _ => SafetyCheck::AskUser,
+    }
+}
+
 pub fn get_platform_sandbox() -> Option<SandboxType> {
     if cfg!(target_os = "macos") {
         Some(SandboxType::MacosSeatbelt)
@@ -266,7 +337,9 @@ mod tests {
         // only `cwd` is writable by default.
         let policy_workspace_only = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
+            readable_roots: None,
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
         };
@@ -287,7 +360,9 @@ mod tests {
         // outside write should be permitted.
         let policy_with_parent = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![parent.clone()],
+            readable_roots: None,
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
         };
@@ -313,6 +388,8 @@ mod tests {
             &sandbox_policy,
             &approved,
             request_escalated_privileges,
+            None,
+            Path::new("/"),
         );
 
         assert_eq!(safety_check, SafetyCheck::AskUser);
@@ -332,6 +409,8 @@ mod tests {
             &sandbox_policy,
             &approved,
             request_escalated_privileges,
+            None,
+            Path::new("/"),
         );
 
         let expected = match get_platform_sandbox() {
@@ -340,4 +419,167 @@ mod tests {
         };
         assert_eq!(safety_check, expected);
     }
+
+    #[test]
+    fn test_known_safe_command_is_sandboxed_when_reads_are_restricted() {
+        let cwd = TempDir::new().unwrap();
+        let command = vec!["cat".to_string(), "/etc/passwd".to_string()];
+        let approved: HashSet<Vec<String>> = HashSet::new();
+        let assess = |sandbox_policy: &SandboxPolicy| {
+            assess_command_safety(
+                &command,
+                AskForApproval::UnlessTrusted,
+                sandbox_policy,
+                &approved,
+                false,
+                None,
+                cwd.path(),
+            )
+        };
+
+        assert_eq!(
+            assess(&SandboxPolicy::ReadOnly),
+            SafetyCheck::AutoApprove {
+                sandbox_type: SandboxType::None
+            }
+        );
+
+        let restricted = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            readable_roots: Some(vec![cwd.path().to_path_buf()]),
+            network_access: false,
+            network_allowlist: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+        let expected = match get_platform_sandbox() {
+            Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
+            None => SafetyCheck::AskUser,
+        };
+        assert_eq!(assess(&restricted), expected);
+    }
+
+    #[test]
+    fn test_exec_policy_forbidden_rejects_approved_command() {
+        let codex_home = TempDir::new().unwrap();
+        let policy_dir = codex_home.path().join("policy");
+        std::fs::create_dir(&policy_dir).unwrap();
+        std::fs::write(
+            policy_dir.join("git.policy"),
+            r#"
+define_program(
+    program="git",
+    args=["push", ARG_UNVERIFIED_VARARGS],
+    forbidden="no pushing",
+)
+"#,
+        )
+        .unwrap();
+        let (exec_policy, _) =
+            ExecPolicy::load(codex_home.path(), codex_home.path(), false).unwrap();
+
+        let command = vec!["git".to_string(), "push".to_string()];
+        let approved: HashSet<Vec<String>> = HashSet::from([command.clone()]);
+        let safety_check = assess_command_safety(
+            &command,
+            AskForApproval::OnRequest,
+            &SandboxPolicy::DangerFullAccess,
+            &approved,
+            false,
+            Some(&exec_policy),
+            codex_home.path(),
+        );
+
+        assert_eq!(
+            safety_check,
+            SafetyCheck::Reject {
+                reason: "forbidden by exec policy: no pushing".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_exec_policy_match_is_auto_approved_when_untrusted() {
+        let cwd = TempDir::new().unwrap();
+        let (exec_policy, _) = ExecPolicy::load(cwd.path(), cwd.path(), false).unwrap();
+        let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            readable_roots: None,
+            network_access: false,
+            network_allowlist: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+        let approved: HashSet<Vec<String>> = HashSet::new();
+        let assess = |command: &[&str]| {
+            assess_command_safety(
+                &command.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+                AskForApproval::UnlessTrusted,
+                &sandbox_policy,
+                &approved,
+                false,
+                Some(&exec_policy),
+                cwd.path(),
+            )
+        };
+
+        let expected_for_write = match get_platform_sandbox() {
+            Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
+            None => SafetyCheck::AskUser,
+        };
+        assert_eq!(assess(&["cp", "a.txt", "b.txt"]), expected_for_write);
+        assert_eq!(
+            assess(&["cp", "a.txt", "/etc/passwd"]),
+            SafetyCheck::AskUser
+        );
+        // Matching a rule that cannot write files does not take the command
+        // out of the sandbox.
+        assert_eq!(assess(&["printenv", "PATH"]), expected_for_write);
+    }
+
+    #[test]
+    fn test_write_stdin_to_unsandboxed_session_needs_approval() {
+        let workspace_write = SandboxPolicy::new_workspace_write_policy();
+
+        assert_eq!(
+            assess_write_stdin_safety(
+                SandboxType::LinuxSeccomp,
+                AskForApproval::OnRequest,
+                &workspace_write,
+            ),
+            SafetyCheck::AutoApprove {
+                sandbox_type: SandboxType::LinuxSeccomp
+            }
+        );
+        assert_eq!(
+            assess_write_stdin_safety(
+                SandboxType::None,
+                AskForApproval::OnRequest,
+                &workspace_write,
+            ),
+            SafetyCheck::AskUser
+        );
+        assert!(matches!(
+            assess_write_stdin_safety(SandboxType::None, AskForApproval::Never, &workspace_write),
+            SafetyCheck::Reject { .. }
+        ));
+        assert_eq!(
+            assess_write_stdin_safety(
+                SandboxType::None,
+                AskForApproval::OnRequest,
+                &SandboxPolicy::DangerFullAccess,
+            ),
+            SafetyCheck::AutoApprove {
+                sandbox_type: SandboxType::None
+            }
+        );
+        assert_eq!(
+            assess_write_stdin_safety(
+                SandboxType::None,
+                AskForApproval::UnlessTrusted,
+                &SandboxPolicy::DangerFullAccess,
+            ),
+            SafetyCheck::AskUser
+        );
+    }
 }
@@ -3,6 +3,7 @@ use std::path::Path;
 use std::path::PathBuf;
 use tokio::process::Child;
 
+use crate::network_proxy::CODEX_NETWORK_PROXY_SOCKET_ENV_VAR;
 use crate::protocol::SandboxPolicy;
 use crate::spawn::CODEX_SANDBOX_ENV_VAR;
 use crate::spawn::StdioPolicy;
@@ -23,7 +24,8 @@ pub async fn spawn_command_under_seatbelt(
     stdio_policy: StdioPolicy,
     mut env: HashMap<String, String>,
 ) -> std::io::Result<Child> {
-    let args = create_seatbelt_command_args(command, sandbox_policy, &cwd);
+    let network_proxy_socket = env.get(CODEX_NETWORK_PROXY_SOCKET_ENV_VAR).map(Path::new);
+    let args = create_seatbelt_command_args(command, sandbox_policy, &cwd, network_proxy_socket);
     let arg0 = None;
     env.insert(CODEX_SANDBOX_ENV_VAR.to_string(), "seatbelt".to_string());
     spawn_child_async(
@@ -42,6 +44,7 @@ fn create_seatbelt_command_args(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
     cwd: &Path,
+    network_proxy_socket: Option<&Path>,
 ) -> Vec<String> {
     let (file_write_policy, extra_cli_args) = {
         if sandbox_policy.has_full_disk_write_access() {
@@ -96,15 +99,52 @@ fn create_seatbelt_command_args(
         }
     };
 
-    let file_read_policy = if sandbox_policy.has_full_disk_read_access() {
-        "; allow read-only file operations\n(allow file-read*)"
+    let (file_read_policy, read_cli_args) = if sandbox_policy.has_full_disk_read_access() {
+        (
+            "; allow read-only file operations\n(allow file-read*)".to_string(),
+            Vec::<String>::new(),
+        )
     } else {
-        ""
+        let mut readable_folder_policies: Vec<String> = Vec::new();
+        let mut cli_args: Vec<String> = Vec::new();
+        for (index, root) in sandbox_policy
+            .get_readable_roots_with_cwd(cwd)
+            .iter()
+            .enumerate()
+        {
+            let canonical_root = root.canonicalize().unwrap_or_else(|_| root.clone());
+            let root_param = format!("READABLE_ROOT_{index}");
+            cli_args.push(format!(
+                "-D{root_param}={}",
+                canonical_root.to_string_lossy()
+            ));
+            readable_folder_policies.push(format!("(subpath (param \"{root_param}\"))"));
+        }
+
+        // Metadata stays readable everywhere so that paths leading to the
+        // readable roots can still be resolved.
+        let file_read_policy = format!(
+            "; allow read-only file operations under the readable roots\n(allow file-read-metadata)\n(allow file-read*\n{}\n)",
+            readable_folder_policies.join(" ")
+        );
+        (file_read_policy, cli_args)
     };
 
     // TODO(mbolin): apply_patch calls must also honor the SandboxPolicy.
+    let mut network_cli_args = Vec::new();
     let network_policy = if sandbox_policy.has_full_network_access() {
         "(allow network-outbound)\n(allow network-inbound)\n(allow system-socket)"
+    } else if let Some(socket) = network_proxy_socket
+        && !sandbox_policy.get_network_allowlist().is_empty()
+    {
+        // Allowlisted hosts are reached through the network proxy. Only its
+        // socket may be connected to: other Unix sockets (such as the Docker
+        // daemon's) would let the command escape the sandbox.
+        let socket = socket
+            .canonicalize()
+            .unwrap_or_else(|_| socket.to_path_buf());
+        network_cli_args.push(format!("-DPROXY_SOCKET={}", socket.to_string_lossy()));
+        r#"(allow network-outbound (remote unix-socket (path-literal (param "PROXY_SOCKET"))))"#
     } else {
         ""
     };
@@ -115,6 +155,8 @@ fn create_seatbelt_command_args(
 
     let mut seatbelt_args: Vec<String> = vec!["-p".to_string(), full_policy];
     seatbelt_args.extend(extra_cli_args);
+    seatbelt_args.extend(read_cli_args);
+    seatbelt_args.extend(network_cli_args);
     seatbelt_args.push("--".to_string());
     seatbelt_args.extend(command);
     seatbelt_args
@@ -154,7 +196,9 @@ mod tests {
         // does not automatically include defaults TMPDIR or /tmp.
         let policy = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![root_with_git.clone(), root_without_git.clone()],
+            readable_roots: None,
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
         };
@@ -163,6 +207,7 @@ mod tests {
             vec!["/bin/echo".to_string(), "hello".to_string()],
             &policy,
             &cwd,
+            None,
         );
 
         // Build the expected policy text using a raw string for readability.
@@ -229,7 +274,9 @@ mod tests {
         // is done properly for cwd.
         let policy = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
+            readable_roots: None,
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
         };
@@ -238,6 +285,7 @@ mod tests {
             vec!["/bin/echo".to_string(), "hello".to_string()],
             &policy,
             root_with_git.as_path(),
+            None,
         );
 
         let tmpdir_env_var = std::env::var("TMPDIR")
@@ -300,6 +348,98 @@ mod tests {
         assert_eq!(expected_args, args);
     }
 
+    #[test]
+    fn create_seatbelt_args_with_restricted_reads() {
+        if cfg!(target_os = "windows") {
+            return;
+        }
+
+        let tmp = TempDir::new().expect("tempdir");
+        let docs = tmp.path().join("docs");
+        let cwd = tmp.path().join("cwd");
+        fs::create_dir_all(&docs).expect("create docs");
+        fs::create_dir_all(&cwd).expect("create cwd");
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            readable_roots: Some(vec![docs.clone()]),
+            network_access: false,
+            network_allowlist: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+
+        let args = create_seatbelt_command_args(
+            vec!["/bin/echo".to_string(), "hello".to_string()],
+            &policy,
+            cwd.as_path(),
+            None,
+        );
+
+        let full_policy = &args[1];
+        assert!(
+            !full_policy.contains("(allow file-read*)\n"),
+            "{full_policy}"
+        );
+        assert!(
+            full_policy.contains("(allow file-read-metadata)"),
+            "{full_policy}"
+        );
+        for root in [&docs, &cwd] {
+            let canonical_root = root.canonicalize().expect("canonicalize");
+            let index = args
+                .iter()
+                .filter(|arg| arg.starts_with("-DREADABLE_ROOT_"))
+                .position(|arg| arg.ends_with(&format!("={}", canonical_root.to_string_lossy())))
+                .expect("readable root should be passed as a parameter");
+            assert!(
+                full_policy.contains(&format!("(subpath (param \"READABLE_ROOT_{index}\"))")),
+                "{full_policy}"
+            );
+        }
+    }
+
+    #[test]
+    fn network_allowlist_only_allows_the_proxy_socket() {
+        let tmp = TempDir::new().expect("tempdir");
+        let socket = tmp.path().join("proxy.sock");
+        fs::write(&socket, "").expect("create socket placeholder");
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            readable_roots: None,
+            network_access: false,
+            network_allowlist: vec!["crates.io".to_string()],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+
+        let args = create_seatbelt_command_args(
+            vec!["/bin/echo".to_string(), "hello".to_string()],
+            &policy,
+            tmp.path(),
+            Some(&socket),
+        );
+
+        let full_policy = &args[1];
+        assert!(
+            full_policy.contains(
+                r#"(allow network-outbound (remote unix-socket (path-literal (param "PROXY_SOCKET"))))"#
+            ),
+            "{full_policy}"
+        );
+        assert!(
+            !full_policy.contains("(remote unix-socket)"),
+            "{full_policy}"
+        );
+        let canonical_socket = socket.canonicalize().expect("canonicalize");
+        assert!(
+            args.contains(&format!(
+                "-DPROXY_SOCKET={}",
+                canonical_socket.to_string_lossy()
+            )),
+            "{args:?}"
+        );
+    }
+
     struct PopulatedTmp {
         root_with_git: PathBuf,
         root_without_git: PathBuf,
@@ -0,0 +1,203 @@
+//! Token and cost accounting for a session. Usage reported by the model is
+//! priced, appended to the usage ledger and checked against
+//! `max_session_tokens` / `max_session_cost`. Once the budget is exceeded the
+//! agent pauses before its next model request until the user allows it to
+//! continue.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use uuid::Uuid;
+
+use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::config::Config;
+use crate::openai_model_pricing::ModelPricing;
+use crate::openai_model_pricing::get_model_pricing;
+use crate::protocol::AskForApproval;
+use crate::protocol::BudgetApprovalRequestEvent;
+use crate::protocol::ReviewDecision;
+use crate::protocol::TokenUsage;
+use crate::usage_ledger::UsageLedger;
+use crate::user_notification::UserNotification;
+use crate::util::MutexExt;
+
+#[derive(Default)]
+struct Usage {
+    tokens: u64,
+    cost_usd: f64,
+    /// The user chose to ignore the budget for the rest of the session.
+    approved_for_session: bool,
+}
+
+pub(crate) struct SessionBudget {
+    max_tokens: Option<u64>,
+    max_cost_usd: Option<f64>,
+    pricing_overrides: HashMap<String, ModelPricing>,
+    ledger: UsageLedger,
+    usage: Mutex<Usage>,
+}
+
+impl SessionBudget {
+    pub(crate) fn new(config: &Config, session_id: Uuid, project: PathBuf) -> Self {
+        Self {
+            max_tokens: config.max_session_tokens,
+            max_cost_usd: config.max_session_cost,
+            pricing_overrides: config.model_pricing.clone(),
+            ledger: UsageLedger::new(&config.codex_home, session_id, project),
+            usage: Mutex::new(Usage::default()),
+        }
+    }
+
+    /// Accounts for the usage of one model request.
+    pub(crate) fn record(&self, model: &str, token_usage: &TokenUsage) {
+        let cost_usd = get_model_pricing(model, &self.pricing_overrides)
+            .map(|pricing| pricing.cost_usd(token_usage));
+        self.ledger.record(model, token_usage, cost_usd);
+
+        let mut usage = self.usage.lock_unchecked();
+        usage.tokens += token_usage.blended_total();
+        usage.cost_usd += cost_usd.unwrap_or(0.0);
+    }
+
+    /// Returns the approval request to send if the budget is exceeded and the
+    /// user has not waived it for the session.
+    fn exceeded(&self) -> Option<BudgetApprovalRequestEvent> {
+        let usage = self.usage.lock_unchecked();
+        let over_tokens = self.max_tokens.is_some_and(|max| usage.tokens >= max);
+        let over_cost = self.max_cost_usd.is_some_and(|max| usage.cost_usd >= max);
+        if usage.approved_for_session || !(over_tokens || over_cost) {
+            return None;
+        }
+        Some(BudgetApprovalRequestEvent {
+            used_tokens: usage.tokens,
+            max_tokens: self.max_tokens,
+            cost_usd: usage.cost_usd,
+            max_cost_usd: self.max_cost_usd,
+        })
+    }
+
+    fn approve_for_session(&self) {
+        self.usage.lock_unchecked().approved_for_session = true;
+    }
+}
+
+/// Called before each model request of a task. Returns `false` if the task
+/// must stop because the budget is exceeded and the user did not allow the
+/// agent to continue. `approved_for_task` remembers an approval for the rest
+/// of the current task.
+pub(crate) async fn enforce_session_budget(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    approved_for_task: &mut bool,
+) -> bool {
+    if *approved_for_task {
+        return true;
+    }
+    let Some(request) = sess.budget().exceeded() else {
+        return true;
+    };
+
+    sess.maybe_notify(UserNotification::BudgetExceeded {
+        turn_id: sub_id.to_string(),
+        used_tokens: request.used_tokens,
+        cost_usd: request.cost_usd,
+    });
+    let summary = format!(
+        "Session budget exceeded: {} tokens used, ${:.2} spent",
+        request.used_tokens, request.cost_usd
+    );
+
+    // Nobody can be asked, so the agent stops.
+    if turn_context.approval_policy == AskForApproval::Never {
+        sess.notify_background_event(sub_id, format!("{summary}; stopping."))
+            .await;
+        return false;
+    }
+
+    let rx_approve = sess
+        .request_budget_approval(sub_id.to_string(), request)
+        .await;
+    match rx_approve.await.unwrap_or_default() {
+        ReviewDecision::Approved => {
+            *approved_for_task = true;
+            true
+        }
+        ReviewDecision::ApprovedForSession => {
+            sess.budget().approve_for_session();
+            true
+        }
+        ReviewDecision::Denied | ReviewDecision::Abort => {
+            sess.notify_background_event(sub_id, format!("{summary}; stopped by user."))
+                .await;
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn budget(
+        codex_home: &TempDir,
+        max_tokens: Option<u64>,
+        max_cost: Option<f64>,
+    ) -> SessionBudget {
+        let mut config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .unwrap();
+        config.max_session_tokens = max_tokens;
+        config.max_session_cost = max_cost;
+        SessionBudget::new(&config, Uuid::new_v4(), PathBuf::from("/src/app"))
+    }
+
+    fn usage(input: u64, output: u64) -> TokenUsage {
+        TokenUsage {
+            input_tokens: input,
+            cached_input_tokens: None,
+            output_tokens: output,
+            reasoning_output_tokens: None,
+            total_tokens: input + output,
+        }
+    }
+
+    #[tokio::test]
+    async fn token_budget_is_exceeded_once_reached() {
+        let codex_home = TempDir::new().unwrap();
+        let budget = budget(&codex_home, Some(1_000), None);
+
+        budget.record("o3", &usage(600, 100));
+        assert_eq!(None, budget.exceeded());
+
+        budget.record("o3", &usage(250, 50));
+        let request = budget.exceeded().unwrap();
+        assert_eq!(1_000, request.used_tokens);
+        assert_eq!(Some(1_000), request.max_tokens);
+
+        budget.approve_for_session();
+        assert_eq!(None, budget.exceeded());
+    }
+
+    #[tokio::test]
+    async fn cost_budget_ignores_unpriced_models() {
+        let codex_home = TempDir::new().unwrap();
+        let budget = budget(&codex_home, None, Some(1.0));
+
+        budget.record("my-local-model", &usage(10_000_000, 0));
+        assert_eq!(None, budget.exceeded());
+
+        // 500k input at $2 per million.
+        budget.record("o3", &usage(500_000, 0));
+        assert_eq!(1.0, budget.exceeded().unwrap().cost_usd);
+    }
+}
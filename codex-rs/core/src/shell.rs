@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use shlex;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -6,40 +8,118 @@ pub struct ZshShell {
     zshrc_path: String,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct BashShell {
+    shell_path: String,
+    bashrc_path: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct FishShell {
+    shell_path: String,
+    config_path: String,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Shell {
     Zsh(ZshShell),
+    Bash(BashShell),
+    Fish(FishShell),
     Unknown,
 }
 
 impl Shell {
     pub fn format_default_shell_invocation(&self, command: Vec<String>) -> Option<Vec<String>> {
         match self {
-            Shell::Zsh(zsh) => {
-                if !std::path::Path::new(&zsh.zshrc_path).exists() {
+            Shell::Zsh(zsh) => source_rc_and_run(&zsh.shell_path, &zsh.zshrc_path, &command),
+            Shell::Bash(bash) => {
+                run_in_interactive_bash(&bash.shell_path, &bash.bashrc_path, &command)
+            }
+            Shell::Fish(fish) => {
+                if !Path::new(&fish.config_path).exists() {
                     return None;
                 }
 
-                let mut result = vec![zsh.shell_path.clone()];
-                result.push("-lc".to_string());
-
-                let joined = strip_bash_lc(&command)
-                    .or_else(|| shlex::try_join(command.iter().map(|s| s.as_str())).ok());
-
-                if let Some(joined) = joined {
-                    result.push(format!("source {} && ({joined})", zsh.zshrc_path));
-                } else {
-                    return None;
-                }
-                Some(result)
+                // Commands are written for a POSIX shell, so they cannot run
+                // in fish itself. fish reads its config on startup and then
+                // hands the resulting environment to bash.
+                let joined = join_command(&command)?;
+                let quoted = shlex::try_quote(&joined).ok()?;
+                Some(vec![
+                    fish.shell_path.clone(),
+                    "-lc".to_string(),
+                    format!("exec bash -c {quoted}"),
+                ])
             }
             Shell::Unknown => None,
         }
     }
+
+    /// Name of the shell as reported to the model, `None` if unknown.
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            Shell::Zsh(_) => Some("zsh"),
+            Shell::Bash(_) => Some("bash"),
+            Shell::Fish(_) => Some("fish"),
+            Shell::Unknown => None,
+        }
+    }
+}
+
+/// Runs `command` with `shell_path -lc` after sourcing `rc_path`, or returns
+/// `None` if there is no rc file to source. The command still runs if the rc
+/// file cannot be read, e.g. because the sandbox restricts reads.
+fn source_rc_and_run(shell_path: &str, rc_path: &str, command: &[String]) -> Option<Vec<String>> {
+    if !Path::new(rc_path).exists() {
+        return None;
+    }
+
+    let joined = join_command(command)?;
+    let rc_path = shlex::try_quote(rc_path).ok()?;
+    Some(vec![
+        shell_path.to_string(),
+        "-lc".to_string(),
+        format!("[ -r {rc_path} ] && source {rc_path}; ({joined})"),
+    ])
 }
 
-fn strip_bash_lc(command: &Vec<String>) -> Option<String> {
-    match command.as_slice() {
+/// Runs `command` in an interactive, non-login bash that reads
+/// `bashrc_path`, or returns `None` if there is no such file.
+///
+/// The shell has to be interactive: the stock `.bashrc` of Debian and Ubuntu
+/// returns early unless `$-` contains `i`, which would skip the PATH, nvm and
+/// pyenv setup at its end. Without a terminal, interactive bash complains
+/// about job control on startup, so stderr is silenced until the rc file has
+/// been read, which hides its errors too. Aliases are dropped so that commands
+/// run as written (think `alias rm='rm -i'`).
+fn run_in_interactive_bash(
+    shell_path: &str,
+    bashrc_path: &str,
+    command: &[String],
+) -> Option<Vec<String>> {
+    if !Path::new(bashrc_path).exists() {
+        return None;
+    }
+
+    let joined = join_command(command)?;
+    Some(vec![
+        shell_path.to_string(),
+        "-c".to_string(),
+        r#"exec "$0" --rcfile "$1" -ic "$2" 3>&2 2>/dev/null"#.to_string(),
+        shell_path.to_string(),
+        bashrc_path.to_string(),
+        // Aliases are expanded as a line is parsed, so the command has to
+        // start on a line of its own.
+        format!("exec 2>&3 3>&-; unalias -a\n({joined})"),
+    ])
+}
+
+fn join_command(command: &[String]) -> Option<String> {
+    strip_bash_lc(command).or_else(|| shlex::try_join(command.iter().map(|s| s.as_str())).ok())
+}
+
+fn strip_bash_lc(command: &[String]) -> Option<String> {
+    match command {
         // exactly three items
         [first, second, third]
             // first two must be "bash", "-lc"
@@ -51,6 +131,30 @@ fn strip_bash_lc(command: &Vec<String>) -> Option<String> {
     }
 }
 
+/// Maps the user's login shell to a [`Shell`], with the rc file it reads
+/// from `home`.
+fn shell_from_path(shell_path: &str, home: &Path) -> Shell {
+    let rc_path = |relative: &str| home.join(relative).to_string_lossy().into_owned();
+    match Path::new(shell_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+    {
+        Some("zsh") => Shell::Zsh(ZshShell {
+            shell_path: shell_path.to_string(),
+            zshrc_path: rc_path(".zshrc"),
+        }),
+        Some("bash") => Shell::Bash(BashShell {
+            shell_path: shell_path.to_string(),
+            bashrc_path: rc_path(".bashrc"),
+        }),
+        Some("fish") => Shell::Fish(FishShell {
+            shell_path: shell_path.to_string(),
+            config_path: rc_path(".config/fish/config.fish"),
+        }),
+        _ => Shell::Unknown,
+    }
+}
+
 #[cfg(target_os = "macos")]
 pub async fn default_user_shell() -> Shell {
     use tokio::process::Command;
@@ -70,13 +174,8 @@ pub async fn default_user_shell() -> Shell {
             }
             let stdout = String::from_utf8_lossy(&o.stdout);
             for line in stdout.lines() {
-                if let Some(shell_path) = line.strip_prefix("UserShell: ")
-                    && shell_path.ends_with("/zsh")
-                {
-                    return Shell::Zsh(ZshShell {
-                        shell_path: shell_path.to_string(),
-                        zshrc_path: format!("{home}/.zshrc"),
-                    });
+                if let Some(shell_path) = line.strip_prefix("UserShell: ") {
+                    return shell_from_path(shell_path, Path::new(&home));
                 }
             }
 
@@ -86,7 +185,60 @@ pub async fn default_user_shell() -> Shell {
     }
 }
 
-#[cfg(not(target_os = "macos"))]
+/// Reads the login shell and home directory from the password database,
+/// falling back to `$SHELL` and `$HOME` (e.g. for users that only exist in a
+/// directory service `getpwuid` cannot reach).
+#[cfg(target_os = "linux")]
+pub async fn default_user_shell() -> Shell {
+    let (shell_path, home) = match passwd_entry() {
+        Some(entry) => entry,
+        None => match (std::env::var("SHELL"), std::env::var("HOME")) {
+            (Ok(shell), Ok(home)) => (shell, std::path::PathBuf::from(home)),
+            _ => return Shell::Unknown,
+        },
+    };
+    shell_from_path(&shell_path, &home)
+}
+
+/// Returns `(pw_shell, pw_dir)` for the current user.
+#[cfg(target_os = "linux")]
+fn passwd_entry() -> Option<(String, std::path::PathBuf)> {
+    use std::ffi::CStr;
+
+    let mut passwd = std::mem::MaybeUninit::<libc::passwd>::uninit();
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    // SAFETY: all pointers are valid for the duration of the call and `buf`
+    // is as long as the length we pass.
+    let rc = unsafe {
+        libc::getpwuid_r(
+            libc::getuid(),
+            passwd.as_mut_ptr(),
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut result,
+        )
+    };
+    if rc != 0 || result.is_null() {
+        return None;
+    }
+    // SAFETY: on success `passwd` is initialized and its strings are
+    // NUL-terminated and point into `buf`, which is still alive.
+    let (shell, home) = unsafe {
+        let passwd = passwd.assume_init();
+        (
+            CStr::from_ptr(passwd.pw_shell),
+            CStr::from_ptr(passwd.pw_dir),
+        )
+    };
+    let shell = shell.to_str().ok().filter(|shell| !shell.is_empty())?;
+    Some((
+        shell.to_string(),
+        std::path::PathBuf::from(home.to_str().ok()?),
+    ))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
 pub async fn default_user_shell() -> Shell {
     Shell::Unknown
 }
@@ -135,12 +287,20 @@ mod tests {
         let cases = vec![
             (
                 vec!["myecho"],
-                vec![shell_path, "-lc", "source ZSHRC_PATH && (myecho)"],
+                vec![
+                    shell_path,
+                    "-lc",
+                    "[ -r ZSHRC_PATH ] && source ZSHRC_PATH; (myecho)",
+                ],
                 Some("It works!\n"),
             ),
             (
                 vec!["myecho"],
-                vec![shell_path, "-lc", "source ZSHRC_PATH && (myecho)"],
+                vec![
+                    shell_path,
+                    "-lc",
+                    "[ -r ZSHRC_PATH ] && source ZSHRC_PATH; (myecho)",
+                ],
                 Some("It works!\n"),
             ),
             (
@@ -148,7 +308,7 @@ mod tests {
                 vec![
                     shell_path,
                     "-lc",
-                    "source ZSHRC_PATH && (bash -c \"echo 'single' \\\"double\\\"\")",
+                    "[ -r ZSHRC_PATH ] && source ZSHRC_PATH; (bash -c \"echo 'single' \\\"double\\\"\")",
                 ],
                 Some("single double\n"),
             ),
@@ -157,7 +317,7 @@ mod tests {
                 vec![
                     shell_path,
                     "-lc",
-                    "source ZSHRC_PATH && (echo 'single' \"double\")",
+                    "[ -r ZSHRC_PATH ] && source ZSHRC_PATH; (echo 'single' \"double\")",
                 ],
                 Some("single double\n"),
             ),
@@ -231,3 +391,210 @@ mod tests {
         }
     }
 }
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod linux_tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    use crate::exec::ExecParams;
+    use crate::exec::SandboxType;
+    use crate::exec::process_exec_tool_call;
+    use crate::protocol::SandboxPolicy;
+
+    #[test]
+    fn detects_shell_from_login_shell_path() {
+        let home = tempfile::tempdir().unwrap();
+        let rc = |relative: &str| home.path().join(relative).to_str().unwrap().to_string();
+
+        assert_eq!(
+            Shell::Bash(BashShell {
+                shell_path: "/usr/bin/bash".to_string(),
+                bashrc_path: rc(".bashrc"),
+            }),
+            shell_from_path("/usr/bin/bash", home.path())
+        );
+        assert_eq!(
+            Shell::Zsh(ZshShell {
+                shell_path: "/bin/zsh".to_string(),
+                zshrc_path: rc(".zshrc"),
+            }),
+            shell_from_path("/bin/zsh", home.path())
+        );
+        assert_eq!(
+            Shell::Fish(FishShell {
+                shell_path: "/usr/bin/fish".to_string(),
+                config_path: rc(".config/fish/config.fish"),
+            }),
+            shell_from_path("/usr/bin/fish", home.path())
+        );
+        assert_eq!(Shell::Unknown, shell_from_path("/bin/tcsh", home.path()));
+    }
+
+    #[tokio::test]
+    async fn default_user_shell_matches_passwd_entry() {
+        let Some((shell_path, home)) = passwd_entry() else {
+            return;
+        };
+        assert_eq!(
+            shell_from_path(&shell_path, &home),
+            default_user_shell().await
+        );
+    }
+
+    #[test]
+    fn no_invocation_without_rc_file() {
+        let home = tempfile::tempdir().unwrap();
+        for shell_path in ["/bin/bash", "/bin/zsh", "/usr/bin/fish"] {
+            let shell = shell_from_path(shell_path, home.path());
+            assert_eq!(
+                None,
+                shell.format_default_shell_invocation(vec!["myecho".to_string()])
+            );
+        }
+    }
+
+    #[test]
+    fn fish_hands_the_command_to_bash() {
+        let home = tempfile::tempdir().unwrap();
+        let config = home.path().join(".config/fish/config.fish");
+        std::fs::create_dir_all(config.parent().unwrap()).unwrap();
+        std::fs::write(&config, "set -gx PATH $HOME/bin $PATH\n").unwrap();
+
+        let shell = shell_from_path("/usr/bin/fish", home.path());
+        assert_eq!(
+            Some(vec![
+                "/usr/bin/fish".to_string(),
+                "-lc".to_string(),
+                "exec bash -c \"echo 'a b' && ls\"".to_string(),
+            ]),
+            shell.format_default_shell_invocation(vec![
+                "bash".to_string(),
+                "-lc".to_string(),
+                "echo 'a b' && ls".to_string(),
+            ])
+        );
+    }
+
+    async fn run(command: Vec<String>, home: &Path) -> crate::exec::ExecToolCallOutput {
+        process_exec_tool_call(
+            ExecParams {
+                command,
+                cwd: home.to_path_buf(),
+                timeout_ms: None,
+                env: HashMap::from([
+                    ("HOME".to_string(), home.to_str().unwrap().to_string()),
+                    (
+                        "PATH".to_string(),
+                        std::env::var("PATH").unwrap_or_default(),
+                    ),
+                ]),
+                with_escalated_permissions: None,
+                justification: None,
+            },
+            SandboxType::None,
+            &SandboxPolicy::DangerFullAccess,
+            &None,
+            None,
+        )
+        .await
+        .unwrap()
+    }
+
+    fn find_bash() -> Option<&'static str> {
+        ["/bin/bash", "/usr/bin/bash"]
+            .into_iter()
+            .find(|path| Path::new(path).exists())
+    }
+
+    #[tokio::test]
+    async fn bash_sources_guarded_bashrc_from_home() {
+        let Some(bash_path) = find_bash() else {
+            return;
+        };
+        let home = tempfile::Builder::new()
+            .prefix("home dir")
+            .tempdir()
+            .unwrap();
+        // Like the `.bashrc` Debian and Ubuntu install for new users.
+        std::fs::write(
+            home.path().join(".bashrc"),
+            r#"
+                # If not running interactively, don't do anything
+                case $- in
+                    *i*) ;;
+                      *) return;;
+                esac
+                export GREETING='It works!'
+                myecho() {
+                    echo "$GREETING"
+                }
+                alias echo='echo aliased'
+            "#,
+        )
+        .unwrap();
+        let shell = shell_from_path(bash_path, home.path());
+
+        for input in [
+            vec!["myecho"],
+            vec!["bash", "-lc", "myecho"],
+            vec!["bash", "-c", "echo \"$GREETING\""],
+        ] {
+            let command = shell
+                .format_default_shell_invocation(input.iter().map(|s| s.to_string()).collect())
+                .unwrap();
+            let output = run(command, home.path()).await;
+
+            assert_eq!(0, output.exit_code, "input: {input:?} output: {output:?}");
+            assert_eq!("It works!\n", output.stdout.text, "input: {input:?}");
+            assert_eq!("", output.stderr.text, "input: {input:?}");
+        }
+
+        let command = shell
+            .format_default_shell_invocation(vec![
+                "bash".to_string(),
+                "-lc".to_string(),
+                "echo oops >&2; exit 3".to_string(),
+            ])
+            .unwrap();
+        let output = run(command, home.path()).await;
+        assert_eq!(3, output.exit_code);
+        assert_eq!("oops\n", output.stderr.text);
+    }
+
+    #[tokio::test]
+    async fn unreadable_rc_file_does_not_skip_the_command() {
+        let Some(bash_path) = find_bash() else {
+            return;
+        };
+        let home = tempfile::Builder::new()
+            .prefix("home dir")
+            .tempdir()
+            .unwrap();
+        let rc_path = home.path().join(".zshrc");
+        std::fs::write(&rc_path, "export GREETING='It works!'\n").unwrap();
+
+        let command =
+            source_rc_and_run(bash_path, rc_path.to_str().unwrap(), &["pwd".to_string()]).unwrap();
+        let quoted = shlex::try_quote(rc_path.to_str().unwrap())
+            .unwrap()
+            .into_owned();
+        assert_eq!(
+            vec![
+                bash_path.to_string(),
+                "-lc".to_string(),
+                format!("[ -r {quoted} ] && source {quoted}; (pwd)"),
+            ],
+            command
+        );
+        let output = run(command.clone(), home.path()).await;
+        assert_eq!(0, output.exit_code, "{output:?}");
+
+        std::fs::remove_file(&rc_path).unwrap();
+        let output = run(command, home.path()).await;
+        assert_eq!(0, output.exit_code, "{output:?}");
+        assert_eq!(format!("{}\n", home.path().display()), output.stdout.text);
+    }
+}
@@ -25,7 +25,15 @@ pub const CODEX_SANDBOX_ENV_VAR: &str = "CODEX_SANDBOX";
 #[derive(Debug, Clone, Copy)]
 pub enum StdioPolicy {
     RedirectForShellTool,
+    /// Like `RedirectForShellTool`, but the child leads a new process group so
+    /// that it can be killed along with everything it started.
+    RedirectForBackgroundJob,
     Inherit,
+    /// Use the slave end of a pseudo-terminal (which must stay open until the
+    /// child is spawned) for stdin, stdout and stderr, and make it the
+    /// controlling terminal of a new session.
+    #[cfg(unix)]
+    Pty(std::os::fd::RawFd),
 }
 
 /// Spawns the appropriate child process for the ExecParams and SandboxPolicy,
@@ -86,7 +94,7 @@ pub(crate) async fn spawn_child_async(
     }
 
     match stdio_policy {
-        StdioPolicy::RedirectForShellTool => {
+        StdioPolicy::RedirectForShellTool | StdioPolicy::RedirectForBackgroundJob => {
             // Do not create a file descriptor for stdin because otherwise some
             // commands may hang forever waiting for input. For example, ripgrep has
             // a heuristic where it may try to read from stdin as explained here:
@@ -94,6 +102,11 @@ pub(crate) async fn spawn_child_async(
             cmd.stdin(Stdio::null());
 
             cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+            #[cfg(unix)]
+            if matches!(stdio_policy, StdioPolicy::RedirectForBackgroundJob) {
+                cmd.process_group(0);
+            }
         }
         StdioPolicy::Inherit => {
             // Inherit stdin, stdout, and stderr from the parent process.
@@ -101,7 +114,59 @@ pub(crate) async fn spawn_child_async(
                 .stdout(Stdio::inherit())
                 .stderr(Stdio::inherit());
         }
+        #[cfg(unix)]
+        StdioPolicy::Pty(slave) => {
+            // SAFETY: the caller keeps `slave` open until the child is spawned.
+            let slave = unsafe { std::os::fd::BorrowedFd::borrow_raw(slave) };
+            cmd.stdin(Stdio::from(slave.try_clone_to_owned()?))
+                .stdout(Stdio::from(slave.try_clone_to_owned()?))
+                .stderr(Stdio::from(slave.try_clone_to_owned()?));
+
+            // The child leads a new session (and process group, so the whole
+            // group can be killed at once) with the PTY as its terminal.
+            // Closures run after stdio has been set up, so fd 0 is the PTY.
+            unsafe {
+                cmd.pre_exec(|| {
+                    if libc::setsid() == -1 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    if libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+        }
     }
 
     cmd.kill_on_drop(true).spawn()
 }
+
+/// Kills the process group led by `pid`, i.e. a child spawned with
+/// [`StdioPolicy::RedirectForBackgroundJob`] or [`StdioPolicy::Pty`] and
+/// everything it started.
+#[cfg(unix)]
+pub(crate) fn kill_process_group(pid: u32) {
+    if let Ok(pid) = libc::pid_t::try_from(pid) {
+        // SAFETY: killpg has no memory-safety preconditions.
+        unsafe {
+            libc::killpg(pid, libc::SIGKILL);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn kill_process_group(_pid: u32) {}
+
+/// Exit code of a child, or `128 + signal` if it was killed by a signal, as
+/// reported by shells.
+pub(crate) fn exit_code_of(status: std::process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(-1)
+}
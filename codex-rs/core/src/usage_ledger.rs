@@ -0,0 +1,265 @@
+//! Persistent ledger of the tokens used, and their estimated cost, across
+//! sessions.
+//!
+//! Every model request that reports usage appends one record to
+//! `~/.codex/usage.jsonl`:
+//!
+//! ````text
+//! {"ts":"2025-08-20T14:03:11Z","session_id":"<uuid>","project":"/src/app","model":"o3","input_tokens":1200,"cached_input_tokens":800,"output_tokens":90,"reasoning_output_tokens":64,"cost_usd":0.0033}
+//! ````
+//!
+//! Per-session and per-day totals are derived from the records when the
+//! ledger is read, e.g. by `codex usage`. Lines are written with a single
+//! `write(2)` on a file opened with `O_APPEND`, so concurrent sessions do not
+//! interleave their records.
+
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::warn;
+use uuid::Uuid;
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+use crate::protocol::TokenUsage;
+
+/// Filename that stores the usage ledger inside `~/.codex`.
+const USAGE_FILENAME: &str = "usage.jsonl";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub ts: DateTime<Utc>,
+    pub session_id: Uuid,
+    /// Root of the git repository the session ran in, or its working
+    /// directory outside of a repository.
+    pub project: PathBuf,
+    pub model: String,
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub cached_input_tokens: u64,
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub reasoning_output_tokens: u64,
+    /// `None` if the model has no known pricing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+}
+
+/// Appends the usage of a single session to the ledger.
+pub(crate) struct UsageLedger {
+    path: PathBuf,
+    session_id: Uuid,
+    project: PathBuf,
+}
+
+impl UsageLedger {
+    pub(crate) fn new(codex_home: &Path, session_id: Uuid, project: PathBuf) -> Self {
+        Self {
+            path: ledger_path(codex_home),
+            session_id,
+            project,
+        }
+    }
+
+    pub(crate) fn record(&self, model: &str, usage: &TokenUsage, cost_usd: Option<f64>) {
+        let record = UsageRecord {
+            ts: Utc::now(),
+            session_id: self.session_id,
+            project: self.project.clone(),
+            model: model.to_string(),
+            input_tokens: usage.input_tokens,
+            cached_input_tokens: usage.cached_input(),
+            output_tokens: usage.output_tokens,
+            reasoning_output_tokens: usage.reasoning_output_tokens.unwrap_or(0),
+            cost_usd,
+        };
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = append_record(&path, &record) {
+                warn!("failed to append to usage ledger: {e}");
+            }
+        });
+    }
+}
+
+fn ledger_path(codex_home: &Path) -> PathBuf {
+    codex_home.join(USAGE_FILENAME)
+}
+
+fn append_record(path: &Path, record: &UsageRecord) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(record)
+        .map_err(|e| std::io::Error::other(format!("failed to serialise usage record: {e}")))?;
+    line.push('\n');
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = OpenOptions::new();
+    options.append(true).create(true);
+    #[cfg(unix)]
+    {
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(line.as_bytes())
+}
+
+/// Reads the records written at or after `since`. Lines that cannot be parsed
+/// are skipped.
+pub fn read_usage_records(
+    codex_home: &Path,
+    since: Option<DateTime<Utc>>,
+) -> std::io::Result<Vec<UsageRecord>> {
+    let file = match std::fs::File::open(ledger_path(codex_home)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut records = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        match serde_json::from_str::<UsageRecord>(&line) {
+            Ok(record) if since.is_none_or(|since| record.ts >= since) => records.push(record),
+            Ok(_) => {}
+            Err(e) => warn!("skipping malformed usage record: {e}"),
+        }
+    }
+    Ok(records)
+}
+
+/// Totals for one `(project, model)` pair.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageSummary {
+    pub project: PathBuf,
+    pub model: String,
+    pub sessions: usize,
+    pub requests: usize,
+    pub input_tokens: u64,
+    pub cached_input_tokens: u64,
+    pub output_tokens: u64,
+    /// Sum of the priced requests; `None` if no request had pricing.
+    pub cost_usd: Option<f64>,
+}
+
+impl UsageSummary {
+    /// Non-cached input plus output, as in [`TokenUsage::blended_total`].
+    pub fn blended_tokens(&self) -> u64 {
+        self.input_tokens.saturating_sub(self.cached_input_tokens) + self.output_tokens
+    }
+}
+
+/// Groups `records` by project and model, sorted by project and then model.
+pub fn summarize_usage(records: &[UsageRecord]) -> Vec<UsageSummary> {
+    let mut groups: BTreeMap<(&Path, &str), (UsageSummary, Vec<Uuid>)> = BTreeMap::new();
+    for record in records {
+        let (summary, sessions) = groups
+            .entry((record.project.as_path(), record.model.as_str()))
+            .or_insert_with(|| {
+                let summary = UsageSummary {
+                    project: record.project.clone(),
+                    model: record.model.clone(),
+                    ..Default::default()
+                };
+                (summary, Vec::new())
+            });
+        if !sessions.contains(&record.session_id) {
+            sessions.push(record.session_id);
+        }
+        summary.requests += 1;
+        summary.input_tokens += record.input_tokens;
+        summary.cached_input_tokens += record.cached_input_tokens;
+        summary.output_tokens += record.output_tokens;
+        if let Some(cost) = record.cost_usd {
+            summary.cost_usd = Some(summary.cost_usd.unwrap_or(0.0) + cost);
+        }
+    }
+    groups
+        .into_values()
+        .map(|(mut summary, sessions)| {
+            summary.sessions = sessions.len();
+            summary
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn record(session_id: Uuid, project: &str, model: &str, day: u32) -> UsageRecord {
+        UsageRecord {
+            ts: Utc.with_ymd_and_hms(2025, 8, day, 12, 0, 0).unwrap(),
+            session_id,
+            project: PathBuf::from(project),
+            model: model.to_string(),
+            input_tokens: 1_000,
+            cached_input_tokens: 400,
+            output_tokens: 100,
+            reasoning_output_tokens: 0,
+            cost_usd: (model != "local").then_some(0.5),
+        }
+    }
+
+    #[test]
+    fn reads_back_records_since_a_date() {
+        let codex_home = TempDir::new().unwrap();
+        let session = Uuid::new_v4();
+        let older = record(session, "/src/app", "o3", 1);
+        let newer = record(session, "/src/app", "o3", 2);
+        append_record(&ledger_path(codex_home.path()), &older).unwrap();
+        append_record(&ledger_path(codex_home.path()), &newer).unwrap();
+
+        assert_eq!(
+            vec![older, newer.clone()],
+            read_usage_records(codex_home.path(), None).unwrap()
+        );
+        assert_eq!(
+            vec![newer.clone()],
+            read_usage_records(codex_home.path(), Some(newer.ts)).unwrap()
+        );
+    }
+
+    #[test]
+    fn summarizes_by_project_and_model() {
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        let records = vec![
+            record(first, "/src/app", "o3", 1),
+            record(second, "/src/app", "o3", 2),
+            record(second, "/src/app", "local", 2),
+            record(second, "/src/lib", "o3", 2),
+        ];
+
+        let summary = |project: &str, model: &str, sessions, requests, cost_usd| UsageSummary {
+            project: PathBuf::from(project),
+            model: model.to_string(),
+            sessions,
+            requests,
+            input_tokens: 1_000 * requests as u64,
+            cached_input_tokens: 400 * requests as u64,
+            output_tokens: 100 * requests as u64,
+            cost_usd,
+        };
+        assert_eq!(
+            vec![
+                summary("/src/app", "local", 1, 1, None),
+                summary("/src/app", "o3", 2, 2, Some(1.0)),
+                summary("/src/lib", "o3", 1, 1, Some(0.5)),
+            ],
+            summarize_usage(&records)
+        );
+    }
+}
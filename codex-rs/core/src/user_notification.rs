@@ -1,8 +1,22 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
 use serde::Serialize;
+use tracing::warn;
+
+use crate::config_types::NotificationKind;
+use crate::config_types::NotificationsConfig;
+use crate::config_types::WebhookConfig;
+
+/// How long a webhook request may take before it is abandoned.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long desktop notifications stay on screen, in milliseconds.
+const DESKTOP_NOTIFICATION_TIMEOUT_MS: i32 = 10_000;
 
 /// User can configure a program that will receive notifications. Each
 /// notification is serialized as JSON and passed as an argument to the
-/// program.
+/// program. The same JSON is the body of webhook requests.
 #[derive(Debug, Clone, PartialEq, Serialize)]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub(crate) enum UserNotification {
@@ -16,11 +30,251 @@ pub(crate) enum UserNotification {
         /// The last message sent by the assistant in the turn.
         last_assistant_message: Option<String>,
     },
+
+    /// The agent is waiting for the user to approve a command or a patch.
+    #[serde(rename_all = "kebab-case")]
+    ApprovalRequested {
+        turn_id: String,
+
+        /// Command awaiting approval; empty for patches.
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        command: Vec<String>,
+
+        /// Files the patch awaiting approval would change; empty for commands.
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        files: Vec<PathBuf>,
+
+        reason: Option<String>,
+    },
+
+    /// The turn ended with an error.
+    #[serde(rename_all = "kebab-case")]
+    TurnFailed { turn_id: String, error: String },
+
+    /// A command ran for at least `notifications.long_command_secs`.
+    #[serde(rename_all = "kebab-case")]
+    LongCommandFinished {
+        turn_id: String,
+        command: Vec<String>,
+        exit_code: i32,
+        duration_secs: u64,
+    },
+
+    /// The session exceeded `max_session_tokens` or `max_session_cost`.
+    #[serde(rename_all = "kebab-case")]
+    BudgetExceeded {
+        turn_id: String,
+        used_tokens: u64,
+        cost_usd: f64,
+    },
+}
+
+impl UserNotification {
+    fn kind(&self) -> NotificationKind {
+        match self {
+            UserNotification::AgentTurnComplete { .. } => NotificationKind::AgentTurnComplete,
+            UserNotification::ApprovalRequested { .. } => NotificationKind::ApprovalRequested,
+            UserNotification::TurnFailed { .. } => NotificationKind::TurnFailed,
+            UserNotification::LongCommandFinished { .. } => NotificationKind::LongCommandFinished,
+            UserNotification::BudgetExceeded { .. } => NotificationKind::BudgetExceeded,
+        }
+    }
+
+    /// Short summary for desktop notifications.
+    fn title(&self) -> &'static str {
+        match self {
+            UserNotification::AgentTurnComplete { .. } => "Codex: turn complete",
+            UserNotification::ApprovalRequested { .. } => "Codex: approval requested",
+            UserNotification::TurnFailed { .. } => "Codex: turn failed",
+            UserNotification::LongCommandFinished { .. } => "Codex: command finished",
+            UserNotification::BudgetExceeded { .. } => "Codex: session budget exceeded",
+        }
+    }
+
+    fn body(&self) -> String {
+        match self {
+            UserNotification::AgentTurnComplete {
+                last_assistant_message,
+                ..
+            } => last_assistant_message.clone().unwrap_or_default(),
+            UserNotification::ApprovalRequested {
+                command,
+                files,
+                reason,
+                ..
+            } => {
+                let subject = if command.is_empty() {
+                    let files: Vec<String> =
+                        files.iter().map(|f| f.display().to_string()).collect();
+                    format!("Edit {}", files.join(", "))
+                } else {
+                    format!("Run {}", command.join(" "))
+                };
+                match reason {
+                    Some(reason) => format!("{subject}\n{reason}"),
+                    None => subject,
+                }
+            }
+            UserNotification::TurnFailed { error, .. } => error.clone(),
+            UserNotification::LongCommandFinished {
+                command,
+                exit_code,
+                duration_secs,
+                ..
+            } => format!(
+                "{} exited with code {exit_code} after {duration_secs}s",
+                command.join(" ")
+            ),
+            UserNotification::BudgetExceeded {
+                used_tokens,
+                cost_usd,
+                ..
+            } => format!("{used_tokens} tokens used, ${cost_usd:.2} spent"),
+        }
+    }
+}
+
+/// Delivers notifications to the `notify` program and to the built-in sinks
+/// configured under `[notifications]`. Delivery is fire-and-forget: failures
+/// are logged and never affect the session.
+pub(crate) struct Notifier {
+    program: Option<Vec<String>>,
+    config: NotificationsConfig,
+    http_client: reqwest::Client,
+}
+
+impl Notifier {
+    pub(crate) fn new(program: Option<Vec<String>>, config: NotificationsConfig) -> Self {
+        Self {
+            program: program.filter(|program| !program.is_empty()),
+            config,
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    pub(crate) fn long_command_threshold(&self) -> Duration {
+        self.config.long_command_threshold()
+    }
+
+    pub(crate) fn notify(&self, notification: UserNotification) {
+        let kind = notification.kind();
+        let program = self
+            .program
+            .as_ref()
+            .filter(|_| self.config.is_enabled_for_program(kind));
+        let enabled = self.config.is_enabled(kind);
+        let desktop = enabled && self.config.desktop;
+        let webhook = self.config.webhook.as_ref().filter(|_| enabled);
+        if program.is_none() && !desktop && webhook.is_none() {
+            return;
+        }
+
+        let Ok(json) = serde_json::to_string(&notification) else {
+            warn!("failed to serialise notification payload");
+            return;
+        };
+
+        if let Some(program) = program {
+            spawn_program(program, &json);
+        }
+        if desktop {
+            show_desktop_notification(notification.title(), &notification.body());
+        }
+        if let Some(webhook) = webhook {
+            post_webhook(&self.http_client, webhook, json);
+        }
+    }
+}
+
+fn spawn_program(program: &[String], json: &str) {
+    let mut command = std::process::Command::new(&program[0]);
+    if program.len() > 1 {
+        command.args(&program[1..]);
+    }
+    command.arg(json);
+
+    // Fire-and-forget – we do not wait for completion.
+    if let Err(e) = command.spawn() {
+        warn!("failed to spawn notifier '{}': {e}", program[0]);
+    }
+}
+
+/// Calls `org.freedesktop.Notifications.Notify` on the session bus through
+/// `gdbus`, which ships with GLib on practically every Linux desktop.
+fn show_desktop_notification(title: &str, body: &str) {
+    let result = std::process::Command::new("gdbus")
+        .args([
+            "call",
+            "--session",
+            "--dest",
+            "org.freedesktop.Notifications",
+            "--object-path",
+            "/org/freedesktop/Notifications",
+            "--method",
+            "org.freedesktop.Notifications.Notify",
+        ])
+        .args(desktop_notification_args(title, body))
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+    if let Err(e) = result {
+        warn!("failed to show desktop notification: {e}");
+    }
+}
+
+/// Arguments of `Notify(app_name, replaces_id, app_icon, summary, body,
+/// actions, hints, expire_timeout)` in GVariant text format.
+fn desktop_notification_args(title: &str, body: &str) -> Vec<String> {
+    vec![
+        gvariant_string("Codex"),
+        "0".to_string(),
+        gvariant_string(""),
+        gvariant_string(title),
+        gvariant_string(body),
+        "[]".to_string(),
+        "{}".to_string(),
+        DESKTOP_NOTIFICATION_TIMEOUT_MS.to_string(),
+    ]
+}
+
+fn gvariant_string(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{escaped}\"")
+}
+
+fn post_webhook(client: &reqwest::Client, webhook: &WebhookConfig, json: String) {
+    let mut request = client
+        .post(&webhook.url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .header("Content-Type", "application/json")
+        .body(json);
+    for (name, value) in &webhook.headers {
+        request = request.header(name, value);
+    }
+    let url = webhook.url.clone();
+    tokio::spawn(async move {
+        match request.send().await {
+            Ok(response) if !response.status().is_success() => {
+                warn!("notification webhook {url} returned {}", response.status());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("failed to post notification to {url}: {e}"),
+        }
+    });
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::body_json;
+    use wiremock::matchers::header;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
 
     #[test]
     fn test_user_notification() {
@@ -37,4 +291,112 @@ mod tests {
             r#"{"type":"agent-turn-complete","turn-id":"12345","input-messages":["Rename `foo` to `bar` and update the callsites."],"last-assistant-message":"Rename complete and verified `cargo build` succeeds."}"#
         );
     }
+
+    #[test]
+    fn serializes_approval_and_long_command_notifications() {
+        let approval = UserNotification::ApprovalRequested {
+            turn_id: "7".to_string(),
+            command: vec!["cargo".to_string(), "publish".to_string()],
+            files: Vec::new(),
+            reason: None,
+        };
+        assert_eq!(
+            r#"{"type":"approval-requested","turn-id":"7","command":["cargo","publish"],"reason":null}"#,
+            serde_json::to_string(&approval).unwrap()
+        );
+        assert_eq!("Run cargo publish", approval.body());
+
+        let long_command = UserNotification::LongCommandFinished {
+            turn_id: "7".to_string(),
+            command: vec!["make".to_string()],
+            exit_code: 2,
+            duration_secs: 95,
+        };
+        assert_eq!(
+            r#"{"type":"long-command-finished","turn-id":"7","command":["make"],"exit-code":2,"duration-secs":95}"#,
+            serde_json::to_string(&long_command).unwrap()
+        );
+    }
+
+    #[test]
+    fn desktop_arguments_are_quoted_gvariant_strings() {
+        assert_eq!(
+            vec![
+                "\"Codex\"",
+                "0",
+                "\"\"",
+                "\"Codex: turn failed\"",
+                "\"say \\\"hi\\\" \\\\o/\"",
+                "[]",
+                "{}",
+                "10000",
+            ],
+            desktop_notification_args("Codex: turn failed", "say \"hi\" \\o/")
+        );
+    }
+
+    #[test]
+    fn notify_program_only_gets_turn_completions_unless_events_are_set() {
+        let unset = NotificationsConfig::default();
+        assert!(unset.is_enabled(NotificationKind::ApprovalRequested));
+        assert!(unset.is_enabled_for_program(NotificationKind::AgentTurnComplete));
+        assert!(!unset.is_enabled_for_program(NotificationKind::ApprovalRequested));
+
+        let set = NotificationsConfig {
+            events: Some(vec![NotificationKind::ApprovalRequested]),
+            ..Default::default()
+        };
+        assert!(!set.is_enabled_for_program(NotificationKind::AgentTurnComplete));
+        assert!(set.is_enabled_for_program(NotificationKind::ApprovalRequested));
+    }
+
+    #[tokio::test]
+    async fn posts_enabled_notifications_to_webhook() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_json(serde_json::json!({
+                "type": "turn-failed",
+                "turn-id": "1",
+                "error": "stream disconnected",
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = Notifier::new(
+            None,
+            NotificationsConfig {
+                events: Some(vec![NotificationKind::TurnFailed]),
+                webhook: Some(WebhookConfig {
+                    url: format!("{}/hook", server.uri()),
+                    headers: HashMap::from([(
+                        "Authorization".to_string(),
+                        "Bearer secret".to_string(),
+                    )]),
+                }),
+                ..Default::default()
+            },
+        );
+        // Filtered out by `events`.
+        notifier.notify(UserNotification::AgentTurnComplete {
+            turn_id: "1".to_string(),
+            input_messages: Vec::new(),
+            last_assistant_message: None,
+        });
+        notifier.notify(UserNotification::TurnFailed {
+            turn_id: "1".to_string(),
+            error: "stream disconnected".to_string(),
+        });
+
+        for _ in 0..50 {
+            if server.received_requests().await.unwrap_or_default().len() == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        server.verify().await;
+    }
 }
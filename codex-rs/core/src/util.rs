@@ -1,7 +1,26 @@
 use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
 use std::time::Duration;
 
 use rand::Rng;
+use tracing::warn;
+
+// A convenience extension trait for acquiring mutex locks where poisoning is
+// unrecoverable and should abort the program. This avoids scattered `.unwrap()`
+// calls on `lock()` while still surfacing a clear panic message when a lock is
+// poisoned.
+pub(crate) trait MutexExt<T> {
+    fn lock_unchecked(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> MutexExt<T> for Mutex<T> {
+    fn lock_unchecked(&self) -> MutexGuard<'_, T> {
+        #[expect(clippy::expect_used)]
+        self.lock().expect("poisoned lock")
+    }
+}
 
 const INITIAL_DELAY_MS: u64 = 200;
 const BACKOFF_FACTOR: f64 = 2.0;
@@ -26,11 +45,17 @@ pub(crate) fn backoff(attempt: u64) -> Duration {
 /// directory. If you need Codex to work from such a checkout simply pass the
 /// `--allow-no-git-exec` CLI flag that disables the repo requirement.
 pub fn is_inside_git_repo(base_dir: &Path) -> bool {
+    find_git_root(base_dir).is_some()
+}
+
+/// Return the closest ancestor of `base_dir` (including itself) that contains
+/// a `.git` file or directory. See [`is_inside_git_repo`] for the caveats.
+pub fn find_git_root(base_dir: &Path) -> Option<PathBuf> {
     let mut dir = base_dir.to_path_buf();
 
     loop {
         if dir.join(".git").exists() {
-            return true;
+            return Some(dir);
         }
 
         // Pop one component (go up one directory).  `pop` returns false when
@@ -40,5 +65,26 @@ pub fn is_inside_git_repo(base_dir: &Path) -> bool {
         }
     }
 
-    false
+    None
+}
+
+/// Deletes the directories in `dir` that have not been modified for
+/// `retention`, such as the leftovers of sessions that are long gone.
+pub(crate) async fn remove_stale_dirs(dir: &Path, retention: Duration) {
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let is_stale = entry
+            .metadata()
+            .await
+            .ok()
+            .filter(std::fs::Metadata::is_dir)
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age >= retention);
+        if is_stale && let Err(e) = tokio::fs::remove_dir_all(entry.path()).await {
+            warn!("failed to delete {}: {e}", entry.path().display());
+        }
+    }
 }
@@ -49,6 +49,17 @@ fn ev_completed(id: &str) -> Value {
     })
 }
 
+/// Convenience: SSE event for a completed response that used `total_tokens`.
+fn ev_completed_with_tokens(id: &str, total_tokens: u64) -> Value {
+    serde_json::json!({
+        "type": "response.completed",
+        "response": {
+            "id": id,
+            "usage": {"input_tokens":total_tokens,"input_tokens_details":null,"output_tokens":0,"output_tokens_details":null,"total_tokens":total_tokens}
+        }
+    })
+}
+
 /// Convenience: SSE event for a single assistant message output item.
 fn ev_assistant_message(id: &str, text: &str) -> Value {
     serde_json::json!({
@@ -248,4 +259,128 @@ async fn summarize_context_three_requests_and_instructions() {
         !messages.iter().any(|(_, t)| t.contains(SUMMARIZE_TRIGGER)),
         "third request should not include the summarize trigger"
     );
+    assert!(
+        messages
+            .iter()
+            .any(|(r, t)| r == "assistant" && t.contains(SUMMARY_TEXT)),
+        "third request should include the summary"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn auto_compacts_when_token_limit_is_reached() {
+    if std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+
+    // SSE 1: the first turn reports usage above the limit.
+    let sse1 = sse(vec![
+        ev_assistant_message("m1", FIRST_REPLY),
+        ev_completed_with_tokens("r1", 5_000),
+    ]);
+    // SSE 2: the automatic summary request.
+    let sse2 = sse(vec![
+        ev_assistant_message("m2", SUMMARY_TEXT),
+        ev_completed("r2"),
+    ]);
+    // SSE 3: the second user turn, sent with the compacted history.
+    let sse3 = sse(vec![ev_completed("r3")]);
+
+    let first_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains("\"text\":\"hello world\"")
+            && !body.contains(&format!("\"text\":\"{SUMMARIZE_TRIGGER}\""))
+    };
+    mount_sse_once(&server, first_matcher, sse1).await;
+
+    let second_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains(&format!("\"text\":\"{SUMMARIZE_TRIGGER}\""))
+    };
+    mount_sse_once(&server, second_matcher, sse2).await;
+
+    let third_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains(&format!("\"text\":\"{THIRD_USER_MSG}\""))
+            && !body.contains(&format!("\"text\":\"{SUMMARIZE_TRIGGER}\""))
+    };
+    mount_sse_once(&server, third_matcher, sse3).await;
+
+    let model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = model_provider;
+    config.model_auto_compact_token_limit = Some(1_000);
+    let conversation_manager = ConversationManager::default();
+    let codex = conversation_manager
+        .new_conversation_with_auth(config, Some(CodexAuth::from_api_key("dummy")))
+        .await
+        .unwrap()
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "hello world".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    // The next turn is compacted first because the last request hit the limit.
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: THIRD_USER_MSG.into(),
+            }],
+        })
+        .await
+        .unwrap();
+    let EventMsg::BackgroundEvent(event) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::BackgroundEvent(_))).await
+    else {
+        unreachable!();
+    };
+    assert!(event.message.contains("compacted"), "{}", event.message);
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3, "expected exactly three requests");
+
+    let body3 = requests[2].body_json::<serde_json::Value>().unwrap();
+    let input3 = body3.get("input").and_then(|v| v.as_array()).unwrap();
+    let messages: Vec<(String, String)> = input3
+        .iter()
+        .filter(|item| item["type"].as_str() == Some("message"))
+        .map(|item| {
+            (
+                item["role"].as_str().unwrap_or_default().to_string(),
+                item["content"][0]["text"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+            )
+        })
+        .collect();
+
+    assert!(
+        !messages.iter().any(|(_, t)| t.contains("hello world")),
+        "compacted request should not include the original user input"
+    );
+    let (summary_role, summary_text) = &messages[messages.len() - 2];
+    assert_eq!(summary_role, "assistant");
+    assert!(summary_text.contains(SUMMARY_TEXT));
+    assert_eq!(
+        messages.last().unwrap(),
+        &("user".to_string(), THIRD_USER_MSG.to_string())
+    );
 }
@@ -58,9 +58,9 @@ async fn exit_code_0_succeeds() {
     assert_eq!(output.stdout.truncated_after_lines, None);
 }
 
-/// Command succeeds with exit code 0 normally
+/// Output is captured in full; it is truncated when formatted for the model
 #[tokio::test]
-async fn truncates_output_lines() {
+async fn captures_all_output_lines() {
     if skip_test() {
         return;
     }
@@ -70,17 +70,17 @@ async fn truncates_output_lines() {
 
     let output = run_test_cmd(tmp, cmd).await.unwrap();
 
-    let expected_output = (1..=256)
+    let expected_output = (1..=300)
         .map(|i| format!("{i}\n"))
         .collect::<Vec<_>>()
         .join("");
     assert_eq!(output.stdout.text, expected_output);
-    assert_eq!(output.stdout.truncated_after_lines, Some(256));
+    assert_eq!(output.stdout.truncated_after_lines, None);
 }
 
-/// Command succeeds with exit code 0 normally
+/// Output is captured in full; it is truncated when formatted for the model
 #[tokio::test]
-async fn truncates_output_bytes() {
+async fn captures_all_output_bytes() {
     if skip_test() {
         return;
     }
@@ -91,8 +91,8 @@ async fn truncates_output_bytes() {
 
     let output = run_test_cmd(tmp, cmd).await.unwrap();
 
-    assert_eq!(output.stdout.text.len(), 10240);
-    assert_eq!(output.stdout.truncated_after_lines, Some(10));
+    assert_eq!(output.stdout.text.len(), 15 * 1001);
+    assert_eq!(output.stdout.truncated_after_lines, None);
 }
 
 /// Command not found returns exit code 127, this is not considered a sandbox error
@@ -0,0 +1,118 @@
+use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::NewConversation;
+use codex_core::built_in_model_providers;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_login::CodexAuth;
+use core_test_support::load_default_config_for_test;
+use core_test_support::load_sse_fixture_with_id;
+use core_test_support::wait_for_event;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+/// Build minimal SSE stream with completed marker using the JSON fixture.
+fn sse_completed(id: &str) -> String {
+    load_sse_fixture_with_id("tests/fixtures/completed_template.json", id)
+}
+
+fn user_text(text: &str) -> Op {
+    Op::UserInput {
+        items: vec![InputItem::Text { text: text.into() }],
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn fork_drops_last_turns_and_records_parent() {
+    use pretty_assertions::assert_eq;
+
+    let server = MockServer::start().await;
+    let template = ResponseTemplate::new(200)
+        .insert_header("content-type", "text/event-stream")
+        .set_body_raw(sse_completed("resp"), "text/event-stream");
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(template)
+        .expect(3)
+        .mount(&server)
+        .await;
+
+    let model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+
+    let cwd = TempDir::new().unwrap();
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.cwd = cwd.path().to_path_buf();
+    config.model_provider = model_provider;
+
+    let conversation_manager = ConversationManager::default();
+    let NewConversation {
+        conversation_id,
+        conversation: codex,
+        ..
+    } = conversation_manager
+        .new_conversation_with_auth(config, Some(CodexAuth::from_api_key("Test API Key")))
+        .await
+        .expect("create new conversation");
+
+    for text in ["hello 1", "hello 2"] {
+        codex.submit(user_text(text)).await.unwrap();
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    }
+
+    let NewConversation {
+        conversation_id: fork_id,
+        conversation: fork,
+        ..
+    } = conversation_manager
+        .fork_conversation_with_auth(
+            conversation_id,
+            1,
+            Some(CodexAuth::from_api_key("Test API Key")),
+        )
+        .await
+        .expect("fork conversation");
+    assert_ne!(conversation_id, fork_id);
+
+    fork.submit(user_text("hello 3")).await.unwrap();
+    wait_for_event(&fork, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3, "expected three POST requests");
+
+    // The fork sees the first turn but not the second one.
+    let body1 = requests[0].body_json::<serde_json::Value>().unwrap();
+    let body3 = requests[2].body_json::<serde_json::Value>().unwrap();
+    let expected_user_message_3 = serde_json::json!({
+        "type": "message",
+        "id": serde_json::Value::Null,
+        "role": "user",
+        "content": [ { "type": "input_text", "text": "hello 3" } ]
+    });
+    let expected_body3 = serde_json::json!(
+        [
+            body1["input"].as_array().unwrap().as_slice(),
+            [expected_user_message_3].as_slice(),
+        ]
+        .concat()
+    );
+    assert_eq!(body3["input"], expected_body3);
+
+    // The fork is recorded in its own rollout file that names its parent.
+    let sessions = codex_core::rollout::list_sessions(codex_home.path()).unwrap();
+    let fork_session = sessions
+        .iter()
+        .find(|session| session.id == fork_id)
+        .expect("rollout for the fork");
+    let rollout = std::fs::read_to_string(&fork_session.path).unwrap();
+    let meta: serde_json::Value = serde_json::from_str(rollout.lines().next().unwrap()).unwrap();
+    assert_eq!(meta["parent_id"], conversation_id.to_string());
+}
@@ -8,6 +8,7 @@ use codex_core::protocol::Op;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol_config_types::ReasoningEffort;
 use codex_core::protocol_config_types::ReasoningSummary;
+use codex_core::shell::default_user_shell;
 use codex_login::CodexAuth;
 use core_test_support::load_default_config_for_test;
 use core_test_support::load_sse_fixture_with_id;
@@ -85,9 +86,15 @@ async fn prefixes_context_and_instructions_once_and_consistently_across_requests
     let requests = server.received_requests().await.unwrap();
     assert_eq!(requests.len(), 2, "expected two POST requests");
 
+    let shell_line = default_user_shell()
+        .await
+        .name()
+        .map(|shell| format!("Shell: {shell}\n"))
+        .unwrap_or_default();
     let expected_env_text = format!(
-        "<environment_context>\nCurrent working directory: {}\nApproval policy: on-request\nSandbox mode: read-only\nNetwork access: restricted\n</environment_context>",
-        cwd.path().to_string_lossy()
+        "<environment_context>\nCurrent working directory: {}\nApproval policy: on-request\nSandbox mode: read-only\nNetwork access: restricted\n{}</environment_context>",
+        cwd.path().to_string_lossy(),
+        shell_line,
     );
     let expected_ui_text =
         "<user_instructions>\n\nbe consistent and helpful\n\n</user_instructions>";
@@ -192,7 +199,9 @@ async fn overrides_turn_context_but_keeps_cached_prefix_and_key_constant() {
             approval_policy: Some(AskForApproval::Never),
             sandbox_policy: Some(SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![writable.path().to_path_buf()],
+                readable_roots: None,
                 network_access: true,
+                network_allowlist: vec![],
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
             }),
@@ -237,9 +246,15 @@ async fn overrides_turn_context_but_keeps_cached_prefix_and_key_constant() {
     });
     // After overriding the turn context, the environment context should be emitted again
     // reflecting the new cwd, approval policy and sandbox settings.
+    let shell_line = default_user_shell()
+        .await
+        .name()
+        .map(|shell| format!("Shell: {shell}\n"))
+        .unwrap_or_default();
     let expected_env_text_2 = format!(
-        "<environment_context>\nCurrent working directory: {}\nApproval policy: never\nSandbox mode: workspace-write\nNetwork access: enabled\n</environment_context>",
-        new_cwd.path().to_string_lossy()
+        "<environment_context>\nCurrent working directory: {}\nApproval policy: never\nSandbox mode: workspace-write\nNetwork access: enabled\n{}</environment_context>",
+        new_cwd.path().to_string_lossy(),
+        shell_line,
     );
     let expected_env_msg_2 = serde_json::json!({
         "type": "message",
@@ -318,7 +333,9 @@ async fn per_turn_overrides_keep_cached_prefix_and_key_constant() {
             approval_policy: AskForApproval::Never,
             sandbox_policy: SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![writable.path().to_path_buf()],
+                readable_roots: None,
                 network_access: true,
+                network_allowlist: vec![],
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
             },
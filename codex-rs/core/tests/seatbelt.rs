@@ -77,7 +77,9 @@ async fn if_parent_of_repo_is_writable_then_dot_git_folder_is_writable() {
     let test_scenario = create_test_scenario(&tmp);
     let policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: vec![test_scenario.repo_parent.clone()],
+        readable_roots: None,
         network_access: false,
+        network_allowlist: vec![],
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
     };
@@ -103,7 +105,9 @@ async fn if_git_repo_is_writable_root_then_dot_git_folder_is_read_only() {
     let test_scenario = create_test_scenario(&tmp);
     let policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: vec![test_scenario.repo_root.clone()],
+        readable_roots: None,
         network_access: false,
+        network_allowlist: vec![],
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
     };
@@ -1,6 +1,7 @@
 use codex_common::elapsed::format_duration;
 use codex_common::elapsed::format_elapsed;
 use codex_core::config::Config;
+use codex_core::plan_tool::PlanProgress;
 use codex_core::plan_tool::UpdatePlanArgs;
 use codex_core::protocol::AgentMessageDeltaEvent;
 use codex_core::protocol::AgentMessageEvent;
@@ -8,15 +9,22 @@ use codex_core::protocol::AgentReasoningDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
 use codex_core::protocol::BackgroundEventEvent;
+use codex_core::protocol::BackgroundJobFinishedEvent;
+use codex_core::protocol::BackgroundJobStartedEvent;
+use codex_core::protocol::CheckpointCreatedEvent;
+use codex_core::protocol::CheckpointRestoredEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
 use codex_core::protocol::FileChange;
+use codex_core::protocol::FileToolCallEndEvent;
+use codex_core::protocol::FileToolInvocation;
 use codex_core::protocol::McpInvocation;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
+use codex_core::protocol::NetworkAccessEvent;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::PatchApplyEndEvent;
 use codex_core::protocol::SessionConfiguredEvent;
@@ -174,10 +182,35 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::BackgroundEvent(BackgroundEventEvent { message }) => {
                 ts_println!(self, "{}", message.style(self.dimmed));
             }
+            EventMsg::NetworkAccess(NetworkAccessEvent {
+                host,
+                port,
+                allowed,
+            }) => {
+                if allowed {
+                    ts_println!(
+                        self,
+                        "{}",
+                        format!("network: allowed {host}:{port}").style(self.dimmed)
+                    );
+                } else {
+                    let prefix = "network: denied".style(self.red);
+                    ts_println!(self, "{prefix} {host}:{port}");
+                }
+            }
             EventMsg::TaskStarted => {
                 // Ignore.
             }
-            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+            EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message,
+                plan_progress,
+            }) => {
+                if let Some(PlanProgress {
+                    completed, total, ..
+                }) = plan_progress
+                {
+                    ts_println!(self, "plan: {completed}/{total} steps completed");
+                }
                 if let Some(output_file) = self.last_message_path.as_deref() {
                     handle_last_message(last_agent_message.as_deref(), output_file);
                 }
@@ -287,6 +320,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 stderr,
                 duration,
                 exit_code,
+                output_file,
             }) => {
                 let exec_command = self.call_id_to_command.remove(&call_id);
                 let (duration, call) = if let Some(ExecCommandBegin { command, .. }) = exec_command
@@ -316,6 +350,13 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     }
                 }
                 println!("{}", truncated_output.style(self.dimmed));
+                if let Some(output_file) = output_file {
+                    println!(
+                        "{}",
+                        format!("full output saved to {}", output_file.display())
+                            .style(self.dimmed)
+                    );
+                }
             }
             EventMsg::McpToolCallBegin(McpToolCallBeginEvent {
                 call_id: _,
@@ -358,6 +399,38 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     }
                 }
             }
+            EventMsg::FileToolCallBegin(_) => {}
+            EventMsg::FileToolCallEnd(FileToolCallEndEvent {
+                call_id: _,
+                invocation,
+                duration,
+                result,
+            }) => {
+                let duration = format!(" in {}", format_duration(duration));
+                let invocation = format_file_tool_invocation(&invocation);
+                match result {
+                    Ok(summary) => {
+                        let more = if summary.truncated { "+" } else { "" };
+                        ts_println!(
+                            self,
+                            "{}",
+                            format!(
+                                "{invocation} success{duration}: {}{more} results",
+                                summary.count
+                            )
+                            .style(self.green)
+                        );
+                    }
+                    Err(e) => {
+                        ts_println!(
+                            self,
+                            "{}",
+                            format!("{invocation} failed{duration}:").style(self.red)
+                        );
+                        println!("{}", e.style(self.dimmed));
+                    }
+                }
+            }
             EventMsg::PatchApplyBegin(PatchApplyBeginEvent {
                 call_id,
                 auto_approved,
@@ -476,12 +549,76 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 ts_println!(self, "{}", "turn diff:".style(self.magenta));
                 println!("{unified_diff}");
             }
+            EventMsg::BackgroundJobStarted(BackgroundJobStartedEvent {
+                job_id, command, ..
+            }) => {
+                ts_println!(
+                    self,
+                    "{} {}",
+                    format!("background job {job_id} started:").style(self.magenta),
+                    escape_command(&command).style(self.bold)
+                );
+            }
+            EventMsg::BackgroundJobFinished(BackgroundJobFinishedEvent {
+                job_id,
+                exit_code,
+                killed,
+                duration,
+            }) => {
+                let status = if killed {
+                    "was killed".to_string()
+                } else {
+                    format!("exited {exit_code}")
+                };
+                ts_println!(
+                    self,
+                    "{}",
+                    format!(
+                        "background job {job_id} {status} after {}",
+                        format_duration(duration)
+                    )
+                    .style(self.dimmed)
+                );
+            }
+            EventMsg::CheckpointCreated(CheckpointCreatedEvent {
+                checkpoint_id,
+                changed_files,
+            }) => {
+                ts_println!(
+                    self,
+                    "{}",
+                    format!(
+                        "checkpoint {checkpoint_id}: {} file(s) changed",
+                        changed_files.len()
+                    )
+                    .style(self.dimmed)
+                );
+            }
+            EventMsg::CheckpointRestored(CheckpointRestoredEvent {
+                checkpoint_id,
+                restored_files,
+            }) => {
+                ts_println!(
+                    self,
+                    "{}",
+                    format!(
+                        "restored checkpoint {checkpoint_id}: {} file(s) reverted",
+                        restored_files.len()
+                    )
+                    .style(self.dimmed)
+                );
+            }
             EventMsg::ExecApprovalRequest(_) => {
                 // Should we exit?
             }
             EventMsg::ApplyPatchApprovalRequest(_) => {
                 // Should we exit?
             }
+            EventMsg::BudgetApprovalRequest(_) => {
+                // `codex exec` never asks for approval, so the session stops
+                // when the budget is exceeded and reports it as a background
+                // event instead.
+            }
             EventMsg::AgentReasoning(agent_reasoning_event) => {
                 if self.show_agent_reasoning {
                     if !self.reasoning_started {
@@ -523,7 +660,9 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::GetHistoryEntryResponse(_) => {
                 // Currently ignored in exec output.
             }
-            EventMsg::McpListToolsResponse(_) => {
+            EventMsg::McpListToolsResponse(_)
+            | EventMsg::McpListResourcesResponse(_)
+            | EventMsg::McpListPromptsResponse(_) => {
                 // Currently ignored in exec output.
             }
             EventMsg::TurnAborted(abort_reason) => match abort_reason.reason {
@@ -557,6 +696,33 @@ fn format_file_change(change: &FileChange) -> &'static str {
     }
 }
 
+fn format_file_tool_invocation(invocation: &FileToolInvocation) -> String {
+    match invocation {
+        FileToolInvocation::ReadFile {
+            path,
+            start_line,
+            end_line,
+        } => match (start_line, end_line) {
+            (None, None) => format!("read_file({})", path.display()),
+            (start, end) => format!(
+                "read_file({}:{}-{})",
+                path.display(),
+                start.unwrap_or(1),
+                end.map(|end| end.to_string()).unwrap_or_default()
+            ),
+        },
+        FileToolInvocation::Grep {
+            pattern,
+            path,
+            include,
+        } => match include {
+            Some(include) => format!("grep({pattern:?} in {} ({include}))", path.display()),
+            None => format!("grep({pattern:?} in {})", path.display()),
+        },
+        FileToolInvocation::ListDir { path, .. } => format!("list_dir({})", path.display()),
+    }
+}
+
 fn format_mcp_invocation(invocation: &McpInvocation) -> String {
     // Build fully-qualified tool name: server.tool
     let fq_tool_name = format!("{}.{}", invocation.server, invocation.tool);
@@ -40,12 +40,18 @@ impl EventProcessor for EventProcessorWithJsonOutput {
     }
 
     fn process_event(&mut self, event: Event) -> CodexStatus {
-        match event.msg {
+        match &event.msg {
             EventMsg::AgentMessageDelta(_) | EventMsg::AgentReasoningDelta(_) => {
                 // Suppress streaming events in JSON mode.
                 CodexStatus::Running
             }
-            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+            EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message, ..
+            }) => {
+                // Printed so consumers see the final message and plan progress.
+                if let Ok(line) = serde_json::to_string(&event) {
+                    println!("{line}");
+                }
                 if let Some(output_file) = self.last_message_path.as_deref() {
                     handle_last_message(last_agent_message.as_deref(), output_file);
                 }
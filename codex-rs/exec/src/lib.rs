@@ -242,12 +242,7 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         info!("Sent images with event ID: {initial_images_event_id}");
         while let Ok(event) = conversation.next_event().await {
             if event.id == initial_images_event_id
-                && matches!(
-                    event.msg,
-                    EventMsg::TaskComplete(TaskCompleteEvent {
-                        last_agent_message: _,
-                    })
-                )
+                && matches!(event.msg, EventMsg::TaskComplete(TaskCompleteEvent { .. }))
             {
                 break;
             }
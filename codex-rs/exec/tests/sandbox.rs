@@ -55,7 +55,9 @@ async fn python_multiprocessing_lock_works_under_sandbox() {
 
     let policy = SandboxPolicy::WorkspaceWrite {
         writable_roots,
+        readable_roots: None,
         network_access: false,
+        network_allowlist: vec![],
         exclude_tmpdir_env_var: false,
         exclude_slash_tmp: false,
     };
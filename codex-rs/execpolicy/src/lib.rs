@@ -43,3 +43,12 @@ pub fn get_default_policy() -> starlark::Result<Policy> {
     let parser = PolicyParser::new("#default", DEFAULT_POLICY);
     parser.parse()
 }
+
+/// Parses the default policy followed by `policies`, in order, into a single
+/// [`Policy`].
+pub fn get_default_policy_extended_by(policies: Vec<PolicyParser>) -> starlark::Result<Policy> {
+    let mut parsers = Vec::with_capacity(policies.len() + 1);
+    parsers.push(PolicyParser::new("#default", DEFAULT_POLICY));
+    parsers.extend(policies);
+    PolicyParser::parse_all(&parsers)
+}
@@ -18,8 +18,9 @@ use starlark::values::starlark_value;
 #[display("opt({})", opt)]
 pub struct Opt {
     /// The option as typed on the command line, e.g., `-h` or `--help`. If
-    /// it can be used in the `--name=value` format, then this should be
-    /// `--name` (though this is subject to change).
+    /// it can be used in the `--name=value` format (see `combined_format`),
+    /// then this should be `--name`. Options that can be bundled (see
+    /// `option_bundling`) must be a single character, e.g., `-a`.
     pub opt: String,
     pub meta: OptMeta,
     pub required: bool,
@@ -35,6 +35,28 @@ impl PolicyParser {
     }
 
     pub fn parse(&self) -> starlark::Result<Policy> {
+        Self::parse_all(std::slice::from_ref(self))
+    }
+
+    /// Evaluates each parser in order into a single [`Policy`].
+    ///
+    /// `define_program` rules from a later source are checked before those
+    /// from earlier sources, so a later source can override how an earlier
+    /// one treats a program (e.g., forbid a subcommand that was allowed).
+    /// Forbidden program regexes and substrings from every source always
+    /// apply.
+    pub fn parse_all(parsers: &[PolicyParser]) -> starlark::Result<Policy> {
+        let policy_builder = PolicyBuilder::new();
+        for parser in parsers {
+            let layer = PolicyBuilder::new();
+            parser.eval_into(&layer)?;
+            policy_builder.overlay(layer);
+        }
+        let policy = policy_builder.build();
+        policy.map_err(|e| starlark::Error::new_kind(starlark::ErrorKind::Other(e.into())))
+    }
+
+    fn eval_into(&self, policy_builder: &PolicyBuilder) -> starlark::Result<()> {
         let mut dialect = Dialect::Extended.clone();
         dialect.enable_f_strings = true;
         let ast = AstModule::parse(&self.policy_source, self.unparsed_policy.clone(), &dialect)?;
@@ -60,14 +82,10 @@ impl PolicyParser {
             heap.alloc(ArgMatcher::UnverifiedVarargs),
         );
 
-        let policy_builder = PolicyBuilder::new();
-        {
-            let mut eval = Evaluator::new(&module);
-            eval.extra = Some(&policy_builder);
-            eval.eval_module(ast, &globals)?;
-        }
-        let policy = policy_builder.build();
-        policy.map_err(|e| starlark::Error::new_kind(starlark::ErrorKind::Other(e.into())))
+        let mut eval = Evaluator::new(&module);
+        eval.extra = Some(policy_builder);
+        eval.eval_module(ast, &globals)?;
+        Ok(())
     }
 }
 
@@ -107,6 +125,24 @@ impl PolicyBuilder {
         programs.insert(name.clone(), program_spec);
     }
 
+    /// Merges the rules collected in `layer` into `self`, giving the program
+    /// specs in `layer` precedence over the ones already present.
+    fn overlay(&self, layer: PolicyBuilder) {
+        let mut programs = self.programs.borrow_mut();
+        for (name, mut specs) in layer.programs.into_inner() {
+            if let Some(existing) = programs.remove(&name) {
+                specs.extend(existing);
+            }
+            programs.insert_many(name, specs);
+        }
+        self.forbidden_program_regexes
+            .borrow_mut()
+            .extend(layer.forbidden_program_regexes.into_inner());
+        self.forbidden_substrings
+            .borrow_mut()
+            .extend(layer.forbidden_substrings.into_inner());
+    }
+
     fn add_forbidden_substrings(&self, substrings: &[String]) {
         let mut forbidden_substrings = self.forbidden_substrings.borrow_mut();
         forbidden_substrings.extend_from_slice(substrings);
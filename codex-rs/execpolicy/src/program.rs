@@ -133,7 +133,23 @@ impl ProgramSpec {
                         }
                     }
                     None => {
-                        // It could be an --option=value style flag...
+                        if let Some((name, value)) = self.split_combined_format(arg) {
+                            if let Some(Opt {
+                                meta: OptMeta::Value(arg_type),
+                                ..
+                            }) = self.allowed_options.get(name)
+                            {
+                                matched_opts.push(MatchedOpt::new(name, value, arg_type.clone())?);
+                                continue;
+                            }
+                        } else if self.is_option_bundle(arg) {
+                            expecting_option_value = self.match_option_bundle(
+                                arg,
+                                &mut matched_flags,
+                                &mut matched_opts,
+                            )?;
+                            continue;
+                        }
                     }
                 }
 
@@ -194,6 +210,55 @@ impl ProgramSpec {
         }
     }
 
+    /// When `combined_format` is enabled, splits `--name=value` into its name
+    /// and value.
+    fn split_combined_format<'a>(&self, arg: &'a str) -> Option<(&'a str, &'a str)> {
+        if !self.combined_format || !arg.starts_with("--") {
+            return None;
+        }
+        arg.split_once('=')
+    }
+
+    /// When `option_bundling` is enabled, whether `arg` could be several
+    /// single-character options combined, e.g., `-al`.
+    fn is_option_bundle(&self, arg: &str) -> bool {
+        self.option_bundling && !arg.starts_with("--") && arg.chars().count() > 2
+    }
+
+    /// Matches each character of `bundle` as a single-character option. As
+    /// with getopt(3), an option that takes a value consumes the rest of the
+    /// bundle as its value (e.g., `-n20`), or the next argument if it is the
+    /// last character of the bundle. In the latter case, the option that is
+    /// still waiting for its value is returned.
+    fn match_option_bundle(
+        &self,
+        bundle: &str,
+        matched_flags: &mut Vec<MatchedFlag>,
+        matched_opts: &mut Vec<MatchedOpt>,
+    ) -> Result<Option<(String, ArgType)>> {
+        for (offset, c) in bundle.char_indices().skip(1) {
+            let name = format!("-{c}");
+            match self.allowed_options.get(&name).map(|opt| &opt.meta) {
+                Some(OptMeta::Flag) => matched_flags.push(MatchedFlag { name }),
+                Some(OptMeta::Value(arg_type)) => {
+                    let value = &bundle[offset + c.len_utf8()..];
+                    if value.is_empty() {
+                        return Ok(Some((name, arg_type.clone())));
+                    }
+                    matched_opts.push(MatchedOpt::new(&name, value, arg_type.clone())?);
+                    return Ok(None);
+                }
+                None => {
+                    return Err(Error::UnknownOption {
+                        program: self.program.clone(),
+                        option: name,
+                    });
+                }
+            }
+        }
+        Ok(None)
+    }
+
     pub fn verify_should_match_list(&self) -> Vec<PositiveExampleFailedCheck> {
         let mut violations = Vec::new();
         for good in &self.should_match {
@@ -0,0 +1,68 @@
+extern crate codex_execpolicy;
+
+use codex_execpolicy::ExecCall;
+use codex_execpolicy::MatchedExec;
+use codex_execpolicy::Policy;
+use codex_execpolicy::PolicyParser;
+use codex_execpolicy::get_default_policy_extended_by;
+
+#[expect(clippy::expect_used)]
+fn setup(user_policy: &str) -> Policy {
+    get_default_policy_extended_by(vec![PolicyParser::new("#user", user_policy)])
+        .expect("failed to load extended policy")
+}
+
+#[test]
+fn test_user_policy_adds_program() {
+    let policy = setup(
+        r#"
+define_program(
+    program="cargo",
+    args=["check"],
+)
+"#,
+    );
+    assert!(matches!(
+        policy.check(&ExecCall::new("cargo", &["check"])),
+        Ok(MatchedExec::Match { .. })
+    ));
+
+    // Rules from the default policy are still present.
+    assert!(matches!(
+        policy.check(&ExecCall::new("ls", &["-l"])),
+        Ok(MatchedExec::Match { .. })
+    ));
+}
+
+#[test]
+fn test_user_policy_error_is_reported() {
+    let result = get_default_policy_extended_by(vec![PolicyParser::new(
+        "#user",
+        "define_program(program=42)",
+    )]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_later_policy_takes_precedence() {
+    let policy = setup(
+        r#"
+define_program(
+    program="ls",
+    args=[ARG_RFILES_OR_CWD],
+    forbidden="use `ls -l` instead",
+)
+"#,
+    );
+    assert!(matches!(
+        policy.check(&ExecCall::new("ls", &[])),
+        Ok(MatchedExec::Forbidden { reason, .. }) if reason == "use `ls -l` instead"
+    ));
+
+    // Invocations the user rule does not cover fall through to the default
+    // policy.
+    assert!(matches!(
+        policy.check(&ExecCall::new("ls", &["-l"])),
+        Ok(MatchedExec::Match { .. })
+    ));
+}
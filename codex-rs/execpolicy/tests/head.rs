@@ -134,3 +134,61 @@ fn test_head_invalid_n_as_negative_int() {
         policy.check(&head)
     )
 }
+
+#[test]
+fn test_head_lines_combined_format() -> Result<()> {
+    let policy = setup();
+    let head = ExecCall::new("head", &["--lines=20", "src/extension.ts"]);
+    assert_eq!(
+        Ok(MatchedExec::Match {
+            exec: ValidExec {
+                program: "head".to_string(),
+                flags: vec![],
+                opts: vec![MatchedOpt::new("--lines", "20", ArgType::PositiveInteger)?],
+                args: vec![MatchedArg::new(
+                    1,
+                    ArgType::ReadableFile,
+                    "src/extension.ts"
+                )?],
+                system_path: vec!["/bin/head".to_string(), "/usr/bin/head".to_string()],
+            }
+        }),
+        policy.check(&head)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_head_n_value_in_bundle() -> Result<()> {
+    let policy = setup();
+    let head = ExecCall::new("head", &["-n20", "src/extension.ts"]);
+    assert_eq!(
+        Ok(MatchedExec::Match {
+            exec: ValidExec {
+                program: "head".to_string(),
+                flags: vec![],
+                opts: vec![MatchedOpt::new("-n", "20", ArgType::PositiveInteger)?],
+                args: vec![MatchedArg::new(
+                    1,
+                    ArgType::ReadableFile,
+                    "src/extension.ts"
+                )?],
+                system_path: vec!["/bin/head".to_string(), "/usr/bin/head".to_string()],
+            }
+        }),
+        policy.check(&head)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_head_invalid_lines_combined_format() {
+    let policy = setup();
+    let head = ExecCall::new("head", &["--lines=0", "src/extension.ts"]);
+    assert_eq!(
+        Err(Error::InvalidPositiveInteger {
+            value: "0".to_string(),
+        }),
+        policy.check(&head)
+    )
+}
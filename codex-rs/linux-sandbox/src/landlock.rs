@@ -2,6 +2,7 @@ use std::collections::BTreeMap;
 use std::path::Path;
 use std::path::PathBuf;
 
+use codex_core::CODEX_NETWORK_PROXY_SOCKET_ENV_VAR;
 use codex_core::error::CodexErr;
 use codex_core::error::Result;
 use codex_core::error::SandboxErr;
@@ -25,6 +26,19 @@ use seccompiler::SeccompRule;
 use seccompiler::TargetArch;
 use seccompiler::apply_filter;
 
+use crate::proxy_bridge::export_proxy_env;
+use crate::proxy_bridge::start_network_proxy_bridge;
+
+/// Network access left to a command that does not have full network access.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum NetworkMode {
+    /// No sockets other than Unix ones, which cannot be connected.
+    None,
+    /// IP sockets confined to the private network namespace set up by
+    /// [`start_network_proxy_bridge`], and no Unix sockets.
+    LoopbackOnly,
+}
+
 /// Apply sandbox policies inside this thread so only the child inherits
 /// them, not the entire CLI process.
 pub(crate) fn apply_sandbox_policy_to_current_thread(
@@ -32,7 +46,19 @@ pub(crate) fn apply_sandbox_policy_to_current_thread(
     cwd: &Path,
 ) -> Result<()> {
     if !sandbox_policy.has_full_network_access() {
-        install_network_seccomp_filter_on_current_thread()?;
+        let proxy_socket = if sandbox_policy.get_network_allowlist().is_empty() {
+            None
+        } else {
+            std::env::var_os(CODEX_NETWORK_PROXY_SOCKET_ENV_VAR)
+        };
+        match proxy_socket {
+            Some(proxy_socket) => {
+                let addr = start_network_proxy_bridge(Path::new(&proxy_socket))?;
+                export_proxy_env(addr);
+                install_network_seccomp_filter_on_current_thread(NetworkMode::LoopbackOnly)?;
+            }
+            None => install_network_seccomp_filter_on_current_thread(NetworkMode::None)?,
+        }
     }
 
     if !sandbox_policy.has_full_disk_write_access() {
@@ -41,22 +67,28 @@ pub(crate) fn apply_sandbox_policy_to_current_thread(
             .into_iter()
             .map(|writable_root| writable_root.root)
             .collect();
-        install_filesystem_landlock_rules_on_current_thread(writable_roots)?;
+        let readable_roots = if sandbox_policy.has_full_disk_read_access() {
+            vec![PathBuf::from("/")]
+        } else {
+            sandbox_policy.get_readable_roots_with_cwd(cwd)
+        };
+        install_filesystem_landlock_rules_on_current_thread(writable_roots, readable_roots)?;
     }
 
-    // TODO(ragona): Add appropriate restrictions if
-    // `sandbox_policy.has_full_disk_read_access()` is `false`.
-
     Ok(())
 }
 
 /// Installs Landlock file-system rules on the current thread allowing read
-/// access to the entire file-system while restricting write access to
-/// `/dev/null` and the provided list of `writable_roots`.
+/// access to the provided list of `readable_roots` (which is `/` unless read
+/// access is restricted) while restricting write access to `/dev/null` and
+/// the provided list of `writable_roots`.
 ///
 /// # Errors
 /// Returns [`CodexErr::Sandbox`] variants when the ruleset fails to apply.
-fn install_filesystem_landlock_rules_on_current_thread(writable_roots: Vec<PathBuf>) -> Result<()> {
+fn install_filesystem_landlock_rules_on_current_thread(
+    writable_roots: Vec<PathBuf>,
+    readable_roots: Vec<PathBuf>,
+) -> Result<()> {
     let abi = ABI::V5;
     let access_rw = AccessFs::from_all(abi);
     let access_ro = AccessFs::from_read(abi);
@@ -65,7 +97,7 @@ fn install_filesystem_landlock_rules_on_current_thread(writable_roots: Vec<PathB
         .set_compatibility(CompatLevel::BestEffort)
         .handle_access(access_rw)?
         .create()?
-        .add_rules(landlock::path_beneath_rules(&["/"], access_ro))?
+        .add_rules(landlock::path_beneath_rules(&readable_roots, access_ro))?
         .add_rules(landlock::path_beneath_rules(&["/dev/null"], access_rw))?
         .set_no_new_privs(true);
 
@@ -82,9 +114,13 @@ fn install_filesystem_landlock_rules_on_current_thread(writable_roots: Vec<PathB
     Ok(())
 }
 
-/// Installs a seccomp filter that blocks outbound network access except for
-/// AF_UNIX domain sockets.
-fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(), SandboxErr> {
+/// Installs a seccomp filter that blocks outbound network access. With
+/// [`NetworkMode::None`] only AF_UNIX domain sockets may be created, and none
+/// may be connected. With [`NetworkMode::LoopbackOnly`] only IP sockets may be
+/// created: they can reach nothing but the private loopback interface.
+fn install_network_seccomp_filter_on_current_thread(
+    mode: NetworkMode,
+) -> std::result::Result<(), SandboxErr> {
     // Build rule map.
     let mut rules: BTreeMap<i64, Vec<SeccompRule>> = BTreeMap::new();
 
@@ -93,35 +129,63 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
         rules.insert(nr, vec![]); // empty rule vec = unconditional match
     };
 
-    deny_syscall(libc::SYS_connect);
-    deny_syscall(libc::SYS_accept);
-    deny_syscall(libc::SYS_accept4);
-    deny_syscall(libc::SYS_bind);
-    deny_syscall(libc::SYS_listen);
-    deny_syscall(libc::SYS_getpeername);
-    deny_syscall(libc::SYS_getsockname);
-    deny_syscall(libc::SYS_shutdown);
-    deny_syscall(libc::SYS_sendto);
-    deny_syscall(libc::SYS_sendmsg);
-    deny_syscall(libc::SYS_sendmmsg);
-    // NOTE: allowing recvfrom allows some tools like: `cargo clippy` to run
-    // with their socketpair + child processes for sub-proc management
-    // deny_syscall(libc::SYS_recvfrom);
-    deny_syscall(libc::SYS_recvmsg);
-    deny_syscall(libc::SYS_recvmmsg);
-    deny_syscall(libc::SYS_getsockopt);
-    deny_syscall(libc::SYS_setsockopt);
     deny_syscall(libc::SYS_ptrace);
+    match mode {
+        NetworkMode::None => {
+            deny_syscall(libc::SYS_connect);
+            deny_syscall(libc::SYS_accept);
+            deny_syscall(libc::SYS_accept4);
+            deny_syscall(libc::SYS_bind);
+            deny_syscall(libc::SYS_listen);
+            deny_syscall(libc::SYS_getpeername);
+            deny_syscall(libc::SYS_getsockname);
+            deny_syscall(libc::SYS_shutdown);
+            deny_syscall(libc::SYS_sendto);
+            deny_syscall(libc::SYS_sendmsg);
+            deny_syscall(libc::SYS_sendmmsg);
+            // NOTE: allowing recvfrom allows some tools like: `cargo clippy` to run
+            // with their socketpair + child processes for sub-proc management
+            // deny_syscall(libc::SYS_recvfrom);
+            deny_syscall(libc::SYS_recvmsg);
+            deny_syscall(libc::SYS_recvmmsg);
+            deny_syscall(libc::SYS_getsockopt);
+            deny_syscall(libc::SYS_setsockopt);
+        }
+        NetworkMode::LoopbackOnly => {
+            // io_uring can create and connect sockets without going through
+            // the syscalls filtered below.
+            deny_syscall(libc::SYS_io_uring_setup);
+        }
+    }
 
-    // For `socket` we allow AF_UNIX (arg0 == AF_UNIX) and deny everything else.
     let unix_only_rule = SeccompRule::new(vec![SeccompCondition::new(
         0, // first argument (domain)
         SeccompCmpArgLen::Dword,
         SeccompCmpOp::Ne,
         libc::AF_UNIX as u64,
     )?])?;
+    let socket_rule = match mode {
+        // For `socket` we allow AF_UNIX (arg0 == AF_UNIX) and deny everything else.
+        NetworkMode::None => unix_only_rule.clone(),
+        // Unix sockets are shared with the host, so they would let the command
+        // reach any of its services. IP sockets stay in the namespace.
+        NetworkMode::LoopbackOnly => SeccompRule::new(vec![
+            SeccompCondition::new(
+                0,
+                SeccompCmpArgLen::Dword,
+                SeccompCmpOp::Ne,
+                libc::AF_INET as u64,
+            )?,
+            SeccompCondition::new(
+                0,
+                SeccompCmpArgLen::Dword,
+                SeccompCmpOp::Ne,
+                libc::AF_INET6 as u64,
+            )?,
+        ])?,
+    };
 
-    rules.insert(libc::SYS_socket, vec![unix_only_rule.clone()]);
+    rules.insert(libc::SYS_socket, vec![socket_rule]);
     rules.insert(libc::SYS_socketpair, vec![unix_only_rule]); // always deny (Unix can use socketpair but fine, keep open?)
 
     let filter = SeccompFilter::new(
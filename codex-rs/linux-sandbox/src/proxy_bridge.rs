@@ -0,0 +1,189 @@
+//! Network allowlist support for the Linux sandbox.
+//!
+//! Seccomp cannot inspect the address passed to `connect(2)`, so it cannot
+//! limit a command to the network proxy's Unix socket the way Seatbelt does.
+//! Instead, the command runs in fresh user and network namespaces whose only
+//! interface is loopback, and a bridge process forwards every connection made
+//! to a loopback port on to the proxy's socket. The seccomp filter then lets
+//! the command create IP sockets, which cannot leave the namespace, but no
+//! Unix sockets, which can.
+
+use std::io;
+use std::net::Ipv4Addr;
+use std::net::Shutdown;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::CODEX_NETWORK_PROXY_SOCKET_ENV_VAR;
+
+/// Proxy variables pointed at the bridge. Both spellings are set because
+/// tools disagree on which one they read (curl only honors `http_proxy`).
+const PROXY_ENV_VARS: &[&str] = &[
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "all_proxy",
+];
+
+/// Moves the current process into new user and network namespaces and
+/// starts a bridge process that forwards connections to a loopback port on
+/// to the network proxy listening on `proxy_socket`. Returns the address of
+/// that port.
+///
+/// Must be called while the process is still single-threaded.
+pub(crate) fn start_network_proxy_bridge(proxy_socket: &Path) -> io::Result<SocketAddr> {
+    enter_network_namespace()?;
+    bring_up_loopback()?;
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let addr = listener.local_addr()?;
+
+    // SAFETY: getpid has no preconditions.
+    let parent = unsafe { libc::getpid() };
+    // SAFETY: the process is single-threaded (see above), so the child gets a
+    // consistent copy of its memory.
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => {
+            // The parent goes on to exec the command: take the bridge down
+            // with it so it never outlives the command.
+            // SAFETY: plain system calls on the current process.
+            unsafe {
+                libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+                if libc::getppid() != parent {
+                    libc::_exit(0);
+                }
+            }
+            // Do not hold on to the command's stdout/stderr, or whoever reads
+            // them would wait for the bridge too.
+            detach_stdio();
+            run_bridge(listener, proxy_socket.to_path_buf());
+            // SAFETY: _exit has no preconditions; it skips the parent's
+            // atexit handlers, which the child must not run.
+            unsafe { libc::_exit(0) }
+        }
+        _ => Ok(addr),
+    }
+}
+
+/// Points the usual proxy environment variables at the bridge listening on
+/// `addr`. The proxy's socket cannot be used from inside the sandbox, so its
+/// variable is removed.
+pub(crate) fn export_proxy_env(addr: SocketAddr) {
+    let proxy_url = format!("http://{addr}");
+    // SAFETY: the sandbox helper is single-threaded until it execs the
+    // command.
+    unsafe {
+        for var in PROXY_ENV_VARS {
+            std::env::set_var(var, &proxy_url);
+        }
+        std::env::remove_var(CODEX_NETWORK_PROXY_SOCKET_ENV_VAR);
+    }
+}
+
+fn enter_network_namespace() -> io::Result<()> {
+    // SAFETY: getuid and getgid have no preconditions.
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    // SAFETY: unshare only changes the namespaces of the current process,
+    // which is still single-threaded as CLONE_NEWUSER requires.
+    if unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) } != 0 {
+        let err = io::Error::last_os_error();
+        return Err(io::Error::new(
+            err.kind(),
+            format!(
+                "failed to create user and network namespaces: {err}; \
+                 the network allowlist requires unprivileged user namespaces"
+            ),
+        ));
+    }
+    // Map the caller's ids into the new user namespace so the command keeps
+    // its identity, and the ownership of the files it creates, in there.
+    std::fs::write("/proc/self/setgroups", "deny")?;
+    std::fs::write("/proc/self/uid_map", format!("{uid} {uid} 1"))?;
+    std::fs::write("/proc/self/gid_map", format!("{gid} {gid} 1"))?;
+    Ok(())
+}
+
+/// The loopback interface of a new network namespace starts out down.
+fn bring_up_loopback() -> io::Result<()> {
+    // SAFETY: socket has no preconditions.
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `fd` is a freshly opened descriptor that nothing else owns.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    // SAFETY: ifreq is plain old data, for which all zeroes is valid.
+    let mut ifr: libc::ifreq = unsafe { std::mem::zeroed() };
+    for (dst, src) in ifr.ifr_name.iter_mut().zip(b"lo") {
+        *dst = *src as libc::c_char;
+    }
+    // SAFETY: SIOCGIFFLAGS fills in the flags of the `ifr` it is passed.
+    if unsafe { libc::ioctl(fd.as_raw_fd(), libc::SIOCGIFFLAGS as _, &mut ifr) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: SIOCGIFFLAGS initialized the flags member of the union.
+    unsafe {
+        ifr.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short;
+    }
+    // SAFETY: SIOCSIFFLAGS only reads the `ifr` it is passed.
+    if unsafe { libc::ioctl(fd.as_raw_fd(), libc::SIOCSIFFLAGS as _, &ifr) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn detach_stdio() {
+    // SAFETY: the path is a valid NUL-terminated string.
+    let dev_null = unsafe { libc::open(c"/dev/null".as_ptr(), libc::O_RDWR) };
+    if dev_null < 0 {
+        return;
+    }
+    // SAFETY: `dev_null` is open, and the bridge never uses the standard
+    // streams it replaces.
+    unsafe {
+        for fd in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+            libc::dup2(dev_null, fd);
+        }
+        libc::close(dev_null);
+    }
+}
+
+fn run_bridge(listener: TcpListener, proxy_socket: PathBuf) {
+    for client in listener.incoming() {
+        let Ok(client) = client else {
+            continue;
+        };
+        let proxy_socket = proxy_socket.clone();
+        std::thread::spawn(move || {
+            if let Ok(proxy) = UnixStream::connect(&proxy_socket) {
+                let _ = splice(client, proxy);
+            }
+        });
+    }
+}
+
+/// Copies bytes both ways until each side has shut down its half.
+fn splice(client: TcpStream, proxy: UnixStream) -> io::Result<()> {
+    let mut client_reader = client.try_clone()?;
+    let mut proxy_writer = proxy.try_clone()?;
+    let upload = std::thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut proxy_writer);
+        let _ = proxy_writer.shutdown(Shutdown::Write);
+    });
+
+    let (mut proxy_reader, mut client_writer) = (proxy, client);
+    let _ = io::copy(&mut proxy_reader, &mut client_writer);
+    let _ = client_writer.shutdown(Shutdown::Write);
+    let _ = upload.join();
+    Ok(())
+}
@@ -1,13 +1,20 @@
 #![cfg(target_os = "linux")]
+use codex_core::CODEX_NETWORK_PROXY_SOCKET_ENV_VAR;
 use codex_core::config_types::ShellEnvironmentPolicy;
 use codex_core::error::CodexErr;
 use codex_core::error::SandboxErr;
 use codex_core::exec::ExecParams;
+use codex_core::exec::ExecToolCallOutput;
 use codex_core::exec::SandboxType;
 use codex_core::exec::process_exec_tool_call;
 use codex_core::exec_env::create_env;
 use codex_core::protocol::SandboxPolicy;
 use std::collections::HashMap;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::unix::net::UnixListener;
+use std::path::Path;
 use std::path::PathBuf;
 use tempfile::NamedTempFile;
 
@@ -33,8 +40,17 @@ fn create_env_from_core_vars() -> HashMap<String, String> {
     create_env(&policy)
 }
 
-#[expect(clippy::print_stdout, clippy::expect_used, clippy::unwrap_used)]
 async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
+    run_cmd_with_readable_roots(cmd, writable_roots, None, timeout_ms).await;
+}
+
+#[expect(clippy::print_stdout, clippy::expect_used, clippy::unwrap_used)]
+async fn run_cmd_with_readable_roots(
+    cmd: &[&str],
+    writable_roots: &[PathBuf],
+    readable_roots: Option<Vec<PathBuf>>,
+    timeout_ms: u64,
+) {
     let params = ExecParams {
         command: cmd.iter().map(|elm| elm.to_string()).collect(),
         cwd: std::env::current_dir().expect("cwd should exist"),
@@ -46,7 +62,9 @@ async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
 
     let sandbox_policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: writable_roots.to_vec(),
+        readable_roots,
         network_access: false,
+        network_allowlist: vec![],
         // Exclude tmp-related folders from writable roots because we need a
         // folder that is writable by tests but that we intentionally disallow
         // writing to in the sandbox.
@@ -120,6 +138,28 @@ async fn test_writable_root() {
     .await;
 }
 
+#[tokio::test]
+#[should_panic]
+async fn test_restricted_read_outside_readable_roots() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let tmpfile_path = tmpfile.path().to_string_lossy();
+    run_cmd_with_readable_roots(&["cat", &tmpfile_path], &[], Some(vec![]), LONG_TIMEOUT_MS).await;
+}
+
+#[tokio::test]
+async fn test_restricted_read_inside_readable_roots() {
+    let tmpfile = NamedTempFile::new().unwrap();
+    let tmpfile_path = tmpfile.path().to_string_lossy();
+    let tmpdir = tmpfile.path().parent().unwrap().to_path_buf();
+    run_cmd_with_readable_roots(
+        &["cat", &tmpfile_path],
+        &[],
+        Some(vec![tmpdir]),
+        LONG_TIMEOUT_MS,
+    )
+    .await;
+}
+
 #[tokio::test]
 #[should_panic(expected = "Sandbox(Timeout)")]
 async fn test_timeout() {
@@ -228,3 +268,95 @@ async fn sandbox_blocks_dev_tcp_redirection() {
     // all images ship bash, so we guard against 127 as well.
     assert_network_blocked(&["bash", "-c", "echo hi > /dev/tcp/127.0.0.1/80"]).await;
 }
+
+/// Runs `cmd` without network access except through the network proxy,
+/// whose socket is at `proxy_socket`.
+#[expect(clippy::expect_used)]
+async fn run_cmd_with_network_allowlist(
+    cmd: &[&str],
+    proxy_socket: &Path,
+) -> codex_core::error::Result<ExecToolCallOutput> {
+    let mut env = create_env_from_core_vars();
+    env.insert(
+        CODEX_NETWORK_PROXY_SOCKET_ENV_VAR.to_string(),
+        proxy_socket.to_string_lossy().into_owned(),
+    );
+    let params = ExecParams {
+        command: cmd.iter().map(|s| s.to_string()).collect(),
+        cwd: std::env::current_dir().expect("cwd should exist"),
+        timeout_ms: Some(NETWORK_TIMEOUT_MS),
+        env,
+        with_escalated_permissions: None,
+        justification: None,
+    };
+
+    let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+        writable_roots: vec![],
+        readable_roots: None,
+        network_access: false,
+        network_allowlist: vec!["crates.io".to_string()],
+        exclude_tmpdir_env_var: true,
+        exclude_slash_tmp: true,
+    };
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let codex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
+    process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        &sandbox_policy,
+        &codex_linux_sandbox_exe,
+        None,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn sandbox_forwards_proxy_env_to_network_proxy() {
+    let socket_dir = tempfile::tempdir().unwrap();
+    let socket_path = socket_dir.path().join("proxy.sock");
+    let listener = UnixListener::bind(&socket_path).unwrap();
+    let proxy = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).unwrap();
+        stream.write_all(format!("got {line}").as_bytes()).unwrap();
+    });
+
+    let output = run_cmd_with_network_allowlist(
+        &[
+            "bash",
+            "-c",
+            r#"exec 3<>"/dev/tcp/127.0.0.1/${https_proxy##*:}"; echo ping >&3; read -r reply <&3; echo "$reply""#,
+        ],
+        &socket_path,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(0, output.exit_code, "{}", output.stderr.text);
+    assert_eq!("got ping\n", output.stdout.text);
+    proxy.join().unwrap();
+}
+
+#[tokio::test]
+async fn sandbox_with_network_allowlist_blocks_other_unix_sockets() {
+    let socket_dir = tempfile::tempdir().unwrap();
+    let proxy_socket = socket_dir.path().join("proxy.sock");
+    let _proxy = UnixListener::bind(&proxy_socket).unwrap();
+    let other_socket = socket_dir.path().join("other.sock");
+    let _other = UnixListener::bind(&other_socket).unwrap();
+
+    let script = format!(
+        "import socket; socket.socket(socket.AF_UNIX).connect({:?})",
+        other_socket.to_string_lossy()
+    );
+    let result = run_cmd_with_network_allowlist(&["python3", "-c", &script], &proxy_socket).await;
+
+    // A missing `python3` also exits non-zero, which keeps lean images green.
+    let exit_code = match result {
+        Ok(output) => output.exit_code,
+        Err(CodexErr::Sandbox(SandboxErr::Denied(exit_code, _, _))) => exit_code,
+        Err(e) => panic!("unexpected error: {e:?}"),
+    };
+    assert_ne!(0, exit_code);
+}
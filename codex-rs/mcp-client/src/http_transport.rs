@@ -0,0 +1,499 @@
+//! HTTP transports for [`McpClient`].
+//!
+//! With Streamable HTTP (MCP 2025-03-26 and later) every JSON-RPC message is
+//! `POST`ed to the server URL. The server answers a request either with a
+//! single JSON body or with an SSE stream that carries the response (plus any
+//! messages it sends before it), and answers notifications with
+//! `202 Accepted`.
+//!
+//! With the legacy HTTP+SSE transport (MCP 2024-11-05) the client first opens
+//! an SSE stream with a `GET`. The server announces the URL to `POST` messages
+//! to in an `endpoint` event and delivers all of its messages on that stream.
+//! As the spec recommends for backwards compatibility, we switch to it when
+//! the very first `POST` is rejected with a 4xx status.
+//!
+//! A Streamable HTTP server may also offer a stream, opened with a `GET`, for
+//! the messages it sends outside of the reply to a request (such as
+//! `notifications/tools/list_changed`). We open it once the session is
+//! initialized and reconnect when it ends. Losing the legacy stream, or the
+//! Streamable HTTP one for good, closes the client.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::time::Duration;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use eventsource_stream::Event;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use mcp_types::InitializeRequest;
+use mcp_types::JSONRPC_VERSION;
+use mcp_types::JSONRPCError;
+use mcp_types::JSONRPCErrorError;
+use mcp_types::JSONRPCMessage;
+use mcp_types::ModelContextProtocolRequest;
+use mcp_types::RequestId;
+use reqwest::StatusCode;
+use reqwest::Url;
+use reqwest::header::ACCEPT;
+use reqwest::header::AUTHORIZATION;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderName;
+use reqwest::header::HeaderValue;
+use tokio::sync::mpsc;
+use tracing::debug;
+use tracing::error;
+use tracing::info;
+use tracing::warn;
+
+use crate::McpClient;
+use crate::mcp_client::Inbox;
+
+const MCP_SESSION_ID_HEADER: &str = "mcp-session-id";
+const MCP_PROTOCOL_VERSION_HEADER: &str = "mcp-protocol-version";
+
+/// JSON-RPC "Internal error", reported to the caller when a request could not
+/// be delivered.
+const INTERNAL_ERROR_CODE: i64 = -32603;
+
+/// Delay before the first attempt to reopen the stream of server messages;
+/// doubled after every failed attempt.
+const STREAM_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Failed attempts to reopen the stream of server messages after which the
+/// connection is considered lost.
+const MAX_STREAM_RECONNECT_ATTEMPTS: u32 = 5;
+
+enum Mode {
+    /// Nothing has been sent yet.
+    Unknown,
+    StreamableHttp,
+    /// Messages are `POST`ed to `endpoint` and every reply arrives on the SSE
+    /// stream opened during the fallback.
+    LegacySse {
+        endpoint: Url,
+    },
+}
+
+/// Writer side of an HTTP connection to an MCP server. Replies are handed to
+/// [`McpClient::dispatch_message`] so requests are correlated exactly as they
+/// are for stdio servers.
+pub(crate) struct HttpTransport {
+    client: reqwest::Client,
+    url: Url,
+
+    /// Headers added to every request (including `Authorization`).
+    headers: HeaderMap,
+
+    /// Assigned by the server in its reply to `initialize`.
+    session_id: Option<HeaderValue>,
+
+    /// Negotiated during `initialize`.
+    protocol_version: Option<HeaderValue>,
+
+    mode: Mode,
+}
+
+impl HttpTransport {
+    pub(crate) fn new(
+        url: &str,
+        headers: HashMap<String, String>,
+        bearer_token: Option<String>,
+    ) -> Result<Self> {
+        let url = Url::parse(url).with_context(|| format!("invalid MCP server URL `{url}`"))?;
+
+        let mut header_map = HeaderMap::new();
+        for (name, value) in headers {
+            let name = HeaderName::try_from(name.as_str())
+                .with_context(|| format!("invalid header name `{name}`"))?;
+            let value = HeaderValue::try_from(value)
+                .with_context(|| format!("invalid value for header `{name}`"))?;
+            header_map.insert(name, value);
+        }
+        if let Some(token) = bearer_token {
+            let mut value =
+                HeaderValue::try_from(format!("Bearer {token}")).context("invalid bearer token")?;
+            value.set_sensitive(true);
+            header_map.insert(AUTHORIZATION, value);
+        }
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            url,
+            headers: header_map,
+            session_id: None,
+            protocol_version: None,
+            mode: Mode::Unknown,
+        })
+    }
+
+    /// Sends every message received on `outgoing_rx` until the channel is
+    /// closed, then ends the session.
+    pub(crate) async fn run(
+        mut self,
+        mut outgoing_rx: mpsc::Receiver<JSONRPCMessage>,
+        inbox: Inbox,
+    ) {
+        while let Some(msg) = outgoing_rx.recv().await {
+            // Once the session is set up, every request is answered in its
+            // own task: a slow tool call must not hold up other requests.
+            if let (Mode::StreamableHttp, JSONRPCMessage::Request(_)) = (&self.mode, &msg) {
+                debug!(
+                    "MCP message to server: {}",
+                    serde_json::to_string(&msg).unwrap_or_default()
+                );
+                let request = self.request(self.url.clone(), &msg);
+                let url = self.url.clone();
+                let inbox = inbox.clone();
+                tokio::spawn(async move {
+                    let result = async {
+                        let resp = error_for_status(request.send().await?).await?;
+                        dispatch_reply(resp, &inbox, None).await
+                    }
+                    .await;
+                    if let Err(e) = result {
+                        fail_request(&url, msg, e, &inbox).await;
+                    }
+                });
+                continue;
+            }
+
+            if let Err(e) = self.send(&msg, &inbox).await {
+                fail_request(&self.url, msg, e, &inbox).await;
+            }
+        }
+
+        self.terminate_session().await;
+        // Also stops the task that listens for server messages.
+        inbox.close().await;
+    }
+
+    async fn send(&mut self, msg: &JSONRPCMessage, inbox: &Inbox) -> Result<()> {
+        debug!("MCP message to server: {}", serde_json::to_string(msg)?);
+
+        if let Mode::LegacySse { endpoint } = &self.mode {
+            let resp = self.post(endpoint.clone(), msg).await?;
+            error_for_status(resp).await?;
+            return Ok(());
+        }
+
+        let resp = self.post(self.url.clone(), msg).await?;
+        if let Mode::Unknown = self.mode {
+            let status = resp.status();
+            if status.is_client_error()
+                && status != StatusCode::UNAUTHORIZED
+                && status != StatusCode::FORBIDDEN
+            {
+                info!(
+                    "MCP server at {} rejected POST with {status}; falling back to HTTP+SSE",
+                    self.url
+                );
+                let endpoint = self.connect_legacy_sse(inbox).await?;
+                let resp = self.post(endpoint.clone(), msg).await?;
+                error_for_status(resp).await?;
+                self.mode = Mode::LegacySse { endpoint };
+                return Ok(());
+            }
+            self.mode = Mode::StreamableHttp;
+        }
+
+        if let Some(session_id) = resp.headers().get(MCP_SESSION_ID_HEADER) {
+            self.session_id = Some(session_id.clone());
+        }
+        let resp = error_for_status(resp).await?;
+
+        // The reply to `initialize` is read before anything else is sent so
+        // the negotiated protocol version can be attached to later requests.
+        let initialize_id = match msg {
+            JSONRPCMessage::Request(request) if request.method == InitializeRequest::METHOD => {
+                Some(&request.id)
+            }
+            _ => None,
+        };
+        let version = dispatch_reply(resp, inbox, initialize_id).await?;
+        if initialize_id.is_some() {
+            self.set_protocol_version(version);
+            self.listen_for_server_messages(inbox);
+        }
+        Ok(())
+    }
+
+    async fn post(&self, url: Url, msg: &JSONRPCMessage) -> Result<reqwest::Response> {
+        Ok(self.request(url, msg).send().await?)
+    }
+
+    fn request(&self, url: Url, msg: &JSONRPCMessage) -> reqwest::RequestBuilder {
+        let mut builder = self
+            .client
+            .post(url)
+            .headers(self.headers.clone())
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(msg);
+        if let Some(session_id) = &self.session_id {
+            builder = builder.header(MCP_SESSION_ID_HEADER, session_id.clone());
+        }
+        if let Some(protocol_version) = &self.protocol_version {
+            builder = builder.header(MCP_PROTOCOL_VERSION_HEADER, protocol_version.clone());
+        }
+        builder
+    }
+
+    /// Opens the server-to-client SSE stream of the legacy transport and
+    /// returns the endpoint announced on it. Messages that follow are
+    /// dispatched from a background task for as long as the stream is open.
+    async fn connect_legacy_sse(&self, inbox: &Inbox) -> Result<Url> {
+        let resp = self
+            .client
+            .get(self.url.clone())
+            .headers(self.headers.clone())
+            .header(ACCEPT, "text/event-stream")
+            .send()
+            .await?;
+        let resp = error_for_status(resp).await?;
+
+        let mut events = resp.bytes_stream().eventsource();
+        let endpoint = loop {
+            match events.next().await {
+                Some(Ok(event)) if event.event == "endpoint" => {
+                    break self.url.join(event.data.trim()).with_context(|| {
+                        format!("invalid endpoint `{}` announced by MCP server", event.data)
+                    })?;
+                }
+                Some(Ok(event)) => debug!("ignoring SSE event before endpoint: {event:?}"),
+                Some(Err(e)) => return Err(anyhow!("failed to read SSE stream: {e}")),
+                None => return Err(anyhow!("SSE stream closed before the endpoint event")),
+            }
+        };
+
+        let inbox = inbox.clone();
+        let url = self.url.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = inbox.closed() => {}
+                _ = forward_sse_messages(events, &inbox, None) => {
+                    // Replies to pending and future requests can only arrive
+                    // on this stream.
+                    warn!("SSE stream from MCP server at {url} closed");
+                    inbox.close().await;
+                }
+            }
+        });
+
+        Ok(endpoint)
+    }
+
+    /// Opens the optional stream of server messages of a Streamable HTTP
+    /// session in a background task that runs until the client is closed.
+    fn listen_for_server_messages(&self, inbox: &Inbox) {
+        let mut request = self
+            .client
+            .get(self.url.clone())
+            .headers(self.headers.clone())
+            .header(ACCEPT, "text/event-stream");
+        if let Some(session_id) = &self.session_id {
+            request = request.header(MCP_SESSION_ID_HEADER, session_id.clone());
+        }
+        if let Some(protocol_version) = &self.protocol_version {
+            request = request.header(MCP_PROTOCOL_VERSION_HEADER, protocol_version.clone());
+        }
+
+        let inbox = inbox.clone();
+        let url = self.url.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = inbox.closed() => {}
+                _ = forward_server_messages(request, &url, &inbox) => {}
+            }
+        });
+    }
+
+    fn set_protocol_version(&mut self, version: Option<String>) {
+        self.protocol_version = version.and_then(|v| HeaderValue::try_from(v).ok());
+    }
+
+    /// Tells the server the session is over, as the spec asks clients to.
+    async fn terminate_session(&self) {
+        let (Mode::StreamableHttp, Some(session_id)) = (&self.mode, &self.session_id) else {
+            return;
+        };
+        let res = self
+            .client
+            .delete(self.url.clone())
+            .headers(self.headers.clone())
+            .header(MCP_SESSION_ID_HEADER, session_id.clone())
+            .send()
+            .await;
+        if let Err(e) = res {
+            debug!("failed to terminate MCP session: {e}");
+        }
+    }
+}
+
+/// Keeps the stream of server messages open, reconnecting whenever it ends.
+/// Returns if the server does not offer one; closes `inbox` if the session is
+/// gone or the stream cannot be reopened.
+async fn forward_server_messages(request: reqwest::RequestBuilder, url: &Url, inbox: &Inbox) {
+    let mut failures = 0;
+    loop {
+        let Some(attempt) = request.try_clone() else {
+            return;
+        };
+        match attempt.send().await {
+            Ok(resp) if resp.status() == StatusCode::METHOD_NOT_ALLOWED => {
+                debug!("MCP server at {url} offers no stream for server messages");
+                return;
+            }
+            Ok(resp) if resp.status() == StatusCode::NOT_FOUND => {
+                warn!("MCP server at {url} ended the session");
+                inbox.close().await;
+                return;
+            }
+            Ok(resp) if resp.status().is_success() => {
+                failures = 0;
+                forward_sse_messages(resp.bytes_stream().eventsource(), inbox, None).await;
+                debug!("stream of server messages from MCP server at {url} ended");
+            }
+            Ok(resp) => {
+                failures += 1;
+                warn!(
+                    "MCP server at {url} returned {} for the stream of server messages",
+                    resp.status()
+                );
+            }
+            Err(e) => {
+                failures += 1;
+                warn!("failed to open the stream of server messages from MCP server at {url}: {e}");
+            }
+        }
+        if failures >= MAX_STREAM_RECONNECT_ATTEMPTS {
+            warn!("lost the connection to MCP server at {url}");
+            inbox.close().await;
+            return;
+        }
+        tokio::time::sleep(STREAM_RECONNECT_DELAY * 2u32.pow(failures)).await;
+    }
+}
+
+/// Fails `msg`, if it is a request, right away rather than letting the caller
+/// wait for a reply that will never come.
+async fn fail_request(url: &Url, msg: JSONRPCMessage, e: anyhow::Error, inbox: &Inbox) {
+    error!("failed to send message to MCP server at {url}: {e:#}");
+    if let JSONRPCMessage::Request(request) = msg {
+        let error = JSONRPCError {
+            id: request.id,
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            error: JSONRPCErrorError {
+                code: INTERNAL_ERROR_CODE,
+                data: None,
+                message: format!("{e:#}"),
+            },
+        };
+        McpClient::dispatch_message(JSONRPCMessage::Error(error), inbox).await;
+    }
+}
+
+/// Dispatches the reply to a Streamable HTTP `POST`: nothing for
+/// `202 Accepted`, otherwise a single JSON body or an SSE stream. Returns the
+/// protocol version from the reply to `until` if it is an `InitializeResult`.
+async fn dispatch_reply(
+    resp: reqwest::Response,
+    inbox: &Inbox,
+    until: Option<&RequestId>,
+) -> Result<Option<String>> {
+    if resp.status() == StatusCode::ACCEPTED {
+        return Ok(None);
+    }
+
+    let content_type = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    if content_type.starts_with("text/event-stream") {
+        let events = resp.bytes_stream().eventsource();
+        Ok(forward_sse_messages(events, inbox, until).await)
+    } else if content_type.starts_with("application/json") {
+        let reply = resp.json::<JSONRPCMessage>().await?;
+        let version = negotiated_protocol_version(&reply);
+        McpClient::dispatch_message(reply, inbox).await;
+        Ok(version)
+    } else {
+        warn!("ignoring MCP server reply with content type `{content_type}`");
+        Ok(None)
+    }
+}
+
+/// Dispatches the JSON-RPC messages carried by an SSE stream until it ends or
+/// until the reply to `until` has been dispatched. Returns the protocol
+/// version from that reply if it is an `InitializeResult`.
+async fn forward_sse_messages<S, E>(
+    mut events: S,
+    inbox: &Inbox,
+    until: Option<&RequestId>,
+) -> Option<String>
+where
+    S: Stream<Item = std::result::Result<Event, E>> + Unpin,
+    E: Display,
+{
+    while let Some(event) = events.next().await {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                warn!("failed to read SSE stream from MCP server: {e}");
+                break;
+            }
+        };
+        if !event.event.is_empty() && event.event != "message" {
+            debug!("ignoring SSE event: {event:?}");
+            continue;
+        }
+        debug!("MCP message from server: {}", event.data);
+
+        let msg = match serde_json::from_str::<JSONRPCMessage>(&event.data) {
+            Ok(msg) => msg,
+            Err(e) => {
+                error!(
+                    "failed to deserialize JSONRPCMessage: {e}; data = {}",
+                    event.data
+                );
+                continue;
+            }
+        };
+        let reply_id = match &msg {
+            JSONRPCMessage::Response(resp) => Some(resp.id.clone()),
+            JSONRPCMessage::Error(err) => Some(err.id.clone()),
+            _ => None,
+        };
+        let version = negotiated_protocol_version(&msg);
+        McpClient::dispatch_message(msg, inbox).await;
+        if until.is_some() && reply_id.as_ref() == until {
+            return version;
+        }
+    }
+    None
+}
+
+fn negotiated_protocol_version(msg: &JSONRPCMessage) -> Option<String> {
+    match msg {
+        JSONRPCMessage::Response(resp) => resp
+            .result
+            .get("protocolVersion")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        _ => None,
+    }
+}
+
+async fn error_for_status(resp: reqwest::Response) -> Result<reqwest::Response> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let body = resp.text().await.unwrap_or_default();
+    Err(anyhow!("MCP server returned {status}: {body}"))
+}
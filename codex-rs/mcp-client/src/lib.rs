@@ -1,3 +1,4 @@
+mod http_transport;
 mod mcp_client;
 
 pub use mcp_client::McpClient;
@@ -7,8 +7,9 @@
 //! ```
 //!
 //! Any additional arguments after the first one are forwarded to the spawned
-//! program. The utility connects, issues a `tools/list` request and prints the
-//! server's response as pretty JSON.
+//! program. If the first argument is an `http://` or `https://` URL, the
+//! utility connects to the server over HTTP instead. It then issues a
+//! `tools/list` request and prints the server's response as pretty JSON.
 
 use std::ffi::OsString;
 use std::time::Duration;
@@ -41,17 +42,28 @@ async fn main() -> Result<()> {
     let mut args: Vec<OsString> = std::env::args_os().skip(1).collect();
 
     if args.is_empty() || args[0] == "--help" || args[0] == "-h" {
-        eprintln!("Usage: mcp-client <program> [args..]\n\nExample: mcp-client codex-mcp-server");
+        eprintln!(
+            "Usage: mcp-client <program> [args..]\n       mcp-client <url>\n\nExample: mcp-client codex-mcp-server"
+        );
         std::process::exit(1);
     }
     let original_args = args.clone();
 
-    // Spawn the subprocess and connect the client.
-    let program = args.remove(0);
-    let env = None;
-    let client = McpClient::new_stdio_client(program, args, env)
-        .await
-        .with_context(|| format!("failed to spawn subprocess: {original_args:?}"))?;
+    let client = match args[0].to_str() {
+        Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+            McpClient::new_streamable_http_client(url.to_string(), Default::default(), None)
+                .await
+                .with_context(|| format!("failed to connect to {url}"))?
+        }
+        _ => {
+            // Spawn the subprocess and connect the client.
+            let program = args.remove(0);
+            let env = None;
+            McpClient::new_stdio_client(program, args, env)
+                .await
+                .with_context(|| format!("failed to spawn subprocess: {original_args:?}"))?
+        }
+    };
 
     let params = InitializeRequestParams {
         capabilities: ClientCapabilities {
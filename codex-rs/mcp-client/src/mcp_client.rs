@@ -2,10 +2,13 @@
 //!
 //! The client is intentionally lightweight – it is only capable of:
 //!   1. Spawning a subprocess that launches a conforming MCP server that
-//!      communicates over stdio.
+//!      communicates over stdio, or connecting to a remote server over
+//!      Streamable HTTP (falling back to the legacy HTTP+SSE transport).
 //!   2. Sending MCP requests and pairing them with their corresponding
 //!      responses.
 //!   3. Offering a convenience helper for the common `tools/list` request.
+//!   4. Reporting server notifications and the exit of a stdio server so the
+//!      caller can react to them.
 //!
 //! The crate hides all JSON‐RPC framing details behind a typed API. Users
 //! interact with the [`ModelContextProtocolRequest`] trait from `mcp-types` to
@@ -23,6 +26,9 @@ use anyhow::Result;
 use anyhow::anyhow;
 use mcp_types::CallToolRequest;
 use mcp_types::CallToolRequestParams;
+use mcp_types::GetPromptRequest;
+use mcp_types::GetPromptRequestParams;
+use mcp_types::GetPromptResult;
 use mcp_types::InitializeRequest;
 use mcp_types::InitializeRequestParams;
 use mcp_types::InitializedNotification;
@@ -31,11 +37,20 @@ use mcp_types::JSONRPCMessage;
 use mcp_types::JSONRPCNotification;
 use mcp_types::JSONRPCRequest;
 use mcp_types::JSONRPCResponse;
+use mcp_types::ListPromptsRequest;
+use mcp_types::ListPromptsRequestParams;
+use mcp_types::ListPromptsResult;
+use mcp_types::ListResourcesRequest;
+use mcp_types::ListResourcesRequestParams;
+use mcp_types::ListResourcesResult;
 use mcp_types::ListToolsRequest;
 use mcp_types::ListToolsRequestParams;
 use mcp_types::ListToolsResult;
 use mcp_types::ModelContextProtocolNotification;
 use mcp_types::ModelContextProtocolRequest;
+use mcp_types::ReadResourceRequest;
+use mcp_types::ReadResourceRequestParams;
+use mcp_types::ReadResourceResult;
 use mcp_types::RequestId;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
@@ -44,14 +59,18 @@ use tokio::io::AsyncWriteExt;
 use tokio::io::BufReader;
 use tokio::process::Command;
 use tokio::sync::Mutex;
+use tokio::sync::broadcast;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
+use tokio::sync::watch;
 use tokio::time;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
 use tracing::warn;
 
+use crate::http_transport::HttpTransport;
+
 /// Capacity of the bounded channels used for transporting messages between the
 /// client API and the IO tasks.
 const CHANNEL_CAPACITY: usize = 128;
@@ -59,20 +78,64 @@ const CHANNEL_CAPACITY: usize = 128;
 /// Internal representation of a pending request sender.
 type PendingSender = oneshot::Sender<JSONRPCMessage>;
 
+/// Map of `request.id -> oneshot::Sender` shared with the transport tasks.
+pub(crate) type PendingMap = Arc<Mutex<HashMap<i64, PendingSender>>>;
+
+/// Destinations of the messages received from the server, shared with the
+/// transport tasks.
+#[derive(Clone)]
+pub(crate) struct Inbox {
+    /// Map of `request.id -> oneshot::Sender` used to dispatch responses back
+    /// to the originating caller.
+    pending: PendingMap,
+
+    /// Server-initiated notifications, for every subscriber.
+    notifications: broadcast::Sender<JSONRPCNotification>,
+
+    /// Set once the connection to the server is gone for good.
+    closed: Arc<watch::Sender<bool>>,
+}
+
+impl Inbox {
+    fn new() -> Self {
+        let (notifications, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            notifications,
+            closed: Arc::new(watch::Sender::new(false)),
+        }
+    }
+
+    /// Marks the connection as closed and fails every pending request.
+    pub(crate) async fn close(&self) {
+        // Set the flag under the lock so that `send_request` cannot register
+        // a request after the map was cleared.
+        let mut pending = self.pending.lock().await;
+        self.closed.send_replace(true);
+        // Dropping the senders wakes up the callers with an error.
+        pending.clear();
+    }
+
+    /// Completes once [`close`](Self::close) has been called.
+    pub(crate) async fn closed(&self) {
+        let mut closed = self.closed.subscribe();
+        let _ = closed.wait_for(|closed| *closed).await;
+    }
+}
+
 /// A running MCP client instance.
 pub struct McpClient {
-    /// Retain this child process until the client is dropped. The Tokio runtime
-    /// will make a "best effort" to reap the process after it exits, but it is
-    /// not a guarantee. See the `kill_on_drop` documentation for details.
+    /// Dropping this sender kills the server process, which is owned by the
+    /// task that waits for it to exit.
+    ///
+    /// `None` for servers reached over HTTP.
     #[allow(dead_code)]
-    child: tokio::process::Child,
+    kill_tx: Option<oneshot::Sender<()>>,
 
     /// Channel for sending JSON-RPC messages *to* the background writer task.
     outgoing_tx: mpsc::Sender<JSONRPCMessage>,
 
-    /// Map of `request.id -> oneshot::Sender` used to dispatch responses back
-    /// to the originating caller.
-    pending: Arc<Mutex<HashMap<i64, PendingSender>>>,
+    inbox: Inbox,
 
     /// Monotonically increasing counter used to generate request IDs.
     id_counter: AtomicI64,
@@ -82,18 +145,22 @@ impl McpClient {
     /// Spawn the given command and establish an MCP session over its STDIO.
     /// Caller is responsible for sending the `initialize` request. See
     /// [`initialize`](Self::initialize) for details.
+    ///
+    /// The server's stderr is written to the log, and the client is
+    /// [closed](Self::closed) when the server exits.
     pub async fn new_stdio_client(
         program: OsString,
         args: Vec<OsString>,
         env: Option<HashMap<String, String>>,
     ) -> std::io::Result<Self> {
+        let program_name = program.to_string_lossy().into_owned();
         let mut child = Command::new(program)
             .args(args)
             .env_clear()
             .envs(create_env_for_mcp_server(env))
             .stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::null())
+            .stderr(std::process::Stdio::piped())
             // As noted in the `kill_on_drop` documentation, the Tokio runtime makes
             // a "best effort" to reap-after-exit to avoid zombie processes, but it
             // is not a guarantee.
@@ -108,9 +175,13 @@ impl McpClient {
             .stdout
             .take()
             .ok_or_else(|| std::io::Error::other("failed to capture child stdout"))?;
+        let stderr = child
+            .stderr
+            .take()
+            .ok_or_else(|| std::io::Error::other("failed to capture child stderr"))?;
 
         let (outgoing_tx, mut outgoing_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
-        let pending: Arc<Mutex<HashMap<i64, PendingSender>>> = Arc::new(Mutex::new(HashMap::new()));
+        let inbox = Inbox::new();
 
         // Spawn writer task. It listens on the `outgoing_rx` channel and
         // writes messages to the child's STDIN.
@@ -143,28 +214,14 @@ impl McpClient {
         // Spawn reader task. It reads line-delimited JSON from the child's
         // STDOUT and dispatches responses to the pending map.
         let reader_handle = {
-            let pending = pending.clone();
+            let inbox = inbox.clone();
             let mut lines = BufReader::new(stdout).lines();
 
             tokio::spawn(async move {
                 while let Ok(Some(line)) = lines.next_line().await {
                     debug!("MCP message from server: {line}");
                     match serde_json::from_str::<JSONRPCMessage>(&line) {
-                        Ok(JSONRPCMessage::Response(resp)) => {
-                            Self::dispatch_response(resp, &pending).await;
-                        }
-                        Ok(JSONRPCMessage::Error(err)) => {
-                            Self::dispatch_error(err, &pending).await;
-                        }
-                        Ok(JSONRPCMessage::Notification(JSONRPCNotification { .. })) => {
-                            // For now we only log server-initiated notifications.
-                            info!("<- notification: {}", line);
-                        }
-                        Ok(other) => {
-                            // Batch responses and requests are currently not
-                            // expected from the server – log and ignore.
-                            info!("<- unhandled message: {:?}", other);
-                        }
+                        Ok(msg) => Self::dispatch_message(msg, &inbox).await,
                         Err(e) => {
                             error!("failed to deserialize JSONRPCMessage: {e}; line = {}", line)
                         }
@@ -173,20 +230,101 @@ impl McpClient {
             })
         };
 
+        // Spawn stderr task. Servers log to stderr, so forward it to our log.
+        let stderr_handle = {
+            let program_name = program_name.clone();
+            let mut lines = BufReader::new(stderr).lines();
+
+            tokio::spawn(async move {
+                while let Ok(Some(line)) = lines.next_line().await {
+                    info!("MCP server `{program_name}` stderr: {line}");
+                }
+            })
+        };
+
+        // Spawn exit task. It owns the child so it can notice when the server
+        // exits, and kills it once the client is dropped.
+        let (kill_tx, kill_rx) = oneshot::channel::<()>();
+        let exit_handle = {
+            let inbox = inbox.clone();
+
+            tokio::spawn(async move {
+                tokio::select! {
+                    status = child.wait() => match status {
+                        Ok(status) => warn!("MCP server `{program_name}` exited: {status}"),
+                        Err(e) => error!("failed to wait for MCP server `{program_name}`: {e}"),
+                    },
+                    _ = kill_rx => {
+                        // As noted in the `kill_on_drop` documentation, this
+                        // is only a "best effort" to reap the process, so
+                        // wait for it explicitly.
+                        let _ = child.start_kill();
+                        let _ = child.wait().await;
+                    }
+                }
+                inbox.close().await;
+            })
+        };
+
         // We intentionally *detach* the tasks. They will keep running in the
         // background as long as their respective resources (channels/stdin/
-        // stdout) are alive. Dropping `McpClient` cancels the tasks due to
-        // dropped resources.
-        let _ = (writer_handle, reader_handle);
+        // stdout/stderr) are alive. Dropping `McpClient` cancels the tasks due
+        // to dropped resources.
+        let _ = (writer_handle, reader_handle, stderr_handle, exit_handle);
 
         Ok(Self {
-            child,
+            kill_tx: Some(kill_tx),
             outgoing_tx,
-            pending,
+            inbox,
             id_counter: AtomicI64::new(1),
         })
     }
 
+    /// Connect to an MCP server listening at `url` using the Streamable HTTP
+    /// transport. Servers that only implement the legacy HTTP+SSE transport
+    /// are detected when the first message is sent. `headers` are added to
+    /// every HTTP request, as is `Authorization: Bearer <bearer_token>` when
+    /// a token is supplied. Caller is responsible for sending the
+    /// `initialize` request. See [`initialize`](Self::initialize) for details.
+    pub async fn new_streamable_http_client(
+        url: String,
+        headers: HashMap<String, String>,
+        bearer_token: Option<String>,
+    ) -> Result<Self> {
+        let transport = HttpTransport::new(&url, headers, bearer_token)?;
+
+        let (outgoing_tx, outgoing_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
+        let inbox = Inbox::new();
+
+        // Detached like the stdio tasks: the writer task exits once
+        // `outgoing_tx` is dropped together with the client.
+        tokio::spawn(transport.run(outgoing_rx, inbox.clone()));
+
+        Ok(Self {
+            kill_tx: None,
+            outgoing_tx,
+            inbox,
+            id_counter: AtomicI64::new(1),
+        })
+    }
+
+    /// Receives the notifications the server sends from now on.
+    pub fn subscribe_notifications(&self) -> broadcast::Receiver<JSONRPCNotification> {
+        self.inbox.notifications.subscribe()
+    }
+
+    /// Completes once the connection to the server is gone for good, which
+    /// happens when a stdio server exits or the stream an HTTP server sends
+    /// its messages on is lost. Requests sent after that fail.
+    pub async fn closed(&self) {
+        self.inbox.closed().await;
+    }
+
+    /// Whether [`closed`](Self::closed) has completed.
+    pub fn is_closed(&self) -> bool {
+        *self.inbox.closed.borrow()
+    }
+
     /// Send an arbitrary MCP request and await the typed result.
     ///
     /// If `timeout` is `None` the call waits indefinitely. If `Some(duration)`
@@ -230,7 +368,10 @@ impl McpClient {
         // Register in pending map *before* sending the message so a race where
         // the response arrives immediately cannot be lost.
         {
-            let mut guard = self.pending.lock().await;
+            let mut guard = self.inbox.pending.lock().await;
+            if self.is_closed() {
+                return Err(anyhow!("connection to the MCP server is closed"));
+            }
             guard.insert(id, tx);
         }
 
@@ -248,7 +389,7 @@ impl McpClient {
                     Ok(Ok(msg)) => msg,
                     Ok(Err(_)) => {
                         // Channel closed without a reply – remove the pending entry.
-                        let mut guard = self.pending.lock().await;
+                        let mut guard = self.inbox.pending.lock().await;
                         guard.remove(&id);
                         return Err(anyhow!(
                             "response channel closed before a reply was received"
@@ -256,7 +397,7 @@ impl McpClient {
                     }
                     Err(_) => {
                         // Timed out. Remove the pending entry so we don't leak.
-                        let mut guard = self.pending.lock().await;
+                        let mut guard = self.inbox.pending.lock().await;
                         guard.remove(&id);
                         return Err(anyhow!("request timed out"));
                     }
@@ -350,11 +491,73 @@ impl McpClient {
         self.send_request::<CallToolRequest>(params, timeout).await
     }
 
+    /// Convenience wrapper around `resources/list`.
+    pub async fn list_resources(
+        &self,
+        params: Option<ListResourcesRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<ListResourcesResult> {
+        self.send_request::<ListResourcesRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `resources/read`.
+    pub async fn read_resource(
+        &self,
+        uri: String,
+        timeout: Option<Duration>,
+    ) -> Result<ReadResourceResult> {
+        let params = ReadResourceRequestParams { uri };
+        self.send_request::<ReadResourceRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `prompts/list`.
+    pub async fn list_prompts(
+        &self,
+        params: Option<ListPromptsRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<ListPromptsResult> {
+        self.send_request::<ListPromptsRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `prompts/get`.
+    pub async fn get_prompt(
+        &self,
+        name: String,
+        arguments: Option<serde_json::Value>,
+        timeout: Option<Duration>,
+    ) -> Result<GetPromptResult> {
+        let params = GetPromptRequestParams { arguments, name };
+        self.send_request::<GetPromptRequest>(params, timeout).await
+    }
+
+    /// Internal helper: route a message received from the server, whatever
+    /// the transport.
+    pub(crate) async fn dispatch_message(msg: JSONRPCMessage, inbox: &Inbox) {
+        match msg {
+            JSONRPCMessage::Response(resp) => {
+                Self::dispatch_response(resp, &inbox.pending).await;
+            }
+            JSONRPCMessage::Error(err) => {
+                Self::dispatch_error(err, &inbox.pending).await;
+            }
+            JSONRPCMessage::Notification(notification) => {
+                info!("<- notification: {notification:?}");
+                // Sending only fails when nobody is subscribed.
+                let _ = inbox.notifications.send(notification);
+            }
+            other => {
+                // Batch responses and requests are currently not
+                // expected from the server – log and ignore.
+                info!("<- unhandled message: {:?}", other);
+            }
+        }
+    }
+
     /// Internal helper: route a JSON-RPC *response* object to the pending map.
-    async fn dispatch_response(
-        resp: JSONRPCResponse,
-        pending: &Arc<Mutex<HashMap<i64, PendingSender>>>,
-    ) {
+    async fn dispatch_response(resp: JSONRPCResponse, pending: &PendingMap) {
         let id = match resp.id {
             RequestId::Integer(i) => i,
             RequestId::String(_) => {
@@ -374,10 +577,7 @@ impl McpClient {
     }
 
     /// Internal helper: route a JSON-RPC *error* object to the pending map.
-    async fn dispatch_error(
-        err: mcp_types::JSONRPCError,
-        pending: &Arc<Mutex<HashMap<i64, PendingSender>>>,
-    ) {
+    async fn dispatch_error(err: mcp_types::JSONRPCError, pending: &PendingMap) {
         let id = match err.id {
             RequestId::Integer(i) => i,
             RequestId::String(_) => return, // see comment above
@@ -389,16 +589,6 @@ impl McpClient {
     }
 }
 
-impl Drop for McpClient {
-    fn drop(&mut self) {
-        // Even though we have already tagged this process with
-        // `kill_on_drop(true)` above, this extra check has the benefit of
-        // forcing the process to be reaped immediately if it has already exited
-        // instead of waiting for the Tokio runtime to reap it later.
-        let _ = self.child.try_wait();
-    }
-}
-
 /// Environment variables that are always included when spawning a new MCP
 /// server.
 #[rustfmt::skip]
@@ -0,0 +1,411 @@
+#![expect(clippy::unwrap_used, clippy::expect_used)]
+
+//! Exercises the HTTP transports of `McpClient` against small axum servers
+//! standing in for remote MCP servers.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::Json;
+use axum::Router;
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::response::sse::Event;
+use axum::response::sse::Sse;
+use axum::routing::get;
+use axum::routing::post;
+use codex_mcp_client::McpClient;
+use futures::stream;
+use mcp_types::ClientCapabilities;
+use mcp_types::Implementation;
+use mcp_types::InitializeRequestParams;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+const TIMEOUT: Option<Duration> = Some(Duration::from_secs(5));
+
+fn initialize_params() -> InitializeRequestParams {
+    InitializeRequestParams {
+        capabilities: ClientCapabilities {
+            elicitation: None,
+            experimental: None,
+            roots: None,
+            sampling: None,
+        },
+        client_info: Implementation {
+            name: "test-client".to_string(),
+            title: None,
+            version: "0.0.0".to_string(),
+        },
+        protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_string(),
+    }
+}
+
+/// The result the stand-in servers send for `method`.
+fn result_for(method: &str) -> Value {
+    match method {
+        "initialize" => json!({
+            "protocolVersion": "2025-03-26",
+            "capabilities": {"tools": {}},
+            "serverInfo": {"name": "stand-in", "version": "1.0.0"},
+        }),
+        "tools/list" => json!({
+            "tools": [{"name": "echo", "inputSchema": {"type": "object"}}],
+        }),
+        other => panic!("unexpected method {other}"),
+    }
+}
+
+fn reply_to(request: &Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": request["id"],
+        "result": result_for(request["method"].as_str().unwrap()),
+    })
+}
+
+async fn serve(router: Router) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    format!("http://{addr}")
+}
+
+/// Method name and the headers of interest that came with it.
+type RecordedRequest = (String, HashMap<String, String>);
+
+/// Requests seen by the server, in order.
+#[derive(Clone, Default)]
+struct Recorded(Arc<Mutex<Vec<RecordedRequest>>>);
+
+impl Recorded {
+    fn record(&self, method: &str, headers: &HeaderMap) {
+        let headers = [
+            "authorization",
+            "mcp-session-id",
+            "mcp-protocol-version",
+            "x-team",
+        ]
+        .into_iter()
+        .filter_map(|name| {
+            headers
+                .get(name)
+                .map(|v| (name.to_string(), v.to_str().unwrap().to_string()))
+        })
+        .collect();
+        self.0.lock().unwrap().push((method.to_string(), headers));
+    }
+
+    fn headers_for(&self, method: &str) -> HashMap<String, String> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(m, _)| m == method)
+            .map(|(_, headers)| headers.clone())
+            .expect("method was not received")
+    }
+}
+
+async fn streamable_http_handler(
+    State(recorded): State<Recorded>,
+    headers: HeaderMap,
+    Json(message): Json<Value>,
+) -> Response {
+    let method = message["method"].as_str().unwrap_or_default().to_string();
+    recorded.record(&method, &headers);
+
+    match method.as_str() {
+        // Answered with a plain JSON body that assigns the session.
+        "initialize" => {
+            ([("mcp-session-id", "session-1")], Json(reply_to(&message))).into_response()
+        }
+        "notifications/initialized" => StatusCode::ACCEPTED.into_response(),
+        // Answered with an SSE stream that carries a notification first.
+        "tools/list" => {
+            let events = vec![
+                Event::default().data(
+                    json!({
+                        "jsonrpc": "2.0",
+                        "method": "notifications/message",
+                        "params": {"level": "info", "data": "listing"},
+                    })
+                    .to_string(),
+                ),
+                Event::default().data(reply_to(&message).to_string()),
+            ];
+            Sse::new(stream::iter(events.into_iter().map(Ok::<_, Infallible>))).into_response()
+        }
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, "boom").into_response(),
+    }
+}
+
+#[tokio::test]
+async fn streamable_http_round_trip() {
+    let recorded = Recorded::default();
+    let router = Router::new()
+        .route(
+            "/mcp",
+            post(streamable_http_handler).delete(|| async { StatusCode::OK }),
+        )
+        .with_state(recorded.clone());
+    let base_url = serve(router).await;
+
+    let client = McpClient::new_streamable_http_client(
+        format!("{base_url}/mcp"),
+        HashMap::from([("x-team".to_string(), "codex".to_string())]),
+        Some("secret".to_string()),
+    )
+    .await
+    .unwrap();
+
+    let init = client
+        .initialize(initialize_params(), None, TIMEOUT)
+        .await
+        .unwrap();
+    assert_eq!("stand-in", init.server_info.name);
+
+    let tools = client.list_tools(None, TIMEOUT).await.unwrap();
+    assert_eq!(
+        vec!["echo"],
+        tools
+            .tools
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect::<Vec<_>>()
+    );
+
+    // Requests after `initialize` carry the session and negotiated version.
+    assert_eq!(
+        HashMap::from([
+            ("authorization".to_string(), "Bearer secret".to_string()),
+            ("mcp-session-id".to_string(), "session-1".to_string()),
+            ("mcp-protocol-version".to_string(), "2025-03-26".to_string()),
+            ("x-team".to_string(), "codex".to_string()),
+        ]),
+        recorded.headers_for("tools/list")
+    );
+
+    // HTTP failures are reported to the caller instead of leaving it waiting.
+    let err = client
+        .call_tool("echo".to_string(), None, TIMEOUT)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("500 Internal Server Error"),
+        "unexpected error: {err}"
+    );
+}
+
+/// Holds `tools/call` until `tools/list` has been answered, which can only
+/// happen if the client does not wait for one reply before sending the next
+/// request.
+async fn slow_tool_call_handler(
+    State(listed): State<Arc<tokio::sync::Notify>>,
+    Json(message): Json<Value>,
+) -> Response {
+    match message["method"].as_str().unwrap_or_default() {
+        "initialize" => Json(reply_to(&message)).into_response(),
+        "notifications/initialized" => StatusCode::ACCEPTED.into_response(),
+        "tools/list" => {
+            listed.notify_one();
+            Json(reply_to(&message)).into_response()
+        }
+        "tools/call" => {
+            listed.notified().await;
+            Json(json!({
+                "jsonrpc": "2.0",
+                "id": message["id"],
+                "result": {"content": [{"type": "text", "text": "done"}]},
+            }))
+            .into_response()
+        }
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, "boom").into_response(),
+    }
+}
+
+#[tokio::test]
+async fn slow_json_reply_does_not_block_other_requests() {
+    let router = Router::new()
+        .route("/mcp", post(slow_tool_call_handler))
+        .with_state(Arc::new(tokio::sync::Notify::new()));
+    let base_url = serve(router).await;
+
+    let client = Arc::new(
+        McpClient::new_streamable_http_client(format!("{base_url}/mcp"), HashMap::new(), None)
+            .await
+            .unwrap(),
+    );
+    client
+        .initialize(initialize_params(), None, TIMEOUT)
+        .await
+        .unwrap();
+
+    let call = tokio::spawn({
+        let client = Arc::clone(&client);
+        async move { client.call_tool("echo".to_string(), None, TIMEOUT).await }
+    });
+    // Give the tool call a head start so it is sent first.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    client.list_tools(None, TIMEOUT).await.unwrap();
+    let result = call.await.unwrap().unwrap();
+    assert_eq!(1, result.content.len());
+}
+
+#[derive(Clone)]
+struct LegacyState {
+    to_client: mpsc::Sender<Value>,
+    from_server: Arc<tokio::sync::Mutex<Option<mpsc::Receiver<Value>>>>,
+}
+
+async fn legacy_sse_stream(State(state): State<LegacyState>) -> impl IntoResponse {
+    let rx = state.from_server.lock().await.take().unwrap();
+    let endpoint = stream::once(async {
+        Ok::<_, Infallible>(
+            Event::default()
+                .event("endpoint")
+                .data("/messages?sessionId=1"),
+        )
+    });
+    let messages = stream::unfold(rx, |mut rx| async move {
+        let message = rx.recv().await?;
+        Some((
+            Ok::<_, Infallible>(Event::default().event("message").data(message.to_string())),
+            rx,
+        ))
+    });
+    Sse::new(futures::StreamExt::chain(endpoint, messages))
+}
+
+async fn legacy_post_message(
+    State(state): State<LegacyState>,
+    Json(message): Json<Value>,
+) -> StatusCode {
+    if message.get("id").is_some() {
+        state.to_client.send(reply_to(&message)).await.unwrap();
+    }
+    StatusCode::ACCEPTED
+}
+
+#[tokio::test]
+async fn falls_back_to_legacy_sse() {
+    let (tx, rx) = mpsc::channel(16);
+    let state = LegacyState {
+        to_client: tx,
+        from_server: Arc::new(tokio::sync::Mutex::new(Some(rx))),
+    };
+    // `POST /sse` is answered with 405 Method Not Allowed by axum.
+    let router = Router::new()
+        .route("/sse", get(legacy_sse_stream))
+        .route("/messages", post(legacy_post_message))
+        .with_state(state);
+    let base_url = serve(router).await;
+
+    let client =
+        McpClient::new_streamable_http_client(format!("{base_url}/sse"), HashMap::new(), None)
+            .await
+            .unwrap();
+
+    let init = client
+        .initialize(initialize_params(), None, TIMEOUT)
+        .await
+        .unwrap();
+    assert_eq!("stand-in", init.server_info.name);
+
+    let tools = client.list_tools(None, TIMEOUT).await.unwrap();
+    assert_eq!(1, tools.tools.len());
+}
+
+/// Sends a notification on the stream of server messages, then keeps it open.
+async fn server_messages_stream(headers: HeaderMap) -> Response {
+    assert_eq!(
+        Some("session-1"),
+        headers.get("mcp-session-id").map(|v| v.to_str().unwrap())
+    );
+    let notification = stream::once(async {
+        Ok::<_, Infallible>(
+            Event::default().data(
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "notifications/tools/list_changed",
+                })
+                .to_string(),
+            ),
+        )
+    });
+    Sse::new(futures::StreamExt::chain(notification, stream::pending())).into_response()
+}
+
+#[tokio::test]
+async fn receives_notifications_on_the_stream_of_server_messages() {
+    let router = Router::new()
+        .route(
+            "/mcp",
+            post(streamable_http_handler).get(server_messages_stream),
+        )
+        .with_state(Recorded::default());
+    let base_url = serve(router).await;
+
+    let client =
+        McpClient::new_streamable_http_client(format!("{base_url}/mcp"), HashMap::new(), None)
+            .await
+            .unwrap();
+    let mut notifications = client.subscribe_notifications();
+    client
+        .initialize(initialize_params(), None, TIMEOUT)
+        .await
+        .unwrap();
+
+    let notification = tokio::time::timeout(TIMEOUT.unwrap(), notifications.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!("notifications/tools/list_changed", notification.method);
+    assert!(!client.is_closed());
+}
+
+#[tokio::test]
+async fn closes_the_client_when_the_legacy_stream_ends() {
+    // The stream ends right after announcing the endpoint, so the reply to
+    // `initialize` never arrives.
+    let router = Router::new()
+        .route(
+            "/sse",
+            get(|| async {
+                Sse::new(stream::once(async {
+                    Ok::<_, Infallible>(
+                        Event::default()
+                            .event("endpoint")
+                            .data("/messages?sessionId=1"),
+                    )
+                }))
+            }),
+        )
+        .route("/messages", post(|| async { StatusCode::ACCEPTED }));
+    let base_url = serve(router).await;
+
+    let client =
+        McpClient::new_streamable_http_client(format!("{base_url}/sse"), HashMap::new(), None)
+            .await
+            .unwrap();
+
+    assert!(
+        client
+            .initialize(initialize_params(), None, None)
+            .await
+            .is_err()
+    );
+    tokio::time::timeout(TIMEOUT.unwrap(), client.closed())
+        .await
+        .unwrap();
+}
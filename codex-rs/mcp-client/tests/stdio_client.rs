@@ -0,0 +1,65 @@
+#![cfg(unix)]
+
+//! Exercises the lifecycle of a stdio `McpClient` against a shell script
+//! standing in for an MCP server.
+
+use std::time::Duration;
+
+use codex_mcp_client::McpClient;
+use mcp_types::ClientCapabilities;
+use mcp_types::Implementation;
+use mcp_types::InitializeRequestParams;
+use pretty_assertions::assert_eq;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Answers `initialize`, announces a tool list change and exits once it has
+/// received `notifications/initialized`.
+const SERVER_SCRIPT: &str = r#"
+read -r _request
+echo '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2025-06-18","capabilities":{"tools":{"listChanged":true}},"serverInfo":{"name":"stand-in","version":"1.0.0"}}}'
+echo '{"jsonrpc":"2.0","method":"notifications/tools/list_changed"}'
+echo 'stand-in server shutting down' >&2
+read -r _notification
+exit 3
+"#;
+
+#[tokio::test]
+async fn reports_notifications_and_exit() {
+    let client =
+        McpClient::new_stdio_client("sh".into(), vec!["-c".into(), SERVER_SCRIPT.into()], None)
+            .await
+            .unwrap();
+    let mut notifications = client.subscribe_notifications();
+
+    let params = InitializeRequestParams {
+        capabilities: ClientCapabilities {
+            elicitation: None,
+            experimental: None,
+            roots: None,
+            sampling: None,
+        },
+        client_info: Implementation {
+            name: "test-client".to_string(),
+            title: None,
+            version: "0.0.0".to_string(),
+        },
+        protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_string(),
+    };
+    client
+        .initialize(params, None, Some(TIMEOUT))
+        .await
+        .unwrap();
+
+    let notification = tokio::time::timeout(TIMEOUT, notifications.recv())
+        .await
+        .expect("notification timeout")
+        .unwrap();
+    assert_eq!("notifications/tools/list_changed", notification.method);
+
+    tokio::time::timeout(TIMEOUT, client.closed())
+        .await
+        .expect("server did not exit");
+    assert!(client.is_closed());
+    assert!(client.list_tools(None, Some(TIMEOUT)).await.is_err());
+}
@@ -8,8 +8,10 @@ use codex_core::ConversationManager;
 use codex_core::NewConversation;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
+use codex_core::error::CodexErr;
 use codex_core::git_info::git_diff_to_remote;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
+use codex_core::protocol::BudgetApprovalRequestEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
@@ -20,13 +22,16 @@ use mcp_types::RequestId;
 use tokio::sync::Mutex;
 use tokio::sync::oneshot;
 use tracing::error;
+use tracing::warn;
 use uuid::Uuid;
 
+use crate::elicitation::ApprovalSettings;
 use crate::error_code::INTERNAL_ERROR_CODE;
 use crate::error_code::INVALID_REQUEST_ERROR_CODE;
 use crate::json_to_toml::json_to_toml;
 use crate::outgoing_message::OutgoingMessageSender;
 use crate::outgoing_message::OutgoingNotification;
+use crate::resources::ResourceStore;
 use codex_core::protocol::InputItem as CoreInputItem;
 use codex_core::protocol::Op;
 use codex_login::CLIENT_ID;
@@ -38,11 +43,16 @@ use codex_protocol::mcp_protocol::AddConversationListenerParams;
 use codex_protocol::mcp_protocol::AddConversationSubscriptionResponse;
 use codex_protocol::mcp_protocol::ApplyPatchApprovalParams;
 use codex_protocol::mcp_protocol::ApplyPatchApprovalResponse;
+use codex_protocol::mcp_protocol::BUDGET_APPROVAL_METHOD;
+use codex_protocol::mcp_protocol::BudgetApprovalParams;
+use codex_protocol::mcp_protocol::BudgetApprovalResponse;
 use codex_protocol::mcp_protocol::ClientRequest;
 use codex_protocol::mcp_protocol::ConversationId;
 use codex_protocol::mcp_protocol::EXEC_COMMAND_APPROVAL_METHOD;
 use codex_protocol::mcp_protocol::ExecCommandApprovalParams;
 use codex_protocol::mcp_protocol::ExecCommandApprovalResponse;
+use codex_protocol::mcp_protocol::ForkConversationParams;
+use codex_protocol::mcp_protocol::ForkConversationResponse;
 use codex_protocol::mcp_protocol::InputItem as WireInputItem;
 use codex_protocol::mcp_protocol::InterruptConversationParams;
 use codex_protocol::mcp_protocol::InterruptConversationResponse;
@@ -57,6 +67,8 @@ use codex_protocol::mcp_protocol::SendUserMessageParams;
 use codex_protocol::mcp_protocol::SendUserMessageResponse;
 use codex_protocol::mcp_protocol::SendUserTurnParams;
 use codex_protocol::mcp_protocol::SendUserTurnResponse;
+use codex_protocol::mcp_protocol::UndoTurnParams;
+use codex_protocol::mcp_protocol::UndoTurnResponse;
 
 // Duration before a ChatGPT login attempt is abandoned.
 const LOGIN_CHATGPT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
@@ -81,6 +93,10 @@ pub(crate) struct CodexMessageProcessor {
     active_login: Arc<Mutex<Option<ActiveLogin>>>,
     // Queue of pending interrupt requests per conversation. We reply when TurnAborted arrives.
     pending_interrupts: Arc<Mutex<HashMap<Uuid, Vec<RequestId>>>>,
+    resources: Arc<ResourceStore>,
+    // How long to wait for the client to answer an approval request before
+    // denying it.
+    approval_timeout: Duration,
 }
 
 impl CodexMessageProcessor {
@@ -88,6 +104,7 @@ impl CodexMessageProcessor {
         conversation_manager: Arc<ConversationManager>,
         outgoing: Arc<OutgoingMessageSender>,
         codex_linux_sandbox_exe: Option<PathBuf>,
+        resources: Arc<ResourceStore>,
     ) -> Self {
         Self {
             conversation_manager,
@@ -96,9 +113,15 @@ impl CodexMessageProcessor {
             conversation_listeners: HashMap::new(),
             active_login: Arc::new(Mutex::new(None)),
             pending_interrupts: Arc::new(Mutex::new(HashMap::new())),
+            resources,
+            approval_timeout: ApprovalSettings::default().timeout,
         }
     }
 
+    pub fn set_approval_timeout(&mut self, approval_timeout: Duration) {
+        self.approval_timeout = approval_timeout;
+    }
+
     pub async fn process_request(&mut self, request: ClientRequest) {
         match request {
             ClientRequest::NewConversation { request_id, params } => {
@@ -107,6 +130,11 @@ impl CodexMessageProcessor {
                 // created before processing any subsequent messages.
                 self.process_new_conversation(request_id, params).await;
             }
+            ClientRequest::ForkConversation { request_id, params } => {
+                // Like new_conversation(), the fork must exist before any
+                // subsequent messages are processed.
+                self.process_fork_conversation(request_id, params).await;
+            }
             ClientRequest::SendUserMessage { request_id, params } => {
                 self.send_user_message(request_id, params).await;
             }
@@ -116,6 +144,9 @@ impl CodexMessageProcessor {
             ClientRequest::InterruptConversation { request_id, params } => {
                 self.interrupt_conversation(request_id, params).await;
             }
+            ClientRequest::UndoTurn { request_id, params } => {
+                self.undo_turn(request_id, params).await;
+            }
             ClientRequest::AddConversationListener { request_id, params } => {
                 self.add_conversation_listener(request_id, params).await;
             }
@@ -298,6 +329,50 @@ impl CodexMessageProcessor {
         }
     }
 
+    async fn process_fork_conversation(
+        &self,
+        request_id: RequestId,
+        params: ForkConversationParams,
+    ) {
+        let ForkConversationParams {
+            conversation_id,
+            drop_last_n_turns,
+        } = params;
+        match self
+            .conversation_manager
+            .fork_conversation(conversation_id.0, drop_last_n_turns)
+            .await
+        {
+            Ok(NewConversation {
+                conversation_id,
+                session_configured,
+                ..
+            }) => {
+                let response = ForkConversationResponse {
+                    conversation_id: ConversationId(conversation_id),
+                    model: session_configured.model,
+                };
+                self.outgoing.send_response(request_id, response).await;
+            }
+            Err(CodexErr::ConversationNotFound(_)) => {
+                let error = JSONRPCErrorError {
+                    code: INVALID_REQUEST_ERROR_CODE,
+                    message: format!("conversation not found: {conversation_id}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+            }
+            Err(err) => {
+                let error = JSONRPCErrorError {
+                    code: INTERNAL_ERROR_CODE,
+                    message: format!("error forking conversation: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+            }
+        }
+    }
+
     async fn send_user_message(&self, request_id: RequestId, params: SendUserMessageParams) {
         let SendUserMessageParams {
             conversation_id,
@@ -391,6 +466,36 @@ impl CodexMessageProcessor {
             .await;
     }
 
+    async fn undo_turn(&self, request_id: RequestId, params: UndoTurnParams) {
+        let UndoTurnParams {
+            conversation_id,
+            checkpoint_id,
+        } = params;
+        let Ok(conversation) = self
+            .conversation_manager
+            .get_conversation(conversation_id.0)
+            .await
+        else {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: format!("conversation not found: {conversation_id}"),
+                data: None,
+            };
+            self.outgoing.send_error(request_id, error).await;
+            return;
+        };
+
+        let op = match checkpoint_id {
+            Some(checkpoint_id) => Op::RestoreCheckpoint { checkpoint_id },
+            None => Op::UndoTurn,
+        };
+        let _ = conversation.submit(op).await;
+
+        self.outgoing
+            .send_response(request_id, UndoTurnResponse {})
+            .await;
+    }
+
     async fn interrupt_conversation(
         &mut self,
         request_id: RequestId,
@@ -447,6 +552,8 @@ impl CodexMessageProcessor {
             .insert(subscription_id, cancel_tx);
         let outgoing_for_task = self.outgoing.clone();
         let pending_interrupts = self.pending_interrupts.clone();
+        let resources = self.resources.clone();
+        let approval_timeout = self.approval_timeout;
         tokio::spawn(async move {
             loop {
                 tokio::select! {
@@ -487,7 +594,9 @@ impl CodexMessageProcessor {
                         })
                         .await;
 
-                        apply_bespoke_event_handling(event.clone(), conversation_id, conversation.clone(), outgoing_for_task.clone(), pending_interrupts.clone()).await;
+                        resources.observe_event(conversation_id.0, &event.msg).await;
+
+                        apply_bespoke_event_handling(event.clone(), conversation_id, conversation.clone(), outgoing_for_task.clone(), pending_interrupts.clone(), approval_timeout).await;
                     }
                 }
             }
@@ -548,6 +657,7 @@ async fn apply_bespoke_event_handling(
     conversation: Arc<CodexConversation>,
     outgoing: Arc<OutgoingMessageSender>,
     pending_interrupts: Arc<Mutex<HashMap<Uuid, Vec<RequestId>>>>,
+    approval_timeout: Duration,
 ) {
     let Event { id: event_id, msg } = event;
     match msg {
@@ -568,9 +678,8 @@ async fn apply_bespoke_event_handling(
             let rx = outgoing
                 .send_request(APPLY_PATCH_APPROVAL_METHOD, Some(value))
                 .await;
-            // TODO(mbolin): Enforce a timeout so this task does not live indefinitely?
             tokio::spawn(async move {
-                on_patch_approval_response(event_id, rx, conversation).await;
+                on_patch_approval_response(event_id, rx, conversation, approval_timeout).await;
             });
         }
         EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
@@ -590,10 +699,29 @@ async fn apply_bespoke_event_handling(
             let rx = outgoing
                 .send_request(EXEC_COMMAND_APPROVAL_METHOD, Some(value))
                 .await;
-
-            // TODO(mbolin): Enforce a timeout so this task does not live indefinitely?
             tokio::spawn(async move {
-                on_exec_approval_response(event_id, rx, conversation).await;
+                on_exec_approval_response(event_id, rx, conversation, approval_timeout).await;
+            });
+        }
+        EventMsg::BudgetApprovalRequest(BudgetApprovalRequestEvent {
+            used_tokens,
+            max_tokens,
+            cost_usd,
+            max_cost_usd,
+        }) => {
+            let params = BudgetApprovalParams {
+                conversation_id,
+                used_tokens,
+                max_tokens,
+                cost_usd,
+                max_cost_usd,
+            };
+            let value = serde_json::to_value(&params).unwrap_or_default();
+            let rx = outgoing
+                .send_request(BUDGET_APPROVAL_METHOD, Some(value))
+                .await;
+            tokio::spawn(async move {
+                on_budget_approval_response(event_id, rx, conversation, approval_timeout).await;
             });
         }
         // If this is a TurnAborted, reply to any pending interrupt requests.
@@ -659,8 +787,21 @@ async fn on_patch_approval_response(
     event_id: String,
     receiver: tokio::sync::oneshot::Receiver<mcp_types::Result>,
     codex: Arc<CodexConversation>,
+    timeout: Duration,
 ) {
-    let response = receiver.await;
+    let Ok(response) = tokio::time::timeout(timeout, receiver).await else {
+        warn!("patch approval request timed out after {timeout:?}");
+        if let Err(err) = codex
+            .submit(Op::PatchApproval {
+                id: event_id,
+                decision: ReviewDecision::Denied,
+            })
+            .await
+        {
+            error!("failed to submit denied PatchApproval after timeout: {err}");
+        }
+        return;
+    };
     let value = match response {
         Ok(value) => value,
         Err(err) => {
@@ -697,12 +838,59 @@ async fn on_patch_approval_response(
     }
 }
 
+async fn on_budget_approval_response(
+    event_id: String,
+    receiver: tokio::sync::oneshot::Receiver<mcp_types::Result>,
+    conversation: Arc<CodexConversation>,
+    timeout: Duration,
+) {
+    let decision = match tokio::time::timeout(timeout, receiver).await {
+        Ok(Ok(value)) => serde_json::from_value::<BudgetApprovalResponse>(value)
+            .map(|response| response.decision)
+            .unwrap_or_else(|err| {
+                error!("failed to deserialize BudgetApprovalResponse: {err}");
+                ReviewDecision::Denied
+            }),
+        Ok(Err(err)) => {
+            error!("request failed: {err:?}");
+            ReviewDecision::Denied
+        }
+        Err(_) => {
+            warn!("budget approval request timed out after {timeout:?}");
+            ReviewDecision::Denied
+        }
+    };
+
+    if let Err(err) = conversation
+        .submit(Op::BudgetApproval {
+            id: event_id,
+            decision,
+        })
+        .await
+    {
+        error!("failed to submit BudgetApproval: {err}");
+    }
+}
+
 async fn on_exec_approval_response(
     event_id: String,
     receiver: tokio::sync::oneshot::Receiver<mcp_types::Result>,
     conversation: Arc<CodexConversation>,
+    timeout: Duration,
 ) {
-    let response = receiver.await;
+    let Ok(response) = tokio::time::timeout(timeout, receiver).await else {
+        warn!("exec approval request timed out after {timeout:?}");
+        if let Err(err) = conversation
+            .submit(Op::ExecApproval {
+                id: event_id,
+                decision: ReviewDecision::Denied,
+            })
+            .await
+        {
+            error!("failed to submit denied ExecApproval after timeout: {err}");
+        }
+        return;
+    };
     let value = match response {
         Ok(value) => value,
         Err(err) => {
@@ -9,34 +9,37 @@ use codex_core::CodexConversation;
 use codex_core::ConversationManager;
 use codex_core::NewConversation;
 use codex_core::config::Config as CodexConfig;
-use codex_core::protocol::AgentMessageEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
 use codex_core::protocol::Submission;
 use codex_core::protocol::TaskCompleteEvent;
 use mcp_types::CallToolResult;
 use mcp_types::ContentBlock;
+use mcp_types::ProgressToken;
 use mcp_types::RequestId;
 use mcp_types::TextContent;
 use serde_json::json;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+use crate::elicitation::ApprovalSettings;
 use crate::exec_approval::handle_exec_approval_request;
 use crate::outgoing_message::OutgoingMessageSender;
 use crate::outgoing_message::OutgoingNotificationMeta;
 use crate::patch_approval::handle_patch_approval_request;
-
-pub(crate) const INVALID_PARAMS_ERROR_CODE: i64 = -32602;
+use crate::progress::ProgressReporter;
+use crate::resources::ResourceStore;
 
 /// Run a complete Codex session and stream events back to the client.
 ///
 /// On completion (success or error) the function sends the appropriate
 /// `tools/call` response so the LLM can continue the conversation.
+#[allow(clippy::too_many_arguments)]
 pub async fn run_codex_tool_session(
     id: RequestId,
     initial_prompt: String,
@@ -44,6 +47,9 @@ pub async fn run_codex_tool_session(
     outgoing: Arc<OutgoingMessageSender>,
     conversation_manager: Arc<ConversationManager>,
     running_requests_id_to_codex_uuid: Arc<Mutex<HashMap<RequestId, Uuid>>>,
+    resources: Arc<ResourceStore>,
+    approval_settings: ApprovalSettings,
+    progress_token: Option<ProgressToken>,
 ) {
     let NewConversation {
         conversation_id,
@@ -74,7 +80,10 @@ pub async fn run_codex_tool_session(
     outgoing
         .send_event_as_notification(
             &session_configured_event,
-            Some(OutgoingNotificationMeta::new(Some(id.clone()))),
+            Some(
+                OutgoingNotificationMeta::new(Some(id.clone()))
+                    .with_progress_token(progress_token.clone()),
+            ),
         )
         .await;
 
@@ -107,13 +116,18 @@ pub async fn run_codex_tool_session(
 
     run_codex_tool_session_inner(
         conversation,
+        conversation_id,
         outgoing,
         id,
         running_requests_id_to_codex_uuid,
+        resources,
+        approval_settings,
+        progress_token,
     )
     .await;
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run_codex_tool_session_reply(
     conversation: Arc<CodexConversation>,
     outgoing: Arc<OutgoingMessageSender>,
@@ -121,6 +135,9 @@ pub async fn run_codex_tool_session_reply(
     prompt: String,
     running_requests_id_to_codex_uuid: Arc<Mutex<HashMap<RequestId, Uuid>>>,
     session_id: Uuid,
+    resources: Arc<ResourceStore>,
+    approval_settings: ApprovalSettings,
+    progress_token: Option<ProgressToken>,
 ) {
     running_requests_id_to_codex_uuid
         .lock()
@@ -143,19 +160,29 @@ pub async fn run_codex_tool_session_reply(
 
     run_codex_tool_session_inner(
         conversation,
+        session_id,
         outgoing,
         request_id,
         running_requests_id_to_codex_uuid,
+        resources,
+        approval_settings,
+        progress_token,
     )
     .await;
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run_codex_tool_session_inner(
     codex: Arc<CodexConversation>,
+    conversation_id: Uuid,
     outgoing: Arc<OutgoingMessageSender>,
     request_id: RequestId,
     running_requests_id_to_codex_uuid: Arc<Mutex<HashMap<RequestId, Uuid>>>,
+    resources: Arc<ResourceStore>,
+    approval_settings: ApprovalSettings,
+    progress_token: Option<ProgressToken>,
 ) {
+    let mut progress = ProgressReporter::new(outgoing.clone(), progress_token);
     let request_id_str = match &request_id {
         RequestId::String(s) => s.clone(),
         RequestId::Integer(n) => n.to_string(),
@@ -169,9 +196,14 @@ async fn run_codex_tool_session_inner(
                 outgoing
                     .send_event_as_notification(
                         &event,
-                        Some(OutgoingNotificationMeta::new(Some(request_id.clone()))),
+                        Some(
+                            OutgoingNotificationMeta::new(Some(request_id.clone()))
+                                .with_progress_token(progress.token().cloned()),
+                        ),
                     )
                     .await;
+                resources.observe_event(conversation_id, &event.msg).await;
+                progress.observe_event(&event.msg).await;
 
                 match event.msg {
                     EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
@@ -189,10 +221,25 @@ async fn run_codex_tool_session_inner(
                             request_id_str.clone(),
                             event.id.clone(),
                             call_id,
+                            &approval_settings,
                         )
                         .await;
                         continue;
                     }
+                    EventMsg::BudgetApprovalRequest(_) => {
+                        // The Codex tool has no way to ask about budgets, so
+                        // the task stops once the session budget is used up.
+                        if let Err(err) = codex
+                            .submit(Op::BudgetApproval {
+                                id: event.id.clone(),
+                                decision: ReviewDecision::Denied,
+                            })
+                            .await
+                        {
+                            tracing::error!("failed to submit denied BudgetApproval: {err}");
+                        }
+                        continue;
+                    }
                     EventMsg::Error(err_event) => {
                         // Return a response to conclude the tool call when the Codex session reports an error (e.g., interruption).
                         let result = json!({
@@ -217,11 +264,14 @@ async fn run_codex_tool_session_inner(
                             request_id.clone(),
                             request_id_str.clone(),
                             event.id.clone(),
+                            &approval_settings,
                         )
                         .await;
                         continue;
                     }
-                    EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                    EventMsg::TaskComplete(TaskCompleteEvent {
+                        last_agent_message, ..
+                    }) => {
                         let text = match last_agent_message {
                             Some(msg) => msg.clone(),
                             None => "".to_string(),
@@ -246,16 +296,10 @@ async fn run_codex_tool_session_inner(
                     EventMsg::SessionConfigured(_) => {
                         tracing::error!("unexpected SessionConfigured event");
                     }
-                    EventMsg::AgentMessageDelta(_) => {
-                        // TODO: think how we want to support this in the MCP
-                    }
-                    EventMsg::AgentReasoningDelta(_) => {
-                        // TODO: think how we want to support this in the MCP
-                    }
-                    EventMsg::AgentMessage(AgentMessageEvent { .. }) => {
-                        // TODO: think how we want to support this in the MCP
-                    }
-                    EventMsg::AgentReasoningRawContent(_)
+                    EventMsg::AgentMessageDelta(_)
+                    | EventMsg::AgentReasoningDelta(_)
+                    | EventMsg::AgentMessage(_)
+                    | EventMsg::AgentReasoningRawContent(_)
                     | EventMsg::AgentReasoningRawContentDelta(_)
                     | EventMsg::TaskStarted
                     | EventMsg::TokenCount(_)
@@ -263,24 +307,30 @@ async fn run_codex_tool_session_inner(
                     | EventMsg::AgentReasoningSectionBreak(_)
                     | EventMsg::McpToolCallBegin(_)
                     | EventMsg::McpToolCallEnd(_)
+                    | EventMsg::FileToolCallBegin(_)
+                    | EventMsg::FileToolCallEnd(_)
                     | EventMsg::McpListToolsResponse(_)
+                    | EventMsg::McpListResourcesResponse(_)
+                    | EventMsg::McpListPromptsResponse(_)
                     | EventMsg::ExecCommandBegin(_)
                     | EventMsg::ExecCommandOutputDelta(_)
                     | EventMsg::ExecCommandEnd(_)
                     | EventMsg::BackgroundEvent(_)
+                    | EventMsg::NetworkAccess(_)
                     | EventMsg::PatchApplyBegin(_)
                     | EventMsg::PatchApplyEnd(_)
                     | EventMsg::TurnDiff(_)
+                    | EventMsg::BackgroundJobStarted(_)
+                    | EventMsg::BackgroundJobFinished(_)
+                    | EventMsg::CheckpointCreated(_)
+                    | EventMsg::CheckpointRestored(_)
                     | EventMsg::GetHistoryEntryResponse(_)
                     | EventMsg::PlanUpdate(_)
                     | EventMsg::TurnAborted(_)
                     | EventMsg::ShutdownComplete => {
-                        // For now, we do not do anything extra for these
-                        // events. Note that
-                        // send(codex_event_to_notification(&event)) above has
-                        // already dispatched these events as notifications,
-                        // though we may want to do give different treatment to
-                        // individual events in the future.
+                        // These events have already been dispatched above as
+                        // `codex/event` notifications and, when the client
+                        // asked for progress, as `notifications/progress`.
                     }
                 }
             }
@@ -0,0 +1,199 @@
+//! Approval requests sent to the client as MCP elicitations.
+//!
+//! The client answers with an `ElicitResult`: `accept` approves the request
+//! (for the rest of the session when `approve_for_session` is set in the
+//! content), `decline` denies it and `cancel` aborts the turn. Requests the
+//! client does not answer in time are denied, and clients that do not support
+//! elicitation get the configured default decision without being asked.
+
+use std::time::Duration;
+
+use codex_core::config_types::McpServerSettings;
+use codex_core::protocol::ReviewDecision;
+use mcp_types::ElicitRequestParamsRequestedSchema;
+use mcp_types::ElicitResult;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::oneshot;
+use tracing::error;
+use tracing::warn;
+
+/// How long to wait for an answer when `mcp_server.approval_timeout_secs` is
+/// not configured.
+const DEFAULT_APPROVAL_TIMEOUT: Duration = Duration::from_secs(600);
+
+pub(crate) const ACCEPT_ACTION: &str = "accept";
+pub(crate) const DECLINE_ACTION: &str = "decline";
+pub(crate) const CANCEL_ACTION: &str = "cancel";
+
+/// Name of the boolean field in the requested schema.
+pub(crate) const APPROVE_FOR_SESSION_FIELD: &str = "approve_for_session";
+
+/// How approval requests are resolved for the connected client.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ApprovalSettings {
+    /// Whether the client advertised the `elicitation` capability.
+    pub elicitation_supported: bool,
+    pub timeout: Duration,
+    /// Decision applied without asking when elicitation is not supported.
+    pub default_decision: ReviewDecision,
+}
+
+impl ApprovalSettings {
+    pub(crate) fn new(elicitation_supported: bool, settings: &McpServerSettings) -> Self {
+        Self {
+            elicitation_supported,
+            timeout: settings
+                .approval_timeout_secs
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_APPROVAL_TIMEOUT),
+            default_decision: settings.default_approval_decision.unwrap_or_default(),
+        }
+    }
+}
+
+impl Default for ApprovalSettings {
+    /// Used until the client has initialized the connection.
+    fn default() -> Self {
+        Self::new(false, &McpServerSettings::default())
+    }
+}
+
+/// Schema of the content accepted along with the `accept` action.
+pub(crate) fn approval_requested_schema() -> ElicitRequestParamsRequestedSchema {
+    ElicitRequestParamsRequestedSchema {
+        r#type: "object".to_string(),
+        properties: json!({
+            APPROVE_FOR_SESSION_FIELD: {
+                "type": "boolean",
+                "title": "Approve for session",
+                "description": "Do not ask again for identical requests in this session",
+                "default": false,
+            }
+        }),
+        required: None,
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ApprovalContent {
+    #[serde(default)]
+    approve_for_session: bool,
+}
+
+/// Maps the client's answer to a decision. Unknown actions deny the request
+/// to be conservative.
+pub(crate) fn decision_from_elicit_result(result: ElicitResult) -> ReviewDecision {
+    match result.action.as_str() {
+        ACCEPT_ACTION => {
+            let content = result
+                .content
+                .and_then(|content| serde_json::from_value::<ApprovalContent>(content).ok())
+                .unwrap_or_default();
+            if content.approve_for_session {
+                ReviewDecision::ApprovedForSession
+            } else {
+                ReviewDecision::Approved
+            }
+        }
+        DECLINE_ACTION => ReviewDecision::Denied,
+        CANCEL_ACTION => ReviewDecision::Abort,
+        other => {
+            error!("unknown elicitation action: {other}");
+            ReviewDecision::Denied
+        }
+    }
+}
+
+/// Waits for the client's answer to an elicitation request. Failed, malformed
+/// and unanswered requests are denied.
+pub(crate) async fn await_elicitation_decision(
+    receiver: oneshot::Receiver<mcp_types::Result>,
+    timeout: Duration,
+) -> ReviewDecision {
+    match tokio::time::timeout(timeout, receiver).await {
+        Ok(Ok(value)) => match serde_json::from_value::<ElicitResult>(value) {
+            Ok(result) => decision_from_elicit_result(result),
+            Err(err) => {
+                error!("failed to deserialize ElicitResult: {err}");
+                ReviewDecision::Denied
+            }
+        },
+        Ok(Err(err)) => {
+            error!("request failed: {err:?}");
+            ReviewDecision::Denied
+        }
+        Err(_) => {
+            warn!("approval request timed out after {timeout:?}");
+            ReviewDecision::Denied
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn result(action: &str, content: Option<serde_json::Value>) -> ElicitResult {
+        ElicitResult {
+            action: action.to_string(),
+            content,
+        }
+    }
+
+    #[test]
+    fn maps_actions_to_decisions() {
+        assert_eq!(
+            ReviewDecision::Approved,
+            decision_from_elicit_result(result(ACCEPT_ACTION, None))
+        );
+        assert_eq!(
+            ReviewDecision::ApprovedForSession,
+            decision_from_elicit_result(result(
+                ACCEPT_ACTION,
+                Some(json!({ APPROVE_FOR_SESSION_FIELD: true }))
+            ))
+        );
+        assert_eq!(
+            ReviewDecision::Denied,
+            decision_from_elicit_result(result(DECLINE_ACTION, None))
+        );
+        assert_eq!(
+            ReviewDecision::Abort,
+            decision_from_elicit_result(result(CANCEL_ACTION, None))
+        );
+        assert_eq!(
+            ReviewDecision::Denied,
+            decision_from_elicit_result(result("maybe", None))
+        );
+    }
+
+    #[tokio::test]
+    async fn unanswered_request_is_denied() {
+        let (_tx, rx) = oneshot::channel();
+        assert_eq!(
+            ReviewDecision::Denied,
+            await_elicitation_decision(rx, Duration::from_millis(10)).await
+        );
+    }
+
+    #[test]
+    fn settings_defaults() {
+        let settings = ApprovalSettings::new(
+            true,
+            &McpServerSettings {
+                approval_timeout_secs: Some(5),
+                default_approval_decision: None,
+            },
+        );
+        assert_eq!(
+            ApprovalSettings {
+                elicitation_supported: true,
+                timeout: Duration::from_secs(5),
+                default_decision: ReviewDecision::Denied,
+            },
+            settings
+        );
+    }
+}
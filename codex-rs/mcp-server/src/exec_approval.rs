@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use codex_core::CodexConversation;
 use codex_core::protocol::Op;
@@ -11,10 +12,12 @@ use mcp_types::ModelContextProtocolRequest;
 use mcp_types::RequestId;
 use serde::Deserialize;
 use serde::Serialize;
-use serde_json::json;
 use tracing::error;
 
-use crate::codex_tool_runner::INVALID_PARAMS_ERROR_CODE;
+use crate::elicitation::ApprovalSettings;
+use crate::elicitation::approval_requested_schema;
+use crate::elicitation::await_elicitation_decision;
+use crate::error_code::INVALID_PARAMS_ERROR_CODE;
 
 /// Conforms to [`mcp_types::ElicitRequestParams`] so that it can be used as the
 /// `params` field of an [`ElicitRequest`].
@@ -37,15 +40,6 @@ pub struct ExecApprovalElicitRequestParams {
     pub codex_cwd: PathBuf,
 }
 
-// TODO(mbolin): ExecApprovalResponse does not conform to ElicitResult. See:
-// - https://github.com/modelcontextprotocol/modelcontextprotocol/blob/f962dc1780fa5eed7fb7c8a0232f1fc83ef220cd/schema/2025-06-18/schema.json#L617-L636
-// - https://modelcontextprotocol.io/specification/draft/client/elicitation#protocol-messages
-// It should have "action" and "content" fields.
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ExecApprovalResponse {
-    pub decision: ReviewDecision,
-}
-
 #[allow(clippy::too_many_arguments)]
 pub(crate) async fn handle_exec_approval_request(
     command: Vec<String>,
@@ -56,7 +50,13 @@ pub(crate) async fn handle_exec_approval_request(
     tool_call_id: String,
     event_id: String,
     call_id: String,
+    approval_settings: &ApprovalSettings,
 ) {
+    if !approval_settings.elicitation_supported {
+        submit_exec_approval(&codex, event_id, approval_settings.default_decision).await;
+        return;
+    }
+
     let escaped_command =
         shlex::try_join(command.iter().map(|s| s.as_str())).unwrap_or_else(|_| command.join(" "));
     let message = format!(
@@ -66,11 +66,7 @@ pub(crate) async fn handle_exec_approval_request(
 
     let params = ExecApprovalElicitRequestParams {
         message,
-        requested_schema: ElicitRequestParamsRequestedSchema {
-            r#type: "object".to_string(),
-            properties: json!({}),
-            required: None,
-        },
+        requested_schema: approval_requested_schema(),
         codex_elicitation: "exec-approval".to_string(),
         codex_mcp_tool_call_id: tool_call_id.clone(),
         codex_event_id: event_id.clone(),
@@ -107,8 +103,9 @@ pub(crate) async fn handle_exec_approval_request(
     {
         let codex = codex.clone();
         let event_id = event_id.clone();
+        let timeout = approval_settings.timeout;
         tokio::spawn(async move {
-            on_exec_approval_response(event_id, on_response, codex).await;
+            on_exec_approval_response(event_id, on_response, codex, timeout).await;
         });
     }
 }
@@ -117,30 +114,21 @@ async fn on_exec_approval_response(
     event_id: String,
     receiver: tokio::sync::oneshot::Receiver<mcp_types::Result>,
     codex: Arc<CodexConversation>,
+    timeout: Duration,
 ) {
-    let response = receiver.await;
-    let value = match response {
-        Ok(value) => value,
-        Err(err) => {
-            error!("request failed: {err:?}");
-            return;
-        }
-    };
-
-    // Try to deserialize `value` and then make the appropriate call to `codex`.
-    let response = serde_json::from_value::<ExecApprovalResponse>(value).unwrap_or_else(|err| {
-        error!("failed to deserialize ExecApprovalResponse: {err}");
-        // If we cannot deserialize the response, we deny the request to be
-        // conservative.
-        ExecApprovalResponse {
-            decision: ReviewDecision::Denied,
-        }
-    });
+    let decision = await_elicitation_decision(receiver, timeout).await;
+    submit_exec_approval(&codex, event_id, decision).await;
+}
 
+async fn submit_exec_approval(
+    codex: &CodexConversation,
+    event_id: String,
+    decision: ReviewDecision,
+) {
     if let Err(err) = codex
         .submit(Op::ExecApproval {
             id: event_id,
-            decision: response.decision,
+            decision,
         })
         .await
     {
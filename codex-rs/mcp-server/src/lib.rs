@@ -1,8 +1,13 @@
 //! Prototype MCP server.
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
+use std::io::ErrorKind;
 use std::io::Result as IoResult;
 use std::path::PathBuf;
+use std::sync::Arc;
+
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
 
 use mcp_types::JSONRPCMessage;
 use tokio::io::AsyncBufReadExt;
@@ -18,12 +23,17 @@ use tracing_subscriber::EnvFilter;
 mod codex_message_processor;
 mod codex_tool_config;
 mod codex_tool_runner;
+mod elicitation;
 mod error_code;
 mod exec_approval;
 mod json_to_toml;
 pub(crate) mod message_processor;
 mod outgoing_message;
+mod pagination;
 mod patch_approval;
+mod progress;
+mod prompts;
+mod resources;
 
 use crate::message_processor::MessageProcessor;
 use crate::outgoing_message::OutgoingMessage;
@@ -32,9 +42,7 @@ use crate::outgoing_message::OutgoingMessageSender;
 pub use crate::codex_tool_config::CodexToolCallParam;
 pub use crate::codex_tool_config::CodexToolCallReplyParam;
 pub use crate::exec_approval::ExecApprovalElicitRequestParams;
-pub use crate::exec_approval::ExecApprovalResponse;
 pub use crate::patch_approval::PatchApprovalElicitRequestParams;
-pub use crate::patch_approval::PatchApprovalResponse;
 
 /// Size of the bounded channels used to communicate between tasks. The value
 /// is a balance between throughput and memory usage – 128 messages should be
@@ -49,6 +57,14 @@ pub async fn run_main(codex_linux_sandbox_exe: Option<PathBuf>) -> IoResult<()>
         .with_env_filter(EnvFilter::from_default_env())
         .init();
 
+    // Load configuration once: resources, prompts and approval settings are
+    // all served from it.
+    let config =
+        Config::load_with_cli_overrides(Vec::new(), ConfigOverrides::default()).map_err(|e| {
+            std::io::Error::new(ErrorKind::InvalidData, format!("error loading config: {e}"))
+        })?;
+    let config = Arc::new(config);
+
     // Set up channels.
     let (incoming_tx, mut incoming_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
     let (outgoing_tx, mut outgoing_rx) = mpsc::channel::<OutgoingMessage>(CHANNEL_CAPACITY);
@@ -80,7 +96,8 @@ pub async fn run_main(codex_linux_sandbox_exe: Option<PathBuf>) -> IoResult<()>
     // Task: process incoming messages.
     let processor_handle = tokio::spawn({
         let outgoing_message_sender = OutgoingMessageSender::new(outgoing_tx);
-        let mut processor = MessageProcessor::new(outgoing_message_sender, codex_linux_sandbox_exe);
+        let mut processor =
+            MessageProcessor::new(outgoing_message_sender, codex_linux_sandbox_exe, config);
         async move {
             while let Some(msg) = incoming_rx.recv().await {
                 match msg {
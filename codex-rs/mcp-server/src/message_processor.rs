@@ -7,8 +7,12 @@ use crate::codex_tool_config::CodexToolCallParam;
 use crate::codex_tool_config::CodexToolCallReplyParam;
 use crate::codex_tool_config::create_tool_for_codex_tool_call_param;
 use crate::codex_tool_config::create_tool_for_codex_tool_call_reply_param;
+use crate::elicitation::ApprovalSettings;
 use crate::error_code::INVALID_REQUEST_ERROR_CODE;
 use crate::outgoing_message::OutgoingMessageSender;
+use crate::progress::progress_token_from_params;
+use crate::prompts;
+use crate::resources::ResourceStore;
 use codex_protocol::mcp_protocol::ClientRequest;
 
 use codex_core::ConversationManager;
@@ -25,7 +29,10 @@ use mcp_types::JSONRPCRequest;
 use mcp_types::JSONRPCResponse;
 use mcp_types::ListToolsResult;
 use mcp_types::ModelContextProtocolRequest;
+use mcp_types::ProgressToken;
 use mcp_types::RequestId;
+use mcp_types::ServerCapabilitiesPrompts;
+use mcp_types::ServerCapabilitiesResources;
 use mcp_types::ServerCapabilitiesTools;
 use mcp_types::ServerNotification;
 use mcp_types::TextContent;
@@ -41,6 +48,10 @@ pub(crate) struct MessageProcessor {
     codex_linux_sandbox_exe: Option<PathBuf>,
     conversation_manager: Arc<ConversationManager>,
     running_requests_id_to_codex_uuid: Arc<Mutex<HashMap<RequestId, Uuid>>>,
+    resources: Arc<ResourceStore>,
+    approval_settings: ApprovalSettings,
+    /// Configuration loaded when the server started.
+    config: Arc<CodexConfig>,
 }
 
 impl MessageProcessor {
@@ -49,13 +60,16 @@ impl MessageProcessor {
     pub(crate) fn new(
         outgoing: OutgoingMessageSender,
         codex_linux_sandbox_exe: Option<PathBuf>,
+        config: Arc<CodexConfig>,
     ) -> Self {
         let outgoing = Arc::new(outgoing);
         let conversation_manager = Arc::new(ConversationManager::default());
+        let resources = Arc::new(ResourceStore::new(outgoing.clone(), config.clone()));
         let codex_message_processor = CodexMessageProcessor::new(
             conversation_manager.clone(),
             outgoing.clone(),
             codex_linux_sandbox_exe.clone(),
+            resources.clone(),
         );
         Self {
             codex_message_processor,
@@ -64,6 +78,9 @@ impl MessageProcessor {
             codex_linux_sandbox_exe,
             conversation_manager,
             running_requests_id_to_codex_uuid: Arc::new(Mutex::new(HashMap::new())),
+            resources,
+            approval_settings: ApprovalSettings::default(),
+            config,
         }
     }
 
@@ -81,6 +98,7 @@ impl MessageProcessor {
 
         // Hold on to the ID so we can respond.
         let request_id = request.id.clone();
+        let progress_token = progress_token_from_params(request.params.as_ref());
 
         let client_request = match McpClientRequest::try_from(request) {
             Ok(client_request) => client_request,
@@ -99,31 +117,33 @@ impl MessageProcessor {
                 self.handle_ping(request_id, params).await;
             }
             McpClientRequest::ListResourcesRequest(params) => {
-                self.handle_list_resources(params);
+                self.handle_list_resources(request_id, params).await;
             }
             McpClientRequest::ListResourceTemplatesRequest(params) => {
-                self.handle_list_resource_templates(params);
+                self.handle_list_resource_templates(request_id, params)
+                    .await;
             }
             McpClientRequest::ReadResourceRequest(params) => {
-                self.handle_read_resource(params);
+                self.handle_read_resource(request_id, params).await;
             }
             McpClientRequest::SubscribeRequest(params) => {
-                self.handle_subscribe(params);
+                self.handle_subscribe(request_id, params).await;
             }
             McpClientRequest::UnsubscribeRequest(params) => {
-                self.handle_unsubscribe(params);
+                self.handle_unsubscribe(request_id, params).await;
             }
             McpClientRequest::ListPromptsRequest(params) => {
-                self.handle_list_prompts(params);
+                self.handle_list_prompts(request_id, params).await;
             }
             McpClientRequest::GetPromptRequest(params) => {
-                self.handle_get_prompt(params);
+                self.handle_get_prompt(request_id, params).await;
             }
             McpClientRequest::ListToolsRequest(params) => {
                 self.handle_list_tools(request_id, params).await;
             }
             McpClientRequest::CallToolRequest(params) => {
-                self.handle_call_tool(request_id, params).await;
+                self.handle_call_tool(request_id, params, progress_token)
+                    .await;
             }
             McpClientRequest::SetLevelRequest(params) => {
                 self.handle_set_level(params);
@@ -203,14 +223,28 @@ impl MessageProcessor {
 
         self.initialized = true;
 
+        // Approval requests are sent as elicitations, so how they are resolved
+        // depends on whether the client supports them.
+        self.approval_settings = ApprovalSettings::new(
+            params.capabilities.elicitation.is_some(),
+            &self.config.mcp_server,
+        );
+        self.codex_message_processor
+            .set_approval_timeout(self.approval_settings.timeout);
+
         // Build a minimal InitializeResult. Fill with placeholders.
         let result = mcp_types::InitializeResult {
             capabilities: mcp_types::ServerCapabilities {
                 completions: None,
                 experimental: None,
                 logging: None,
-                prompts: None,
-                resources: None,
+                prompts: Some(ServerCapabilitiesPrompts {
+                    list_changed: Some(false),
+                }),
+                resources: Some(ServerCapabilitiesResources {
+                    list_changed: Some(true),
+                    subscribe: Some(true),
+                }),
                 tools: Some(ServerCapabilitiesTools {
                     list_changed: Some(true),
                 }),
@@ -246,54 +280,109 @@ impl MessageProcessor {
             .await;
     }
 
-    fn handle_list_resources(
+    async fn handle_list_resources(
         &self,
+        id: RequestId,
         params: <mcp_types::ListResourcesRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("resources/list -> params: {:?}", params);
+        let cursor = params.as_ref().and_then(|p| p.cursor.as_deref());
+        match self.resources.list(cursor).await {
+            Ok(result) => {
+                self.send_response::<mcp_types::ListResourcesRequest>(id, result)
+                    .await;
+            }
+            Err(error) => self.outgoing.send_error(id, error).await,
+        }
     }
 
-    fn handle_list_resource_templates(
+    async fn handle_list_resource_templates(
         &self,
+        id: RequestId,
         params:
             <mcp_types::ListResourceTemplatesRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("resources/templates/list -> params: {:?}", params);
+        let cursor = params.as_ref().and_then(|p| p.cursor.as_deref());
+        match self.resources.list_templates(cursor) {
+            Ok(result) => {
+                self.send_response::<mcp_types::ListResourceTemplatesRequest>(id, result)
+                    .await;
+            }
+            Err(error) => self.outgoing.send_error(id, error).await,
+        }
     }
 
-    fn handle_read_resource(
+    async fn handle_read_resource(
         &self,
+        id: RequestId,
         params: <mcp_types::ReadResourceRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("resources/read -> params: {:?}", params);
+        match self.resources.read(&params.uri).await {
+            Ok(result) => {
+                self.send_response::<mcp_types::ReadResourceRequest>(id, result)
+                    .await;
+            }
+            Err(error) => self.outgoing.send_error(id, error).await,
+        }
     }
 
-    fn handle_subscribe(
+    async fn handle_subscribe(
         &self,
+        id: RequestId,
         params: <mcp_types::SubscribeRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("resources/subscribe -> params: {:?}", params);
+        match self.resources.subscribe(params.uri).await {
+            Ok(()) => {
+                self.send_response::<mcp_types::SubscribeRequest>(id, json!({}))
+                    .await;
+            }
+            Err(error) => self.outgoing.send_error(id, error).await,
+        }
     }
 
-    fn handle_unsubscribe(
+    async fn handle_unsubscribe(
         &self,
+        id: RequestId,
         params: <mcp_types::UnsubscribeRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("resources/unsubscribe -> params: {:?}", params);
+        self.resources.unsubscribe(&params.uri).await;
+        self.send_response::<mcp_types::UnsubscribeRequest>(id, json!({}))
+            .await;
     }
 
-    fn handle_list_prompts(
+    async fn handle_list_prompts(
         &self,
+        id: RequestId,
         params: <mcp_types::ListPromptsRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("prompts/list -> params: {:?}", params);
+        let cursor = params.as_ref().and_then(|p| p.cursor.as_deref());
+        match prompts::list_prompts(&self.config.codex_home, cursor).await {
+            Ok(result) => {
+                self.send_response::<mcp_types::ListPromptsRequest>(id, result)
+                    .await;
+            }
+            Err(error) => self.outgoing.send_error(id, error).await,
+        }
     }
 
-    fn handle_get_prompt(
+    async fn handle_get_prompt(
         &self,
+        id: RequestId,
         params: <mcp_types::GetPromptRequest as mcp_types::ModelContextProtocolRequest>::Params,
     ) {
         tracing::info!("prompts/get -> params: {:?}", params);
+        match prompts::get_prompt(&self.config.codex_home, params).await {
+            Ok(result) => {
+                self.send_response::<mcp_types::GetPromptRequest>(id, result)
+                    .await;
+            }
+            Err(error) => self.outgoing.send_error(id, error).await,
+        }
     }
 
     async fn handle_list_tools(
@@ -318,14 +407,18 @@ impl MessageProcessor {
         &self,
         id: RequestId,
         params: <mcp_types::CallToolRequest as mcp_types::ModelContextProtocolRequest>::Params,
+        progress_token: Option<ProgressToken>,
     ) {
         tracing::info!("tools/call -> params: {:?}", params);
         let CallToolRequestParams { name, arguments } = params;
 
         match name.as_str() {
-            "codex" => self.handle_tool_call_codex(id, arguments).await,
+            "codex" => {
+                self.handle_tool_call_codex(id, arguments, progress_token)
+                    .await
+            }
             "codex-reply" => {
-                self.handle_tool_call_codex_session_reply(id, arguments)
+                self.handle_tool_call_codex_session_reply(id, arguments, progress_token)
                     .await
             }
             _ => {
@@ -343,7 +436,12 @@ impl MessageProcessor {
             }
         }
     }
-    async fn handle_tool_call_codex(&self, id: RequestId, arguments: Option<serde_json::Value>) {
+    async fn handle_tool_call_codex(
+        &self,
+        id: RequestId,
+        arguments: Option<serde_json::Value>,
+        progress_token: Option<ProgressToken>,
+    ) {
         let (initial_prompt, config): (String, CodexConfig) = match arguments {
             Some(json_val) => match serde_json::from_value::<CodexToolCallParam>(json_val) {
                 Ok(tool_cfg) => match tool_cfg.into_config(self.codex_linux_sandbox_exe.clone()) {
@@ -402,6 +500,8 @@ impl MessageProcessor {
         let outgoing = self.outgoing.clone();
         let conversation_manager = self.conversation_manager.clone();
         let running_requests_id_to_codex_uuid = self.running_requests_id_to_codex_uuid.clone();
+        let resources = self.resources.clone();
+        let approval_settings = self.approval_settings.clone();
 
         // Spawn an async task to handle the Codex session so that we do not
         // block the synchronous message-processing loop.
@@ -414,6 +514,9 @@ impl MessageProcessor {
                 outgoing,
                 conversation_manager,
                 running_requests_id_to_codex_uuid,
+                resources,
+                approval_settings,
+                progress_token,
             )
             .await;
         });
@@ -423,6 +526,7 @@ impl MessageProcessor {
         &self,
         request_id: RequestId,
         arguments: Option<serde_json::Value>,
+        progress_token: Option<ProgressToken>,
     ) {
         tracing::info!("tools/call -> params: {:?}", arguments);
 
@@ -511,6 +615,8 @@ impl MessageProcessor {
             let outgoing = outgoing.clone();
             let prompt = prompt.clone();
             let running_requests_id_to_codex_uuid = running_requests_id_to_codex_uuid.clone();
+            let resources = self.resources.clone();
+            let approval_settings = self.approval_settings.clone();
 
             async move {
                 crate::codex_tool_runner::run_codex_tool_session_reply(
@@ -520,6 +626,9 @@ impl MessageProcessor {
                     prompt,
                     running_requests_id_to_codex_uuid,
                     session_id,
+                    resources,
+                    approval_settings,
+                    progress_token,
                 )
                 .await;
             }
@@ -10,6 +10,7 @@ use mcp_types::JSONRPCMessage;
 use mcp_types::JSONRPCNotification;
 use mcp_types::JSONRPCRequest;
 use mcp_types::JSONRPCResponse;
+use mcp_types::ProgressToken;
 use mcp_types::RequestId;
 use mcp_types::Result;
 use serde::Serialize;
@@ -46,6 +47,9 @@ impl OutgoingMessageSender {
         let (tx_approve, rx_approve) = oneshot::channel();
         {
             let mut request_id_to_callback = self.request_id_to_callback.lock().await;
+            // Drop callbacks whose receivers gave up waiting, e.g. approval
+            // requests that timed out.
+            request_id_to_callback.retain(|_, tx| !tx.is_closed());
             request_id_to_callback.insert(id, tx_approve);
         }
 
@@ -206,11 +210,23 @@ pub(crate) struct OutgoingNotificationParams {
 #[serde(rename_all = "camelCase")]
 pub(crate) struct OutgoingNotificationMeta {
     pub request_id: Option<RequestId>,
+
+    /// Progress token the client attached to the originating request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress_token: Option<ProgressToken>,
 }
 
 impl OutgoingNotificationMeta {
     pub(crate) fn new(request_id: Option<RequestId>) -> Self {
-        Self { request_id }
+        Self {
+            request_id,
+            progress_token: None,
+        }
+    }
+
+    pub(crate) fn with_progress_token(mut self, progress_token: Option<ProgressToken>) -> Self {
+        self.progress_token = progress_token;
+        self
     }
 }
 
@@ -282,9 +298,8 @@ mod tests {
             id: "1".to_string(),
             msg: EventMsg::SessionConfigured(session_configured_event.clone()),
         };
-        let meta = OutgoingNotificationMeta {
-            request_id: Some(RequestId::String("123".to_string())),
-        };
+        let meta = OutgoingNotificationMeta::new(Some(RequestId::String("123".to_string())))
+            .with_progress_token(Some(ProgressToken::Integer(7)));
 
         outgoing_message_sender
             .send_event_as_notification(&event, Some(meta))
@@ -298,6 +313,7 @@ mod tests {
         let expected_params = json!({
             "_meta": {
                 "requestId": "123",
+                "progressToken": 7,
             },
             "id": "1",
             "msg": {
@@ -0,0 +1,55 @@
+//! Cursor-based pagination of list results. Cursors are opaque to clients;
+//! this server uses the offset of the first item of the next page.
+
+use mcp_types::JSONRPCErrorError;
+
+use crate::error_code::INVALID_PARAMS_ERROR_CODE;
+
+/// Number of items returned per page.
+pub(crate) const PAGE_SIZE: usize = 100;
+
+/// Returns the page of `items` that starts at `cursor` (or at the beginning)
+/// and the cursor of the next page, if there is one.
+pub(crate) fn paginate<T>(
+    items: Vec<T>,
+    cursor: Option<&str>,
+) -> Result<(Vec<T>, Option<String>), JSONRPCErrorError> {
+    let start = match cursor {
+        Some(cursor) => cursor
+            .parse::<usize>()
+            .ok()
+            .filter(|start| *start <= items.len())
+            .ok_or_else(|| JSONRPCErrorError {
+                code: INVALID_PARAMS_ERROR_CODE,
+                message: format!("invalid cursor: {cursor}"),
+                data: None,
+            })?,
+        None => 0,
+    };
+    let end = items.len().min(start + PAGE_SIZE);
+    let next_cursor = (end < items.len()).then(|| end.to_string());
+    let page = items.into_iter().skip(start).take(end - start).collect();
+    Ok((page, next_cursor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn pages_through_items() {
+        let items: Vec<usize> = (0..PAGE_SIZE + 5).collect();
+
+        let (page, next_cursor) = paginate(items.clone(), None).unwrap();
+        assert_eq!((0..PAGE_SIZE).collect::<Vec<_>>(), page);
+        assert_eq!(Some(PAGE_SIZE.to_string()), next_cursor);
+
+        let (page, next_cursor) = paginate(items.clone(), next_cursor.as_deref()).unwrap();
+        assert_eq!((PAGE_SIZE..PAGE_SIZE + 5).collect::<Vec<_>>(), page);
+        assert_eq!(None, next_cursor);
+
+        assert!(paginate(items.clone(), Some("nope")).is_err());
+        assert!(paginate(items, Some(&(PAGE_SIZE + 6).to_string())).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use codex_core::CodexConversation;
 use codex_core::protocol::FileChange;
@@ -11,12 +12,13 @@ use mcp_types::ElicitRequestParamsRequestedSchema;
 use mcp_types::JSONRPCErrorError;
 use mcp_types::ModelContextProtocolRequest;
 use mcp_types::RequestId;
-use serde::Deserialize;
 use serde::Serialize;
-use serde_json::json;
 use tracing::error;
 
-use crate::codex_tool_runner::INVALID_PARAMS_ERROR_CODE;
+use crate::elicitation::ApprovalSettings;
+use crate::elicitation::approval_requested_schema;
+use crate::elicitation::await_elicitation_decision;
+use crate::error_code::INVALID_PARAMS_ERROR_CODE;
 use crate::outgoing_message::OutgoingMessageSender;
 
 #[derive(Debug, Serialize)]
@@ -35,11 +37,6 @@ pub struct PatchApprovalElicitRequestParams {
     pub codex_changes: HashMap<PathBuf, FileChange>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct PatchApprovalResponse {
-    pub decision: ReviewDecision,
-}
-
 #[allow(clippy::too_many_arguments)]
 pub(crate) async fn handle_patch_approval_request(
     call_id: String,
@@ -51,7 +48,13 @@ pub(crate) async fn handle_patch_approval_request(
     request_id: RequestId,
     tool_call_id: String,
     event_id: String,
+    approval_settings: &ApprovalSettings,
 ) {
+    if !approval_settings.elicitation_supported {
+        submit_patch_approval(&codex, event_id, approval_settings.default_decision).await;
+        return;
+    }
+
     let mut message_lines = Vec::new();
     if let Some(r) = &reason {
         message_lines.push(r.clone());
@@ -60,11 +63,7 @@ pub(crate) async fn handle_patch_approval_request(
 
     let params = PatchApprovalElicitRequestParams {
         message: message_lines.join("\n"),
-        requested_schema: ElicitRequestParamsRequestedSchema {
-            r#type: "object".to_string(),
-            properties: json!({}),
-            required: None,
-        },
+        requested_schema: approval_requested_schema(),
         codex_elicitation: "patch-approval".to_string(),
         codex_mcp_tool_call_id: tool_call_id.clone(),
         codex_event_id: event_id.clone(),
@@ -102,8 +101,9 @@ pub(crate) async fn handle_patch_approval_request(
     {
         let codex = codex.clone();
         let event_id = event_id.clone();
+        let timeout = approval_settings.timeout;
         tokio::spawn(async move {
-            on_patch_approval_response(event_id, on_response, codex).await;
+            on_patch_approval_response(event_id, on_response, codex, timeout).await;
         });
     }
 }
@@ -112,36 +112,21 @@ pub(crate) async fn on_patch_approval_response(
     event_id: String,
     receiver: tokio::sync::oneshot::Receiver<mcp_types::Result>,
     codex: Arc<CodexConversation>,
+    timeout: Duration,
 ) {
-    let response = receiver.await;
-    let value = match response {
-        Ok(value) => value,
-        Err(err) => {
-            error!("request failed: {err:?}");
-            if let Err(submit_err) = codex
-                .submit(Op::PatchApproval {
-                    id: event_id.clone(),
-                    decision: ReviewDecision::Denied,
-                })
-                .await
-            {
-                error!("failed to submit denied PatchApproval after request failure: {submit_err}");
-            }
-            return;
-        }
-    };
-
-    let response = serde_json::from_value::<PatchApprovalResponse>(value).unwrap_or_else(|err| {
-        error!("failed to deserialize PatchApprovalResponse: {err}");
-        PatchApprovalResponse {
-            decision: ReviewDecision::Denied,
-        }
-    });
+    let decision = await_elicitation_decision(receiver, timeout).await;
+    submit_patch_approval(&codex, event_id, decision).await;
+}
 
+async fn submit_patch_approval(
+    codex: &CodexConversation,
+    event_id: String,
+    decision: ReviewDecision,
+) {
     if let Err(err) = codex
         .submit(Op::PatchApproval {
             id: event_id,
-            decision: response.decision,
+            decision,
         })
         .await
     {
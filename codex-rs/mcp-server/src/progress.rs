@@ -0,0 +1,165 @@
+//! `notifications/progress` for `codex` tool calls.
+//!
+//! When the client attaches a `progressToken` to a `tools/call` request, the
+//! events of the Codex session are summarized as progress notifications keyed
+//! to that token. Clients that understand the `codex/event` notifications
+//! receive the full events regardless.
+
+use std::sync::Arc;
+
+use codex_core::protocol::AgentMessageDeltaEvent;
+use codex_core::protocol::AgentMessageEvent;
+use codex_core::protocol::AgentReasoningDeltaEvent;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::ExecCommandBeginEvent;
+use codex_core::protocol::ExecCommandEndEvent;
+use codex_core::protocol::McpToolCallBeginEvent;
+use codex_core::protocol::PatchApplyEndEvent;
+use mcp_types::ModelContextProtocolNotification;
+use mcp_types::ProgressNotification;
+use mcp_types::ProgressNotificationParams;
+use mcp_types::ProgressToken;
+use serde_json::Value;
+use shlex::try_join;
+
+use crate::outgoing_message::OutgoingMessageSender;
+use crate::outgoing_message::OutgoingNotification;
+
+/// Returns the `_meta.progressToken` of a request's params, if any.
+pub(crate) fn progress_token_from_params(params: Option<&Value>) -> Option<ProgressToken> {
+    let token = params?.get("_meta")?.get("progressToken")?;
+    serde_json::from_value(token.clone()).ok()
+}
+
+pub(crate) struct ProgressReporter {
+    outgoing: Arc<OutgoingMessageSender>,
+    token: Option<ProgressToken>,
+    /// Number of notifications sent so far. The spec requires the progress
+    /// value to increase with every notification, and a Codex session has no
+    /// known total, so this count is the progress.
+    sent: u64,
+}
+
+impl ProgressReporter {
+    pub(crate) fn new(outgoing: Arc<OutgoingMessageSender>, token: Option<ProgressToken>) -> Self {
+        Self {
+            outgoing,
+            token,
+            sent: 0,
+        }
+    }
+
+    pub(crate) fn token(&self) -> Option<&ProgressToken> {
+        self.token.as_ref()
+    }
+
+    /// Sends a progress notification for `msg` if the client asked for
+    /// progress and the event is worth reporting.
+    pub(crate) async fn observe_event(&mut self, msg: &EventMsg) {
+        let Some(token) = &self.token else {
+            return;
+        };
+        let Some(message) = progress_message(msg) else {
+            return;
+        };
+        self.sent += 1;
+        let params = ProgressNotificationParams {
+            message: Some(message),
+            progress: self.sent as f64,
+            progress_token: token.clone(),
+            total: None,
+        };
+        self.outgoing
+            .send_notification(OutgoingNotification {
+                method: ProgressNotification::METHOD.to_string(),
+                params: serde_json::to_value(params).ok(),
+            })
+            .await;
+    }
+}
+
+/// Human-readable summary of an event, or `None` for events that are not
+/// reported as progress.
+fn progress_message(msg: &EventMsg) -> Option<String> {
+    let message = match msg {
+        EventMsg::TaskStarted => "Task started".to_string(),
+        EventMsg::AgentMessageDelta(AgentMessageDeltaEvent { delta })
+        | EventMsg::AgentReasoningDelta(AgentReasoningDeltaEvent { delta }) => delta.clone(),
+        EventMsg::AgentMessage(AgentMessageEvent { message }) => message.clone(),
+        EventMsg::TokenCount(usage) => format!("{} tokens used", usage.total_tokens),
+        EventMsg::ExecCommandBegin(ExecCommandBeginEvent { command, .. }) => {
+            let command =
+                try_join(command.iter().map(String::as_str)).unwrap_or_else(|_| command.join(" "));
+            format!("Running {command}")
+        }
+        EventMsg::ExecCommandEnd(ExecCommandEndEvent { exit_code, .. }) => {
+            format!("Command exited with code {exit_code}")
+        }
+        EventMsg::PatchApplyBegin(_) => "Applying patch".to_string(),
+        EventMsg::PatchApplyEnd(PatchApplyEndEvent { success, .. }) => {
+            if *success {
+                "Patch applied".to_string()
+            } else {
+                "Patch failed".to_string()
+            }
+        }
+        EventMsg::McpToolCallBegin(McpToolCallBeginEvent { invocation, .. }) => {
+            format!("Calling {}.{}", invocation.server, invocation.tool)
+        }
+        _ => return None,
+    };
+    Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::protocol::TokenUsage;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    #[test]
+    fn reads_progress_token_from_meta() {
+        assert_eq!(
+            Some(ProgressToken::String("abc".to_string())),
+            progress_token_from_params(Some(&json!({
+                "name": "codex",
+                "_meta": { "progressToken": "abc" },
+            })))
+        );
+        assert_eq!(
+            Some(ProgressToken::Integer(7)),
+            progress_token_from_params(Some(&json!({ "_meta": { "progressToken": 7 } })))
+        );
+        assert_eq!(
+            None,
+            progress_token_from_params(Some(&json!({ "name": "codex" })))
+        );
+        assert_eq!(None, progress_token_from_params(None));
+    }
+
+    #[test]
+    fn summarizes_events() {
+        assert_eq!(
+            Some("Running echo 'hello world'".to_string()),
+            progress_message(&EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
+                call_id: "call".to_string(),
+                command: vec!["echo".to_string(), "hello world".to_string()],
+                cwd: PathBuf::from("/tmp"),
+                parsed_cmd: Vec::new(),
+            }))
+        );
+        assert_eq!(
+            Some("42 tokens used".to_string()),
+            progress_message(&EventMsg::TokenCount(TokenUsage {
+                input_tokens: 40,
+                cached_input_tokens: None,
+                output_tokens: 2,
+                reasoning_output_tokens: None,
+                total_tokens: 42,
+            }))
+        );
+        assert_eq!(None, progress_message(&EventMsg::ShutdownComplete));
+    }
+}
@@ -0,0 +1,248 @@
+//! Custom prompt templates exposed as MCP prompts.
+//!
+//! Every `*.md` file in `$CODEX_HOME/prompts` is a prompt named after the file
+//! stem. The file may start with a front matter block delimited by `---` lines
+//! whose `description:` entry becomes the prompt description. `{{name}}`
+//! placeholders in the body are the (required) arguments of the prompt.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use mcp_types::ContentBlock;
+use mcp_types::GetPromptRequestParams;
+use mcp_types::GetPromptResult;
+use mcp_types::JSONRPCErrorError;
+use mcp_types::ListPromptsResult;
+use mcp_types::Prompt;
+use mcp_types::PromptArgument;
+use mcp_types::PromptMessage;
+use mcp_types::Role;
+use mcp_types::TextContent;
+
+use crate::error_code::INTERNAL_ERROR_CODE;
+use crate::error_code::INVALID_PARAMS_ERROR_CODE;
+use crate::pagination::paginate;
+
+const PROMPTS_SUBDIR: &str = "prompts";
+
+#[derive(Debug, PartialEq)]
+struct PromptTemplate {
+    name: String,
+    description: Option<String>,
+    body: String,
+}
+
+impl PromptTemplate {
+    fn parse(name: String, contents: &str) -> Self {
+        let mut description = None;
+        let mut body = contents;
+        if let Some(rest) = contents
+            .strip_prefix("---\n")
+            .or_else(|| contents.strip_prefix("---\r\n"))
+            && let Some(end) = rest.find("\n---")
+        {
+            for line in rest[..end].lines() {
+                if let Some(value) = line.strip_prefix("description:") {
+                    description = Some(value.trim().to_string());
+                }
+            }
+            body = rest[end + "\n---".len()..].trim_start_matches(['\r', '\n']);
+        }
+        Self {
+            name,
+            description,
+            body: body.to_string(),
+        }
+    }
+
+    /// Names of the `{{name}}` placeholders, in order of first appearance.
+    fn arguments(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        let mut rest = self.body.as_str();
+        while let Some(start) = rest.find("{{") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("}}") else {
+                break;
+            };
+            let name = after[..end].trim();
+            if !name.is_empty() && !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+            rest = &after[end + 2..];
+        }
+        names
+    }
+
+    fn render(&self, values: &BTreeMap<String, String>) -> Result<String, String> {
+        let mut out = String::with_capacity(self.body.len());
+        let mut rest = self.body.as_str();
+        while let Some(start) = rest.find("{{") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("}}") else {
+                break;
+            };
+            let name = after[..end].trim();
+            let value = values
+                .get(name)
+                .ok_or_else(|| format!("missing argument `{name}`"))?;
+            out.push_str(&rest[..start]);
+            out.push_str(value);
+            rest = &after[end + 2..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    fn to_prompt(&self) -> Prompt {
+        let arguments = self.arguments();
+        Prompt {
+            arguments: (!arguments.is_empty()).then(|| {
+                arguments
+                    .into_iter()
+                    .map(|name| PromptArgument {
+                        description: None,
+                        name,
+                        required: Some(true),
+                        title: None,
+                    })
+                    .collect()
+            }),
+            description: self.description.clone(),
+            name: self.name.clone(),
+            title: None,
+        }
+    }
+}
+
+/// Reads every template in `$CODEX_HOME/prompts`, sorted by name. A missing
+/// directory means there are no prompts.
+async fn load_templates(codex_home: &Path) -> std::io::Result<Vec<PromptTemplate>> {
+    let mut entries = match tokio::fs::read_dir(codex_home.join(PROMPTS_SUBDIR)).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut templates = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("md") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let contents = tokio::fs::read_to_string(&path).await?;
+        templates.push(PromptTemplate::parse(name.to_string(), &contents));
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+pub(crate) async fn list_prompts(
+    codex_home: &Path,
+    cursor: Option<&str>,
+) -> Result<ListPromptsResult, JSONRPCErrorError> {
+    let templates = load_templates(codex_home).await.map_err(load_error)?;
+    let (templates, next_cursor) = paginate(templates, cursor)?;
+    Ok(ListPromptsResult {
+        next_cursor,
+        prompts: templates.iter().map(PromptTemplate::to_prompt).collect(),
+    })
+}
+
+pub(crate) async fn get_prompt(
+    codex_home: &Path,
+    params: GetPromptRequestParams,
+) -> Result<GetPromptResult, JSONRPCErrorError> {
+    let GetPromptRequestParams { arguments, name } = params;
+    let invalid_params = |message: String| JSONRPCErrorError {
+        code: INVALID_PARAMS_ERROR_CODE,
+        message,
+        data: None,
+    };
+
+    let template = load_templates(codex_home)
+        .await
+        .map_err(load_error)?
+        .into_iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| invalid_params(format!("unknown prompt: {name}")))?;
+
+    let values: BTreeMap<String, String> = match arguments {
+        Some(arguments) => serde_json::from_value(arguments)
+            .map_err(|e| invalid_params(format!("invalid prompt arguments: {e}")))?,
+        None => BTreeMap::new(),
+    };
+    let text = template.render(&values).map_err(invalid_params)?;
+
+    Ok(GetPromptResult {
+        description: template.description,
+        messages: vec![PromptMessage {
+            content: ContentBlock::TextContent(TextContent {
+                annotations: None,
+                text,
+                r#type: "text".to_string(),
+            }),
+            role: Role::User,
+        }],
+    })
+}
+
+fn load_error(e: std::io::Error) -> JSONRPCErrorError {
+    JSONRPCErrorError {
+        code: INTERNAL_ERROR_CODE,
+        message: format!("failed to load prompts: {e}"),
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_front_matter_and_arguments() {
+        let template = PromptTemplate::parse(
+            "review".to_string(),
+            "---\ndescription: Review a file\n---\nReview {{ path }} for {{focus}}, then {{path}} again.\n",
+        );
+        assert_eq!(Some("Review a file".to_string()), template.description);
+        assert_eq!(vec!["path", "focus"], template.arguments());
+
+        let values = BTreeMap::from([
+            ("path".to_string(), "src/lib.rs".to_string()),
+            ("focus".to_string(), "bugs".to_string()),
+        ]);
+        assert_eq!(
+            Ok("Review src/lib.rs for bugs, then src/lib.rs again.\n".to_string()),
+            template.render(&values)
+        );
+        assert_eq!(
+            Err("missing argument `focus`".to_string()),
+            template.render(&BTreeMap::from([(
+                "path".to_string(),
+                "src/lib.rs".to_string()
+            )]))
+        );
+    }
+
+    #[test]
+    fn template_without_front_matter() {
+        let template = PromptTemplate::parse("plain".to_string(), "Just do it.");
+        assert_eq!(None, template.description);
+        assert_eq!("Just do it.", template.body);
+        assert!(template.arguments().is_empty());
+    }
+
+    #[test]
+    fn parses_front_matter_with_crlf_line_endings() {
+        let template = PromptTemplate::parse(
+            "review".to_string(),
+            "---\r\ndescription: Review a file\r\n---\r\nReview {{path}}.\r\n",
+        );
+        assert_eq!(Some("Review a file".to_string()), template.description);
+        assert_eq!("Review {{path}}.\r\n", template.body);
+    }
+}
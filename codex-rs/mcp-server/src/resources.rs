@@ -0,0 +1,325 @@
+//! MCP resources exposed by the server: the rollout of every recorded
+//! session, the latest turn diff of each conversation followed by this server
+//! and the project's `AGENTS.md`.
+//!
+//! Clients can subscribe to session rollouts and turn diffs; the server sends
+//! `notifications/resources/updated` whenever a turn of the corresponding
+//! conversation completes or produces a new diff.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use codex_core::config::Config;
+use codex_core::project_doc::find_project_doc;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::TurnDiffEvent;
+use codex_core::rollout::find_session;
+use codex_core::rollout::list_sessions;
+use mcp_types::JSONRPCErrorError;
+use mcp_types::ListResourceTemplatesResult;
+use mcp_types::ListResourcesResult;
+use mcp_types::ModelContextProtocolNotification;
+use mcp_types::ReadResourceResult;
+use mcp_types::ReadResourceResultContents;
+use mcp_types::Resource;
+use mcp_types::ResourceListChangedNotification;
+use mcp_types::ResourceTemplate;
+use mcp_types::ResourceUpdatedNotification;
+use mcp_types::ResourceUpdatedNotificationParams;
+use mcp_types::TextResourceContents;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::error_code::INTERNAL_ERROR_CODE;
+use crate::error_code::INVALID_PARAMS_ERROR_CODE;
+use crate::error_code::RESOURCE_NOT_FOUND_ERROR_CODE;
+use crate::outgoing_message::OutgoingMessageSender;
+use crate::outgoing_message::OutgoingNotification;
+use crate::pagination::paginate;
+
+const SESSION_URI_PREFIX: &str = "codex://sessions/";
+const TURN_DIFF_URI_SUFFIX: &str = "/turn-diff";
+const AGENTS_MD_URI: &str = "codex://agents-md";
+
+const ROLLOUT_MIME_TYPE: &str = "application/jsonl";
+const DIFF_MIME_TYPE: &str = "text/x-diff";
+const MARKDOWN_MIME_TYPE: &str = "text/markdown";
+
+#[derive(Debug, PartialEq)]
+enum ResourceUri {
+    /// `codex://sessions/<id>`
+    Session(Uuid),
+    /// `codex://sessions/<id>/turn-diff`
+    TurnDiff(Uuid),
+    /// `codex://agents-md`
+    AgentsMd,
+}
+
+impl ResourceUri {
+    fn parse(uri: &str) -> Option<Self> {
+        if uri == AGENTS_MD_URI {
+            return Some(Self::AgentsMd);
+        }
+        let rest = uri.strip_prefix(SESSION_URI_PREFIX)?;
+        match rest.strip_suffix(TURN_DIFF_URI_SUFFIX) {
+            Some(id) => Uuid::parse_str(id).ok().map(Self::TurnDiff),
+            None => Uuid::parse_str(rest).ok().map(Self::Session),
+        }
+    }
+}
+
+fn session_uri(id: Uuid) -> String {
+    format!("{SESSION_URI_PREFIX}{id}")
+}
+
+fn turn_diff_uri(id: Uuid) -> String {
+    format!("{SESSION_URI_PREFIX}{id}{TURN_DIFF_URI_SUFFIX}")
+}
+
+pub(crate) struct ResourceStore {
+    outgoing: Arc<OutgoingMessageSender>,
+    config: Arc<Config>,
+
+    /// Latest unified diff reported for each conversation.
+    turn_diffs: Mutex<HashMap<Uuid, String>>,
+
+    /// URIs the client subscribed to with `resources/subscribe`.
+    subscriptions: Mutex<HashSet<String>>,
+}
+
+impl ResourceStore {
+    pub(crate) fn new(outgoing: Arc<OutgoingMessageSender>, config: Arc<Config>) -> Self {
+        Self {
+            outgoing,
+            config,
+            turn_diffs: Mutex::new(HashMap::new()),
+            subscriptions: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub(crate) async fn list(
+        &self,
+        cursor: Option<&str>,
+    ) -> Result<ListResourcesResult, JSONRPCErrorError> {
+        let mut resources = Vec::new();
+
+        if let Ok(Some(_)) = find_project_doc(&self.config).await {
+            resources.push(Resource {
+                annotations: None,
+                description: Some("Project instructions for the agent".to_string()),
+                mime_type: Some(MARKDOWN_MIME_TYPE.to_string()),
+                name: "AGENTS.md".to_string(),
+                size: None,
+                title: None,
+                uri: AGENTS_MD_URI.to_string(),
+            });
+        }
+
+        let mut turn_diffs: Vec<(Uuid, usize)> = self
+            .turn_diffs
+            .lock()
+            .await
+            .iter()
+            .map(|(id, diff)| (*id, diff.len()))
+            .collect();
+        turn_diffs.sort();
+        resources.extend(turn_diffs.into_iter().map(|(id, len)| Resource {
+            annotations: None,
+            description: Some(format!("Changes made during the latest turn of {id}")),
+            mime_type: Some(DIFF_MIME_TYPE.to_string()),
+            name: format!("turn-diff-{id}"),
+            size: Some(len as i64),
+            title: None,
+            uri: turn_diff_uri(id),
+        }));
+
+        let sessions = list_sessions(&self.config.codex_home).map_err(internal_error)?;
+        resources.extend(sessions.into_iter().map(|session| {
+            Resource {
+                annotations: None,
+                description: session.first_user_message,
+                mime_type: Some(ROLLOUT_MIME_TYPE.to_string()),
+                name: format!("rollout-{}", session.id),
+                size: std::fs::metadata(&session.path)
+                    .ok()
+                    .map(|m| m.len() as i64),
+                title: Some(format!("Session started {}", session.timestamp)),
+                uri: session_uri(session.id),
+            }
+        }));
+
+        let (resources, next_cursor) = paginate(resources, cursor)?;
+        Ok(ListResourcesResult {
+            next_cursor,
+            resources,
+        })
+    }
+
+    pub(crate) fn list_templates(
+        &self,
+        cursor: Option<&str>,
+    ) -> Result<ListResourceTemplatesResult, JSONRPCErrorError> {
+        let resource_templates = vec![
+            ResourceTemplate {
+                annotations: None,
+                description: Some("Rollout of a recorded session".to_string()),
+                mime_type: Some(ROLLOUT_MIME_TYPE.to_string()),
+                name: "session-rollout".to_string(),
+                title: None,
+                uri_template: format!("{SESSION_URI_PREFIX}{{id}}"),
+            },
+            ResourceTemplate {
+                annotations: None,
+                description: Some(
+                    "Changes made during the latest turn of a conversation".to_string(),
+                ),
+                mime_type: Some(DIFF_MIME_TYPE.to_string()),
+                name: "turn-diff".to_string(),
+                title: None,
+                uri_template: format!("{SESSION_URI_PREFIX}{{id}}{TURN_DIFF_URI_SUFFIX}"),
+            },
+        ];
+        let (resource_templates, next_cursor) = paginate(resource_templates, cursor)?;
+        Ok(ListResourceTemplatesResult {
+            next_cursor,
+            resource_templates,
+        })
+    }
+
+    pub(crate) async fn read(&self, uri: &str) -> Result<ReadResourceResult, JSONRPCErrorError> {
+        let (text, mime_type) = match parse_uri(uri)? {
+            ResourceUri::AgentsMd => {
+                match find_project_doc(&self.config)
+                    .await
+                    .map_err(internal_error)?
+                {
+                    Some(doc) => (doc, MARKDOWN_MIME_TYPE),
+                    None => return Err(not_found(uri)),
+                }
+            }
+            ResourceUri::TurnDiff(id) => match self.turn_diffs.lock().await.get(&id) {
+                Some(diff) => (diff.clone(), DIFF_MIME_TYPE),
+                None => return Err(not_found(uri)),
+            },
+            ResourceUri::Session(id) => {
+                let session = find_session(&self.config.codex_home, &id.to_string())
+                    .map_err(internal_error)?
+                    .ok_or_else(|| not_found(uri))?;
+                let rollout = tokio::fs::read_to_string(&session.path)
+                    .await
+                    .map_err(internal_error)?;
+                (rollout, ROLLOUT_MIME_TYPE)
+            }
+        };
+
+        Ok(ReadResourceResult {
+            contents: vec![ReadResourceResultContents::TextResourceContents(
+                TextResourceContents {
+                    mime_type: Some(mime_type.to_string()),
+                    text,
+                    uri: uri.to_string(),
+                },
+            )],
+        })
+    }
+
+    pub(crate) async fn subscribe(&self, uri: String) -> Result<(), JSONRPCErrorError> {
+        parse_uri(&uri)?;
+        self.subscriptions.lock().await.insert(uri);
+        Ok(())
+    }
+
+    pub(crate) async fn unsubscribe(&self, uri: &str) {
+        self.subscriptions.lock().await.remove(uri);
+    }
+
+    /// Called for every event of a conversation this server is following.
+    pub(crate) async fn observe_event(&self, conversation_id: Uuid, msg: &EventMsg) {
+        match msg {
+            EventMsg::TurnDiff(TurnDiffEvent { unified_diff }) => {
+                let is_new = self
+                    .turn_diffs
+                    .lock()
+                    .await
+                    .insert(conversation_id, unified_diff.clone())
+                    .is_none();
+                if is_new {
+                    self.outgoing
+                        .send_notification(OutgoingNotification {
+                            method: ResourceListChangedNotification::METHOD.to_string(),
+                            params: None,
+                        })
+                        .await;
+                }
+                self.notify_updated(turn_diff_uri(conversation_id)).await;
+            }
+            EventMsg::TaskComplete(_) | EventMsg::TurnAborted(_) => {
+                self.notify_updated(session_uri(conversation_id)).await;
+            }
+            _ => {}
+        }
+    }
+
+    async fn notify_updated(&self, uri: String) {
+        if !self.subscriptions.lock().await.contains(&uri) {
+            return;
+        }
+        let params = ResourceUpdatedNotificationParams { uri };
+        self.outgoing
+            .send_notification(OutgoingNotification {
+                method: ResourceUpdatedNotification::METHOD.to_string(),
+                params: serde_json::to_value(params).ok(),
+            })
+            .await;
+    }
+}
+
+fn parse_uri(uri: &str) -> Result<ResourceUri, JSONRPCErrorError> {
+    ResourceUri::parse(uri).ok_or_else(|| JSONRPCErrorError {
+        code: INVALID_PARAMS_ERROR_CODE,
+        message: format!("unknown resource URI: {uri}"),
+        data: None,
+    })
+}
+
+fn not_found(uri: &str) -> JSONRPCErrorError {
+    JSONRPCErrorError {
+        code: RESOURCE_NOT_FOUND_ERROR_CODE,
+        message: format!("resource not found: {uri}"),
+        data: Some(serde_json::json!({ "uri": uri })),
+    }
+}
+
+fn internal_error(e: std::io::Error) -> JSONRPCErrorError {
+    JSONRPCErrorError {
+        code: INTERNAL_ERROR_CODE,
+        message: e.to_string(),
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_resource_uris() {
+        let id = Uuid::new_v4();
+        assert_eq!(
+            Some(ResourceUri::Session(id)),
+            ResourceUri::parse(&session_uri(id))
+        );
+        assert_eq!(
+            Some(ResourceUri::TurnDiff(id)),
+            ResourceUri::parse(&turn_diff_uri(id))
+        );
+        assert_eq!(
+            Some(ResourceUri::AgentsMd),
+            ResourceUri::parse(AGENTS_MD_URI)
+        );
+        assert_eq!(None, ResourceUri::parse("codex://sessions/not-a-uuid"));
+        assert_eq!(None, ResourceUri::parse("file:///AGENTS.md"));
+    }
+}
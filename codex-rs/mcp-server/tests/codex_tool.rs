@@ -4,15 +4,13 @@ use std::path::Path;
 use std::path::PathBuf;
 
 use codex_core::protocol::FileChange;
-use codex_core::protocol::ReviewDecision;
 use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
 use codex_mcp_server::CodexToolCallParam;
 use codex_mcp_server::ExecApprovalElicitRequestParams;
-use codex_mcp_server::ExecApprovalResponse;
 use codex_mcp_server::PatchApprovalElicitRequestParams;
-use codex_mcp_server::PatchApprovalResponse;
 use mcp_types::ElicitRequest;
 use mcp_types::ElicitRequestParamsRequestedSchema;
+use mcp_types::ElicitResult;
 use mcp_types::JSONRPC_VERSION;
 use mcp_types::JSONRPCRequest;
 use mcp_types::JSONRPCResponse;
@@ -108,8 +106,9 @@ async fn shell_command_approval_triggers_elicitation() -> anyhow::Result<()> {
     mcp_process
         .send_response(
             elicitation_request_id,
-            serde_json::to_value(ExecApprovalResponse {
-                decision: ReviewDecision::Approved,
+            serde_json::to_value(ElicitResult {
+                action: "accept".to_string(),
+                content: None,
             })?,
         )
         .await?;
@@ -173,11 +172,7 @@ fn create_expected_elicitation_request(
         method: ElicitRequest::METHOD.to_string(),
         params: Some(serde_json::to_value(&ExecApprovalElicitRequestParams {
             message: expected_message,
-            requested_schema: ElicitRequestParamsRequestedSchema {
-                r#type: "object".to_string(),
-                properties: json!({}),
-                required: None,
-            },
+            requested_schema: expected_requested_schema(),
             codex_elicitation: "exec-approval".to_string(),
             codex_mcp_tool_call_id,
             codex_event_id,
@@ -263,8 +258,9 @@ async fn patch_approval_triggers_elicitation() -> anyhow::Result<()> {
     mcp_process
         .send_response(
             elicitation_request_id,
-            serde_json::to_value(PatchApprovalResponse {
-                decision: ReviewDecision::Approved,
+            serde_json::to_value(ElicitResult {
+                action: "accept".to_string(),
+                content: None,
             })?,
         )
         .await?;
@@ -366,6 +362,98 @@ async fn codex_tool_passes_base_instructions() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Test that a `codex` tool call with a progress token reports the session's
+/// events as `notifications/progress` keyed to that token.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_codex_tool_reports_progress() {
+    if env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    if let Err(err) = codex_tool_reports_progress().await {
+        panic!("failure: {err}");
+    }
+}
+
+async fn codex_tool_reports_progress() -> anyhow::Result<()> {
+    let McpHandle {
+        process: mut mcp_process,
+        server: _server,
+        dir: _dir,
+    } = create_mcp_process(vec![create_final_assistant_message_sse_response(
+        "All done.",
+    )?])
+    .await?;
+
+    let codex_request_id = mcp_process
+        .send_codex_tool_call_with_progress_token(
+            CodexToolCallParam {
+                prompt: "say you are done".to_string(),
+                ..Default::default()
+            },
+            "progress-1",
+        )
+        .await?;
+
+    let started = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp_process.read_stream_until_notification_message("notifications/progress"),
+    )
+    .await??;
+    assert_eq!(
+        Some(json!({
+            "message": "Task started",
+            "progress": 1.0,
+            "progressToken": "progress-1",
+        })),
+        started.params
+    );
+
+    let delta = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp_process.read_stream_until_notification_message("notifications/progress"),
+    )
+    .await??;
+    assert_eq!(
+        Some(json!({
+            "message": "All done.",
+            "progress": 2.0,
+            "progressToken": "progress-1",
+        })),
+        delta.params
+    );
+
+    let task_complete = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp_process.read_stream_until_legacy_task_complete_notification(),
+    )
+    .await??;
+    assert_eq!(
+        Some(&json!({
+            "requestId": codex_request_id,
+            "progressToken": "progress-1",
+        })),
+        task_complete.params.as_ref().and_then(|p| p.get("_meta"))
+    );
+
+    let response = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp_process.read_stream_until_response_message(RequestId::Integer(codex_request_id)),
+    )
+    .await??;
+    assert_eq!(
+        json!({
+            "content": [{ "type": "text", "text": "All done." }],
+        }),
+        response.result
+    );
+
+    Ok(())
+}
+
 fn create_expected_patch_approval_elicitation_request(
     elicitation_request_id: RequestId,
     changes: HashMap<PathBuf, FileChange>,
@@ -386,11 +474,7 @@ fn create_expected_patch_approval_elicitation_request(
         method: ElicitRequest::METHOD.to_string(),
         params: Some(serde_json::to_value(&PatchApprovalElicitRequestParams {
             message: message_lines.join("\n"),
-            requested_schema: ElicitRequestParamsRequestedSchema {
-                r#type: "object".to_string(),
-                properties: json!({}),
-                required: None,
-            },
+            requested_schema: expected_requested_schema(),
             codex_elicitation: "patch-approval".to_string(),
             codex_mcp_tool_call_id,
             codex_event_id,
@@ -452,3 +536,18 @@ stream_max_retries = 0
         ),
     )
 }
+
+fn expected_requested_schema() -> ElicitRequestParamsRequestedSchema {
+    ElicitRequestParamsRequestedSchema {
+        r#type: "object".to_string(),
+        properties: json!({
+            "approve_for_session": {
+                "type": "boolean",
+                "title": "Approve for session",
+                "description": "Do not ask again for identical requests in this session",
+                "default": false,
+            }
+        }),
+        required: None,
+    }
+}
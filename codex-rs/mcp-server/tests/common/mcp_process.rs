@@ -13,6 +13,7 @@ use anyhow::Context;
 use assert_cmd::prelude::*;
 use codex_mcp_server::CodexToolCallParam;
 use codex_protocol::mcp_protocol::AddConversationListenerParams;
+use codex_protocol::mcp_protocol::ForkConversationParams;
 use codex_protocol::mcp_protocol::InterruptConversationParams;
 use codex_protocol::mcp_protocol::NewConversationParams;
 use codex_protocol::mcp_protocol::RemoveConversationListenerParams;
@@ -21,6 +22,7 @@ use codex_protocol::mcp_protocol::SendUserTurnParams;
 
 use mcp_types::CallToolRequestParams;
 use mcp_types::ClientCapabilities;
+use mcp_types::GetPromptRequestParams;
 use mcp_types::Implementation;
 use mcp_types::InitializeRequestParams;
 use mcp_types::JSONRPC_VERSION;
@@ -30,7 +32,9 @@ use mcp_types::JSONRPCRequest;
 use mcp_types::JSONRPCResponse;
 use mcp_types::ModelContextProtocolNotification;
 use mcp_types::ModelContextProtocolRequest;
+use mcp_types::ReadResourceRequestParams;
 use mcp_types::RequestId;
+use mcp_types::SubscribeRequestParams;
 use pretty_assertions::assert_eq;
 use serde_json::json;
 use std::process::Command as StdCommand;
@@ -49,6 +53,12 @@ pub struct McpProcess {
 
 impl McpProcess {
     pub async fn new(codex_home: &Path) -> anyhow::Result<Self> {
+        Self::new_with_cwd(codex_home, None).await
+    }
+
+    /// Like [`McpProcess::new`], but runs the server from `cwd` instead of
+    /// inheriting the working directory of the test.
+    pub async fn new_with_cwd(codex_home: &Path, cwd: Option<&Path>) -> anyhow::Result<Self> {
         // Use assert_cmd to locate the binary path and then switch to tokio::process::Command
         let std_cmd = StdCommand::cargo_bin("codex-mcp-server")
             .context("should find binary for codex-mcp-server")?;
@@ -61,6 +71,9 @@ impl McpProcess {
         cmd.stdout(Stdio::piped());
         cmd.env("CODEX_HOME", codex_home);
         cmd.env("RUST_LOG", "debug");
+        if let Some(cwd) = cwd {
+            cmd.current_dir(cwd);
+        }
 
         let mut process = cmd
             .kill_on_drop(true)
@@ -118,6 +131,13 @@ impl McpProcess {
                 id: RequestId::Integer(request_id),
                 result: json!({
                     "capabilities": {
+                        "prompts": {
+                            "listChanged": false
+                        },
+                        "resources": {
+                            "listChanged": true,
+                            "subscribe": true
+                        },
                         "tools": {
                             "listChanged": true
                         },
@@ -161,6 +181,25 @@ impl McpProcess {
         .await
     }
 
+    /// Like [`Self::send_codex_tool_call`], but asks for
+    /// `notifications/progress` keyed to `progress_token`.
+    pub async fn send_codex_tool_call_with_progress_token(
+        &mut self,
+        params: CodexToolCallParam,
+        progress_token: &str,
+    ) -> anyhow::Result<i64> {
+        let mut codex_tool_call_params = serde_json::to_value(CallToolRequestParams {
+            name: "codex".to_string(),
+            arguments: Some(serde_json::to_value(params)?),
+        })?;
+        codex_tool_call_params["_meta"] = json!({ "progressToken": progress_token });
+        self.send_request(
+            mcp_types::CallToolRequest::METHOD,
+            Some(codex_tool_call_params),
+        )
+        .await
+    }
+
     /// Send a `newConversation` JSON-RPC request.
     pub async fn send_new_conversation_request(
         &mut self,
@@ -170,6 +209,15 @@ impl McpProcess {
         self.send_request("newConversation", params).await
     }
 
+    /// Send a `forkConversation` JSON-RPC request.
+    pub async fn send_fork_conversation_request(
+        &mut self,
+        params: ForkConversationParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request("forkConversation", params).await
+    }
+
     /// Send an `addConversationListener` JSON-RPC request.
     pub async fn send_add_conversation_listener_request(
         &mut self,
@@ -217,6 +265,46 @@ impl McpProcess {
         self.send_request("interruptConversation", params).await
     }
 
+    /// Send a `resources/list` JSON-RPC request.
+    pub async fn send_list_resources_request(&mut self) -> anyhow::Result<i64> {
+        self.send_request(mcp_types::ListResourcesRequest::METHOD, None)
+            .await
+    }
+
+    /// Send a `resources/read` JSON-RPC request.
+    pub async fn send_read_resource_request(&mut self, uri: &str) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(ReadResourceRequestParams {
+            uri: uri.to_string(),
+        })?);
+        self.send_request(mcp_types::ReadResourceRequest::METHOD, params)
+            .await
+    }
+
+    /// Send a `resources/subscribe` JSON-RPC request.
+    pub async fn send_subscribe_request(&mut self, uri: &str) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(SubscribeRequestParams {
+            uri: uri.to_string(),
+        })?);
+        self.send_request(mcp_types::SubscribeRequest::METHOD, params)
+            .await
+    }
+
+    /// Send a `prompts/list` JSON-RPC request.
+    pub async fn send_list_prompts_request(&mut self) -> anyhow::Result<i64> {
+        self.send_request(mcp_types::ListPromptsRequest::METHOD, None)
+            .await
+    }
+
+    /// Send a `prompts/get` JSON-RPC request.
+    pub async fn send_get_prompt_request(
+        &mut self,
+        params: GetPromptRequestParams,
+    ) -> anyhow::Result<i64> {
+        let params = Some(serde_json::to_value(params)?);
+        self.send_request(mcp_types::GetPromptRequest::METHOD, params)
+            .await
+    }
+
     async fn send_request(
         &mut self,
         method: &str,
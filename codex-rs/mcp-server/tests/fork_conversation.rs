@@ -0,0 +1,121 @@
+use std::path::Path;
+
+use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::mcp_protocol::ForkConversationParams;
+use codex_protocol::mcp_protocol::ForkConversationResponse;
+use codex_protocol::mcp_protocol::NewConversationParams;
+use codex_protocol::mcp_protocol::NewConversationResponse;
+use mcp_test_support::McpProcess;
+use mcp_test_support::create_mock_chat_completions_server;
+use mcp_test_support::to_response;
+use mcp_types::JSONRPCResponse;
+use mcp_types::RequestId;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use tokio::time::timeout;
+use uuid::Uuid;
+
+const DEFAULT_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_fork_conversation() {
+    let server = create_mock_chat_completions_server(vec![]).await;
+
+    let codex_home = TempDir::new().expect("create temp dir");
+    create_config_toml(codex_home.path(), &server.uri()).expect("write config.toml");
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timeout")
+        .expect("init failed");
+
+    let new_conv_id = mcp
+        .send_new_conversation_request(NewConversationParams {
+            model: Some("o3".to_string()),
+            ..Default::default()
+        })
+        .await
+        .expect("send newConversation");
+    let new_conv_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(new_conv_id)),
+    )
+    .await
+    .expect("newConversation timeout")
+    .expect("newConversation resp");
+    let NewConversationResponse {
+        conversation_id, ..
+    } = to_response::<NewConversationResponse>(new_conv_resp)
+        .expect("deserialize newConversation response");
+
+    // Forking an existing conversation yields a new one with the same model.
+    let fork_id = mcp
+        .send_fork_conversation_request(ForkConversationParams {
+            conversation_id,
+            drop_last_n_turns: 0,
+        })
+        .await
+        .expect("send forkConversation");
+    let fork_resp: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(fork_id)),
+    )
+    .await
+    .expect("forkConversation timeout")
+    .expect("forkConversation resp");
+    let ForkConversationResponse {
+        conversation_id: forked_conversation_id,
+        model,
+    } = to_response::<ForkConversationResponse>(fork_resp)
+        .expect("deserialize forkConversation response");
+    assert_eq!(model, "o3");
+    assert_ne!(forked_conversation_id, conversation_id);
+
+    // Forking an unknown conversation is an error.
+    let missing_id = mcp
+        .send_fork_conversation_request(ForkConversationParams {
+            conversation_id: ConversationId(Uuid::new_v4()),
+            drop_last_n_turns: 0,
+        })
+        .await
+        .expect("send forkConversation");
+    let error = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_error_message(RequestId::Integer(missing_id)),
+    )
+    .await
+    .expect("forkConversation timeout")
+    .expect("forkConversation error");
+    assert!(
+        error.error.message.starts_with("conversation not found"),
+        "{}",
+        error.error.message
+    );
+}
+
+// Helper to create a config.toml pointing at the mock model server.
+fn create_config_toml(codex_home: &Path, server_uri: &str) -> std::io::Result<()> {
+    let config_toml = codex_home.join("config.toml");
+    std::fs::write(
+        config_toml,
+        format!(
+            r#"
+model = "mock-model"
+approval_policy = "never"
+sandbox_mode = "danger-full-access"
+
+model_provider = "mock_provider"
+
+[model_providers.mock_provider]
+name = "Mock provider for test"
+base_url = "{server_uri}/v1"
+wire_api = "chat"
+request_max_retries = 0
+stream_max_retries = 0
+"#
+        ),
+    )
+}
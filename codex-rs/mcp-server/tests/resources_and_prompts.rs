@@ -0,0 +1,289 @@
+use std::env;
+use std::path::Path;
+
+use codex_core::protocol::AskForApproval;
+use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use codex_protocol::config_types::SandboxMode;
+use codex_protocol::mcp_protocol::AddConversationListenerParams;
+use codex_protocol::mcp_protocol::InputItem;
+use codex_protocol::mcp_protocol::NewConversationParams;
+use codex_protocol::mcp_protocol::NewConversationResponse;
+use codex_protocol::mcp_protocol::SendUserMessageParams;
+use mcp_test_support::McpProcess;
+use mcp_test_support::create_apply_patch_sse_response;
+use mcp_test_support::create_final_assistant_message_sse_response;
+use mcp_test_support::create_mock_chat_completions_server;
+use mcp_test_support::to_response;
+use mcp_types::GetPromptRequestParams;
+use mcp_types::GetPromptResult;
+use mcp_types::ListPromptsResult;
+use mcp_types::ListResourcesResult;
+use mcp_types::ReadResourceResult;
+use mcp_types::ReadResourceResultContents;
+use mcp_types::RequestId;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tempfile::TempDir;
+use tokio::time::timeout;
+
+const DEFAULT_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_turn_diff_resource_subscription() {
+    if env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    if let Err(err) = turn_diff_resource_subscription().await {
+        panic!("failure: {err}");
+    }
+}
+
+async fn turn_diff_resource_subscription() -> anyhow::Result<()> {
+    let tmp = TempDir::new()?;
+    let codex_home = tmp.path().join("codex_home");
+    std::fs::create_dir(&codex_home)?;
+    let working_directory = tmp.path().join("workdir");
+    std::fs::create_dir(&working_directory)?;
+    let test_file = working_directory.join("notes.txt");
+    std::fs::write(&test_file, "original content\n")?;
+
+    let patch_content = format!(
+        "*** Begin Patch\n*** Update File: {}\n-original content\n+modified content\n*** End Patch",
+        test_file.to_string_lossy()
+    );
+    let server = create_mock_chat_completions_server(vec![
+        create_apply_patch_sse_response(&patch_content, "call1234")?,
+        create_final_assistant_message_sse_response("Done.")?,
+    ])
+    .await;
+    create_config_toml(&codex_home, &server.uri())?;
+
+    let mut mcp = McpProcess::new(&codex_home).await?;
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize()).await??;
+
+    let new_conv_id = mcp
+        .send_new_conversation_request(NewConversationParams {
+            cwd: Some(working_directory.to_string_lossy().into_owned()),
+            approval_policy: Some(AskForApproval::Never),
+            sandbox: Some(SandboxMode::DangerFullAccess),
+            ..Default::default()
+        })
+        .await?;
+    let new_conv_resp = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(new_conv_id)),
+    )
+    .await??;
+    let NewConversationResponse {
+        conversation_id, ..
+    } = to_response::<NewConversationResponse>(new_conv_resp)?;
+
+    let turn_diff_uri = format!("codex://sessions/{conversation_id}/turn-diff");
+    let subscribe_id = mcp.send_subscribe_request(&turn_diff_uri).await?;
+    timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(subscribe_id)),
+    )
+    .await??;
+
+    let add_listener_id = mcp
+        .send_add_conversation_listener_request(AddConversationListenerParams { conversation_id })
+        .await?;
+    timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(add_listener_id)),
+    )
+    .await??;
+
+    let send_user_id = mcp
+        .send_send_user_message_request(SendUserMessageParams {
+            conversation_id,
+            items: vec![InputItem::Text {
+                text: "please edit notes.txt".to_string(),
+            }],
+        })
+        .await?;
+    timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(send_user_id)),
+    )
+    .await??;
+
+    let updated = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_notification_message("notifications/resources/updated"),
+    )
+    .await??;
+    assert_eq!(Some(json!({ "uri": turn_diff_uri })), updated.params);
+
+    let read_id = mcp.send_read_resource_request(&turn_diff_uri).await?;
+    let read_resp = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(read_id)),
+    )
+    .await??;
+    let ReadResourceResult { contents } = to_response::<ReadResourceResult>(read_resp)?;
+    let [ReadResourceResultContents::TextResourceContents(diff)] = contents.as_slice() else {
+        panic!("expected a single text resource, got {contents:?}");
+    };
+    assert_eq!(Some("text/x-diff".to_string()), diff.mime_type);
+    assert!(
+        diff.text.contains("-original content\n+modified content"),
+        "unexpected diff: {}",
+        diff.text
+    );
+
+    let list_id = mcp.send_list_resources_request().await?;
+    let list_resp = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(list_id)),
+    )
+    .await??;
+    let ListResourcesResult { resources, .. } = to_response::<ListResourcesResult>(list_resp)?;
+    let uris: Vec<&str> = resources.iter().map(|r| r.uri.as_str()).collect();
+    assert!(uris.contains(&turn_diff_uri.as_str()), "{uris:?}");
+    let session_uri = format!("codex://sessions/{conversation_id}");
+    assert!(uris.contains(&session_uri.as_str()), "{uris:?}");
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_agents_md_resource_and_unknown_uri() {
+    if let Err(err) = agents_md_resource_and_unknown_uri().await {
+        panic!("failure: {err}");
+    }
+}
+
+async fn agents_md_resource_and_unknown_uri() -> anyhow::Result<()> {
+    let codex_home = TempDir::new()?;
+    let project = TempDir::new()?;
+    std::fs::write(project.path().join("AGENTS.md"), "Always run the tests.")?;
+
+    let mut mcp = McpProcess::new_with_cwd(codex_home.path(), Some(project.path())).await?;
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize()).await??;
+
+    let read_id = mcp.send_read_resource_request("codex://agents-md").await?;
+    let read_resp = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(read_id)),
+    )
+    .await??;
+    let ReadResourceResult { contents } = to_response::<ReadResourceResult>(read_resp)?;
+    let [ReadResourceResultContents::TextResourceContents(doc)] = contents.as_slice() else {
+        panic!("expected a single text resource, got {contents:?}");
+    };
+    assert_eq!("Always run the tests.", doc.text);
+
+    // No diff has been produced for this conversation yet.
+    let missing_uri = "codex://sessions/67e55044-10b1-426f-9247-bb680e5fe0c8/turn-diff";
+    let missing_id = mcp.send_read_resource_request(missing_uri).await?;
+    let err = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_error_message(RequestId::Integer(missing_id)),
+    )
+    .await??;
+    assert_eq!(-32002, err.error.code);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_prompts_from_codex_home() {
+    if let Err(err) = prompts_from_codex_home().await {
+        panic!("failure: {err}");
+    }
+}
+
+async fn prompts_from_codex_home() -> anyhow::Result<()> {
+    let codex_home = TempDir::new()?;
+    let prompts_dir = codex_home.path().join("prompts");
+    std::fs::create_dir(&prompts_dir)?;
+    std::fs::write(
+        prompts_dir.join("review.md"),
+        "---\ndescription: Review a file\n---\nReview {{path}} carefully.\n",
+    )?;
+    std::fs::write(prompts_dir.join("notes.txt"), "not a prompt")?;
+
+    let mut mcp = McpProcess::new(codex_home.path()).await?;
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize()).await??;
+
+    let list_id = mcp.send_list_prompts_request().await?;
+    let list_resp = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(list_id)),
+    )
+    .await??;
+    let ListPromptsResult { prompts, .. } = to_response::<ListPromptsResult>(list_resp)?;
+    assert_eq!(
+        json!([{
+            "name": "review",
+            "description": "Review a file",
+            "arguments": [{"name": "path", "required": true}],
+        }]),
+        serde_json::to_value(prompts)?
+    );
+
+    let get_id = mcp
+        .send_get_prompt_request(GetPromptRequestParams {
+            name: "review".to_string(),
+            arguments: Some(json!({"path": "src/lib.rs"})),
+        })
+        .await?;
+    let get_resp = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(get_id)),
+    )
+    .await??;
+    let result = to_response::<GetPromptResult>(get_resp)?;
+    assert_eq!(
+        json!({
+            "description": "Review a file",
+            "messages": [{
+                "role": "user",
+                "content": {"type": "text", "text": "Review src/lib.rs carefully.\n"},
+            }],
+        }),
+        serde_json::to_value(result)?
+    );
+
+    let missing_arg_id = mcp
+        .send_get_prompt_request(GetPromptRequestParams {
+            name: "review".to_string(),
+            arguments: None,
+        })
+        .await?;
+    let err = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_error_message(RequestId::Integer(missing_arg_id)),
+    )
+    .await??;
+    assert_eq!(-32602, err.error.code);
+
+    Ok(())
+}
+
+fn create_config_toml(codex_home: &Path, server_uri: &str) -> std::io::Result<()> {
+    let config_toml = codex_home.join("config.toml");
+    std::fs::write(
+        config_toml,
+        format!(
+            r#"
+model = "mock-model"
+
+model_provider = "mock_provider"
+
+[model_providers.mock_provider]
+name = "Mock provider for test"
+base_url = "{server_uri}/v1"
+wire_api = "chat"
+request_max_retries = 0
+stream_max_retries = 0
+"#
+        ),
+    )
+}
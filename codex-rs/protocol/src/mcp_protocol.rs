@@ -45,6 +45,11 @@ pub enum ClientRequest {
         request_id: RequestId,
         params: NewConversationParams,
     },
+    ForkConversation {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: ForkConversationParams,
+    },
     SendUserMessage {
         #[serde(rename = "id")]
         request_id: RequestId,
@@ -60,6 +65,11 @@ pub enum ClientRequest {
         request_id: RequestId,
         params: InterruptConversationParams,
     },
+    UndoTurn {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: UndoTurnParams,
+    },
     AddConversationListener {
         #[serde(rename = "id")]
         request_id: RequestId,
@@ -136,6 +146,25 @@ pub struct NewConversationResponse {
     pub model: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkConversationParams {
+    /// Conversation to copy the history from.
+    pub conversation_id: ConversationId,
+
+    /// Number of most recent turns (a user message and everything after it)
+    /// to leave out of the fork. `0` copies the whole history.
+    #[serde(default)]
+    pub drop_last_n_turns: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkConversationResponse {
+    pub conversation_id: ConversationId,
+    pub model: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 #[serde(rename_all = "camelCase")]
 pub struct AddConversationSubscriptionResponse {
@@ -225,6 +254,23 @@ pub struct InterruptConversationResponse {
     pub abort_reason: TurnAbortReason,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoTurnParams {
+    pub conversation_id: ConversationId,
+    /// Checkpoint to restore, as reported by a `CheckpointCreated` event.
+    /// Defaults to the checkpoint of the most recent turn that modified
+    /// files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkpoint_id: Option<String>,
+}
+
+/// The outcome is reported to conversation listeners as a
+/// `CheckpointRestored` or `Error` event.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoTurnResponse {}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 #[serde(rename_all = "camelCase")]
 pub struct SendUserMessageResponse {}
@@ -264,6 +310,7 @@ pub enum InputItem {
 
 pub const APPLY_PATCH_APPROVAL_METHOD: &str = "applyPatchApproval";
 pub const EXEC_COMMAND_APPROVAL_METHOD: &str = "execCommandApproval";
+pub const BUDGET_APPROVAL_METHOD: &str = "budgetApproval";
 
 /// Request initiated from the server and sent to the client.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -281,6 +328,12 @@ pub enum ServerRequest {
         request_id: RequestId,
         params: ExecCommandApprovalParams,
     },
+    /// Request to continue after the session budget was exceeded.
+    BudgetApproval {
+        #[serde(rename = "id")]
+        request_id: RequestId,
+        params: BudgetApprovalParams,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -311,6 +364,19 @@ pub struct ExecCommandApprovalParams {
     pub reason: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct BudgetApprovalParams {
+    pub conversation_id: ConversationId,
+    /// Tokens used by the conversation so far.
+    pub used_tokens: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+    /// Estimated cost of the conversation so far, in US dollars.
+    pub cost_usd: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_cost_usd: Option<f64>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 pub struct ExecCommandApprovalResponse {
     pub decision: ReviewDecision,
@@ -321,6 +387,11 @@ pub struct ApplyPatchApprovalResponse {
     pub decision: ReviewDecision,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct BudgetApprovalResponse {
+    pub decision: ReviewDecision,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -355,4 +426,48 @@ mod tests {
             serde_json::to_value(&request).unwrap(),
         );
     }
+
+    #[test]
+    fn serialize_fork_conversation() {
+        let conversation_id = ConversationId(Uuid::nil());
+        let request = ClientRequest::ForkConversation {
+            request_id: RequestId::Integer(7),
+            params: ForkConversationParams {
+                conversation_id,
+                drop_last_n_turns: 1,
+            },
+        };
+        assert_eq!(
+            json!({
+                "method": "forkConversation",
+                "id": 7,
+                "params": {
+                    "conversationId": "00000000-0000-0000-0000-000000000000",
+                    "dropLastNTurns": 1
+                }
+            }),
+            serde_json::to_value(&request).unwrap(),
+        );
+    }
+
+    #[test]
+    fn serialize_undo_turn() {
+        let request = ClientRequest::UndoTurn {
+            request_id: RequestId::Integer(8),
+            params: UndoTurnParams {
+                conversation_id: ConversationId(Uuid::nil()),
+                checkpoint_id: None,
+            },
+        };
+        assert_eq!(
+            json!({
+                "method": "undoTurn",
+                "id": 8,
+                "params": {
+                    "conversationId": "00000000-0000-0000-0000-000000000000"
+                }
+            }),
+            serde_json::to_value(&request).unwrap(),
+        );
+    }
 }
@@ -24,3 +24,55 @@ pub struct UpdatePlanArgs {
     pub explanation: Option<String>,
     pub plan: Vec<PlanItemArg>,
 }
+
+/// How far along a plan is, as reported when a task completes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanProgress {
+    pub completed: usize,
+    pub in_progress: usize,
+    pub total: usize,
+}
+
+impl UpdatePlanArgs {
+    pub fn progress(&self) -> PlanProgress {
+        let count = |status: fn(&StepStatus) -> bool| {
+            self.plan.iter().filter(|item| status(&item.status)).count()
+        };
+        PlanProgress {
+            completed: count(|s| matches!(s, StepStatus::Completed)),
+            in_progress: count(|s| matches!(s, StepStatus::InProgress)),
+            total: self.plan.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn counts_steps_by_status() {
+        let step = |status| PlanItemArg {
+            step: "step".to_string(),
+            status,
+        };
+        let args = UpdatePlanArgs {
+            explanation: None,
+            plan: vec![
+                step(StepStatus::Completed),
+                step(StepStatus::Completed),
+                step(StepStatus::InProgress),
+                step(StepStatus::Pending),
+            ],
+        };
+        assert_eq!(
+            PlanProgress {
+                completed: 2,
+                in_progress: 1,
+                total: 4,
+            },
+            args.progress()
+        );
+    }
+}
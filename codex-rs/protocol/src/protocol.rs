@@ -11,6 +11,8 @@ use std::str::FromStr;
 use std::time::Duration;
 
 use mcp_types::CallToolResult;
+use mcp_types::Prompt as McpPrompt;
+use mcp_types::Resource as McpResource;
 use mcp_types::Tool as McpTool;
 use serde::Deserialize;
 use serde::Serialize;
@@ -23,6 +25,7 @@ use crate::config_types::ReasoningEffort as ReasoningEffortConfig;
 use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
 use crate::message_history::HistoryEntry;
 use crate::parse_command::ParsedCommand;
+use crate::plan_tool::PlanProgress;
 use crate::plan_tool::UpdatePlanArgs;
 
 /// Submission Queue Entry - requests from user
@@ -125,6 +128,14 @@ pub enum Op {
         decision: ReviewDecision,
     },
 
+    /// Allow the agent to keep going after it exceeded the session budget.
+    BudgetApproval {
+        /// The id of the submission we are approving
+        id: String,
+        /// The user's decision in response to the request.
+        decision: ReviewDecision,
+    },
+
     /// Append an entry to the persistent cross-session message history.
     ///
     /// Note the entry is not guaranteed to be logged if the user has
@@ -141,10 +152,44 @@ pub enum Op {
     /// Reply is delivered via `EventMsg::McpListToolsResponse`.
     ListMcpTools,
 
+    /// Request the resources offered by each configured MCP server.
+    /// Reply is delivered via `EventMsg::McpListResourcesResponse`.
+    ListMcpResources,
+
+    /// Request the prompts offered by each configured MCP server.
+    /// Reply is delivered via `EventMsg::McpListPromptsResponse`.
+    ListMcpPrompts,
+
+    /// Fetch a prompt from an MCP server and submit its trailing user
+    /// messages as user input, exactly as [`Op::UserInput`] would. Earlier
+    /// messages are added to the history with their roles first.
+    RunMcpPrompt {
+        /// Name of the MCP server as defined in the config.
+        server: String,
+        /// Name of the prompt as given by the MCP server.
+        prompt: String,
+        /// Values for the prompt's arguments.
+        arguments: HashMap<String, String>,
+    },
+
     /// Request the agent to summarize the current conversation context.
     /// The agent will use its existing context (either conversation history or previous response id)
     /// to generate a summary which will be returned as an AgentMessage event.
     Compact,
+
+    /// Restore the working tree to the checkpoint taken before the most
+    /// recent turn that modified files. Reply is delivered via
+    /// `EventMsg::CheckpointRestored`.
+    UndoTurn,
+
+    /// Restore the working tree to the given checkpoint. The checkpoint and
+    /// all checkpoints taken after it are discarded. Reply is delivered via
+    /// `EventMsg::CheckpointRestored`.
+    RestoreCheckpoint {
+        /// Id reported in `EventMsg::CheckpointCreated`.
+        checkpoint_id: String,
+    },
+
     /// Request to shut down codex instance.
     Shutdown,
 }
@@ -199,11 +244,24 @@ pub enum SandboxPolicy {
         #[serde(default, skip_serializing_if = "Vec::is_empty")]
         writable_roots: Vec<PathBuf>,
 
+        /// When set, read access is restricted to these folders in addition
+        /// to the writable roots and a default set of system and toolchain
+        /// folders (see [`SandboxPolicy::get_readable_roots_with_cwd`]). When
+        /// `None`, the entire file-system is readable.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        readable_roots: Option<Vec<PathBuf>>,
+
         /// When set to `true`, outbound network access is allowed. `false` by
         /// default.
         #[serde(default)]
         network_access: bool,
 
+        /// Hosts that commands may reach through the local network proxy when
+        /// `network_access` is `false`. Entries are host names, optionally
+        /// prefixed with `*.` to match subdomains and suffixed with `:port`.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        network_allowlist: Vec<String>,
+
         /// When set to `true`, will NOT include the per-user `TMPDIR`
         /// environment variable among the default writable roots. Defaults to
         /// `false`.
@@ -248,6 +306,28 @@ impl WritableRoot {
     }
 }
 
+/// Folders that remain readable when read access is restricted so that shells
+/// and the programs they launch keep working.
+#[cfg(target_os = "macos")]
+const DEFAULT_READABLE_ROOTS: &[&str] = &[
+    "/bin",
+    "/sbin",
+    "/usr",
+    "/dev",
+    "/System",
+    "/Library",
+    "/private/etc",
+    "/private/var/db",
+    "/opt/homebrew",
+];
+
+/// Folders that remain readable when read access is restricted so that shells
+/// and the programs they launch keep working.
+#[cfg(not(target_os = "macos"))]
+const DEFAULT_READABLE_ROOTS: &[&str] = &[
+    "/bin", "/sbin", "/usr", "/lib", "/lib32", "/lib64", "/etc", "/dev", "/proc",
+];
+
 impl FromStr for SandboxPolicy {
     type Err = serde_json::Error;
 
@@ -268,15 +348,20 @@ impl SandboxPolicy {
     pub fn new_workspace_write_policy() -> Self {
         SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
+            readable_roots: None,
             network_access: false,
+            network_allowlist: vec![],
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
         }
     }
 
-    /// Always returns `true`; restricting read access is not supported.
     pub fn has_full_disk_read_access(&self) -> bool {
-        true
+        match self {
+            SandboxPolicy::DangerFullAccess => true,
+            SandboxPolicy::ReadOnly => true,
+            SandboxPolicy::WorkspaceWrite { readable_roots, .. } => readable_roots.is_none(),
+        }
     }
 
     pub fn has_full_disk_write_access(&self) -> bool {
@@ -295,6 +380,19 @@ impl SandboxPolicy {
         }
     }
 
+    /// Hosts reachable through the network proxy. Empty unless network
+    /// access is otherwise disabled.
+    pub fn get_network_allowlist(&self) -> &[String] {
+        match self {
+            SandboxPolicy::WorkspaceWrite {
+                network_access: false,
+                network_allowlist,
+                ..
+            } => network_allowlist,
+            _ => &[],
+        }
+    }
+
     /// Returns the list of writable roots (tailored to the current working
     /// directory) together with subpaths that should remain read‑only under
     /// each writable root.
@@ -306,7 +404,9 @@ impl SandboxPolicy {
                 writable_roots,
                 exclude_tmpdir_env_var,
                 exclude_slash_tmp,
+                readable_roots: _,
                 network_access: _,
+                network_allowlist: _,
             } => {
                 // Start from explicitly configured writable roots.
                 let mut roots: Vec<PathBuf> = writable_roots.clone();
@@ -356,6 +456,44 @@ impl SandboxPolicy {
             }
         }
     }
+
+    /// Returns the folders (tailored to the current working directory) that
+    /// are readable from within the sandbox when read access is restricted.
+    /// This includes the writable roots, the configured `readable_roots`, the
+    /// Rust toolchain folders under `CARGO_HOME` and `RUSTUP_HOME`, and a
+    /// default set of system folders. Folders that do not exist are omitted.
+    ///
+    /// Returns an empty list if [`Self::has_full_disk_read_access`] is `true`.
+    pub fn get_readable_roots_with_cwd(&self, cwd: &Path) -> Vec<PathBuf> {
+        let SandboxPolicy::WorkspaceWrite {
+            readable_roots: Some(readable_roots),
+            ..
+        } = self
+        else {
+            return Vec::new();
+        };
+
+        let mut roots: Vec<PathBuf> = DEFAULT_READABLE_ROOTS.iter().map(PathBuf::from).collect();
+
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        for (env_var, default_dir) in [("CARGO_HOME", ".cargo"), ("RUSTUP_HOME", ".rustup")] {
+            match std::env::var_os(env_var) {
+                Some(dir) if !dir.is_empty() => roots.push(PathBuf::from(dir)),
+                _ => roots.extend(home.as_ref().map(|home| home.join(default_dir))),
+            }
+        }
+
+        roots.extend(readable_roots.iter().cloned());
+        roots.extend(
+            self.get_writable_roots_with_cwd(cwd)
+                .into_iter()
+                .map(|writable_root| writable_root.root),
+        );
+
+        roots.retain(|root| root.exists());
+        roots.dedup();
+        roots
+    }
 }
 
 /// User input
@@ -432,6 +570,12 @@ pub enum EventMsg {
 
     McpToolCallEnd(McpToolCallEndEvent),
 
+    /// Notification that a built-in file tool (`read_file`, `grep` or
+    /// `list_dir`) is about to run.
+    FileToolCallBegin(FileToolCallBeginEvent),
+
+    FileToolCallEnd(FileToolCallEndEvent),
+
     /// Notification that the server is about to execute a command.
     ExecCommandBegin(ExecCommandBeginEvent),
 
@@ -444,8 +588,15 @@ pub enum EventMsg {
 
     ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent),
 
+    /// The session exceeded `max_session_tokens` or `max_session_cost`; the
+    /// agent is paused until the user decides whether it may continue.
+    BudgetApprovalRequest(BudgetApprovalRequestEvent),
+
     BackgroundEvent(BackgroundEventEvent),
 
+    /// A sandboxed command asked the network proxy to connect to a host.
+    NetworkAccess(NetworkAccessEvent),
+
     /// Notification that the agent is about to apply a code patch. Mirrors
     /// `ExecCommandBegin` so front‑ends can show progress indicators.
     PatchApplyBegin(PatchApplyBeginEvent),
@@ -455,12 +606,32 @@ pub enum EventMsg {
 
     TurnDiff(TurnDiffEvent),
 
+    /// The agent started a command in the background; it keeps running after
+    /// the turn ends.
+    BackgroundJobStarted(BackgroundJobStartedEvent),
+
+    /// A background job exited or was killed.
+    BackgroundJobFinished(BackgroundJobFinishedEvent),
+
+    /// The working tree was snapshotted before a turn that modified files,
+    /// so the turn can be undone.
+    CheckpointCreated(CheckpointCreatedEvent),
+
+    /// The working tree was restored to a checkpoint.
+    CheckpointRestored(CheckpointRestoredEvent),
+
     /// Response to GetHistoryEntryRequest.
     GetHistoryEntryResponse(GetHistoryEntryResponseEvent),
 
     /// List of MCP tools available to the agent.
     McpListToolsResponse(McpListToolsResponseEvent),
 
+    /// List of MCP resources, grouped by server.
+    McpListResourcesResponse(McpListResourcesResponseEvent),
+
+    /// List of MCP prompts, grouped by server.
+    McpListPromptsResponse(McpListPromptsResponseEvent),
+
     PlanUpdate(UpdatePlanArgs),
 
     TurnAborted(TurnAbortedEvent),
@@ -479,6 +650,9 @@ pub struct ErrorEvent {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TaskCompleteEvent {
     pub last_agent_message: Option<String>,
+    /// Progress of the session's plan, if the model recorded one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_progress: Option<PlanProgress>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -647,6 +821,57 @@ impl McpToolCallEndEvent {
     }
 }
 
+/// A call to one of the built-in file tools, with paths resolved against the
+/// working directory of the turn.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FileToolInvocation {
+    ReadFile {
+        path: PathBuf,
+        /// 1-based, inclusive.
+        start_line: Option<usize>,
+        /// 1-based, inclusive.
+        end_line: Option<usize>,
+    },
+    Grep {
+        /// Regular expression to search for.
+        pattern: String,
+        path: PathBuf,
+        /// Glob that file names must match, e.g. `*.rs`.
+        include: Option<String>,
+    },
+    ListDir {
+        path: PathBuf,
+        depth: usize,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FileToolCallBeginEvent {
+    /// Identifier so this can be paired with the FileToolCallEnd event.
+    pub call_id: String,
+    pub invocation: FileToolInvocation,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FileToolCallEndEvent {
+    /// Identifier for the corresponding FileToolCallBegin that finished.
+    pub call_id: String,
+    pub invocation: FileToolInvocation,
+    pub duration: Duration,
+    /// Result of the tool call. Note this could be an error.
+    pub result: Result<FileToolCallSummary, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FileToolCallSummary {
+    /// Lines read by `read_file`, matches found by `grep` or entries listed
+    /// by `list_dir`.
+    pub count: usize,
+    /// Whether the output was cut short because it hit a limit.
+    pub truncated: bool,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ExecCommandBeginEvent {
     /// Identifier so this can be paired with the ExecCommandEnd event.
@@ -670,6 +895,10 @@ pub struct ExecCommandEndEvent {
     pub exit_code: i32,
     /// The duration of the command execution.
     pub duration: Duration,
+    /// File holding the full output if it was too long to send to the model
+    /// in full.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_file: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -716,6 +945,31 @@ pub struct ApplyPatchApprovalRequestEvent {
     pub grant_root: Option<PathBuf>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct BudgetApprovalRequestEvent {
+    /// Tokens used by the session so far (non-cached input plus output).
+    pub used_tokens: u64,
+    /// Configured `max_session_tokens`, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+    /// Estimated cost of the session so far, in US dollars. Requests to
+    /// models without known pricing are not included.
+    pub cost_usd: f64,
+    /// Configured `max_session_cost`, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_cost_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct NetworkAccessEvent {
+    /// Host the command tried to reach.
+    pub host: String,
+    pub port: u16,
+    /// Whether the host is on the network allowlist. Connections to other
+    /// hosts are refused.
+    pub allowed: bool,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct BackgroundEventEvent {
     pub message: String,
@@ -748,6 +1002,40 @@ pub struct TurnDiffEvent {
     pub unified_diff: String,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BackgroundJobStartedEvent {
+    pub job_id: u32,
+    /// Identifier of the tool call that started the job.
+    pub call_id: String,
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BackgroundJobFinishedEvent {
+    pub job_id: u32,
+    pub exit_code: i32,
+    /// The job was stopped with `kill_background_job` or when the session
+    /// shut down.
+    pub killed: bool,
+    /// How long the job ran.
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CheckpointCreatedEvent {
+    pub checkpoint_id: String,
+    /// Files the turn added, modified or deleted.
+    pub changed_files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CheckpointRestoredEvent {
+    pub checkpoint_id: String,
+    /// Files that were reverted, recreated or deleted by the restore.
+    pub restored_files: Vec<PathBuf>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GetHistoryEntryResponseEvent {
     pub offset: usize,
@@ -762,6 +1050,35 @@ pub struct GetHistoryEntryResponseEvent {
 pub struct McpListToolsResponseEvent {
     /// Fully qualified tool name -> tool definition.
     pub tools: std::collections::HashMap<String, McpTool>,
+
+    /// Server name -> health of every server that started.
+    #[serde(default)]
+    pub servers: std::collections::HashMap<String, McpServerStatus>,
+}
+
+/// Health of a running MCP server.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum McpServerStatus {
+    Running,
+    /// The server exited and is being restarted.
+    Restarting,
+    /// The server exited and could not be restarted.
+    Failed,
+}
+
+/// Response payload for `Op::ListMcpResources`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct McpListResourcesResponseEvent {
+    /// Server name -> resources offered by that server.
+    pub resources: HashMap<String, Vec<McpResource>>,
+}
+
+/// Response payload for `Op::ListMcpPrompts`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct McpListPromptsResponseEvent {
+    /// Server name -> prompts offered by that server.
+    pub prompts: HashMap<String, Vec<McpPrompt>>,
 }
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
@@ -858,4 +1175,32 @@ mod tests {
             r#"{"id":"1234","msg":{"type":"session_configured","session_id":"67e55044-10b1-426f-9247-bb680e5fe0c8","model":"codex-mini-latest","history_log_id":0,"history_entry_count":0}}"#
         );
     }
+
+    #[test]
+    fn readable_roots_only_apply_when_configured() {
+        let cwd = std::env::current_dir().unwrap();
+
+        let full_read = SandboxPolicy::new_workspace_write_policy();
+        assert!(full_read.has_full_disk_read_access());
+        assert_eq!(
+            Vec::<PathBuf>::new(),
+            full_read.get_readable_roots_with_cwd(&cwd)
+        );
+
+        let restricted_read = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            readable_roots: Some(vec![]),
+            network_access: false,
+            network_allowlist: vec![],
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        };
+        assert!(!restricted_read.has_full_disk_read_access());
+        let readable_roots = restricted_read.get_readable_roots_with_cwd(&cwd);
+        assert!(readable_roots.contains(&cwd), "{readable_roots:?}");
+        assert!(
+            !readable_roots.contains(&PathBuf::from("/")),
+            "{readable_roots:?}"
+        );
+    }
 }
@@ -382,6 +382,9 @@ impl App<'_> {
                             self.app_event_tx.send(AppEvent::CodexOp(Op::Compact));
                         }
                     }
+                    SlashCommand::Undo => {
+                        self.app_event_tx.send(AppEvent::CodexOp(Op::UndoTurn));
+                    }
                     SlashCommand::Model => {
                         if let AppState::Chat { widget } = &mut self.app_state {
                             widget.open_model_popup();
@@ -499,6 +502,15 @@ impl App<'_> {
                         )),
                     }
                 }
+                AppEvent::RunMcpPrompt {
+                    server,
+                    prompt,
+                    arguments,
+                } => {
+                    if let AppState::Chat { widget } = &mut self.app_state {
+                        widget.run_mcp_prompt(server, prompt, arguments);
+                    }
+                }
                 AppEvent::StartFileSearch(query) => {
                     if !query.is_empty() {
                         self.file_search.on_user_query(query);
@@ -529,6 +541,26 @@ impl App<'_> {
                         widget.set_sandbox_policy(policy);
                     }
                 }
+                AppEvent::ForkConversation {
+                    conversation_id,
+                    drop_last_n_turns,
+                    prefill,
+                    user_messages,
+                    config,
+                } => {
+                    let new_widget = Box::new(ChatWidget::new_forked(
+                        *config,
+                        self.server.clone(),
+                        self.app_event_tx.clone(),
+                        conversation_id,
+                        drop_last_n_turns,
+                        prefill,
+                        user_messages,
+                        self.enhanced_keys_supported,
+                    ));
+                    self.app_state = AppState::Chat { widget: new_widget };
+                    self.app_event_tx.send(AppEvent::RequestRedraw);
+                }
             }
         }
         terminal.clear()?;
@@ -1,8 +1,11 @@
+use codex_core::config::Config;
 use codex_core::protocol::Event;
 use codex_file_search::FileMatch;
 use crossterm::event::KeyEvent;
 use ratatui::text::Line;
+use std::collections::HashMap;
 use std::time::Duration;
+use uuid::Uuid;
 
 use crate::app::ChatWidgetArgs;
 use crate::slash_command::SlashCommand;
@@ -41,6 +44,13 @@ pub(crate) enum AppEvent {
     /// layer so it can be handled centrally.
     DispatchCommand(SlashCommand),
 
+    /// Run a prompt offered by an MCP server, selected from the slash popup.
+    RunMcpPrompt {
+        server: String,
+        prompt: String,
+        arguments: HashMap<String, String>,
+    },
+
     /// Kick off an asynchronous file search for the given query (text after
     /// the `@`). Previous searches may be cancelled by the app layer so there
     /// is at most one in-flight search.
@@ -78,4 +88,15 @@ pub(crate) enum AppEvent {
 
     /// Update the current sandbox policy in the running app and widget.
     UpdateSandboxPolicy(SandboxPolicy),
+
+    /// Replace the chat with a fork of `conversation_id` that drops its last
+    /// `drop_last_n_turns` turns, with `prefill` placed in the composer.
+    ForkConversation {
+        conversation_id: Uuid,
+        drop_last_n_turns: usize,
+        prefill: String,
+        /// Messages the user submitted before the dropped turns.
+        user_messages: Vec<String>,
+        config: Box<Config>,
+    },
 }
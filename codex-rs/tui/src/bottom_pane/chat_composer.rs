@@ -21,6 +21,7 @@ use ratatui::widgets::StatefulWidgetRef;
 use ratatui::widgets::WidgetRef;
 
 use super::chat_composer_history::ChatComposerHistory;
+use super::command_popup::CommandItem;
 use super::command_popup::CommandPopup;
 use super::file_search_popup::FileSearchPopup;
 
@@ -28,6 +29,8 @@ use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
 use crate::bottom_pane::textarea::TextArea;
 use crate::bottom_pane::textarea::TextAreaState;
+use crate::slash_command::McpPromptCommand;
+use codex_common::fuzzy_match::fuzzy_match;
 use codex_file_search::FileMatch;
 use std::cell::RefCell;
 
@@ -63,6 +66,7 @@ pub(crate) struct ChatComposer {
     app_event_tx: AppEventSender,
     history: ChatComposerHistory,
     ctrl_c_quit_hint: bool,
+    esc_backtrack_hint: bool,
     use_shift_enter_hint: bool,
     dismissed_file_popup_token: Option<String>,
     current_file_query: Option<String>,
@@ -70,6 +74,11 @@ pub(crate) struct ChatComposer {
     token_usage_info: Option<TokenUsageInfo>,
     has_focus: bool,
     placeholder_text: String,
+    /// Prompts of the configured MCP servers, offered in the slash popup.
+    mcp_prompts: Vec<McpPromptCommand>,
+    /// Resources of the configured MCP servers as `server:uri`, offered in
+    /// the `@` popup once the token names one of the servers.
+    mcp_resources: Vec<String>,
 }
 
 /// Popup state – at most one can be visible at any time.
@@ -95,6 +104,7 @@ impl ChatComposer {
             app_event_tx,
             history: ChatComposerHistory::new(),
             ctrl_c_quit_hint: false,
+            esc_backtrack_hint: false,
             use_shift_enter_hint,
             dismissed_file_popup_token: None,
             current_file_query: None,
@@ -102,6 +112,8 @@ impl ChatComposer {
             token_usage_info: None,
             has_focus: has_input_focus,
             placeholder_text,
+            mcp_prompts: Vec::new(),
+            mcp_resources: Vec::new(),
         }
     }
 
@@ -211,11 +223,23 @@ impl ChatComposer {
         }
     }
 
+    pub(crate) fn set_mcp_prompts(&mut self, prompts: Vec<McpPromptCommand>) {
+        self.mcp_prompts = prompts;
+    }
+
+    pub(crate) fn set_mcp_resources(&mut self, resources: Vec<String>) {
+        self.mcp_resources = resources;
+    }
+
     pub fn set_ctrl_c_quit_hint(&mut self, show: bool, has_focus: bool) {
         self.ctrl_c_quit_hint = show;
         self.set_has_focus(has_focus);
     }
 
+    pub(crate) fn set_esc_backtrack_hint(&mut self, show: bool) {
+        self.esc_backtrack_hint = show;
+    }
+
     pub(crate) fn insert_str(&mut self, text: &str) {
         self.textarea.insert_str(text);
         self.sync_command_popup();
@@ -264,15 +288,15 @@ impl ChatComposer {
             KeyEvent {
                 code: KeyCode::Tab, ..
             } => {
-                if let Some(cmd) = popup.selected_command() {
+                if let Some(item) = popup.selected_item() {
+                    let command = item.command();
                     let first_line = self.textarea.text().lines().next().unwrap_or("");
 
-                    let starts_with_cmd = first_line
-                        .trim_start()
-                        .starts_with(&format!("/{}", cmd.command()));
+                    let starts_with_cmd =
+                        first_line.trim_start().starts_with(&format!("/{command}"));
 
                     if !starts_with_cmd {
-                        self.textarea.set_text(&format!("/{} ", cmd.command()));
+                        self.textarea.set_text(&format!("/{command} "));
                         self.textarea.set_cursor(self.textarea.text().len());
                     }
                 }
@@ -283,9 +307,28 @@ impl ChatComposer {
                 modifiers: KeyModifiers::NONE,
                 ..
             } => {
-                if let Some(cmd) = popup.selected_command() {
-                    // Send command to the app layer.
-                    self.app_event_tx.send(AppEvent::DispatchCommand(*cmd));
+                if let Some(item) = popup.selected_item() {
+                    match item {
+                        CommandItem::Builtin(cmd) => {
+                            // Send command to the app layer.
+                            self.app_event_tx.send(AppEvent::DispatchCommand(cmd));
+                        }
+                        CommandItem::McpPrompt(prompt) => {
+                            // Words after the command on the first line are
+                            // the prompt arguments.
+                            let first_line = self.textarea.text().lines().next().unwrap_or("");
+                            let args = first_line
+                                .trim_start()
+                                .split_once(char::is_whitespace)
+                                .map(|(_, args)| args)
+                                .unwrap_or("");
+                            self.app_event_tx.send(AppEvent::RunMcpPrompt {
+                                server: prompt.server.clone(),
+                                prompt: prompt.prompt.name.clone(),
+                                arguments: prompt.parse_arguments(args),
+                            });
+                        }
+                    }
 
                     // Clear textarea so no residual text remains.
                     self.textarea.set_text("");
@@ -340,7 +383,12 @@ impl ChatComposer {
                 ..
             } => {
                 if let Some(sel) = popup.selected_match() {
-                    let sel_path = sel.to_string();
+                    // Resources keep their `@` so they are attached on submit.
+                    let sel_path = if self.mcp_resources.iter().any(|r| r == sel) {
+                        format!("@{sel}")
+                    } else {
+                        sel.to_string()
+                    };
                     // Drop popup borrow before using self mutably again.
                     self.insert_selected_path(&sel_path);
                     self.active_popup = ActivePopup::None;
@@ -578,7 +626,7 @@ impl ChatComposer {
             }
             _ => {
                 if input_starts_with_slash {
-                    let mut command_popup = CommandPopup::new();
+                    let mut command_popup = CommandPopup::new(self.mcp_prompts.clone());
                     command_popup.on_composer_text_change(first_line.to_string());
                     self.active_popup = ActivePopup::Command(command_popup);
                 }
@@ -604,7 +652,10 @@ impl ChatComposer {
             return;
         }
 
-        if !query.is_empty() {
+        // `@server:` tokens are completed from the MCP resources instead of
+        // the file system.
+        let resource_matches = self.mcp_resource_matches(&query);
+        if !query.is_empty() && resource_matches.is_none() {
             self.app_event_tx
                 .send(AppEvent::StartFileSearch(query.clone()));
         }
@@ -627,11 +678,46 @@ impl ChatComposer {
                 self.active_popup = ActivePopup::File(popup);
             }
         }
+        if let (Some(matches), ActivePopup::File(popup)) =
+            (resource_matches, &mut self.active_popup)
+        {
+            popup.set_matches(&query, matches);
+        }
 
         self.current_file_query = Some(query);
         self.dismissed_file_popup_token = None;
     }
 
+    /// Fuzzy-matches `query` against the MCP resources when it starts with
+    /// the name of a server that offers resources, e.g. `docs:intro`.
+    fn mcp_resource_matches(&self, query: &str) -> Option<Vec<FileMatch>> {
+        let (server, _) = query.split_once(':')?;
+        let prefix = format!("{server}:");
+        if !self.mcp_resources.iter().any(|r| r.starts_with(&prefix)) {
+            return None;
+        }
+        let mut matches: Vec<(i32, &String, Vec<usize>)> = self
+            .mcp_resources
+            .iter()
+            .filter_map(|resource| {
+                let (indices, score) = fuzzy_match(resource, query)?;
+                Some((score, resource, indices))
+            })
+            .collect();
+        // Lower fuzzy scores are better matches.
+        matches.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+        Some(
+            matches
+                .into_iter()
+                .map(|(score, resource, indices)| FileMatch {
+                    score: score.max(0) as u32,
+                    path: resource.clone(),
+                    indices: Some(indices.into_iter().map(|i| i as u32).collect()),
+                })
+                .collect(),
+        )
+    }
+
     fn set_has_focus(&mut self, has_focus: bool) {
         self.has_focus = has_focus;
     }
@@ -662,6 +748,12 @@ impl WidgetRef for &ChatComposer {
                         "Ctrl+C again".set_style(key_hint_style),
                         Span::from(" to quit"),
                     ]
+                } else if self.esc_backtrack_hint {
+                    vec![
+                        Span::from(" "),
+                        "Esc again".set_style(key_hint_style),
+                        Span::from(" to edit previous message"),
+                    ]
                 } else {
                     let newline_hint_key = if self.use_shift_enter_hint {
                         "Shift+⏎"
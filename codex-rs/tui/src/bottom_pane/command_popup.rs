@@ -6,21 +6,48 @@ use super::popup_consts::MAX_POPUP_ROWS;
 use super::scroll_state::ScrollState;
 use super::selection_popup_common::GenericDisplayRow;
 use super::selection_popup_common::render_rows;
+use crate::slash_command::McpPromptCommand;
 use crate::slash_command::SlashCommand;
 use crate::slash_command::built_in_slash_commands;
 use codex_common::fuzzy_match::fuzzy_match;
 
+/// An entry of the popup: a built-in command or a prompt offered by an MCP
+/// server.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum CommandItem<'a> {
+    Builtin(SlashCommand),
+    McpPrompt(&'a McpPromptCommand),
+}
+
+impl CommandItem<'_> {
+    pub(crate) fn command(&self) -> String {
+        match self {
+            CommandItem::Builtin(cmd) => cmd.command().to_string(),
+            CommandItem::McpPrompt(prompt) => prompt.command(),
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            CommandItem::Builtin(cmd) => cmd.description().to_string(),
+            CommandItem::McpPrompt(prompt) => prompt.description(),
+        }
+    }
+}
+
 pub(crate) struct CommandPopup {
     command_filter: String,
     all_commands: Vec<(&'static str, SlashCommand)>,
+    mcp_prompts: Vec<McpPromptCommand>,
     state: ScrollState,
 }
 
 impl CommandPopup {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(mcp_prompts: Vec<McpPromptCommand>) -> Self {
         Self {
             command_filter: String::new(),
             all_commands: built_in_slash_commands(),
+            mcp_prompts,
             state: ScrollState::new(),
         }
     }
@@ -62,30 +89,42 @@ impl CommandPopup {
         self.filtered_commands().len().clamp(1, MAX_POPUP_ROWS) as u16
     }
 
+    /// All entries in presentation order: built-in commands first, then MCP
+    /// prompts.
+    fn all_items(&self) -> impl Iterator<Item = CommandItem<'_>> {
+        self.all_commands
+            .iter()
+            .map(|(_, cmd)| CommandItem::Builtin(*cmd))
+            .chain(self.mcp_prompts.iter().map(CommandItem::McpPrompt))
+    }
+
     /// Compute fuzzy-filtered matches paired with optional highlight indices and score.
     /// Sorted by ascending score, then by command name for stability.
-    fn filtered(&self) -> Vec<(&SlashCommand, Option<Vec<usize>>, i32)> {
+    fn filtered(&self) -> Vec<(CommandItem<'_>, Option<Vec<usize>>, i32)> {
         let filter = self.command_filter.trim();
-        let mut out: Vec<(&SlashCommand, Option<Vec<usize>>, i32)> = Vec::new();
+        let mut out: Vec<(CommandItem<'_>, Option<Vec<usize>>, i32)> = Vec::new();
         if filter.is_empty() {
-            for (_, cmd) in self.all_commands.iter() {
-                out.push((cmd, None, 0));
+            for item in self.all_items() {
+                out.push((item, None, 0));
             }
             // Keep the original presentation order when no filter is applied.
             return out;
         } else {
-            for (_, cmd) in self.all_commands.iter() {
-                if let Some((indices, score)) = fuzzy_match(cmd.command(), filter) {
-                    out.push((cmd, Some(indices), score));
+            for item in self.all_items() {
+                if let Some((indices, score)) = fuzzy_match(&item.command(), filter) {
+                    out.push((item, Some(indices), score));
                 }
             }
         }
         // When filtering, sort by ascending score and then by command for stability.
-        out.sort_by(|a, b| a.2.cmp(&b.2).then_with(|| a.0.command().cmp(b.0.command())));
+        out.sort_by(|a, b| {
+            a.2.cmp(&b.2)
+                .then_with(|| a.0.command().cmp(&b.0.command()))
+        });
         out
     }
 
-    fn filtered_commands(&self) -> Vec<&SlashCommand> {
+    fn filtered_commands(&self) -> Vec<CommandItem<'_>> {
         self.filtered().into_iter().map(|(c, _, _)| c).collect()
     }
 
@@ -106,8 +145,8 @@ impl CommandPopup {
             .ensure_visible(matches_len, MAX_POPUP_ROWS.min(matches_len));
     }
 
-    /// Return currently selected command, if any.
-    pub(crate) fn selected_command(&self) -> Option<&SlashCommand> {
+    /// Return currently selected entry, if any.
+    pub(crate) fn selected_item(&self) -> Option<CommandItem<'_>> {
         let matches = self.filtered_commands();
         self.state
             .selected_idx
@@ -127,7 +166,7 @@ impl WidgetRef for CommandPopup {
                     name: format!("/{}", cmd.command()),
                     match_indices: indices.map(|v| v.into_iter().map(|i| i + 1).collect()),
                     is_current: false,
-                    description: Some(cmd.description().to_string()),
+                    description: Some(cmd.description()),
                 })
                 .collect()
         };
@@ -141,7 +180,7 @@ mod tests {
 
     #[test]
     fn filter_includes_init_when_typing_prefix() {
-        let mut popup = CommandPopup::new();
+        let mut popup = CommandPopup::new(Vec::new());
         // Simulate the composer line starting with '/in' so the popup filters
         // matching commands by prefix.
         popup.on_composer_text_change("/in".to_string());
@@ -157,15 +196,32 @@ mod tests {
 
     #[test]
     fn selecting_init_by_exact_match() {
-        let mut popup = CommandPopup::new();
+        let mut popup = CommandPopup::new(Vec::new());
         popup.on_composer_text_change("/init".to_string());
 
         // When an exact match exists, the selected command should be that
         // command by default.
-        let selected = popup.selected_command();
+        let selected = popup.selected_item();
         match selected {
             Some(cmd) => assert_eq!(cmd.command(), "init"),
             None => panic!("expected a selected command for exact match"),
         }
     }
+
+    #[test]
+    fn mcp_prompts_are_listed_and_filtered_by_server() {
+        let prompt = McpPromptCommand {
+            server: "docs".to_string(),
+            prompt: mcp_types::Prompt {
+                arguments: None,
+                description: None,
+                name: "summarize".to_string(),
+                title: None,
+            },
+        };
+        let mut popup = CommandPopup::new(vec![prompt.clone()]);
+        popup.on_composer_text_change("/docs:sum".to_string());
+
+        assert_eq!(Some(CommandItem::McpPrompt(&prompt)), popup.selected_item());
+    }
 }
@@ -2,6 +2,7 @@
 
 use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
+use crate::slash_command::McpPromptCommand;
 use crate::user_approval_widget::ApprovalRequest;
 use bottom_pane_view::BottomPaneView;
 use codex_core::protocol::TokenUsage;
@@ -188,6 +189,16 @@ impl BottomPane<'_> {
         }
     }
 
+    pub(crate) fn show_esc_backtrack_hint(&mut self) {
+        self.composer.set_esc_backtrack_hint(true);
+        self.request_redraw();
+    }
+
+    pub(crate) fn clear_esc_backtrack_hint(&mut self) {
+        self.composer.set_esc_backtrack_hint(false);
+        self.request_redraw();
+    }
+
     pub(crate) fn ctrl_c_quit_hint_visible(&self) -> bool {
         self.ctrl_c_quit_hint
     }
@@ -252,6 +263,11 @@ impl BottomPane<'_> {
         self.composer.is_empty()
     }
 
+    /// True when a modal or status view is shown instead of the composer.
+    pub(crate) fn has_active_view(&self) -> bool {
+        self.active_view.is_some()
+    }
+
     pub(crate) fn is_task_running(&self) -> bool {
         self.is_task_running
     }
@@ -320,6 +336,16 @@ impl BottomPane<'_> {
         self.composer.on_file_search_result(query, matches);
         self.request_redraw();
     }
+
+    // --- MCP helpers ---
+
+    pub(crate) fn set_mcp_prompts(&mut self, prompts: Vec<McpPromptCommand>) {
+        self.composer.set_mcp_prompts(prompts);
+    }
+
+    pub(crate) fn set_mcp_resources(&mut self, resources: Vec<String>) {
+        self.composer.set_mcp_resources(resources);
+    }
 }
 
 impl WidgetRef for &BottomPane<'_> {
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -11,24 +12,35 @@ use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
 use codex_core::protocol::BackgroundEventEvent;
+use codex_core::protocol::BackgroundJobFinishedEvent;
+use codex_core::protocol::BackgroundJobStartedEvent;
+use codex_core::protocol::BudgetApprovalRequestEvent;
+use codex_core::protocol::CheckpointCreatedEvent;
+use codex_core::protocol::CheckpointRestoredEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
+use codex_core::protocol::FileToolCallEndEvent;
 use codex_core::protocol::InputItem;
+use codex_core::protocol::McpListPromptsResponseEvent;
+use codex_core::protocol::McpListResourcesResponseEvent;
 use codex_core::protocol::McpListToolsResponseEvent;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
+use codex_core::protocol::NetworkAccessEvent;
 use codex_core::protocol::Op;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TokenUsage;
 use codex_core::protocol::TurnDiffEvent;
 use codex_protocol::parse_command::ParsedCommand;
+use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
 use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
 use rand::Rng;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Constraint;
@@ -52,12 +64,16 @@ use crate::history_cell::CommandOutput;
 use crate::history_cell::ExecCell;
 use crate::history_cell::HistoryCell;
 use crate::history_cell::PatchEventType;
+use crate::plan_panel::PlanPanel;
+use crate::slash_command::McpPromptCommand;
+use crate::terminal_notification::TerminalNotifier;
 // streaming internals are provided by crate::streaming and crate::markdown_stream
 use crate::user_approval_widget::ApprovalRequest;
 mod interrupts;
 use self::interrupts::InterruptManager;
 mod agent;
 use self::agent::spawn_agent;
+use self::agent::spawn_forked_agent;
 use crate::streaming::controller::AppEventHistorySink;
 use crate::streaming::controller::StreamController;
 use codex_common::approval_presets::ApprovalPreset;
@@ -98,6 +114,16 @@ pub(crate) struct ChatWidget<'a> {
     // Whether a redraw is needed after handling the current event
     needs_redraw: bool,
     session_id: Option<Uuid>,
+    // Text of each message the user submitted, used to edit the most recent one
+    user_messages: Vec<String>,
+    // Set after a first Esc on an empty composer; a second Esc edits the previous message
+    esc_backtrack_armed: bool,
+    // Current plan pinned above the composer
+    plan_panel: PlanPanel,
+    // Writes OSC notifications for the events enabled under [notifications]
+    terminal_notifier: TerminalNotifier,
+    // Commands of the background jobs that are still running, by job ID.
+    background_jobs: BTreeMap<u32, Vec<String>>,
 }
 
 struct UserMessage {
@@ -142,6 +168,11 @@ impl ChatWidget<'_> {
         if let Some(user_message) = self.initial_user_message.take() {
             self.submit_user_message(user_message);
         }
+        // Fetch what the MCP servers offer for the composer's popups.
+        if !self.config.mcp_servers.is_empty() {
+            self.submit_op(Op::ListMcpResources);
+            self.submit_op(Op::ListMcpPrompts);
+        }
         self.mark_needs_redraw();
     }
 
@@ -218,6 +249,7 @@ impl ChatWidget<'_> {
     }
 
     fn on_plan_update(&mut self, update: codex_core::plan_tool::UpdatePlanArgs) {
+        self.plan_panel.set_plan(update.clone());
         self.add_to_history(&history_cell::new_plan_update(update));
     }
 
@@ -239,6 +271,21 @@ impl ChatWidget<'_> {
         );
     }
 
+    fn on_budget_approval_request(&mut self, id: String, ev: BudgetApprovalRequestEvent) {
+        // Budgets are only checked between model requests, so there is no
+        // streamed output to keep in order with.
+        self.flush_answer_stream_with_separator();
+        let request = ApprovalRequest::Budget {
+            id,
+            used_tokens: ev.used_tokens,
+            max_tokens: ev.max_tokens,
+            cost_usd: ev.cost_usd,
+            max_cost_usd: ev.max_cost_usd,
+        };
+        self.bottom_pane.push_approval_request(request);
+        self.mark_needs_redraw();
+    }
+
     fn on_exec_command_begin(&mut self, ev: ExecCommandBeginEvent) {
         self.flush_answer_stream_with_separator();
         let ev2 = ev.clone();
@@ -284,6 +331,14 @@ impl ChatWidget<'_> {
         self.defer_or_handle(|q| q.push_mcp_end(ev), |s| s.handle_mcp_end_now(ev2));
     }
 
+    fn on_file_tool_call_end(&mut self, ev: FileToolCallEndEvent) {
+        let ev2 = ev.clone();
+        self.defer_or_handle(
+            |q| q.push_file_tool_end(ev),
+            |s| s.handle_file_tool_end_now(ev2),
+        );
+    }
+
     fn on_get_history_entry_response(
         &mut self,
         event: codex_core::protocol::GetHistoryEntryResponseEvent,
@@ -308,6 +363,20 @@ impl ChatWidget<'_> {
     fn on_background_event(&mut self, message: String) {
         debug!("BackgroundEvent: {message}");
     }
+
+    fn on_network_access(&mut self, ev: NetworkAccessEvent) {
+        let NetworkAccessEvent {
+            host,
+            port,
+            allowed,
+        } = ev;
+        if allowed {
+            debug!("NetworkAccess: allowed {host}:{port}");
+        } else {
+            self.add_to_history(&history_cell::new_network_access_denied(host, port));
+            self.mark_needs_redraw();
+        }
+    }
     /// Periodic tick to commit at most one queued line to history with a small delay,
     /// animating the output.
     pub(crate) fn on_commit_tick(&mut self) {
@@ -474,6 +543,14 @@ impl ChatWidget<'_> {
             ev.result,
         ));
     }
+    pub(crate) fn handle_file_tool_end_now(&mut self, ev: FileToolCallEndEvent) {
+        self.flush_answer_stream_with_separator();
+        self.add_to_history(&history_cell::new_completed_file_tool_call(
+            &self.config.cwd,
+            ev.invocation,
+            ev.result,
+        ));
+    }
     fn interrupt_running_task(&mut self) {
         if self.bottom_pane.is_task_running() {
             self.active_exec_cell = None;
@@ -485,13 +562,14 @@ impl ChatWidget<'_> {
             self.request_redraw();
         }
     }
-    fn layout_areas(&self, area: Rect) -> [Rect; 2] {
+    fn layout_areas(&self, area: Rect) -> [Rect; 3] {
         Layout::vertical([
             Constraint::Max(
                 self.active_exec_cell
                     .as_ref()
                     .map_or(0, |c| c.desired_height(area.width)),
             ),
+            Constraint::Max(self.plan_panel.desired_height(area.width)),
             Constraint::Min(self.bottom_pane.desired_height(area.width)),
         ])
         .areas(area)
@@ -504,10 +582,50 @@ impl ChatWidget<'_> {
         initial_prompt: Option<String>,
         initial_images: Vec<PathBuf>,
         enhanced_keys_supported: bool,
+    ) -> Self {
+        let codex_op_tx = spawn_agent(config.clone(), app_event_tx.clone(), conversation_manager);
+        let mut widget =
+            Self::with_agent(config, codex_op_tx, app_event_tx, enhanced_keys_supported);
+        widget.initial_user_message =
+            create_initial_user_message(initial_prompt.unwrap_or_default(), initial_images);
+        widget
+    }
+
+    /// Creates a widget for a fork of `conversation_id` without its last
+    /// `drop_last_n_turns` turns. `prefill` is placed in the composer so the
+    /// user can edit and resubmit it.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new_forked(
+        config: Config,
+        conversation_manager: Arc<ConversationManager>,
+        app_event_tx: AppEventSender,
+        conversation_id: Uuid,
+        drop_last_n_turns: usize,
+        prefill: String,
+        user_messages: Vec<String>,
+        enhanced_keys_supported: bool,
+    ) -> Self {
+        let codex_op_tx = spawn_forked_agent(
+            conversation_id,
+            drop_last_n_turns,
+            app_event_tx.clone(),
+            conversation_manager,
+        );
+        let mut widget =
+            Self::with_agent(config, codex_op_tx, app_event_tx, enhanced_keys_supported);
+        widget.user_messages = user_messages;
+        widget.bottom_pane.insert_str(&prefill);
+        widget
+    }
+
+    fn with_agent(
+        config: Config,
+        codex_op_tx: UnboundedSender<Op>,
+        app_event_tx: AppEventSender,
+        enhanced_keys_supported: bool,
     ) -> Self {
         let mut rng = rand::rng();
         let placeholder = EXAMPLE_PROMPTS[rng.random_range(0..EXAMPLE_PROMPTS.len())].to_string();
-        let codex_op_tx = spawn_agent(config.clone(), app_event_tx.clone(), conversation_manager);
 
         Self {
             app_event_tx: app_event_tx.clone(),
@@ -520,10 +638,9 @@ impl ChatWidget<'_> {
             }),
             active_exec_cell: None,
             config: config.clone(),
-            initial_user_message: create_initial_user_message(
-                initial_prompt.unwrap_or_default(),
-                initial_images,
-            ),
+            terminal_notifier: TerminalNotifier::new(config.notifications.clone()),
+            background_jobs: BTreeMap::new(),
+            initial_user_message: None,
             total_token_usage: TokenUsage::default(),
             last_token_usage: TokenUsage::default(),
             stream: StreamController::new(config),
@@ -534,11 +651,15 @@ impl ChatWidget<'_> {
             interrupts: InterruptManager::new(),
             needs_redraw: false,
             session_id: None,
+            user_messages: Vec::new(),
+            esc_backtrack_armed: false,
+            plan_panel: PlanPanel::new(),
         }
     }
 
     pub fn desired_height(&self, width: u16) -> u16 {
         self.bottom_pane.desired_height(width)
+            + self.plan_panel.desired_height(width)
             + self
                 .active_exec_cell
                 .as_ref()
@@ -548,6 +669,17 @@ impl ChatWidget<'_> {
     pub(crate) fn handle_key_event(&mut self, key_event: KeyEvent) {
         if key_event.kind == KeyEventKind::Press {
             self.bottom_pane.clear_ctrl_c_quit_hint();
+            if self.handle_esc_backtrack(&key_event) {
+                return;
+            }
+            if key_event.code == KeyCode::Char('t')
+                && key_event.modifiers == KeyModifiers::CONTROL
+                && self.plan_panel.has_plan()
+            {
+                self.plan_panel.toggle();
+                self.request_redraw();
+                return;
+            }
         }
 
         match self.bottom_pane.handle_key_event(key_event) {
@@ -558,6 +690,51 @@ impl ChatWidget<'_> {
         }
     }
 
+    /// Esc on an idle, empty composer arms "edit previous message"; a second
+    /// Esc forks the conversation before the last user message and puts that
+    /// message back in the composer. Returns true if the key was consumed.
+    fn handle_esc_backtrack(&mut self, key_event: &KeyEvent) -> bool {
+        let is_plain_esc =
+            key_event.code == KeyCode::Esc && key_event.modifiers == KeyModifiers::NONE;
+        let idle = !self.bottom_pane.is_task_running()
+            && !self.bottom_pane.has_active_view()
+            && self.bottom_pane.composer_is_empty();
+
+        if !is_plain_esc || !idle {
+            if self.esc_backtrack_armed {
+                self.esc_backtrack_armed = false;
+                self.bottom_pane.clear_esc_backtrack_hint();
+            }
+            return false;
+        }
+
+        let Some(conversation_id) = self.session_id else {
+            return false;
+        };
+        if self.user_messages.is_empty() {
+            return false;
+        }
+
+        if !self.esc_backtrack_armed {
+            self.esc_backtrack_armed = true;
+            self.bottom_pane.show_esc_backtrack_hint();
+            return true;
+        }
+
+        self.esc_backtrack_armed = false;
+        self.bottom_pane.clear_esc_backtrack_hint();
+        let mut user_messages = self.user_messages.clone();
+        let prefill = user_messages.pop().unwrap_or_default();
+        self.app_event_tx.send(AppEvent::ForkConversation {
+            conversation_id,
+            drop_last_n_turns: 1,
+            prefill,
+            user_messages,
+            config: Box::new(self.config.clone()),
+        });
+        true
+    }
+
     pub(crate) fn handle_paste(&mut self, text: String) {
         self.bottom_pane.handle_paste(text);
     }
@@ -609,6 +786,7 @@ impl ChatWidget<'_> {
         // Only show the text portion in conversation history.
         if !text.is_empty() {
             self.add_to_history(&history_cell::new_user_prompt(text.clone()));
+            self.user_messages.push(text);
         }
     }
 
@@ -616,6 +794,7 @@ impl ChatWidget<'_> {
         // Reset redraw flag for this dispatch
         self.needs_redraw = false;
         let Event { id, msg } = event;
+        self.terminal_notifier.on_event(&msg);
 
         match msg {
             EventMsg::AgentMessageDelta(_)
@@ -649,6 +828,7 @@ impl ChatWidget<'_> {
             EventMsg::PlanUpdate(update) => self.on_plan_update(update),
             EventMsg::ExecApprovalRequest(ev) => self.on_exec_approval_request(id, ev),
             EventMsg::ApplyPatchApprovalRequest(ev) => self.on_apply_patch_approval_request(id, ev),
+            EventMsg::BudgetApprovalRequest(ev) => self.on_budget_approval_request(id, ev),
             EventMsg::ExecCommandBegin(ev) => self.on_exec_command_begin(ev),
             EventMsg::ExecCommandOutputDelta(delta) => self.on_exec_command_output_delta(delta),
             EventMsg::PatchApplyBegin(ev) => self.on_patch_apply_begin(ev),
@@ -656,13 +836,39 @@ impl ChatWidget<'_> {
             EventMsg::ExecCommandEnd(ev) => self.on_exec_command_end(ev),
             EventMsg::McpToolCallBegin(ev) => self.on_mcp_tool_call_begin(ev),
             EventMsg::McpToolCallEnd(ev) => self.on_mcp_tool_call_end(ev),
+            // File tools run in-process and finish quickly, so only their
+            // completion is shown.
+            EventMsg::FileToolCallBegin(_) => {}
+            EventMsg::FileToolCallEnd(ev) => self.on_file_tool_call_end(ev),
             EventMsg::GetHistoryEntryResponse(ev) => self.on_get_history_entry_response(ev),
             EventMsg::McpListToolsResponse(ev) => self.on_list_mcp_tools(ev),
+            EventMsg::McpListResourcesResponse(ev) => self.on_list_mcp_resources(ev),
+            EventMsg::McpListPromptsResponse(ev) => self.on_list_mcp_prompts(ev),
             EventMsg::ShutdownComplete => self.on_shutdown_complete(),
             EventMsg::TurnDiff(TurnDiffEvent { unified_diff }) => self.on_turn_diff(unified_diff),
+            EventMsg::CheckpointCreated(CheckpointCreatedEvent { changed_files, .. }) => {
+                self.add_to_history(&history_cell::new_checkpoint_created(changed_files.len()));
+            }
+            EventMsg::CheckpointRestored(CheckpointRestoredEvent { restored_files, .. }) => {
+                self.add_to_history(&history_cell::new_checkpoint_restored(
+                    &self.config.cwd,
+                    restored_files,
+                ));
+            }
+            EventMsg::BackgroundJobStarted(BackgroundJobStartedEvent {
+                job_id, command, ..
+            }) => {
+                self.add_to_history(&history_cell::new_background_job_started(job_id, &command));
+                self.background_jobs.insert(job_id, command);
+            }
+            EventMsg::BackgroundJobFinished(ev) => {
+                self.background_jobs.remove(&ev.job_id);
+                self.add_to_history(&history_cell::new_background_job_finished(ev));
+            }
             EventMsg::BackgroundEvent(BackgroundEventEvent { message }) => {
                 self.on_background_event(message)
             }
+            EventMsg::NetworkAccess(ev) => self.on_network_access(ev),
         }
         // Coalesce redraws: issue at most one after handling the event
         if self.needs_redraw {
@@ -693,6 +899,7 @@ impl ChatWidget<'_> {
             &self.config,
             &self.total_token_usage,
             &self.session_id,
+            &self.background_jobs,
         ));
     }
 
@@ -848,7 +1055,62 @@ impl ChatWidget<'_> {
     }
 
     fn on_list_mcp_tools(&mut self, ev: McpListToolsResponseEvent) {
-        self.add_to_history(&history_cell::new_mcp_tools_output(&self.config, ev.tools));
+        self.add_to_history(&history_cell::new_mcp_tools_output(
+            &self.config,
+            ev.tools,
+            ev.servers,
+        ));
+    }
+
+    fn on_list_mcp_resources(&mut self, ev: McpListResourcesResponseEvent) {
+        let mut resources: Vec<String> = ev
+            .resources
+            .into_iter()
+            .flat_map(|(server, resources)| {
+                resources
+                    .into_iter()
+                    .map(move |resource| format!("{server}:{}", resource.uri))
+            })
+            .collect();
+        resources.sort();
+        self.bottom_pane.set_mcp_resources(resources);
+    }
+
+    fn on_list_mcp_prompts(&mut self, ev: McpListPromptsResponseEvent) {
+        let mut prompts: Vec<McpPromptCommand> = ev
+            .prompts
+            .into_iter()
+            .flat_map(|(server, prompts)| {
+                prompts.into_iter().map(move |prompt| McpPromptCommand {
+                    server: server.clone(),
+                    prompt,
+                })
+            })
+            .collect();
+        prompts.sort_by_key(McpPromptCommand::command);
+        self.bottom_pane.set_mcp_prompts(prompts);
+    }
+
+    /// Run a prompt offered by an MCP server as the next user message.
+    pub(crate) fn run_mcp_prompt(
+        &mut self,
+        server: String,
+        prompt: String,
+        arguments: HashMap<String, String>,
+    ) {
+        let mut text = format!("/{server}:{prompt}");
+        let mut arguments_sorted: Vec<(&String, &String)> = arguments.iter().collect();
+        arguments_sorted.sort();
+        for (name, value) in arguments_sorted {
+            text.push_str(&format!(" {name}={value}"));
+        }
+        self.add_to_history(&history_cell::new_user_prompt(text.clone()));
+        self.user_messages.push(text);
+        self.submit_op(Op::RunMcpPrompt {
+            server,
+            prompt,
+            arguments,
+        });
     }
 
     /// Programmatically submit a user text message as if typed in the
@@ -875,15 +1137,16 @@ impl ChatWidget<'_> {
     }
 
     pub fn cursor_pos(&self, area: Rect) -> Option<(u16, u16)> {
-        let [_, bottom_pane_area] = self.layout_areas(area);
+        let [_, _, bottom_pane_area] = self.layout_areas(area);
         self.bottom_pane.cursor_pos(bottom_pane_area)
     }
 }
 
 impl WidgetRef for &ChatWidget<'_> {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
-        let [active_cell_area, bottom_pane_area] = self.layout_areas(area);
+        let [active_cell_area, plan_panel_area, bottom_pane_area] = self.layout_areas(area);
         (&self.bottom_pane).render(bottom_pane_area, buf);
+        (&self.plan_panel).render_ref(plan_panel_area, buf);
         if let Some(cell) = &self.active_exec_cell {
             cell.render_ref(active_cell_area, buf);
         }
@@ -4,8 +4,10 @@ use codex_core::ConversationManager;
 use codex_core::NewConversation;
 use codex_core::config::Config;
 use codex_core::protocol::Op;
+use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::mpsc::unbounded_channel;
+use uuid::Uuid;
 
 use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
@@ -17,15 +19,10 @@ pub(crate) fn spawn_agent(
     app_event_tx: AppEventSender,
     server: Arc<ConversationManager>,
 ) -> UnboundedSender<Op> {
-    let (codex_op_tx, mut codex_op_rx) = unbounded_channel::<Op>();
+    let (codex_op_tx, codex_op_rx) = unbounded_channel::<Op>();
 
-    let app_event_tx_clone = app_event_tx.clone();
     tokio::spawn(async move {
-        let NewConversation {
-            conversation_id: _,
-            conversation,
-            session_configured,
-        } = match server.new_conversation(config).await {
+        let new_conversation = match server.new_conversation(config).await {
             Ok(v) => v,
             Err(e) => {
                 // TODO: surface this error to the user.
@@ -33,29 +30,72 @@ pub(crate) fn spawn_agent(
                 return;
             }
         };
+        forward_conversation(new_conversation, codex_op_rx, app_event_tx).await;
+    });
 
-        // Forward the captured `SessionConfigured` event so it can be rendered in the UI.
-        let ev = codex_core::protocol::Event {
-            // The `id` does not matter for rendering, so we can use a fake value.
-            id: "".to_string(),
-            msg: codex_core::protocol::EventMsg::SessionConfigured(session_configured),
-        };
-        app_event_tx_clone.send(AppEvent::CodexEvent(ev));
-
-        let conversation_clone = conversation.clone();
-        tokio::spawn(async move {
-            while let Some(op) = codex_op_rx.recv().await {
-                let id = conversation_clone.submit(op).await;
-                if let Err(e) = id {
-                    tracing::error!("failed to submit op: {e}");
-                }
+    codex_op_tx
+}
+
+/// Like [`spawn_agent`], but the agent continues a fork of the conversation
+/// `conversation_id` without its last `drop_last_n_turns` turns.
+pub(crate) fn spawn_forked_agent(
+    conversation_id: Uuid,
+    drop_last_n_turns: usize,
+    app_event_tx: AppEventSender,
+    server: Arc<ConversationManager>,
+) -> UnboundedSender<Op> {
+    let (codex_op_tx, codex_op_rx) = unbounded_channel::<Op>();
+
+    tokio::spawn(async move {
+        let new_conversation = match server
+            .fork_conversation(conversation_id, drop_last_n_turns)
+            .await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                // TODO: surface this error to the user.
+                tracing::error!("failed to fork conversation {conversation_id}: {e}");
+                return;
             }
-        });
+        };
+        forward_conversation(new_conversation, codex_op_rx, app_event_tx).await;
+    });
+
+    codex_op_tx
+}
 
-        while let Ok(event) = conversation.next_event().await {
-            app_event_tx_clone.send(AppEvent::CodexEvent(event));
+/// Forwards ops from the UI to the conversation and its events back to the
+/// UI until the conversation ends.
+async fn forward_conversation(
+    new_conversation: NewConversation,
+    mut codex_op_rx: UnboundedReceiver<Op>,
+    app_event_tx: AppEventSender,
+) {
+    let NewConversation {
+        conversation_id: _,
+        conversation,
+        session_configured,
+    } = new_conversation;
+
+    // Forward the captured `SessionConfigured` event so it can be rendered in the UI.
+    let ev = codex_core::protocol::Event {
+        // The `id` does not matter for rendering, so we can use a fake value.
+        id: "".to_string(),
+        msg: codex_core::protocol::EventMsg::SessionConfigured(session_configured),
+    };
+    app_event_tx.send(AppEvent::CodexEvent(ev));
+
+    let conversation_clone = conversation.clone();
+    tokio::spawn(async move {
+        while let Some(op) = codex_op_rx.recv().await {
+            let id = conversation_clone.submit(op).await;
+            if let Err(e) = id {
+                tracing::error!("failed to submit op: {e}");
+            }
         }
     });
 
-    codex_op_tx
+    while let Ok(event) = conversation.next_event().await {
+        app_event_tx.send(AppEvent::CodexEvent(event));
+    }
 }
@@ -4,6 +4,7 @@ use codex_core::protocol::ApplyPatchApprovalRequestEvent;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
+use codex_core::protocol::FileToolCallEndEvent;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::PatchApplyEndEvent;
@@ -18,6 +19,7 @@ pub(crate) enum QueuedInterrupt {
     ExecEnd(ExecCommandEndEvent),
     McpBegin(McpToolCallBeginEvent),
     McpEnd(McpToolCallEndEvent),
+    FileToolEnd(FileToolCallEndEvent),
     PatchEnd(PatchApplyEndEvent),
 }
 
@@ -67,6 +69,10 @@ impl InterruptManager {
         self.queue.push_back(QueuedInterrupt::McpEnd(ev));
     }
 
+    pub(crate) fn push_file_tool_end(&mut self, ev: FileToolCallEndEvent) {
+        self.queue.push_back(QueuedInterrupt::FileToolEnd(ev));
+    }
+
     pub(crate) fn push_patch_end(&mut self, ev: PatchApplyEndEvent) {
         self.queue.push_back(QueuedInterrupt::PatchEnd(ev));
     }
@@ -82,6 +88,7 @@ impl InterruptManager {
                 QueuedInterrupt::ExecEnd(ev) => chat.handle_exec_end_now(ev),
                 QueuedInterrupt::McpBegin(ev) => chat.handle_mcp_begin_now(ev),
                 QueuedInterrupt::McpEnd(ev) => chat.handle_mcp_end_now(ev),
+                QueuedInterrupt::FileToolEnd(ev) => chat.handle_file_tool_end_now(ev),
                 QueuedInterrupt::PatchEnd(ev) => chat.handle_patch_apply_end_now(ev),
             }
         }
@@ -132,6 +132,8 @@ fn make_chatwidget_manual() -> (
         bottom_pane: bottom,
         active_exec_cell: None,
         config: cfg.clone(),
+        terminal_notifier: TerminalNotifier::new(cfg.notifications.clone()),
+        background_jobs: BTreeMap::new(),
         initial_user_message: None,
         total_token_usage: TokenUsage::default(),
         last_token_usage: TokenUsage::default(),
@@ -143,6 +145,9 @@ fn make_chatwidget_manual() -> (
         interrupts: InterruptManager::new(),
         needs_redraw: false,
         session_id: None,
+        user_messages: Vec::new(),
+        esc_backtrack_armed: false,
+        plan_panel: PlanPanel::new(),
     };
     (widget, rx, op_rx)
 }
@@ -223,6 +228,7 @@ fn exec_history_cell_shows_working_then_completed() {
             stderr: String::new(),
             exit_code: 0,
             duration: std::time::Duration::from_millis(5),
+            output_file: None,
         }),
     });
 
@@ -268,6 +274,7 @@ fn exec_history_cell_shows_working_then_failed() {
             stderr: "error".into(),
             exit_code: 2,
             duration: std::time::Duration::from_millis(7),
+            output_file: None,
         }),
     });
 
@@ -855,6 +862,7 @@ fn multiple_agent_messages_in_single_turn_emit_multiple_headers() {
         id: "s1".into(),
         msg: EventMsg::TaskComplete(TaskCompleteEvent {
             last_agent_message: None,
+            plan_progress: None,
         }),
     });
 
@@ -979,3 +987,58 @@ fn deltas_then_same_final_message_are_rendered_snapshot() {
         .collect::<String>();
     assert_snapshot!(combined);
 }
+
+#[test]
+fn esc_esc_on_empty_composer_forks_before_last_message() {
+    let (mut chat, rx, _op_rx) = make_chatwidget_manual();
+    let session_id = Uuid::new_v4();
+    chat.session_id = Some(session_id);
+    chat.submit_text_message("first".to_string());
+    chat.submit_text_message("second".to_string());
+    while rx.try_recv().is_ok() {}
+
+    let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+    chat.handle_key_event(esc);
+    assert!(chat.esc_backtrack_armed);
+
+    chat.handle_key_event(esc);
+    assert!(!chat.esc_backtrack_armed);
+    let fork = std::iter::from_fn(|| rx.try_recv().ok()).find_map(|ev| match ev {
+        AppEvent::ForkConversation {
+            conversation_id,
+            drop_last_n_turns,
+            prefill,
+            user_messages,
+            ..
+        } => Some((conversation_id, drop_last_n_turns, prefill, user_messages)),
+        _ => None,
+    });
+    assert_eq!(
+        Some((
+            session_id,
+            1,
+            "second".to_string(),
+            vec!["first".to_string()]
+        )),
+        fork
+    );
+}
+
+#[test]
+fn other_keys_disarm_esc_backtrack() {
+    let (mut chat, rx, _op_rx) = make_chatwidget_manual();
+    chat.session_id = Some(Uuid::new_v4());
+    chat.submit_text_message("hello".to_string());
+
+    chat.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+    assert!(chat.esc_backtrack_armed);
+    chat.handle_key_event(KeyEvent::new(KeyCode::Left, KeyModifiers::NONE));
+    assert!(!chat.esc_backtrack_armed);
+    chat.handle_key_event(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+    assert!(chat.esc_backtrack_armed);
+
+    assert!(
+        !std::iter::from_fn(|| rx.try_recv().ok())
+            .any(|ev| matches!(ev, AppEvent::ForkConversation { .. }))
+    );
+}
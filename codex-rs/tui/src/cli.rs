@@ -56,4 +56,19 @@ pub struct Cli {
 
     #[clap(skip)]
     pub config_overrides: CliConfigOverrides,
+
+    /// Set by `codex resume` to continue a recorded session.
+    #[clap(skip)]
+    pub resume: Option<ResumeSelection>,
+}
+
+/// Which recorded session `codex resume` should continue.
+#[derive(Debug, Clone)]
+pub enum ResumeSelection {
+    /// The most recently started session.
+    Last,
+    /// The session with this id (or unique id prefix).
+    Id(String),
+    /// Let the user pick from a list of sessions.
+    Picker,
 }
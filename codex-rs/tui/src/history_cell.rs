@@ -8,11 +8,16 @@ use codex_ansi_escape::ansi_escape_line;
 use codex_common::create_config_summary_entries;
 use codex_common::elapsed::format_duration;
 use codex_core::config::Config;
+use codex_core::get_model_pricing;
 use codex_core::plan_tool::PlanItemArg;
 use codex_core::plan_tool::StepStatus;
 use codex_core::plan_tool::UpdatePlanArgs;
+use codex_core::protocol::BackgroundJobFinishedEvent;
 use codex_core::protocol::FileChange;
+use codex_core::protocol::FileToolCallSummary;
+use codex_core::protocol::FileToolInvocation;
 use codex_core::protocol::McpInvocation;
+use codex_core::protocol::McpServerStatus;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol::SessionConfiguredEvent;
 use codex_core::protocol::TokenUsage;
@@ -31,8 +36,10 @@ use ratatui::widgets::Paragraph;
 use ratatui::widgets::WidgetRef;
 use ratatui::widgets::Wrap;
 use shlex::try_join as shlex_try_join;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::io::Cursor;
+use std::path::Path;
 use std::path::PathBuf;
 use std::time::Duration;
 use std::time::Instant;
@@ -481,6 +488,78 @@ pub(crate) fn new_completed_mcp_tool_call(
     Box::new(PlainHistoryCell { lines })
 }
 
+/// Render a completed `read_file`, `grep` or `list_dir` call in the style of
+/// the parsed commands of an exec cell.
+pub(crate) fn new_completed_file_tool_call(
+    cwd: &Path,
+    invocation: FileToolInvocation,
+    result: Result<FileToolCallSummary, String>,
+) -> PlainHistoryCell {
+    let relative = |path: &Path| match path.strip_prefix(cwd) {
+        Ok(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+        Ok(rel) => rel.display().to_string(),
+        Err(_) => path.display().to_string(),
+    };
+    let count = |summary: &FileToolCallSummary, noun: &str| {
+        let more = if summary.truncated { "+" } else { "" };
+        format!("{}{more} {noun}", summary.count)
+    };
+    let text = match &invocation {
+        FileToolInvocation::ReadFile {
+            path,
+            start_line,
+            end_line,
+        } => {
+            let range = match (start_line, end_line, &result) {
+                (None, None, _) => String::new(),
+                (start, _, Ok(summary)) if summary.count > 0 => {
+                    let start = start.unwrap_or(1);
+                    format!(":{start}-{}", start + summary.count - 1)
+                }
+                (start, end, _) => format!(
+                    ":{}-{}",
+                    start.unwrap_or(1),
+                    end.map(|end| end.to_string()).unwrap_or_default()
+                ),
+            };
+            format!("📖 {}{range}", relative(path))
+        }
+        FileToolInvocation::Grep {
+            pattern,
+            path,
+            include,
+        } => {
+            let mut text = format!("🔎 {pattern} in {}", relative(path));
+            if let Some(include) = include {
+                text.push_str(&format!(" ({include})"));
+            }
+            if let Ok(summary) = &result {
+                text.push_str(&format!(" · {}", count(summary, "matches")));
+            }
+            text
+        }
+        FileToolInvocation::ListDir { path, .. } => {
+            let mut text = format!("📂 {}", relative(path));
+            if let Ok(summary) = &result {
+                text.push_str(&format!(" · {}", count(summary, "entries")));
+            }
+            text
+        }
+    };
+
+    let mut lines: Vec<Line<'static>> = Vec::new();
+    match &result {
+        Ok(_) => lines.push(Line::from(vec!["✓".green(), " Completed".into()])),
+        Err(_) => lines.push(Line::from(vec!["✗".red(), " Failed".into()])),
+    }
+    lines.push(Line::from(vec!["  └ ".dim(), text.dim()]));
+    if let Err(e) = result {
+        lines.push(Line::from(vec!["    ".into(), e.red()]));
+    }
+    lines.push(Line::from(""));
+    PlainHistoryCell { lines }
+}
+
 pub(crate) fn new_diff_output(message: String) -> PlainHistoryCell {
     let mut lines: Vec<Line<'static>> = Vec::new();
     lines.push(Line::from("/diff".magenta()));
@@ -499,6 +578,7 @@ pub(crate) fn new_status_output(
     config: &Config,
     usage: &TokenUsage,
     session_id: &Option<Uuid>,
+    background_jobs: &BTreeMap<u32, Vec<String>>,
 ) -> PlainHistoryCell {
     let mut lines: Vec<Line<'static>> = Vec::new();
     lines.push(Line::from("/status".magenta()));
@@ -628,8 +708,40 @@ pub(crate) fn new_status_output(
         "  • Total: ".into(),
         usage.blended_total().to_string().into(),
     ]));
+    if let Some(max_tokens) = config.max_session_tokens {
+        lines.push(Line::from(vec![
+            "  • Token Budget: ".into(),
+            max_tokens.to_string().into(),
+        ]));
+    }
+    // Cost: estimated with the pricing of the current model.
+    if let Some(pricing) = get_model_pricing(&config.model, &config.model_pricing) {
+        lines.push(Line::from(vec![
+            "  • Estimated Cost: ".into(),
+            format!("${:.2}", pricing.cost_usd(usage)).into(),
+        ]));
+    }
+    if let Some(max_cost) = config.max_session_cost {
+        lines.push(Line::from(vec![
+            "  • Cost Budget: ".into(),
+            format!("${max_cost:.2}").into(),
+        ]));
+    }
 
     lines.push(Line::from(""));
+
+    // ⚙️ Background Jobs (only if any are running)
+    if !background_jobs.is_empty() {
+        lines.push(Line::from(vec!["⚙️ ".into(), "Background Jobs".bold()]));
+        for (job_id, command) in background_jobs {
+            lines.push(Line::from(vec![
+                format!("  • {job_id}: ").into(),
+                strip_bash_lc_and_escape(command).into(),
+            ]));
+        }
+        lines.push(Line::from(""));
+    }
+
     PlainHistoryCell { lines }
 }
 
@@ -651,6 +763,7 @@ pub(crate) fn empty_mcp_output() -> PlainHistoryCell {
 pub(crate) fn new_mcp_tools_output(
     config: &Config,
     tools: std::collections::HashMap<String, mcp_types::Tool>,
+    servers: std::collections::HashMap<String, McpServerStatus>,
 ) -> PlainHistoryCell {
     let mut lines: Vec<Line<'static>> = vec![
         Line::from("/mcp".magenta()),
@@ -659,7 +772,7 @@ pub(crate) fn new_mcp_tools_output(
         Line::from(""),
     ];
 
-    if tools.is_empty() {
+    if tools.is_empty() && servers.is_empty() {
         lines.push(Line::from("  • No MCP tools available.".italic()));
         lines.push(Line::from(""));
         return PlainHistoryCell { lines };
@@ -679,8 +792,18 @@ pub(crate) fn new_mcp_tools_output(
             server.clone().into(),
         ]));
 
-        if !cfg.command.is_empty() {
-            let cmd_display = format!("{} {}", cfg.command, cfg.args.join(" "));
+        let status: Span<'static> = match servers.get(server) {
+            Some(McpServerStatus::Running) => "running".green(),
+            Some(McpServerStatus::Restarting) => "restarting".yellow(),
+            Some(McpServerStatus::Failed) => "failed".red(),
+            None => "not started".red(),
+        };
+        lines.push(Line::from(vec!["    • Status: ".into(), status]));
+
+        if let Some(command) = cfg.command.as_ref()
+            && !command.is_empty()
+        {
+            let cmd_display = format!("{} {}", command, cfg.args.join(" "));
 
             lines.push(Line::from(vec![
                 "    • Command: ".into(),
@@ -688,6 +811,10 @@ pub(crate) fn new_mcp_tools_output(
             ]));
         }
 
+        if let Some(url) = cfg.url.as_ref() {
+            lines.push(Line::from(vec!["    • URL: ".into(), url.clone().into()]));
+        }
+
         if let Some(env) = cfg.env.as_ref()
             && !env.is_empty()
         {
@@ -718,6 +845,81 @@ pub(crate) fn new_error_event(message: String) -> PlainHistoryCell {
     PlainHistoryCell { lines }
 }
 
+pub(crate) fn new_network_access_denied(host: String, port: u16) -> PlainHistoryCell {
+    let lines: Vec<Line<'static>> = vec![
+        vec![
+            "🌐 ".into(),
+            "network access denied: ".red(),
+            format!("{host}:{port}").into(),
+        ]
+        .into(),
+        "".into(),
+    ];
+    PlainHistoryCell { lines }
+}
+
+pub(crate) fn new_checkpoint_created(changed_files: usize) -> PlainHistoryCell {
+    let lines: Vec<Line<'static>> = vec![
+        Line::from(
+            format!("checkpoint saved · {changed_files} file(s) changed · /undo to revert").dim(),
+        ),
+        "".into(),
+    ];
+    PlainHistoryCell { lines }
+}
+
+pub(crate) fn new_checkpoint_restored(
+    cwd: &Path,
+    restored_files: Vec<PathBuf>,
+) -> PlainHistoryCell {
+    let mut lines: Vec<Line<'static>> = Vec::new();
+    lines.push(Line::from("/undo".magenta()));
+    if restored_files.is_empty() {
+        lines.push(Line::from("No files needed to be restored.".italic()));
+    } else {
+        for path in restored_files {
+            let display = path
+                .strip_prefix(cwd)
+                .unwrap_or(&path)
+                .display()
+                .to_string();
+            lines.push(Line::from(vec!["  ↺ ".dim(), display.into()]));
+        }
+    }
+    lines.push(Line::from(""));
+    PlainHistoryCell { lines }
+}
+
+pub(crate) fn new_background_job_started(job_id: u32, command: &[String]) -> PlainHistoryCell {
+    let lines: Vec<Line<'static>> = vec![
+        Line::from(vec![
+            format!("⚙ background job {job_id} started: ").dim(),
+            strip_bash_lc_and_escape(command).into(),
+        ]),
+        "".into(),
+    ];
+    PlainHistoryCell { lines }
+}
+
+pub(crate) fn new_background_job_finished(ev: BackgroundJobFinishedEvent) -> PlainHistoryCell {
+    let BackgroundJobFinishedEvent {
+        job_id,
+        exit_code,
+        killed,
+        duration,
+    } = ev;
+    let status = if killed {
+        format!("killed after {}", format_duration(duration))
+    } else {
+        format!("exited {exit_code} after {}", format_duration(duration))
+    };
+    let lines: Vec<Line<'static>> = vec![
+        Line::from(format!("⚙ background job {job_id} {status}").dim()),
+        "".into(),
+    ];
+    PlainHistoryCell { lines }
+}
+
 /// Render a user‑friendly plan update styled like a checkbox todo list.
 pub(crate) fn new_plan_update(update: UpdatePlanArgs) -> PlainHistoryCell {
     let UpdatePlanArgs { explanation, plan } = update;
@@ -776,7 +978,21 @@ pub(crate) fn new_plan_update(update: UpdatePlanArgs) -> PlainHistoryCell {
     if plan.is_empty() {
         lines.push(Line::from("(no steps provided)".dim().italic()));
     } else {
-        for (idx, PlanItemArg { step, status }) in plan.into_iter().enumerate() {
+        lines.extend(plan_step_lines(&plan));
+    }
+
+    lines.push(Line::from(""));
+
+    PlainHistoryCell { lines }
+}
+
+/// Plan steps styled as checkbox items, shared by the history cell and the
+/// pinned plan panel.
+pub(crate) fn plan_step_lines(plan: &[PlanItemArg]) -> Vec<Line<'static>> {
+    plan.iter()
+        .enumerate()
+        .map(|(idx, PlanItemArg { step, status })| {
+            let step = step.clone();
             let (box_span, text_span) = match status {
                 StepStatus::Completed => (
                     Span::styled("✔", Style::default().fg(Color::Green)),
@@ -804,18 +1020,9 @@ pub(crate) fn new_plan_update(update: UpdatePlanArgs) -> PlainHistoryCell {
             } else {
                 Span::raw("    ")
             };
-            lines.push(Line::from(vec![
-                prefix,
-                box_span,
-                Span::raw(" "),
-                text_span,
-            ]));
-        }
-    }
-
-    lines.push(Line::from(""));
-
-    PlainHistoryCell { lines }
+            Line::from(vec![prefix, box_span, Span::raw(" "), text_span])
+        })
+        .collect()
 }
 
 /// Create a new `PendingPatch` cell that lists the file‑level summary of
@@ -12,6 +12,9 @@ use codex_core::config::find_codex_home;
 use codex_core::config::load_config_as_toml_with_cli_overrides;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::rollout::find_session;
+use codex_core::rollout::list_sessions;
+use codex_core::rollout::read_session_state;
 use codex_login::AuthMode;
 use codex_login::CodexAuth;
 use codex_ollama::DEFAULT_OSS_MODEL;
@@ -42,12 +45,15 @@ pub mod live_wrap;
 mod markdown;
 mod markdown_stream;
 pub mod onboarding;
+mod plan_panel;
 mod render;
+mod resume_picker;
 mod session_log;
 mod shimmer;
 mod slash_command;
 mod status_indicator_widget;
 mod streaming;
+mod terminal_notification;
 mod text_formatting;
 mod tui;
 mod user_approval_widget;
@@ -63,6 +69,7 @@ mod updates;
 use color_eyre::owo_colors::OwoColorize;
 
 pub use cli::Cli;
+pub use cli::ResumeSelection;
 
 // (tests access modules directly within the crate)
 
@@ -70,6 +77,21 @@ pub async fn run_main(
     cli: Cli,
     codex_linux_sandbox_exe: Option<PathBuf>,
 ) -> std::io::Result<codex_core::protocol::TokenUsage> {
+    // Resolve the session to resume first: its turn context provides the
+    // defaults for anything not given on the command line.
+    let resume_path = match &cli.resume {
+        Some(selection) => match resolve_resume_path(selection)? {
+            Some(path) => Some(path),
+            // Nothing to resume, or the user cancelled the picker.
+            None => return Ok(codex_core::protocol::TokenUsage::default()),
+        },
+        None => None,
+    };
+    let resumed_state = match &resume_path {
+        Some(path) => Some(read_session_state(path)?),
+        None => None,
+    };
+
     let (sandbox_mode, approval_policy) = if cli.full_auto {
         (
             Some(SandboxMode::WorkspaceWrite),
@@ -95,8 +117,14 @@ pub async fn run_main(
     } else if cli.oss {
         Some(DEFAULT_OSS_MODEL.to_owned())
     } else {
-        None // No model specified, will use the default.
+        // No model specified, will use the resumed session's or the default.
+        resumed_state.as_ref().and_then(|state| state.model.clone())
     };
+    let approval_policy = approval_policy.or_else(|| {
+        resumed_state
+            .as_ref()
+            .and_then(|state| state.approval_policy)
+    });
 
     let model_provider_override = if cli.oss {
         Some(BUILT_IN_OSS_MODEL_PROVIDER_ID.to_owned())
@@ -105,7 +133,11 @@ pub async fn run_main(
     };
 
     // canonicalize the cwd
-    let cwd = cli.cwd.clone().map(|p| p.canonicalize().unwrap_or(p));
+    let cwd = cli
+        .cwd
+        .clone()
+        .map(|p| p.canonicalize().unwrap_or(p))
+        .or_else(|| resumed_state.as_ref().and_then(|state| state.cwd.clone()));
 
     let overrides = ConfigOverrides {
         model,
@@ -165,6 +197,16 @@ pub async fn run_main(
         }
     };
 
+    // The resumed session's sandbox policy is restored as-is (including
+    // writable roots) unless a sandbox mode was given on the command line.
+    let resumed_sandbox_policy = resumed_state
+        .and_then(|state| state.sandbox_policy)
+        .filter(|_| sandbox_mode.is_none());
+    if let Some(sandbox_policy) = resumed_sandbox_policy {
+        config.sandbox_policy = sandbox_policy;
+    }
+    config.experimental_resume = resume_path.or(config.experimental_resume);
+
     let should_show_trust_screen = determine_repo_trust_state(
         &mut config,
         &config_toml,
@@ -297,6 +339,37 @@ fn restore() {
     }
 }
 
+/// Returns the rollout file of the session to resume, or `None` if there is
+/// nothing to resume or the user cancelled the picker.
+#[allow(clippy::print_stderr)]
+fn resolve_resume_path(selection: &ResumeSelection) -> std::io::Result<Option<PathBuf>> {
+    let codex_home = find_codex_home()?;
+    match selection {
+        ResumeSelection::Last => {
+            let last = list_sessions(&codex_home)?.into_iter().next();
+            if last.is_none() {
+                eprintln!("No recorded sessions to resume.");
+            }
+            Ok(last.map(|info| info.path))
+        }
+        ResumeSelection::Id(id) => match find_session(&codex_home, id)? {
+            Some(info) => Ok(Some(info.path)),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no recorded session with id `{id}`"),
+            )),
+        },
+        ResumeSelection::Picker => {
+            let sessions = list_sessions(&codex_home)?;
+            if sessions.is_empty() {
+                eprintln!("No recorded sessions to resume.");
+                return Ok(None);
+            }
+            resume_picker::run_resume_picker(&sessions)
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LoginStatus {
     AuthMode(AuthMode),
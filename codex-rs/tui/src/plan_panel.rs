@@ -0,0 +1,133 @@
+//! A panel that pins the current plan above the composer so it stays visible
+//! while the history scrolls. Toggled with Ctrl+T.
+
+use codex_core::plan_tool::StepStatus;
+use codex_core::plan_tool::UpdatePlanArgs;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::WidgetRef;
+
+use crate::history_cell::plan_step_lines;
+
+/// Longer plans are shown as a window starting at the first unfinished step.
+const MAX_VISIBLE_STEPS: usize = 6;
+
+pub(crate) struct PlanPanel {
+    plan: Option<UpdatePlanArgs>,
+    visible: bool,
+}
+
+impl PlanPanel {
+    pub(crate) fn new() -> Self {
+        Self {
+            plan: None,
+            visible: true,
+        }
+    }
+
+    pub(crate) fn set_plan(&mut self, plan: UpdatePlanArgs) {
+        self.plan = Some(plan);
+    }
+
+    pub(crate) fn has_plan(&self) -> bool {
+        self.plan.is_some()
+    }
+
+    pub(crate) fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub(crate) fn desired_height(&self, _width: u16) -> u16 {
+        self.lines().len() as u16
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let Some(plan) = self.plan.as_ref().filter(|_| self.visible) else {
+            return Vec::new();
+        };
+        let progress = plan.progress();
+
+        let mut lines = vec![Line::from(vec![
+            Span::styled("📋 Plan", Style::default().add_modifier(Modifier::BOLD)).magenta(),
+            Span::raw(format!(" {}/{}", progress.completed, progress.total)),
+            "  Ctrl+T to hide".dim(),
+        ])];
+
+        let steps = &plan.plan;
+        let first_unfinished = steps
+            .iter()
+            .position(|item| !matches!(item.status, StepStatus::Completed))
+            .unwrap_or(steps.len());
+        let start = first_unfinished.min(steps.len().saturating_sub(MAX_VISIBLE_STEPS));
+        let end = (start + MAX_VISIBLE_STEPS).min(steps.len());
+        lines.extend(plan_step_lines(&steps[start..end]));
+        if end < steps.len() {
+            lines.push(Line::from(
+                format!("    … {} more", steps.len() - end).dim(),
+            ));
+        }
+        lines
+    }
+}
+
+impl WidgetRef for &PlanPanel {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        Paragraph::new(self.lines()).render_ref(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::plan_tool::PlanItemArg;
+    use pretty_assertions::assert_eq;
+
+    fn plan(statuses: &[StepStatus]) -> UpdatePlanArgs {
+        UpdatePlanArgs {
+            explanation: None,
+            plan: statuses
+                .iter()
+                .enumerate()
+                .map(|(idx, status)| PlanItemArg {
+                    step: format!("step {idx}"),
+                    status: status.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn hidden_until_a_plan_is_set_and_when_toggled_off() {
+        let mut panel = PlanPanel::new();
+        assert_eq!(0, panel.desired_height(80));
+
+        panel.set_plan(plan(&[StepStatus::InProgress, StepStatus::Pending]));
+        assert_eq!(3, panel.desired_height(80));
+
+        panel.toggle();
+        assert_eq!(0, panel.desired_height(80));
+    }
+
+    #[test]
+    fn long_plans_start_at_the_first_unfinished_step() {
+        let mut statuses = vec![StepStatus::Completed; 4];
+        statuses.extend(vec![StepStatus::Pending; 8]);
+        let mut panel = PlanPanel::new();
+        panel.set_plan(plan(&statuses));
+
+        let text: Vec<String> = panel
+            .lines()
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+        assert_eq!(1 + MAX_VISIBLE_STEPS + 1, text.len());
+        assert!(text[1].ends_with("step 4"));
+        assert!(text[MAX_VISIBLE_STEPS + 1].ends_with("2 more"));
+    }
+}
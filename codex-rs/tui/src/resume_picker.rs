@@ -0,0 +1,130 @@
+//! Full-screen list of recorded sessions shown by `codex resume` when no
+//! session id is given.
+
+use std::io::Result;
+use std::io::stdout;
+use std::path::PathBuf;
+
+use codex_core::rollout::SessionInfo;
+use crossterm::event::Event;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
+use crossterm::terminal::EnterAlternateScreen;
+use crossterm::terminal::LeaveAlternateScreen;
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::disable_raw_mode;
+use ratatui::crossterm::terminal::enable_raw_mode;
+use ratatui::style::Style;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::widgets::Block;
+use ratatui::widgets::Borders;
+use ratatui::widgets::List;
+use ratatui::widgets::ListItem;
+use ratatui::widgets::ListState;
+
+use crate::exec_command::relativize_to_home;
+
+/// Maximum number of characters of the first user message shown per row.
+const MAX_PREVIEW_CHARS: usize = 80;
+
+/// Lets the user choose one of `sessions`. Returns the path of the chosen
+/// rollout file, or `None` if the user cancelled.
+pub(crate) fn run_resume_picker(sessions: &[SessionInfo]) -> Result<Option<PathBuf>> {
+    if sessions.is_empty() {
+        return Ok(None);
+    }
+
+    execute!(stdout(), EnterAlternateScreen)?;
+    enable_raw_mode()?;
+    let result = pick_session(sessions);
+    let _ = disable_raw_mode();
+    let _ = execute!(stdout(), LeaveAlternateScreen);
+    result
+}
+
+fn pick_session(sessions: &[SessionInfo]) -> Result<Option<PathBuf>> {
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+    let items: Vec<ListItem> = sessions.iter().map(session_row).collect();
+    let mut state = ListState::default().with_selected(Some(0));
+
+    loop {
+        terminal.draw(|frame| {
+            let list = List::new(items.clone())
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(" Resume a session (↑/↓ to move, Enter to resume, Esc to cancel) "),
+                )
+                .highlight_symbol("› ")
+                .highlight_style(Style::default().cyan().bold());
+            frame.render_stateful_widget(list, frame.area(), &mut state);
+        })?;
+
+        let Event::Key(key) = crossterm::event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let selected = state.selected().unwrap_or(0);
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                state.select(Some(selected.saturating_sub(1)));
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                state.select(Some((selected + 1).min(sessions.len() - 1)));
+            }
+            KeyCode::Enter => {
+                return Ok(sessions.get(selected).map(|info| info.path.clone()));
+            }
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Ok(None);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn session_row(info: &SessionInfo) -> ListItem<'static> {
+    // Timestamps look like `2025-05-07T17:24:21.123Z`; show minutes only.
+    let timestamp = info
+        .timestamp
+        .get(..16)
+        .unwrap_or(&info.timestamp)
+        .replace('T', " ");
+    let cwd = info
+        .cwd
+        .as_ref()
+        .map(|cwd| match relativize_to_home(cwd) {
+            Some(rel) if !rel.as_os_str().is_empty() => format!("~/{}", rel.display()),
+            Some(_) => "~".to_string(),
+            None => cwd.display().to_string(),
+        })
+        .unwrap_or_else(|| "?".to_string());
+    let preview = info
+        .first_user_message
+        .as_deref()
+        .map(|message| {
+            let message = message.split_whitespace().collect::<Vec<_>>().join(" ");
+            if message.chars().count() > MAX_PREVIEW_CHARS {
+                let truncated: String = message.chars().take(MAX_PREVIEW_CHARS).collect();
+                format!("{truncated}…")
+            } else {
+                message
+            }
+        })
+        .unwrap_or_else(|| "(no messages)".to_string());
+
+    let mut spans = vec![timestamp.dim(), "  ".into(), cwd.into()];
+    if let Some(branch) = &info.git_branch {
+        spans.push(format!(" ({branch})").dim());
+    }
+    spans.push("  ".into());
+    spans.push(preview.into());
+    ListItem::new(Line::from(spans))
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use strum::IntoEnumIterator;
 use strum_macros::AsRefStr;
 use strum_macros::EnumIter;
@@ -18,6 +20,7 @@ pub enum SlashCommand {
     Init,
     Compact,
     Diff,
+    Undo,
     Mention,
     Status,
     Mcp,
@@ -36,6 +39,7 @@ impl SlashCommand {
             SlashCommand::Compact => "summarize conversation to prevent hitting the context limit",
             SlashCommand::Quit => "exit Codex",
             SlashCommand::Diff => "show git diff (including untracked files)",
+            SlashCommand::Undo => "restore the files changed by the last turn",
             SlashCommand::Mention => "mention a file",
             SlashCommand::Status => "show current session configuration and token usage",
             SlashCommand::Model => "choose a model preset (model + reasoning effort)",
@@ -58,3 +62,133 @@ impl SlashCommand {
 pub fn built_in_slash_commands() -> Vec<(&'static str, SlashCommand)> {
     SlashCommand::iter().map(|c| (c.command(), c)).collect()
 }
+
+/// A prompt offered by a configured MCP server, invoked as `/server:prompt`
+/// followed by its arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct McpPromptCommand {
+    pub server: String,
+    pub prompt: mcp_types::Prompt,
+}
+
+impl McpPromptCommand {
+    /// Command string without the leading '/'.
+    pub fn command(&self) -> String {
+        format!("{}:{}", self.server, self.prompt.name)
+    }
+
+    /// User-visible description shown in the popup.
+    pub fn description(&self) -> String {
+        let description = self
+            .prompt
+            .description
+            .clone()
+            .unwrap_or_else(|| format!("prompt from {}", self.server));
+        let arguments = self.argument_names();
+        if arguments.is_empty() {
+            description
+        } else {
+            format!("{description} ({})", arguments.join(", "))
+        }
+    }
+
+    fn argument_names(&self) -> Vec<String> {
+        self.prompt
+            .arguments
+            .iter()
+            .flatten()
+            .map(|argument| argument.name.clone())
+            .collect()
+    }
+
+    /// Maps the text typed after the command to prompt arguments. Words of
+    /// the form `name=value` set the named argument; the remaining words fill
+    /// the other arguments in order, with any surplus appended to the last
+    /// one.
+    pub fn parse_arguments(&self, text: &str) -> HashMap<String, String> {
+        let names = self.argument_names();
+        let mut arguments = HashMap::new();
+        let mut positional = Vec::new();
+        for word in text.split_whitespace() {
+            match word.split_once('=') {
+                Some((name, value)) if names.iter().any(|n| n == name) => {
+                    arguments.insert(name.to_string(), value.to_string());
+                }
+                _ => positional.push(word),
+            }
+        }
+
+        let unset: Vec<&String> = names
+            .iter()
+            .filter(|name| !arguments.contains_key(*name))
+            .collect();
+        let mut positional = positional.into_iter();
+        for (i, name) in unset.iter().enumerate() {
+            let value = if i + 1 == unset.len() {
+                positional.by_ref().collect::<Vec<_>>().join(" ")
+            } else {
+                positional.next().unwrap_or_default().to_string()
+            };
+            if !value.is_empty() {
+                arguments.insert((*name).clone(), value);
+            }
+        }
+        arguments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_types::Prompt;
+    use mcp_types::PromptArgument;
+    use pretty_assertions::assert_eq;
+
+    fn review_prompt() -> McpPromptCommand {
+        let argument = |name: &str| PromptArgument {
+            description: None,
+            name: name.to_string(),
+            required: Some(true),
+            title: None,
+        };
+        McpPromptCommand {
+            server: "docs".to_string(),
+            prompt: Prompt {
+                arguments: Some(vec![argument("path"), argument("focus")]),
+                description: Some("Review a file".to_string()),
+                name: "review".to_string(),
+                title: None,
+            },
+        }
+    }
+
+    #[test]
+    fn mcp_prompt_command_and_description() {
+        let prompt = review_prompt();
+        assert_eq!("docs:review", prompt.command());
+        assert_eq!("Review a file (path, focus)", prompt.description());
+    }
+
+    #[test]
+    fn mcp_prompt_arguments_by_name_and_position() {
+        let prompt = review_prompt();
+        assert_eq!(
+            HashMap::from([
+                ("path".to_string(), "src/lib.rs".to_string()),
+                ("focus".to_string(), "error handling".to_string()),
+            ]),
+            prompt.parse_arguments("src/lib.rs error handling")
+        );
+        assert_eq!(
+            HashMap::from([
+                ("path".to_string(), "src/main.rs".to_string()),
+                ("focus".to_string(), "tests".to_string()),
+            ]),
+            prompt.parse_arguments("focus=tests src/main.rs")
+        );
+        assert_eq!(
+            HashMap::from([("path".to_string(), "a.rs".to_string())]),
+            prompt.parse_arguments("a.rs")
+        );
+    }
+}
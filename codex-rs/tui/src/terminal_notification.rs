@@ -0,0 +1,173 @@
+//! Notifications delivered through the terminal emulator: `OSC 9` or
+//! `OSC 777` escape sequences written when an event matches one of the
+//! notification types enabled under `[notifications]`.
+
+use std::io::Write;
+
+use codex_core::config_types::NotificationKind;
+use codex_core::config_types::NotificationsConfig;
+use codex_core::config_types::TerminalNotificationMethod;
+use codex_core::protocol::ApplyPatchApprovalRequestEvent;
+use codex_core::protocol::BudgetApprovalRequestEvent;
+use codex_core::protocol::ErrorEvent;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::ExecApprovalRequestEvent;
+use codex_core::protocol::ExecCommandEndEvent;
+use codex_core::protocol::TaskCompleteEvent;
+use tracing::warn;
+
+/// Terminals truncate long notifications anyway; keep the body short.
+const MAX_BODY_CHARS: usize = 200;
+
+pub(crate) struct TerminalNotifier {
+    config: NotificationsConfig,
+}
+
+impl TerminalNotifier {
+    pub(crate) fn new(config: NotificationsConfig) -> Self {
+        Self { config }
+    }
+
+    pub(crate) fn on_event(&self, msg: &EventMsg) {
+        let Some(method) = self.config.terminal else {
+            return;
+        };
+        let Some((kind, title, body)) = notification_for_event(msg, &self.config) else {
+            return;
+        };
+        if !self.config.is_enabled(kind) {
+            return;
+        }
+        let sequence = escape_sequence(method, title, &body);
+        let mut stdout = std::io::stdout();
+        if let Err(e) = stdout
+            .write_all(sequence.as_bytes())
+            .and_then(|()| stdout.flush())
+        {
+            warn!("failed to write terminal notification: {e}");
+        }
+    }
+}
+
+fn notification_for_event(
+    msg: &EventMsg,
+    config: &NotificationsConfig,
+) -> Option<(NotificationKind, &'static str, String)> {
+    let notification = match msg {
+        EventMsg::TaskComplete(TaskCompleteEvent {
+            last_agent_message, ..
+        }) => (
+            NotificationKind::AgentTurnComplete,
+            "Codex: turn complete",
+            last_agent_message.clone().unwrap_or_default(),
+        ),
+        EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent { command, .. }) => (
+            NotificationKind::ApprovalRequested,
+            "Codex: approval requested",
+            format!("Run {}", command.join(" ")),
+        ),
+        EventMsg::ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent { changes, .. }) => (
+            NotificationKind::ApprovalRequested,
+            "Codex: approval requested",
+            format!("Edit {} file(s)", changes.len()),
+        ),
+        EventMsg::BudgetApprovalRequest(BudgetApprovalRequestEvent {
+            used_tokens,
+            cost_usd,
+            ..
+        }) => (
+            NotificationKind::BudgetExceeded,
+            "Codex: session budget exceeded",
+            format!("{used_tokens} tokens used, ${cost_usd:.2} spent"),
+        ),
+        EventMsg::Error(ErrorEvent { message }) => (
+            NotificationKind::TurnFailed,
+            "Codex: turn failed",
+            message.clone(),
+        ),
+        EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+            exit_code,
+            duration,
+            ..
+        }) if *duration >= config.long_command_threshold() => (
+            NotificationKind::LongCommandFinished,
+            "Codex: command finished",
+            format!("Exited with code {exit_code} after {}s", duration.as_secs()),
+        ),
+        _ => return None,
+    };
+    Some(notification)
+}
+
+fn escape_sequence(method: TerminalNotificationMethod, title: &str, body: &str) -> String {
+    let body = sanitize(body);
+    match method {
+        TerminalNotificationMethod::Osc9 => format!("\x1b]9;{title}: {body}\x07"),
+        TerminalNotificationMethod::Osc777 => format!("\x1b]777;notify;{title};{body}\x07"),
+    }
+}
+
+/// Control characters would end the escape sequence early, so the body is
+/// flattened to a single line of printable text.
+fn sanitize(body: &str) -> String {
+    body.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .take(MAX_BODY_CHARS)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn formats_osc_sequences() {
+        assert_eq!(
+            "\x1b]9;Codex: turn failed: boom  now\x07",
+            escape_sequence(
+                TerminalNotificationMethod::Osc9,
+                "Codex: turn failed",
+                "boom\x1b\nnow"
+            )
+        );
+        assert_eq!(
+            "\x1b]777;notify;Codex: turn complete;done\x07",
+            escape_sequence(
+                TerminalNotificationMethod::Osc777,
+                "Codex: turn complete",
+                "done"
+            )
+        );
+    }
+
+    #[test]
+    fn only_long_commands_notify() {
+        let config = NotificationsConfig {
+            long_command_secs: Some(30),
+            ..Default::default()
+        };
+        let end = |secs| {
+            EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+                call_id: "call".to_string(),
+                stdout: String::new(),
+                stderr: String::new(),
+                exit_code: 0,
+                duration: Duration::from_secs(secs),
+                output_file: None,
+            })
+        };
+        assert!(notification_for_event(&end(5), &config).is_none());
+        assert_eq!(
+            Some((
+                NotificationKind::LongCommandFinished,
+                "Codex: command finished",
+                "Exited with code 0 after 45s".to_string()
+            )),
+            notification_for_event(&end(45), &config)
+        );
+    }
+}
@@ -42,6 +42,13 @@ pub(crate) enum ApprovalRequest {
         reason: Option<String>,
         grant_root: Option<PathBuf>,
     },
+    Budget {
+        id: String,
+        used_tokens: u64,
+        max_tokens: Option<u64>,
+        cost_usd: f64,
+        max_cost_usd: Option<f64>,
+    },
 }
 
 /// Options displayed in the *select* mode.
@@ -94,6 +101,29 @@ static PATCH_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
     ]
 });
 
+static BUDGET_SELECT_OPTIONS: LazyLock<Vec<SelectOption>> = LazyLock::new(|| {
+    vec![
+        SelectOption {
+            label: Line::from(vec!["Y".underlined(), "es".into()]),
+            description: "Continue this task over budget",
+            key: KeyCode::Char('y'),
+            decision: ReviewDecision::Approved,
+        },
+        SelectOption {
+            label: Line::from(vec!["A".underlined(), "lways".into()]),
+            description: "Ignore the budget for the remainder of this session",
+            key: KeyCode::Char('a'),
+            decision: ReviewDecision::ApprovedForSession,
+        },
+        SelectOption {
+            label: Line::from(vec!["N".underlined(), "o".into()]),
+            description: "Stop the task",
+            key: KeyCode::Char('n'),
+            decision: ReviewDecision::Denied,
+        },
+    ]
+});
+
 /// A modal prompting the user to approve or deny the pending request.
 pub(crate) struct UserApprovalWidget<'a> {
     approval_request: ApprovalRequest,
@@ -177,12 +207,37 @@ impl UserApprovalWidget<'_> {
 
                 Paragraph::new(contents).wrap(Wrap { trim: false })
             }
+            ApprovalRequest::Budget {
+                used_tokens,
+                max_tokens,
+                cost_usd,
+                max_cost_usd,
+                ..
+            } => {
+                let mut contents: Vec<Line> = vec![Line::from(vec![
+                    "? ".fg(Color::Cyan),
+                    "Codex has used up the session budget".bold(),
+                ])];
+                let tokens = match max_tokens {
+                    Some(max) => format!("{used_tokens} of {max}"),
+                    None => used_tokens.to_string(),
+                };
+                contents.push(Line::from(format!("  tokens: {tokens}")));
+                let cost = match max_cost_usd {
+                    Some(max) => format!("${cost_usd:.2} of ${max:.2}"),
+                    None => format!("${cost_usd:.2}"),
+                };
+                contents.push(Line::from(format!("  cost:   {cost}")));
+                contents.push(Line::from(""));
+                Paragraph::new(contents).wrap(Wrap { trim: false })
+            }
         };
 
         Self {
             select_options: match &approval_request {
                 ApprovalRequest::Exec { .. } => &COMMAND_SELECT_OPTIONS,
                 ApprovalRequest::ApplyPatch { .. } => &PATCH_SELECT_OPTIONS,
+                ApprovalRequest::Budget { .. } => &BUDGET_SELECT_OPTIONS,
             },
             approval_request,
             app_event_tx,
@@ -320,6 +375,29 @@ impl UserApprovalWidget<'_> {
             ApprovalRequest::ApplyPatch { .. } => {
                 lines.push(Line::from(format!("patch approval decision: {decision:?}")));
             }
+            ApprovalRequest::Budget { .. } => {
+                let line = match decision {
+                    ReviewDecision::Approved => Line::from(vec![
+                        "✔ ".fg(Color::Green),
+                        "You ".into(),
+                        "allowed".bold(),
+                        " codex to continue over budget".into(),
+                    ]),
+                    ReviewDecision::ApprovedForSession => Line::from(vec![
+                        "✔ ".fg(Color::Green),
+                        "You ".into(),
+                        "lifted".bold(),
+                        " the budget for this session".into(),
+                    ]),
+                    ReviewDecision::Denied | ReviewDecision::Abort => Line::from(vec![
+                        "✗ ".fg(Color::Red),
+                        "You ".into(),
+                        "stopped".bold(),
+                        " codex at the session budget".into(),
+                    ]),
+                };
+                lines.push(line);
+            }
         }
         if !feedback.trim().is_empty() {
             lines.push(Line::from("feedback:"));
@@ -339,6 +417,10 @@ impl UserApprovalWidget<'_> {
                 id: id.clone(),
                 decision,
             },
+            ApprovalRequest::Budget { id, .. } => Op::BudgetApproval {
+                id: id.clone(),
+                decision,
+            },
         };
 
         self.app_event_tx.send(AppEvent::CodexOp(op));
@@ -387,6 +469,7 @@ impl WidgetRef for &UserApprovalWidget<'_> {
         let title = match &self.approval_request {
             ApprovalRequest::Exec { .. } => "Allow command?",
             ApprovalRequest::ApplyPatch { .. } => "Apply changes?",
+            ApprovalRequest::Budget { .. } => "Continue?",
         };
         Line::from(title).render(title_area, buf);
 